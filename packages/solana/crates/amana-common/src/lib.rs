@@ -0,0 +1,102 @@
+// Copyright 2026 TAWF Labs
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! AMANA Common - Helpers shared by the reserve, HAI and DAO programs
+//!
+//! Each program keeps its own `AuditLog` account, `AuditAction` codes and
+//! `AuditEntryEvent`, since accounts and events belong to the program that
+//! owns them. The ring layout and bookkeeping behind them live here.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_error::ProgramError;
+
+/// Number of entries kept on-chain by an audit log ring
+pub const AUDIT_LOG_CAPACITY: usize = 32;
+
+/// Space of an `AuditLog` account: discriminator, `next_sequence`, the
+/// entry ring and `bump`
+pub const AUDIT_LOG_SPACE: usize = 8 + 8 + 4 + AUDIT_LOG_CAPACITY * AuditEntry::SPACE + 1;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub action: u8,             // The owning program's AuditAction code
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+impl AuditEntry {
+    pub const SPACE: usize = 8 + 1 + 32 + 8;
+}
+
+/// Append an entry to an audit log ring and return it for the caller to emit
+///
+/// Holds at most `AUDIT_LOG_CAPACITY` entries; once full, the entry with
+/// sequence `n` overwrites slot `n % AUDIT_LOG_CAPACITY`.
+pub fn push_audit_entry(
+    entries: &mut Vec<AuditEntry>,
+    next_sequence: &mut u64,
+    action: u8,
+    actor: Pubkey,
+) -> Result<AuditEntry> {
+    let entry = AuditEntry {
+        sequence: *next_sequence,
+        action,
+        actor,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    if entries.len() < AUDIT_LOG_CAPACITY {
+        entries.push(entry);
+    } else {
+        entries[(entry.sequence % AUDIT_LOG_CAPACITY as u64) as usize] = entry;
+    }
+    *next_sequence = next_sequence
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    Ok(entry)
+}
+
+/// Advance a creation nonce and return the value for the new account
+pub fn next_nonce(nonce: &mut u64) -> Result<u64> {
+    *nonce = nonce
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(*nonce)
+}
+
+/// Create a PDA of `space` bytes owned by `owner`, funded by `payer`
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+    owner: &Pubkey,
+) -> Result<()> {
+    let lamports = Rent::get()?.minimum_balance(space);
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: target.clone(),
+            },
+            &[signer_seeds],
+        ),
+        lamports,
+        space as u64,
+        owner,
+    )
+}
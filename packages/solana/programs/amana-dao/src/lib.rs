@@ -16,19 +16,37 @@
 //! This program implements governance functionality for the AMANA system,
 //! including proposal creation, voting, and Sharia board oversight.
 
+use amana_common::{next_nonce, push_audit_entry, AuditEntry, AUDIT_LOG_CAPACITY, AUDIT_LOG_SPACE};
+use amana_hai::{Hai, MAX_SCORE_BPS};
+use amana_reserve::program::AmanaReserve;
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
 
 #[program]
 pub mod amana_dao {
     use super::*;
 
     /// Initialize the DAO
+    ///
+    /// `voting_delay` must lie within `MIN_VOTING_DELAY..=MAX_VOTING_DELAY`
+    /// and `voting_period` within `MIN_VOTING_PERIOD..=MAX_VOTING_PERIOD`.
+    /// `quorum_percentage` is in basis points of supply, so at most 10000.
     pub fn initialize(
         ctx: Context<InitializeDao>,
         voting_delay: i64,
         voting_period: i64,
         quorum_percentage: u16,
     ) -> Result<()> {
+        require!(
+            (MIN_VOTING_DELAY..=MAX_VOTING_DELAY).contains(&voting_delay),
+            DaoError::InvalidTiming
+        );
+        require!(
+            (MIN_VOTING_PERIOD..=MAX_VOTING_PERIOD).contains(&voting_period),
+            DaoError::InvalidTiming
+        );
+        require!(quorum_percentage <= 10000, DaoError::InvalidQuorum);
+
         let dao = &mut ctx.accounts.dao;
         dao.admin = ctx.accounts.admin.key();
         dao.token_mint = ctx.accounts.token_mint.key();
@@ -36,7 +54,20 @@ pub mod amana_dao {
         dao.voting_delay = voting_delay;
         dao.voting_period = voting_period;
         dao.quorum_percentage = quorum_percentage;
+        // Flat quorum until the admin configures impact scaling
+        dao.base_quorum_bps = quorum_percentage;
+        dao.max_quorum_bps = quorum_percentage;
+        dao.sharia_classifier = Pubkey::default();
+        dao.strict_sharia_classification = false;
+        dao.execution_window_seconds = 0;
+        dao.sharia_review_grace_seconds = 0;
         dao.proposal_count = 0;
+        dao.creation_nonce = 0;
+        dao.min_hai_for_spending = 0;
+        dao.max_vote_weight_bps = 0;
+        dao.total_supply = 0;
+        dao.supply_refreshed_at = 0;
+        dao.max_supply_age_seconds = DEFAULT_MAX_SUPPLY_AGE;
         dao.bump = ctx.bumps.dao;
 
         emit!(DaoInitializedEvent {
@@ -47,17 +78,33 @@ pub mod amana_dao {
         Ok(())
     }
 
+    /// Create the DAO's audit log of admin actions
+    pub fn initialize_audit_log(ctx: Context<InitializeAuditLog>) -> Result<()> {
+        let audit_log = &mut ctx.accounts.audit_log;
+        audit_log.next_sequence = 0;
+        audit_log.entries = Vec::with_capacity(AUDIT_LOG_CAPACITY);
+        audit_log.bump = ctx.bumps.audit_log;
+        Ok(())
+    }
+
     /// Initialize the Sharia board
     pub fn init_sharia_board(ctx: Context<InitShariaBoard>) -> Result<()> {
         let board = &mut ctx.accounts.sharia_board;
         board.admin = ctx.accounts.admin.key();
         board.member_count = 0;
+        board.required_approvals = 1;
         board.bump = ctx.bumps.sharia_board;
 
         Ok(())
     }
 
     /// Create a new proposal
+    ///
+    /// While `min_hai_for_spending` is set, a spending proposal (non-zero
+    /// `amount`) must pass amana-hai's `Hai` account and is rejected when its
+    /// score is below the floor. Governance proposals are never gated.
+    /// The execution allowlist in force now is copied onto the proposal, so
+    /// later allowlist changes do not affect it.
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
         target_account: Pubkey,
@@ -67,11 +114,20 @@ pub mod amana_dao {
         let dao = &mut ctx.accounts.dao;
         let proposal = &mut ctx.accounts.proposal;
 
+        if amount > 0 && dao.min_hai_for_spending > 0 {
+            let hai = ctx.accounts.hai.as_ref().ok_or(DaoError::InvalidHaiAccount)?;
+            require!(
+                hai_score_bps(hai) >= dao.min_hai_for_spending,
+                DaoError::HaiTooLow
+            );
+        }
+
+        // The id matches the count the proposal PDA was seeded with
+        proposal.proposal_id = dao.proposal_count;
         dao.proposal_count = dao.proposal_count
             .checked_add(1)
             .ok_or(DaoError::MathOverflow)?;
 
-        proposal.proposal_id = dao.proposal_count;
         proposal.proposer = ctx.accounts.proposer.key();
         proposal.target_account = target_account;
         proposal.amount = amount;
@@ -84,6 +140,21 @@ pub mod amana_dao {
         proposal.against_votes = 0;
         proposal.abstain_votes = 0;
         proposal.sharia_approved = !affects_sharia; // Auto-approve if doesn't affect Sharia
+        proposal.quorum_bps = scaled_quorum_bps(dao, amount, ctx.accounts.treasury.lamports())?;
+        proposal.sharia_classified = false;
+        proposal.approval_count = 0;
+        proposal.sharia_reviewed = false;
+
+        // The allowlist exists only once the admin has configured one
+        let allowlist = &ctx.accounts.execution_allowlist;
+        if allowlist.owner == ctx.program_id && !allowlist.data_is_empty() {
+            let allowlist = Account::<ExecutionAllowlist>::try_from(allowlist)?;
+            proposal.allowlist_enforced = true;
+            proposal.allowed_programs = allowlist.programs.clone();
+        } else {
+            proposal.allowlist_enforced = false;
+            proposal.allowed_programs = Vec::new();
+        }
         proposal.bump = ctx.bumps.proposal;
 
         emit!(ProposalCreatedEvent {
@@ -96,12 +167,29 @@ pub mod amana_dao {
     }
 
     /// Cast a vote on a proposal
+    ///
+    /// The weight is the balance of the voter's governance token account,
+    /// which must hold `dao.token_mint` and be owned by the voter. The whole
+    /// balance is moved into a per-vote escrow token account until
+    /// `reclaim_vote_tokens`, so the same tokens cannot be moved to another
+    /// wallet and counted again. While `max_vote_weight_bps` is set, the
+    /// counted weight is capped at that share of the mint's supply.
+    /// Each voter gets one `VoteRecord` per proposal, so a second vote fails
+    /// because the record already exists.
     pub fn vote(
         ctx: Context<Vote>,
         proposal_id: u64,
         vote: VoteType,
-        weight: u64,
     ) -> Result<()> {
+        let balance = ctx.accounts.voter_token_account.amount;
+        require!(balance > 0, DaoError::NoVotingPower);
+
+        let weight = if ctx.accounts.dao.max_vote_weight_bps > 0 {
+            balance.min(max_vote_weight(&ctx.accounts.dao, ctx.accounts.token_mint.supply)?)
+        } else {
+            balance
+        };
+
         let proposal = &mut ctx.accounts.proposal;
         let clock = Clock::get()?;
 
@@ -145,17 +233,89 @@ pub mod amana_dao {
             }
         }
 
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.voter_token_account.to_account_info(),
+                    to: ctx.accounts.vote_escrow.to_account_info(),
+                    authority: ctx.accounts.voter.to_account_info(),
+                },
+            ),
+            balance,
+        )?;
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.proposal_id = proposal.proposal_id;
+        vote_record.voter = ctx.accounts.voter.key();
+        vote_record.vote = vote;
+        vote_record.weight = weight;
+        vote_record.voted_at = clock.unix_timestamp;
+        vote_record.bump = ctx.bumps.vote_record;
+
         emit!(VoteCastEvent {
             proposal_id,
             voter: ctx.accounts.voter.key(),
             vote: vote as u8,
             weight,
+            balance,
+        });
+
+        Ok(())
+    }
+
+    /// Return a voter's escrowed governance tokens
+    ///
+    /// Allowed once voting has ended or the proposal was canceled, so the
+    /// escrowed tokens cannot be counted on the proposal twice. The escrow
+    /// token account is closed and its rent returned to the voter.
+    pub fn reclaim_vote_tokens(ctx: Context<ReclaimVoteTokens>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            Clock::get()?.unix_timestamp > proposal.voting_ends_at
+                || proposal.status == ProposalStatus::Canceled,
+            DaoError::VoteTokensLocked
+        );
+
+        let dao_seeds: &[&[u8]] = &[b"dao", &[ctx.accounts.dao.bump]];
+        let amount = ctx.accounts.vote_escrow.amount;
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vote_escrow.to_account_info(),
+                    to: ctx.accounts.voter_token_account.to_account_info(),
+                    authority: ctx.accounts.dao.to_account_info(),
+                },
+                &[dao_seeds],
+            ),
+            amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vote_escrow.to_account_info(),
+                destination: ctx.accounts.voter.to_account_info(),
+                authority: ctx.accounts.dao.to_account_info(),
+            },
+            &[dao_seeds],
+        ))?;
+
+        emit!(VoteTokensReclaimedEvent {
+            proposal_id: proposal.proposal_id,
+            voter: ctx.accounts.voter.key(),
+            amount,
         });
 
         Ok(())
     }
 
     /// Sharia board review of a proposal
+    ///
+    /// The signing `board_member` must hold an active `ShariaBoardMember`
+    /// record created by `add_sharia_board_member`. Approvals are counted and
+    /// the proposal only becomes `sharia_approved` once `required_approvals`
+    /// members have approved; a rejection is recorded but not counted.
     pub fn sharia_review(
         ctx: Context<ShariaReview>,
         approved: bool,
@@ -168,24 +328,187 @@ pub mod amana_dao {
             DaoError::NotShariaRelevant
         );
 
-        proposal.sharia_approved = approved;
+        // Reviewing fixes the classification, so in strict mode it must come first
+        if ctx.accounts.dao.strict_sharia_classification {
+            require!(
+                proposal.sharia_classified,
+                DaoError::ShariaClassificationPending
+            );
+        }
+        proposal.sharia_reviewed = true;
+
+        if approved {
+            proposal.approval_count = proposal.approval_count
+                .checked_add(1)
+                .ok_or(DaoError::MathOverflow)?;
+
+            let record = &mut ctx.accounts.board_member_record;
+            record.open_approvals = record.open_approvals
+                .checked_add(1)
+                .ok_or(DaoError::MathOverflow)?;
+
+            let required_approvals = ctx.accounts.sharia_board.required_approvals;
+            if !proposal.sharia_approved && proposal.approval_count >= required_approvals {
+                proposal.sharia_approved = true;
+
+                emit!(ShariaApprovalThresholdMetEvent {
+                    proposal_id: proposal.proposal_id,
+                    approval_count: proposal.approval_count,
+                    required_approvals,
+                });
+            }
+        }
 
         review.proposal_id = proposal.proposal_id;
         review.board_member = ctx.accounts.board_member.key();
         review.approved = approved;
         review.timestamp = Clock::get()?.unix_timestamp;
+        review.creation_nonce = next_nonce(&mut ctx.accounts.dao.creation_nonce)?;
         review.bump = ctx.bumps.review;
 
         emit!(ShariaReviewEvent {
             proposal_id: proposal.proposal_id,
             board_member: review.board_member,
             approved,
+            creation_nonce: review.creation_nonce,
+        });
+
+        Ok(())
+    }
+
+    /// Authoritatively classify a proposal's Sharia relevance
+    ///
+    /// Run by the Sharia board admin or the DAO's designated classifier. The
+    /// proposal's `target_account` is looked up in the registry of
+    /// Sharia-sensitive targets; the result overrides the proposer's
+    /// `affects_sharia` claim. Newly relevant proposals lose any automatic
+    /// Sharia approval and must go through board review. The classification
+    /// is final once any board member has reviewed the proposal, so it can
+    /// never undo a board rejection.
+    pub fn classify_proposal_sharia(
+        ctx: Context<ClassifyProposalSharia>,
+        proposal_id: u64,
+    ) -> Result<()> {
+        let dao = &ctx.accounts.dao;
+        let classifier = ctx.accounts.classifier.key();
+        require!(
+            classifier == ctx.accounts.sharia_board.admin
+                || (dao.sharia_classifier != Pubkey::default()
+                    && classifier == dao.sharia_classifier),
+            DaoError::Unauthorized
+        );
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Pending ||
+            proposal.status == ProposalStatus::Active,
+            DaoError::InvalidProposalStatus
+        );
+        require!(!proposal.sharia_reviewed, DaoError::ShariaAlreadyReviewed);
+
+        // The registry entry exists only for sensitive targets
+        let entry = &ctx.accounts.sensitive_target;
+        let affects_sharia = entry.owner == ctx.program_id && !entry.data_is_empty();
+
+        let proposer_claim = proposal.affects_sharia;
+        if affects_sharia != proposer_claim {
+            proposal.affects_sharia = affects_sharia;
+            proposal.sharia_approved = !affects_sharia;
+        }
+        proposal.sharia_classified = true;
+
+        emit!(ProposalShariaClassifiedEvent {
+            proposal_id,
+            classifier,
+            proposer_claim,
+            affects_sharia,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::ClassifyProposalSharia, classifier)?;
+
+        Ok(())
+    }
+
+    /// Register a target account as Sharia-sensitive
+    pub fn add_sensitive_target(ctx: Context<AddSensitiveTarget>, target: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.sensitive_target;
+        entry.target = target;
+        entry.added_by = ctx.accounts.admin.key();
+        entry.bump = ctx.bumps.sensitive_target;
+
+        emit!(SensitiveTargetUpdatedEvent {
+            target,
+            sensitive: true,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::AddSensitiveTarget, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Remove a target account from the Sharia-sensitive registry
+    pub fn remove_sensitive_target(ctx: Context<RemoveSensitiveTarget>) -> Result<()> {
+        emit!(SensitiveTargetUpdatedEvent {
+            target: ctx.accounts.sensitive_target.target,
+            sensitive: false,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::RemoveSensitiveTarget, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Replace the set of programs proposals may invoke at execution
+    ///
+    /// Creates the allowlist on first use. Proposals created from then on
+    /// copy it and may only invoke listed programs; an empty list denies
+    /// every invocation. Without an allowlist every program is allowed.
+    pub fn set_execution_allowlist(
+        ctx: Context<SetExecutionAllowlist>,
+        programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            programs.len() <= MAX_ALLOWLISTED_PROGRAMS,
+            DaoError::AllowlistFull
+        );
+
+        let allowlist = &mut ctx.accounts.execution_allowlist;
+        allowlist.programs = programs.clone();
+        allowlist.bump = ctx.bumps.execution_allowlist;
+
+        emit!(ExecutionAllowlistUpdatedEvent {
+            programs,
+            enforced: true,
         });
 
+        ctx.accounts.audit_log.record(AuditAction::SetExecutionAllowlist, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Close the execution allowlist, allowing every program for new proposals
+    pub fn clear_execution_allowlist(ctx: Context<ClearExecutionAllowlist>) -> Result<()> {
+        emit!(ExecutionAllowlistUpdatedEvent {
+            programs: Vec::new(),
+            enforced: false,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::ClearExecutionAllowlist, ctx.accounts.admin.key())?;
+
         Ok(())
     }
 
     /// Execute a successful proposal
+    ///
+    /// When `execution_window_seconds` is set, a proposal expires that long
+    /// after voting ends. Sharia-affecting proposals get a further
+    /// `sharia_review_grace_seconds` so a late board review does not strand them.
+    /// A spending proposal pays its `amount` in lamports from the treasury PDA
+    /// to `target_account` through the System Program; a governance proposal
+    /// (`amount` 0) moves nothing and invokes no program. If the proposal was
+    /// created under an execution allowlist, every program it invokes must be
+    /// on that copy of the list; one created without an allowlist may invoke
+    /// any program.
     pub fn execute_proposal(
         ctx: Context<ExecuteProposal>,
     ) -> Result<()> {
@@ -198,12 +521,35 @@ pub mod amana_dao {
             DaoError::InvalidProposalStatus
         );
 
+        // The payout's transfer is the only invocation
+        if proposal.amount > 0 && proposal.allowlist_enforced {
+            require!(
+                proposal.allowed_programs.contains(&ctx.accounts.system_program.key()),
+                DaoError::TargetNotAllowed
+            );
+        }
+
         // Check if voting has ended
         require!(
             clock.unix_timestamp > proposal.voting_ends_at,
             DaoError::VotingNotEnded
         );
 
+        if dao.execution_window_seconds > 0 {
+            require!(
+                clock.unix_timestamp <= execution_deadline(dao, proposal)?,
+                DaoError::ProposalExpired
+            );
+        }
+
+        // In strict mode the proposer's affects_sharia claim is never trusted
+        if dao.strict_sharia_classification {
+            require!(
+                proposal.sharia_classified,
+                DaoError::ShariaClassificationPending
+            );
+        }
+
         // Check Sharia approval if applicable
         if proposal.affects_sharia {
             require!(
@@ -218,31 +564,272 @@ pub mod amana_dao {
             .and_then(|v| v.checked_add(proposal.abstain_votes))
             .ok_or(DaoError::MathOverflow)?;
 
-        // Simple quorum check (would need actual supply in production)
         if total_votes == 0 {
             return Err(DaoError::QuorumNotMet.into());
         }
 
+        // Quorum is a share of the cached governance token supply, which must be fresh
+        let supply_age = clock.unix_timestamp
+            .checked_sub(dao.supply_refreshed_at)
+            .ok_or(DaoError::MathOverflow)?;
+        require!(
+            supply_age <= dao.max_supply_age_seconds,
+            DaoError::StaleSupply
+        );
+        let required_votes = (dao.total_supply as u128)
+            .checked_mul(proposal.quorum_bps as u128)
+            .ok_or(DaoError::MathOverflow)?
+            / 10000;
+        require!(
+            total_votes as u128 >= required_votes,
+            DaoError::QuorumNotMet
+        );
+
         // Check if passed (more for than against)
         require!(
             proposal.for_votes > proposal.against_votes,
             DaoError::ProposalFailed
         );
 
+        // Marked executed before paying out, so the proposal can never run twice
         proposal.status = ProposalStatus::Executed;
 
+        if proposal.amount > 0 {
+            require!(
+                ctx.accounts.treasury.lamports() >= proposal.amount,
+                DaoError::InsufficientTreasury
+            );
+            let treasury_seeds: &[&[u8]] = &[b"treasury", &[ctx.bumps.treasury]];
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.treasury.to_account_info(),
+                        to: ctx.accounts.recipient.to_account_info(),
+                    },
+                    &[treasury_seeds],
+                ),
+                proposal.amount,
+            )?;
+        }
+
         emit!(ProposalExecutedEvent {
             proposal_id: proposal.proposal_id,
+            recipient: proposal.target_account,
+            amount: proposal.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Apply an executed capacity proposal to a governed amana-reserve reserve
+    ///
+    /// The proposal must target `amana_reserve::capacity_commitment` of the new
+    /// caps. The DAO PDA then signs a CPI into the reserve's `set_capacity`,
+    /// which is how a governed reserve recognises DAO approval. The reserve
+    /// admin still signs, and the reserve refuses proposal ids it has already
+    /// consumed.
+    pub fn apply_capacity_proposal(
+        ctx: Context<ApplyCapacityProposal>,
+        proposal_id: u64,
+        max_participants: u64,
+        max_total_capital: u64,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Executed,
+            DaoError::InvalidProposalStatus
+        );
+        require_keys_eq!(
+            proposal.target_account,
+            amana_reserve::capacity_commitment(max_participants, max_total_capital),
+            DaoError::CapacityNotApproved
+        );
+
+        let dao_seeds: &[&[u8]] = &[b"dao", &[ctx.accounts.dao.bump]];
+        amana_reserve::cpi::set_capacity(
+            CpiContext::new_with_signer(
+                ctx.accounts.amana_reserve_program.to_account_info(),
+                amana_reserve::cpi::accounts::SetCapacity {
+                    reserve: ctx.accounts.reserve.to_account_info(),
+                    audit_log: ctx.accounts.audit_log.to_account_info(),
+                    dao: Some(ctx.accounts.dao.to_account_info()),
+                    admin: ctx.accounts.admin.to_account_info(),
+                },
+                &[dao_seeds],
+            ),
+            max_participants,
+            max_total_capital,
+            Some(proposal_id),
+        )
+    }
+
+    /// Set the quorum bounds proposals interpolate between by amount
+    pub fn set_quorum_bounds(
+        ctx: Context<UpdateDaoConfig>,
+        base_quorum_bps: u16,
+        max_quorum_bps: u16,
+    ) -> Result<()> {
+        require!(
+            base_quorum_bps <= max_quorum_bps && max_quorum_bps <= 10000,
+            DaoError::InvalidQuorumBounds
+        );
+
+        let dao = &mut ctx.accounts.dao;
+        dao.base_quorum_bps = base_quorum_bps;
+        dao.max_quorum_bps = max_quorum_bps;
+
+        emit!(QuorumBoundsUpdatedEvent {
+            base_quorum_bps,
+            max_quorum_bps,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetQuorumBounds, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the Sharia classifier and whether execution requires classification
+    pub fn set_sharia_classification(
+        ctx: Context<UpdateDaoConfig>,
+        sharia_classifier: Pubkey,
+        strict: bool,
+    ) -> Result<()> {
+        let dao = &mut ctx.accounts.dao;
+        dao.sharia_classifier = sharia_classifier;
+        dao.strict_sharia_classification = strict;
+
+        emit!(ShariaClassificationUpdatedEvent {
+            sharia_classifier,
+            strict,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetShariaClassification, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set how long passed proposals stay executable after voting ends
+    ///
+    /// `execution_window_seconds` of 0 disables expiry entirely, in which case
+    /// the Sharia review grace has no effect.
+    pub fn set_execution_window(
+        ctx: Context<UpdateDaoConfig>,
+        execution_window_seconds: i64,
+        sharia_review_grace_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            execution_window_seconds >= 0 && sharia_review_grace_seconds >= 0,
+            DaoError::InvalidExecutionWindow
+        );
+
+        let dao = &mut ctx.accounts.dao;
+        dao.execution_window_seconds = execution_window_seconds;
+        dao.sharia_review_grace_seconds = sharia_review_grace_seconds;
+
+        emit!(ExecutionWindowUpdatedEvent {
+            execution_window_seconds,
+            sharia_review_grace_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetExecutionWindow, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Cap any single vote at `max_vote_weight_bps` of the token supply
+    ///
+    /// 0 disables the cap.
+    pub fn set_max_vote_weight(
+        ctx: Context<UpdateDaoConfig>,
+        max_vote_weight_bps: u16,
+    ) -> Result<()> {
+        require!(max_vote_weight_bps <= 10000, DaoError::InvalidVoteWeightCap);
+
+        ctx.accounts.dao.max_vote_weight_bps = max_vote_weight_bps;
+
+        emit!(MaxVoteWeightUpdatedEvent { max_vote_weight_bps });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMaxVoteWeight, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the HAI score below which spending proposals cannot be created
+    ///
+    /// 0 disables the gate.
+    pub fn set_min_hai_for_spending(
+        ctx: Context<UpdateDaoConfig>,
+        min_hai_for_spending: u16,
+    ) -> Result<()> {
+        require!(min_hai_for_spending <= 10000, DaoError::InvalidHaiThreshold);
+
+        ctx.accounts.dao.min_hai_for_spending = min_hai_for_spending;
+
+        emit!(MinHaiForSpendingUpdatedEvent { min_hai_for_spending });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMinHaiForSpending, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Cache the governance token supply for quorum checks
+    ///
+    /// Anyone may call it. `execute_proposal` uses the cached `total_supply`
+    /// and fails with `StaleSupply` once it is older than
+    /// `max_supply_age_seconds`.
+    pub fn refresh_supply(ctx: Context<RefreshSupply>) -> Result<()> {
+        let total_supply = ctx.accounts.token_mint.supply;
+        let refreshed_at = Clock::get()?.unix_timestamp;
+
+        let dao = &mut ctx.accounts.dao;
+        dao.total_supply = total_supply;
+        dao.supply_refreshed_at = refreshed_at;
+
+        emit!(SupplyRefreshedEvent {
+            total_supply,
+            refreshed_at,
         });
 
         Ok(())
     }
 
+    /// Set how old the cached token supply may be at execution
+    pub fn set_max_supply_age(
+        ctx: Context<UpdateDaoConfig>,
+        max_supply_age_seconds: i64,
+    ) -> Result<()> {
+        require!(max_supply_age_seconds > 0, DaoError::InvalidTiming);
+
+        ctx.accounts.dao.max_supply_age_seconds = max_supply_age_seconds;
+
+        emit!(MaxSupplyAgeUpdatedEvent { max_supply_age_seconds });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMaxSupplyAge, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
     /// Cancel a proposal (admin or Sharia board veto)
+    ///
+    /// The signer must be the DAO admin, the proposal's proposer, or an active
+    /// Sharia board member passing their `ShariaBoardMember` record.
+    /// `reason_code` is passed through to `ProposalCanceledEvent` for indexers.
     pub fn cancel_proposal(
         ctx: Context<CancelProposal>,
+        reason_code: u8,
     ) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let is_board_member = ctx.accounts.board_member_record
+            .as_ref()
+            .map_or(false, |record| record.is_active);
         let proposal = &mut ctx.accounts.proposal;
+        require!(
+            authority == ctx.accounts.dao.admin
+                || authority == proposal.proposer
+                || is_board_member,
+            DaoError::Unauthorized
+        );
 
         require!(
             proposal.status == ProposalStatus::Pending ||
@@ -254,14 +841,20 @@ pub mod amana_dao {
 
         emit!(ProposalCanceledEvent {
             proposal_id: proposal.proposal_id,
+            canceled_by: authority,
+            reason_code,
         });
 
         Ok(())
     }
 
     /// Add a Sharia board member
+    ///
+    /// Creates the member's `ShariaBoardMember` PDA, which `sharia_review`
+    /// requires of the reviewing signer.
     pub fn add_sharia_board_member(
-        ctx: Context<ModifyShariaBoard>,
+        ctx: Context<AddShariaBoardMember>,
+        member: Pubkey,
     ) -> Result<()> {
         let board = &mut ctx.accounts.sharia_board;
 
@@ -269,33 +862,244 @@ pub mod amana_dao {
             .checked_add(1)
             .ok_or(DaoError::MathOverflow)?;
 
-        emit!(ShariaBoardMemberAddedEvent {
-            member: ctx.accounts.admin.key(),
-        });
+        let record = &mut ctx.accounts.board_member;
+        record.member = member;
+        record.is_active = true;
+        record.open_approvals = 0;
+        record.bump = ctx.bumps.board_member;
+
+        emit!(ShariaBoardMemberAddedEvent { member });
+
+        ctx.accounts.audit_log.record(AuditAction::AddShariaBoardMember, ctx.accounts.admin.key())?;
 
         Ok(())
     }
 
-    /// Remove a Sharia board member
-    pub fn remove_sharia_board_member(
-        ctx: Context<ModifyShariaBoard>,
+    /// Remove a Sharia board member, closing their `ShariaBoardMember` PDA
+    ///
+    /// Refused if fewer than `required_approvals` members would remain, as
+    /// no proposal could then be approved. The member's open approvals are
+    /// dropped: every approving review still counted in `open_approvals` is
+    /// passed writable via `remaining_accounts` as a (proposal, review) pair
+    /// and closed to the member. A proposal not yet executed or canceled
+    /// loses the approval, and its `sharia_approved` once below the threshold.
+    pub fn remove_sharia_board_member<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveShariaBoardMember<'info>>,
     ) -> Result<()> {
         let board = &mut ctx.accounts.sharia_board;
 
-        board.member_count = board.member_count
+        let member_count = board.member_count
             .checked_sub(1)
             .ok_or(DaoError::MathOverflow)?;
+        require!(
+            member_count >= board.required_approvals as u32,
+            DaoError::BoardBelowThreshold
+        );
+        board.member_count = member_count;
+        let required_approvals = board.required_approvals;
+
+        let member = ctx.accounts.board_member.member;
+        let mut open_approvals = ctx.accounts.board_member.open_approvals;
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0,
+            DaoError::InvalidRemainingAccount
+        );
+
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (proposal_info, review_info) = (&pair[0], &pair[1]);
+            require!(
+                proposal_info.is_writable && review_info.is_writable,
+                DaoError::InvalidRemainingAccount
+            );
+
+            let review = Account::<ShariaReview>::try_from(review_info)?;
+            let proposal_id = review.proposal_id.to_le_bytes();
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"review", proposal_id.as_ref(), member.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(review_info.key(), expected, DaoError::InvalidRemainingAccount);
+            require!(review.approved, DaoError::InvalidRemainingAccount);
+
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"proposal", proposal_id.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(proposal_info.key(), expected, DaoError::InvalidRemainingAccount);
+
+            let mut proposal = Account::<Proposal>::try_from(proposal_info)?;
+            if proposal.status != ProposalStatus::Executed
+                && proposal.status != ProposalStatus::Canceled
+            {
+                proposal.approval_count = proposal.approval_count
+                    .checked_sub(1)
+                    .ok_or(DaoError::MathOverflow)?;
+                if proposal.approval_count < required_approvals {
+                    proposal.sharia_approved = false;
+                }
+                proposal.exit(ctx.program_id)?;
+            }
+
+            open_approvals = open_approvals
+                .checked_sub(1)
+                .ok_or(DaoError::MathOverflow)?;
+            review.close(ctx.accounts.member.to_account_info())?;
+        }
+        require!(open_approvals == 0, DaoError::OpenApprovalsRemain);
 
         emit!(ShariaBoardMemberRemovedEvent {
-            member: ctx.accounts.admin.key(),
+            member: ctx.accounts.board_member.member,
         });
 
+        ctx.accounts.audit_log.record(AuditAction::RemoveShariaBoardMember, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Close the signer's review once its proposal is executed or canceled
+    ///
+    /// Refunds the review's rent and releases an approval from the member's
+    /// `open_approvals`, so it no longer has to be passed on removal.
+    pub fn close_sharia_review(ctx: Context<CloseShariaReview>) -> Result<()> {
+        let status = ctx.accounts.proposal.status;
+        require!(
+            status == ProposalStatus::Executed || status == ProposalStatus::Canceled,
+            DaoError::ReviewStillOpen
+        );
+
+        if ctx.accounts.review.approved {
+            let record = &mut ctx.accounts.board_member_record;
+            record.open_approvals = record.open_approvals
+                .checked_sub(1)
+                .ok_or(DaoError::MathOverflow)?;
+        }
+
+        Ok(())
+    }
+
+    /// Set how many board approvals a Sharia-relevant proposal needs
+    ///
+    /// Must be at least 1 and at most the current `member_count`. Proposals
+    /// already reviewed keep their approval state.
+    pub fn set_required_approvals(
+        ctx: Context<SetRequiredApprovals>,
+        required_approvals: u8,
+    ) -> Result<()> {
+        let board = &mut ctx.accounts.sharia_board;
+        require!(
+            required_approvals > 0 && required_approvals as u32 <= board.member_count,
+            DaoError::InvalidApprovalThreshold
+        );
+
+        board.required_approvals = required_approvals;
+
+        emit!(RequiredApprovalsUpdatedEvent { required_approvals });
+
+        ctx.accounts.audit_log.record(AuditAction::SetRequiredApprovals, ctx.accounts.admin.key())?;
+
         Ok(())
     }
 }
 
+/// Maximum number of programs the execution allowlist may hold
+pub const MAX_ALLOWLISTED_PROGRAMS: usize = 16;
+
+/// Shortest and longest delay between proposal creation and the start of
+/// voting (1 second to 7 days)
+pub const MIN_VOTING_DELAY: i64 = 1;
+pub const MAX_VOTING_DELAY: i64 = 7 * 24 * 60 * 60;
+
+/// Shortest and longest voting period (1 second to 30 days)
+pub const MIN_VOTING_PERIOD: i64 = 1;
+pub const MAX_VOTING_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+/// Initial limit on the age of the cached token supply at execution (1 hour)
+pub const DEFAULT_MAX_SUPPLY_AGE: i64 = 60 * 60;
+
+/// Quorum for a proposal moving `amount` out of a treasury holding `treasury`
+///
+/// Interpolates linearly from `base_quorum_bps` for a zero-amount proposal to
+/// `max_quorum_bps` for one spending the whole treasury:
+///
+///   quorum = base + (max - base) * min(amount, treasury) / treasury
+///
+/// An empty treasury yields `max_quorum_bps`.
+fn scaled_quorum_bps(dao: &Dao, amount: u64, treasury: u64) -> Result<u16> {
+    if treasury == 0 {
+        return Ok(dao.max_quorum_bps);
+    }
+
+    let span = (dao.max_quorum_bps - dao.base_quorum_bps) as u128;
+    let scaled = span
+        .checked_mul(amount.min(treasury) as u128)
+        .ok_or(DaoError::MathOverflow)?
+        / treasury as u128;
+    Ok(dao.base_quorum_bps + scaled as u16)
+}
+
+/// Largest weight a single vote counts for under `max_vote_weight_bps`
+///
+/// Voting power is a trust held for the whole community (amanah), so no
+/// single holder's balance should be able to decide a proposal on its own.
+fn max_vote_weight(dao: &Dao, supply: u64) -> Result<u64> {
+    let cap = (supply as u128)
+        .checked_mul(dao.max_vote_weight_bps as u128)
+        .ok_or(DaoError::MathOverflow)?
+        / 10000;
+    Ok(cap as u64)
+}
+
+/// Last moment a passed proposal may be executed
+fn execution_deadline(dao: &Dao, proposal: &Proposal) -> Result<i64> {
+    let mut deadline = proposal.voting_ends_at
+        .checked_add(dao.execution_window_seconds)
+        .ok_or(DaoError::MathOverflow)?;
+    if proposal.affects_sharia {
+        deadline = deadline
+            .checked_add(dao.sharia_review_grace_seconds)
+            .ok_or(DaoError::MathOverflow)?;
+    }
+    Ok(deadline)
+}
+
+/// `current_score` of amana-hai's `Hai` account, in basis points
+///
+/// Scores kept in parts per million are converted down to basis points.
+fn hai_score_bps(hai: &Hai) -> u16 {
+    let bps = hai.current_score as u64 * MAX_SCORE_BPS as u64
+        / hai.precision_mode.max_score() as u64;
+    bps.min(MAX_SCORE_BPS as u64) as u16
+}
+
 // Account structs
 
+/// Bounded ring of the most recent DAO admin actions; every entry is
+/// also emitted as an `AuditEntryEvent`, which is the complete history
+#[account]
+pub struct AuditLog {
+    pub next_sequence: u64,
+    pub entries: Vec<AuditEntry>,
+    pub bump: u8,
+}
+
+impl AuditLog {
+    pub const SPACE: usize = AUDIT_LOG_SPACE;
+
+    /// Append an entry, overwriting the oldest one once the ring is full
+    pub fn record(&mut self, action: AuditAction, actor: Pubkey) -> Result<()> {
+        let entry = push_audit_entry(&mut self.entries, &mut self.next_sequence, action as u8, actor)?;
+
+        emit!(AuditEntryEvent {
+            sequence: entry.sequence,
+            action: entry.action,
+            actor,
+            timestamp: entry.timestamp,
+        });
+
+        Ok(())
+    }
+}
+
 #[account]
 pub struct Dao {
     pub admin: Pubkey,
@@ -303,8 +1107,20 @@ pub struct Dao {
     pub timelock: Pubkey,
     pub voting_delay: i64,
     pub voting_period: i64,
-    pub quorum_percentage: u16,
+    pub quorum_percentage: u16,    // Initial quorum in basis points of supply
+    pub base_quorum_bps: u16,      // Quorum for a zero-amount proposal
+    pub max_quorum_bps: u16,       // Quorum for a proposal spending the whole treasury
+    pub sharia_classifier: Pubkey, // May classify proposals alongside the board admin
+    pub strict_sharia_classification: bool, // Execution requires classification
+    pub execution_window_seconds: i64,      // Expiry after voting ends (0 = never)
+    pub sharia_review_grace_seconds: i64,   // Extra window for Sharia-affecting proposals
     pub proposal_count: u64,
+    pub creation_nonce: u64,       // Last nonce stamped onto a Sharia review
+    pub min_hai_for_spending: u16, // HAI score spending proposals require (0 = no gate)
+    pub max_vote_weight_bps: u16,  // Cap on one vote as a share of supply (0 = uncapped)
+    pub total_supply: u64,         // Cached governance token supply used for quorum
+    pub supply_refreshed_at: i64,
+    pub max_supply_age_seconds: i64, // Oldest cached supply execute_proposal accepts
     pub bump: u8,
 }
 
@@ -323,6 +1139,12 @@ pub struct Proposal {
     pub against_votes: u64,
     pub abstain_votes: u64,
     pub sharia_approved: bool,
+    pub quorum_bps: u16,           // Snapshot of the scaled quorum at creation
+    pub sharia_classified: bool,   // affects_sharia set by classify_proposal_sharia
+    pub approval_count: u8,        // Board members who approved in sharia_review
+    pub sharia_reviewed: bool,     // A board member has reviewed; classification is final
+    pub allowlist_enforced: bool,  // An execution allowlist existed at creation
+    pub allowed_programs: Vec<Pubkey>, // Copy of that allowlist
     pub bump: u8,
 }
 
@@ -332,24 +1154,72 @@ pub struct ShariaReview {
     pub board_member: Pubkey,
     pub approved: bool,
     pub timestamp: i64,
+    pub creation_nonce: u64,    // Distinguishes a recreated review under the same seeds
     pub bump: u8,
 }
 
+/// A voter's ballot on one proposal; its existence blocks a second vote
 #[account]
-pub struct ShariaBoard {
-    pub admin: Pubkey,
-    pub member_count: u32,
+pub struct VoteRecord {
+    pub proposal_id: u64,
+    pub voter: Pubkey,
+    pub vote: VoteType,
+    pub weight: u64,            // Counted weight, after any cap
+    pub voted_at: i64,
     pub bump: u8,
 }
 
-// Context structs
+impl VoteRecord {
+    pub const SPACE: usize = 8 + 8 + 32 + 1 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct SensitiveTarget {
+    pub target: Pubkey,
+    pub added_by: Pubkey,
+    pub bump: u8,
+}
+
+/// Programs proposals may invoke at execution; absent means any program
+#[account]
+pub struct ExecutionAllowlist {
+    pub programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl ExecutionAllowlist {
+    pub const SPACE: usize = 8 + 4 + MAX_ALLOWLISTED_PROGRAMS * 32 + 1;
+}
+
+#[account]
+pub struct ShariaBoard {
+    pub admin: Pubkey,
+    pub member_count: u32,
+    pub required_approvals: u8,    // Member approvals a proposal needs to become sharia_approved
+    pub bump: u8,
+}
+
+/// Membership record of one Sharia board member; closed on removal
+#[account]
+pub struct ShariaBoardMember {
+    pub member: Pubkey,
+    pub is_active: bool,
+    pub open_approvals: u32,    // Approving reviews not yet closed or dropped
+    pub bump: u8,
+}
+
+impl ShariaBoardMember {
+    pub const SPACE: usize = 8 + 32 + 1 + 4 + 1;
+}
+
+// Context structs
 
 #[derive(Accounts)]
 pub struct InitializeDao<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 32 + 32 + 8 + 8 + 2 + 8 + 1,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 2 + 2 + 2 + 32 + 1 + 8 + 8 + 8 + 8 + 2 + 2 + 8 + 8 + 8 + 1,
         seeds = [b"dao"],
         bump
     )]
@@ -366,12 +1236,35 @@ pub struct InitializeDao<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeAuditLog<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump,
+        constraint = dao.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AuditLog::SPACE,
+        seeds = [b"audit_log"],
+        bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct InitShariaBoard<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 4 + 1,
+        space = 8 + 32 + 4 + 1 + 1,
         seeds = [b"sharia_board"],
         bump
     )]
@@ -394,12 +1287,36 @@ pub struct CreateProposal<'info> {
     #[account(
         init,
         payer = proposer,
-        space = 8 + 8 + 32 + 32 + 8 + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1,
+        space = 8 + 8 + 32 + 32 + 8 + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 2 + 1 + 1
+            + 1 + 1 + 4 + MAX_ALLOWLISTED_PROGRAMS * 32 + 1,
         seeds = [b"proposal", dao.proposal_count.to_le_bytes().as_ref()],
         bump
     )]
     pub proposal: Account<'info, Proposal>,
 
+    /// DAO treasury; its balance scales the proposal's quorum
+    #[account(
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// amana-hai's `Hai` PDA. Required for spending proposals while
+    /// `min_hai_for_spending` is set.
+    #[account(
+        seeds = [b"hai"],
+        bump,
+        seeds::program = amana_hai::ID
+    )]
+    pub hai: Option<Account<'info, Hai>>,
+
+    /// CHECK: Execution allowlist PDA; may not exist
+    #[account(
+        seeds = [b"execution_allowlist"],
+        bump
+    )]
+    pub execution_allowlist: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub proposer: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -420,11 +1337,86 @@ pub struct Vote<'info> {
     )]
     pub proposal: Account<'info, Proposal>,
 
+    /// Governance token mint; its supply caps a vote while `max_vote_weight_bps` is set
+    #[account(address = dao.token_mint @ DaoError::InvalidTokenMint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// Voter's governance token account; its whole balance is escrowed
+    #[account(
+        mut,
+        constraint = voter_token_account.mint == dao.token_mint @ DaoError::InvalidTokenAccount,
+        constraint = voter_token_account.owner == voter.key() @ DaoError::InvalidTokenAccount
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    /// Holds the voted tokens until `reclaim_vote_tokens`
+    #[account(
+        init,
+        payer = voter,
+        token::mint = token_mint,
+        token::authority = dao,
+        seeds = [b"vote_escrow", proposal.proposal_id.to_le_bytes().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::SPACE,
+        seeds = [b"vote", proposal.proposal_id.to_le_bytes().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimVoteTokens<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"vote_escrow", proposal.proposal_id.to_le_bytes().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = voter_token_account.mint == dao.token_mint @ DaoError::InvalidTokenAccount,
+        constraint = voter_token_account.owner == voter.key() @ DaoError::InvalidTokenAccount
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
     pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 pub struct ShariaReview<'info> {
+    #[account(
+        mut,
+        seeds = [b"dao"],
+        bump = dao.bump
+    )]
+    pub dao: Account<'info, Dao>,
+
     #[account(
         mut,
         seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
@@ -432,19 +1424,132 @@ pub struct ShariaReview<'info> {
     )]
     pub proposal: Account<'info, Proposal>,
 
+    #[account(
+        seeds = [b"sharia_board"],
+        bump = sharia_board.bump
+    )]
+    pub sharia_board: Account<'info, ShariaBoard>,
+
     #[account(
         init,
         payer = board_member,
-        space = 8 + 8 + 32 + 1 + 8 + 1,
+        space = 8 + 8 + 32 + 1 + 8 + 8 + 1,
         seeds = [b"review", proposal.proposal_id.to_le_bytes().as_ref(), board_member.key().as_ref()],
         bump
     )]
     pub review: Account<'info, ShariaReview>,
 
+    /// Membership record of the reviewing signer
+    #[account(
+        mut,
+        seeds = [b"sharia_member", board_member.key().as_ref()],
+        bump = board_member_record.bump,
+        constraint = board_member_record.is_active @ DaoError::NotBoardMember
+    )]
+    pub board_member_record: Account<'info, ShariaBoardMember>,
+
     pub board_member: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct ClassifyProposalSharia<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        seeds = [b"sharia_board"],
+        bump = sharia_board.bump
+    )]
+    pub sharia_board: Account<'info, ShariaBoard>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: Registry PDA for the proposal's target; may not exist
+    #[account(
+        seeds = [b"sensitive_target", proposal.target_account.as_ref()],
+        bump
+    )]
+    pub sensitive_target: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub classifier: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(target: Pubkey)]
+pub struct AddSensitiveTarget<'info> {
+    #[account(
+        seeds = [b"sharia_board"],
+        bump = sharia_board.bump,
+        constraint = sharia_board.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub sharia_board: Account<'info, ShariaBoard>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 1,
+        seeds = [b"sensitive_target", target.as_ref()],
+        bump
+    )]
+    pub sensitive_target: Account<'info, SensitiveTarget>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveSensitiveTarget<'info> {
+    #[account(
+        seeds = [b"sharia_board"],
+        bump = sharia_board.bump,
+        constraint = sharia_board.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub sharia_board: Account<'info, ShariaBoard>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"sensitive_target", sensitive_target.target.as_ref()],
+        bump = sensitive_target.bump
+    )]
+    pub sensitive_target: Account<'info, SensitiveTarget>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ExecuteProposal<'info> {
     #[account(
@@ -459,21 +1564,174 @@ pub struct ExecuteProposal<'info> {
         bump = proposal.bump
     )]
     pub proposal: Account<'info, Proposal>,
+
+    /// DAO treasury; pays out spending proposals
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Only receives lamports; must be the proposal's target
+    #[account(
+        mut,
+        address = proposal.target_account @ DaoError::InvalidRecipient
+    )]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct ApplyCapacityProposal<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        seeds = [b"proposal", proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: Reserve being resized, validated by amana-reserve
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECK: The reserve's audit log, validated by amana-reserve
+    #[account(mut)]
+    pub audit_log: UncheckedAccount<'info>,
+
+    /// Reserve admin; amana-reserve checks it against the reserve
+    pub admin: Signer<'info>,
+
+    pub amana_reserve_program: Program<'info, AmanaReserve>,
+}
+
+#[derive(Accounts)]
+pub struct SetExecutionAllowlist<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump,
+        constraint = dao.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ExecutionAllowlist::SPACE,
+        seeds = [b"execution_allowlist"],
+        bump
+    )]
+    pub execution_allowlist: Account<'info, ExecutionAllowlist>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearExecutionAllowlist<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump,
+        constraint = dao.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"execution_allowlist"],
+        bump = execution_allowlist.bump
+    )]
+    pub execution_allowlist: Account<'info, ExecutionAllowlist>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDaoConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"dao"],
+        bump = dao.bump,
+        constraint = dao.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshSupply<'info> {
+    #[account(
+        mut,
+        seeds = [b"dao"],
+        bump = dao.bump
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(address = dao.token_mint @ DaoError::InvalidTokenMint)]
+    pub token_mint: Account<'info, Mint>,
 }
 
 #[derive(Accounts)]
 pub struct CancelProposal<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump
+    )]
+    pub dao: Account<'info, Dao>,
+
     #[account(
         mut,
         seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
         bump = proposal.bump
     )]
     pub proposal: Account<'info, Proposal>,
+
+    /// Membership record of `authority`; required for a Sharia board veto
+    #[account(
+        seeds = [b"sharia_member", authority.key().as_ref()],
+        bump = board_member_record.bump
+    )]
+    pub board_member_record: Option<Account<'info, ShariaBoardMember>>,
+
+    /// DAO admin, the proposer, or an active Sharia board member
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ModifyShariaBoard<'info> {
+#[instruction(member: Pubkey)]
+pub struct AddShariaBoardMember<'info> {
     #[account(
         seeds = [b"dao"],
         bump = dao.bump,
@@ -488,7 +1746,120 @@ pub struct ModifyShariaBoard<'info> {
     )]
     pub sharia_board: Account<'info, ShariaBoard>,
 
+    #[account(
+        init,
+        payer = admin,
+        space = ShariaBoardMember::SPACE,
+        seeds = [b"sharia_member", member.as_ref()],
+        bump
+    )]
+    pub board_member: Account<'info, ShariaBoardMember>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
     pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRequiredApprovals<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump,
+        constraint = dao.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        mut,
+        seeds = [b"sharia_board"],
+        bump = sharia_board.bump
+    )]
+    pub sharia_board: Account<'info, ShariaBoard>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveShariaBoardMember<'info> {
+    #[account(
+        seeds = [b"dao"],
+        bump = dao.bump,
+        constraint = dao.admin == admin.key() @ DaoError::Unauthorized
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(
+        mut,
+        seeds = [b"sharia_board"],
+        bump = sharia_board.bump
+    )]
+    pub sharia_board: Account<'info, ShariaBoard>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"sharia_member", board_member.member.as_ref()],
+        bump = board_member.bump
+    )]
+    pub board_member: Account<'info, ShariaBoardMember>,
+
+    /// The removed member's wallet, refunded the rent of dropped reviews
+    #[account(
+        mut,
+        address = board_member.member
+    )]
+    pub member: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseShariaReview<'info> {
+    #[account(
+        seeds = [b"proposal", review.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        close = board_member,
+        seeds = [b"review", review.proposal_id.to_le_bytes().as_ref(), board_member.key().as_ref()],
+        bump = review.bump
+    )]
+    pub review: Account<'info, ShariaReview>,
+
+    #[account(
+        mut,
+        seeds = [b"sharia_member", board_member.key().as_ref()],
+        bump = board_member_record.bump
+    )]
+    pub board_member_record: Account<'info, ShariaBoardMember>,
+
+    #[account(mut)]
+    pub board_member: Signer<'info>,
 }
 
 // Enums
@@ -510,8 +1881,35 @@ pub enum VoteType {
     Abstain,
 }
 
+/// Admin actions recorded in the audit log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    AddShariaBoardMember = 1,
+    RemoveShariaBoardMember = 2,
+    SetQuorumBounds = 3,
+    SetShariaClassification = 4,
+    AddSensitiveTarget = 5,
+    RemoveSensitiveTarget = 6,
+    ClassifyProposalSharia = 7,
+    SetExecutionWindow = 8,
+    SetMinHaiForSpending = 9,
+    SetExecutionAllowlist = 10,
+    ClearExecutionAllowlist = 11,
+    SetMaxVoteWeight = 12,
+    SetRequiredApprovals = 13,
+    SetMaxSupplyAge = 14,
+}
+
 // Events
 
+#[event]
+pub struct AuditEntryEvent {
+    pub sequence: u64,
+    pub action: u8,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DaoInitializedEvent {
     pub voting_period: i64,
@@ -530,7 +1928,15 @@ pub struct VoteCastEvent {
     pub proposal_id: u64,
     pub voter: Pubkey,
     pub vote: u8,
-    pub weight: u64,
+    pub weight: u64,            // Counted weight, after any cap
+    pub balance: u64,           // Voter's governance token balance, all of it escrowed
+}
+
+#[event]
+pub struct VoteTokensReclaimedEvent {
+    pub proposal_id: u64,
+    pub voter: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
@@ -538,16 +1944,92 @@ pub struct ShariaReviewEvent {
     pub proposal_id: u64,
     pub board_member: Pubkey,
     pub approved: bool,
+    pub creation_nonce: u64,
+}
+
+#[event]
+pub struct ShariaApprovalThresholdMetEvent {
+    pub proposal_id: u64,
+    pub approval_count: u8,
+    pub required_approvals: u8,
+}
+
+#[event]
+pub struct RequiredApprovalsUpdatedEvent {
+    pub required_approvals: u8,
+}
+
+#[event]
+pub struct ProposalShariaClassifiedEvent {
+    pub proposal_id: u64,
+    pub classifier: Pubkey,
+    pub proposer_claim: bool,
+    pub affects_sharia: bool,
+}
+
+#[event]
+pub struct SensitiveTargetUpdatedEvent {
+    pub target: Pubkey,
+    pub sensitive: bool,
+}
+
+#[event]
+pub struct ExecutionWindowUpdatedEvent {
+    pub execution_window_seconds: i64,
+    pub sharia_review_grace_seconds: i64,
+}
+
+#[event]
+pub struct MaxVoteWeightUpdatedEvent {
+    pub max_vote_weight_bps: u16,
+}
+
+#[event]
+pub struct SupplyRefreshedEvent {
+    pub total_supply: u64,
+    pub refreshed_at: i64,
+}
+
+#[event]
+pub struct MaxSupplyAgeUpdatedEvent {
+    pub max_supply_age_seconds: i64,
+}
+
+#[event]
+pub struct MinHaiForSpendingUpdatedEvent {
+    pub min_hai_for_spending: u16,
+}
+
+#[event]
+pub struct ShariaClassificationUpdatedEvent {
+    pub sharia_classifier: Pubkey,
+    pub strict: bool,
+}
+
+#[event]
+pub struct ExecutionAllowlistUpdatedEvent {
+    pub programs: Vec<Pubkey>,
+    pub enforced: bool,
 }
 
 #[event]
 pub struct ProposalExecutedEvent {
     pub proposal_id: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct QuorumBoundsUpdatedEvent {
+    pub base_quorum_bps: u16,
+    pub max_quorum_bps: u16,
 }
 
 #[event]
 pub struct ProposalCanceledEvent {
     pub proposal_id: u64,
+    pub canceled_by: Pubkey,
+    pub reason_code: u8,
 }
 
 #[event]
@@ -584,4 +2066,58 @@ pub enum DaoError {
     NotShariaRelevant,
     #[msg("Unauthorized access")]
     Unauthorized,
+    #[msg("Quorum bounds must satisfy base <= max <= 10000")]
+    InvalidQuorumBounds,
+    #[msg("Account is not the DAO's governance token mint")]
+    InvalidTokenMint,
+    #[msg("Proposal has not been Sharia-classified yet")]
+    ShariaClassificationPending,
+    #[msg("Execution window and grace must not be negative")]
+    InvalidExecutionWindow,
+    #[msg("Proposal execution window has passed")]
+    ProposalExpired,
+    #[msg("Voting delay or period is outside the allowed bounds")]
+    InvalidTiming,
+    #[msg("Quorum must not exceed 10000 basis points")]
+    InvalidQuorum,
+    #[msg("HAI threshold must not exceed 10000")]
+    InvalidHaiThreshold,
+    #[msg("Missing or invalid amana-hai HAI account")]
+    InvalidHaiAccount,
+    #[msg("HAI score is below the floor for spending proposals")]
+    HaiTooLow,
+    #[msg("Program invoked by the proposal is not on its execution allowlist")]
+    TargetNotAllowed,
+    #[msg("Execution allowlist holds too many programs")]
+    AllowlistFull,
+    #[msg("Vote weight cap must not exceed 10000")]
+    InvalidVoteWeightCap,
+    #[msg("Token account must hold the governance token and belong to the voter")]
+    InvalidTokenAccount,
+    #[msg("Voter holds no governance tokens")]
+    NoVotingPower,
+    #[msg("Recipient does not match the proposal's target")]
+    InvalidRecipient,
+    #[msg("Treasury cannot cover the proposal amount")]
+    InsufficientTreasury,
+    #[msg("Signer is not an active Sharia board member")]
+    NotBoardMember,
+    #[msg("Required approvals must be between 1 and the board's member count")]
+    InvalidApprovalThreshold,
+    #[msg("Cached token supply is stale; call refresh_supply")]
+    StaleSupply,
+    #[msg("Voted tokens stay escrowed until voting ends or the proposal is canceled")]
+    VoteTokensLocked,
+    #[msg("Sharia board has already reviewed this proposal; its classification is final")]
+    ShariaAlreadyReviewed,
+    #[msg("Removal would leave fewer board members than required approvals")]
+    BoardBelowThreshold,
+    #[msg("Remaining account is not an approving review of the member or its proposal")]
+    InvalidRemainingAccount,
+    #[msg("Every open approval of the member must be passed for removal")]
+    OpenApprovalsRemain,
+    #[msg("Review stays open until its proposal is executed or canceled")]
+    ReviewStillOpen,
+    #[msg("Proposal does not approve this capacity change")]
+    CapacityNotApproved,
 }
@@ -22,19 +22,31 @@
 //! - Privacy-preserving score computation
 //! - Cross-chain HAI synchronization
 
+use amana_common::{
+    create_pda_account, next_nonce, push_audit_entry, AuditEntry, AUDIT_LOG_CAPACITY,
+    AUDIT_LOG_SPACE,
+};
+use amana_reserve::{Activity, Validator};
 use anchor_lang::prelude::*;
 use ephemeral_rollups_sdk::anchor::{commit, ephemeral};
-use ephemeral_rollups_sdk::ephem::commit_accounts;
+use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
+use ephemeral_vrf_sdk::anchor::vrf;
+use ephemeral_vrf_sdk::instructions::{create_request_randomness_ix, RequestRandomnessParams};
+use ephemeral_vrf_sdk::types::SerializableAccountMeta;
 
 #[program]
 pub mod amana_hai {
     use super::*;
 
     /// Initialize the HAI tracker
+    ///
+    /// The tracker starts in `Bps` mode, so `initial_score` is capped at
+    /// `MAX_SCORE_BPS`.
     pub fn initialize(
         ctx: Context<InitializeHai>,
-        initial_score: u16,
+        initial_score: u32,
     ) -> Result<()> {
+        let initial_score = initial_score.min(PrecisionMode::Bps.max_score());
         let hai = &mut ctx.accounts.hai;
         hai.admin = ctx.accounts.admin.key();
         hai.current_score = initial_score;
@@ -43,6 +55,27 @@ pub mod amana_hai {
         hai.asset_backed_activities = 0;
         hai.economic_value_activities = 0;
         hai.snapshot_count = 0;
+        // Documented default weights, summing to 10000 like update_weights requires
+        hai.compliance_weight = 4000;
+        hai.asset_backing_weight = 2500;
+        hai.economic_value_weight = 2000;
+        hai.validator_participation_weight = 1500;
+        hai.is_frozen = false;
+        hai.min_validator_stake = 0;
+        hai.creation_nonce = 0;
+        hai.precision_mode = PrecisionMode::Bps;
+        hai.category_activities = [0; ActivityCategory::COUNT];
+        hai.category_compliant = [0; ActivityCategory::COUNT];
+        hai.total_capital = 0;
+        hai.compliant_capital = 0;
+        hai.asset_backed_capital = 0;
+        hai.economic_value_capital = 0;
+        hai.min_weight_update_interval = 0;
+        hai.last_weight_update_at = 0;
+        hai.total_validator_count = 0;
+        hai.total_positive_votes = 0;
+        hai.aggregate_programs = Vec::new();
+        hai.final_snapshot_pending = false;
         hai.bump = ctx.bumps.hai;
 
         emit!(HaiInitializedEvent {
@@ -52,7 +85,27 @@ pub mod amana_hai {
         Ok(())
     }
 
+    /// Create the HAI program's audit log of admin actions
+    pub fn initialize_audit_log(ctx: Context<InitializeAuditLog>) -> Result<()> {
+        let audit_log = &mut ctx.accounts.audit_log;
+        audit_log.next_sequence = 0;
+        audit_log.entries = Vec::with_capacity(AUDIT_LOG_CAPACITY);
+        audit_log.bump = ctx.bumps.audit_log;
+        Ok(())
+    }
+
     /// Track an activity for HAI calculation
+    ///
+    /// The payer must be the HAI admin or an authorized updater passing its
+    /// `Updater` record. While `min_validator_stake` is set, the payer must be a validator
+    /// registered in amana-reserve with at least that much stake bonded.
+    /// A `category` additionally counts the activity towards that category's
+    /// sub-score; the blended score is unaffected by it.
+    ///
+    /// The blended score is capital-weighted: each activity contributes its
+    /// `capital` to the compliant, asset-backed and economic-value sums, so a
+    /// large non-compliant activity outweighs many small compliant ones. Until
+    /// any capital has been tracked the score falls back to plain counts.
     pub fn track_activity(
         ctx: Context<TrackActivity>,
         activity_id: [u8; 32],
@@ -61,31 +114,28 @@ pub mod amana_hai {
         has_real_economic_value: bool,
         validator_count: u32,
         positive_votes: u32,
+        category: Option<ActivityCategory>,
+        capital: u64,
     ) -> Result<()> {
         let hai = &mut ctx.accounts.hai;
         let metrics = &mut ctx.accounts.metrics;
 
-        hai.total_activities = hai.total_activities
-            .checked_add(1)
-            .ok_or(HaiError::MathOverflow)?;
-
-        if is_compliant {
-            hai.compliant_activities = hai.compliant_activities
-                .checked_add(1)
-                .ok_or(HaiError::MathOverflow)?;
-        }
-
-        if is_asset_backed {
-            hai.asset_backed_activities = hai.asset_backed_activities
-                .checked_add(1)
-                .ok_or(HaiError::MathOverflow)?;
-        }
+        require!(!hai.is_frozen, HaiError::Frozen);
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+        require_admin_or_updater(hai, &ctx.accounts.payer.key(), ctx.accounts.updater_account.as_ref())?;
+        require_validator(hai, ctx.accounts.validator.as_ref(), &ctx.accounts.payer.key())?;
+        require_booked_capital(&activity_id, capital, ctx.accounts.activity.as_ref())?;
 
-        if has_real_economic_value {
-            hai.economic_value_activities = hai.economic_value_activities
-                .checked_add(1)
-                .ok_or(HaiError::MathOverflow)?;
-        }
+        count_tracked_activity(
+            hai,
+            is_compliant,
+            is_asset_backed,
+            has_real_economic_value,
+            validator_count,
+            positive_votes,
+            category,
+            capital,
+        )?;
 
         // Store activity metrics
         metrics.activity_id = activity_id;
@@ -95,6 +145,9 @@ pub mod amana_hai {
         metrics.validator_count = validator_count;
         metrics.positive_votes = positive_votes;
         metrics.timestamp = Clock::get()?.unix_timestamp;
+        metrics.creation_nonce = next_nonce(&mut hai.creation_nonce)?;
+        metrics.category = category;
+        metrics.capital = capital;
         metrics.bump = ctx.bumps.metrics;
 
         // Recalculate HAI score
@@ -104,34 +157,237 @@ pub mod amana_hai {
             activity_id,
             is_compliant,
             is_asset_backed,
+            category,
+            capital,
             new_score: hai.current_score,
+            creation_nonce: metrics.creation_nonce,
         });
 
         Ok(())
     }
 
-    /// Create a snapshot of current HAI metrics
-    pub fn create_snapshot(ctx: Context<CreateSnapshot>) -> Result<()> {
-        let hai = &ctx.accounts.hai;
-        let snapshot = &mut ctx.accounts.snapshot;
-
-        snapshot.score = hai.current_score;
-        snapshot.total_activities = hai.total_activities;
-        snapshot.compliant_activities = hai.compliant_activities;
-        snapshot.asset_backed_activities = hai.asset_backed_activities;
-        snapshot.timestamp = Clock::get()?.unix_timestamp;
-        snapshot.snapshot_id = hai.snapshot_count;
-        snapshot.bump = ctx.bumps.snapshot;
-
-        emit!(SnapshotCreatedEvent {
-            snapshot_id: snapshot.snapshot_id,
-            score: snapshot.score,
+    /// Track several activities at once
+    ///
+    /// Each entry's `ActivityMetrics` PDA (`[b"metrics", activity_id]`) is
+    /// passed via `remaining_accounts` in entry order and created here. They
+    /// are followed by the reserve `Activity` of every entry with non-zero
+    /// capital, again in entry order. The counters are updated per entry but
+    /// the score is recomputed once, so the result matches tracking the
+    /// entries individually. Gated like `track_activity`.
+    pub fn track_activities_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, TrackActivitiesBatch<'info>>,
+        entries: Vec<ActivityMetricInput>,
+    ) -> Result<()> {
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_TRACK_BATCH,
+            HaiError::InvalidBatchSize
+        );
+        let booked = entries.iter().filter(|entry| entry.capital > 0).count();
+        require!(
+            ctx.remaining_accounts.len() == entries.len() + booked,
+            HaiError::InvalidRemainingAccount
+        );
+        let (metrics_infos, activity_infos) = ctx.remaining_accounts.split_at(entries.len());
+        let mut activity_infos = activity_infos.iter();
+
+        let hai = &mut ctx.accounts.hai;
+        require!(!hai.is_frozen, HaiError::Frozen);
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+        require_admin_or_updater(hai, &ctx.accounts.payer.key(), ctx.accounts.updater_account.as_ref())?;
+        require_validator(hai, ctx.accounts.validator.as_ref(), &ctx.accounts.payer.key())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut activity_ids: Vec<[u8; 32]> = Vec::with_capacity(entries.len());
+
+        for (metrics_info, entry) in metrics_infos.iter().zip(entries.iter()) {
+            require!(
+                !activity_ids.contains(&entry.activity_id),
+                HaiError::DuplicateActivity
+            );
+            activity_ids.push(entry.activity_id);
+
+            if entry.capital > 0 {
+                let activity_info = activity_infos.next().ok_or(HaiError::InvalidRemainingAccount)?;
+                let (expected, _) = Pubkey::find_program_address(
+                    &[b"activity", entry.activity_id.as_ref()],
+                    &amana_reserve::ID,
+                );
+                require_keys_eq!(activity_info.key(), expected, HaiError::InvalidRemainingAccount);
+                let activity = Account::<Activity>::try_from(activity_info)?;
+                require_booked_capital(&entry.activity_id, entry.capital, Some(&activity))?;
+            }
+
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"metrics", entry.activity_id.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(metrics_info.key(), expected, HaiError::InvalidRemainingAccount);
+
+            create_pda_account(
+                &ctx.accounts.payer.to_account_info(),
+                metrics_info,
+                &ctx.accounts.system_program.to_account_info(),
+                ActivityMetrics::SPACE,
+                &[b"metrics", entry.activity_id.as_ref(), &[bump]],
+                &crate::ID,
+            )?;
+
+            let metrics = ActivityMetrics {
+                activity_id: entry.activity_id,
+                is_compliant: entry.is_compliant,
+                is_asset_backed: entry.is_asset_backed,
+                has_real_economic_value: entry.has_real_economic_value,
+                validator_count: entry.validator_count,
+                positive_votes: entry.positive_votes,
+                timestamp: now,
+                creation_nonce: next_nonce(&mut hai.creation_nonce)?,
+                category: entry.category,
+                capital: entry.capital,
+                bump,
+            };
+            let mut data = metrics_info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            metrics.try_serialize(&mut writer)?;
+
+            count_tracked_activity(
+                hai,
+                entry.is_compliant,
+                entry.is_asset_backed,
+                entry.has_real_economic_value,
+                entry.validator_count,
+                entry.positive_votes,
+                entry.category,
+                entry.capital,
+            )?;
+        }
+
+        hai.current_score = calculate_hai_score(hai)?;
+
+        emit!(ActivitiesBatchTrackedEvent {
+            activity_ids,
+            new_score: hai.current_score,
         });
 
         Ok(())
     }
 
+    /// Void an erroneously tracked activity
+    ///
+    /// Reverses the counters the metrics contributed, recomputes the score and
+    /// closes the metrics account, returning its rent to the admin; a second
+    /// void finds no account. Voiding the last tracked activity resets the
+    /// score to 0, since nothing is left to score.
+    pub fn void_activity_metric(
+        ctx: Context<VoidActivityMetric>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let hai = &mut ctx.accounts.hai;
+        let metrics = &ctx.accounts.metrics;
+
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+
+        hai.total_activities = hai.total_activities
+            .checked_sub(1)
+            .ok_or(HaiError::MathOverflow)?;
+        hai.total_validator_count = hai.total_validator_count
+            .checked_sub(metrics.validator_count as u64)
+            .ok_or(HaiError::MathOverflow)?;
+        hai.total_positive_votes = hai.total_positive_votes
+            .checked_sub(metrics.positive_votes as u64)
+            .ok_or(HaiError::MathOverflow)?;
+
+        if metrics.is_compliant {
+            hai.compliant_activities = hai.compliant_activities
+                .checked_sub(1)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        if metrics.is_asset_backed {
+            hai.asset_backed_activities = hai.asset_backed_activities
+                .checked_sub(1)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        if metrics.has_real_economic_value {
+            hai.economic_value_activities = hai.economic_value_activities
+                .checked_sub(1)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        let capital = metrics.capital;
+        hai.total_capital = hai.total_capital
+            .checked_sub(capital)
+            .ok_or(HaiError::MathOverflow)?;
+
+        if metrics.is_compliant {
+            hai.compliant_capital = hai.compliant_capital
+                .checked_sub(capital)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        if metrics.is_asset_backed {
+            hai.asset_backed_capital = hai.asset_backed_capital
+                .checked_sub(capital)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        if metrics.has_real_economic_value {
+            hai.economic_value_capital = hai.economic_value_capital
+                .checked_sub(capital)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        if let Some(category) = metrics.category {
+            let index = category.index();
+            hai.category_activities[index] = hai.category_activities[index]
+                .checked_sub(1)
+                .ok_or(HaiError::MathOverflow)?;
+
+            if metrics.is_compliant {
+                hai.category_compliant[index] = hai.category_compliant[index]
+                    .checked_sub(1)
+                    .ok_or(HaiError::MathOverflow)?;
+            }
+        }
+
+        // calculate_hai_score keeps the stored score when nothing is tracked
+        hai.current_score = if hai.total_activities == 0 {
+            0
+        } else {
+            calculate_hai_score(hai)?
+        };
+
+        emit!(MetricVoidedEvent {
+            activity_id,
+            new_score: hai.current_score,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::VoidActivityMetric, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Create a snapshot of current HAI metrics
+    ///
+    /// Restricted to the HAI admin or an authorized updater.
+    pub fn create_snapshot(ctx: Context<CreateSnapshot>) -> Result<()> {
+        require_admin_or_updater(
+            &ctx.accounts.hai,
+            &ctx.accounts.payer.key(),
+            ctx.accounts.updater_account.as_ref(),
+        )?;
+
+        record_snapshot(
+            &mut ctx.accounts.hai,
+            &mut ctx.accounts.snapshot,
+            ctx.bumps.snapshot,
+        )
+    }
+
     /// Update HAI calculation weights
+    ///
+    /// Fails with `WeightUpdateTooSoon` until `min_weight_update_interval`
+    /// has passed since the previous weight update.
     pub fn update_weights(
         ctx: Context<UpdateWeights>,
         compliance_weight: u16,
@@ -140,6 +396,8 @@ pub mod amana_hai {
         validator_participation_weight: u16,
     ) -> Result<()> {
         let hai = &mut ctx.accounts.hai;
+        require!(!hai.is_frozen, HaiError::Frozen);
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
 
         let total_weight = (compliance_weight as u32)
             .checked_add(asset_backing_weight as u32)
@@ -149,6 +407,13 @@ pub mod amana_hai {
 
         require!(total_weight == 10000, HaiError::InvalidWeights);
 
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= hai.last_weight_update_at.saturating_add(hai.min_weight_update_interval),
+            HaiError::WeightUpdateTooSoon
+        );
+        hai.last_weight_update_at = now;
+
         hai.compliance_weight = compliance_weight;
         hai.asset_backing_weight = asset_backing_weight;
         hai.economic_value_weight = economic_value_weight;
@@ -163,6 +428,290 @@ pub mod amana_hai {
             validator_participation_weight,
         });
 
+        ctx.accounts.audit_log.record(AuditAction::UpdateWeights, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Freeze all score-changing updates during an incident
+    ///
+    /// Snapshots and read-only instructions stay available while frozen.
+    pub fn freeze_hai(ctx: Context<SetHaiFrozen>) -> Result<()> {
+        ctx.accounts.hai.is_frozen = true;
+
+        emit!(HaiFreezeUpdatedEvent { is_frozen: true });
+
+        ctx.accounts.audit_log.record(AuditAction::FreezeHai, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Resume score updates after an incident
+    pub fn unfreeze_hai(ctx: Context<SetHaiFrozen>) -> Result<()> {
+        ctx.accounts.hai.is_frozen = false;
+
+        emit!(HaiFreezeUpdatedEvent { is_frozen: false });
+
+        ctx.accounts.audit_log.record(AuditAction::UnfreezeHai, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the bonded stake a validator needs to track activities (0 disables)
+    pub fn set_min_validator_stake(
+        ctx: Context<UpdateHaiConfig>,
+        min_validator_stake: u64,
+    ) -> Result<()> {
+        ctx.accounts.hai.min_validator_stake = min_validator_stake;
+
+        emit!(MinValidatorStakeUpdatedEvent { min_validator_stake });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMinValidatorStake, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the cooldown between weight updates (0 disables)
+    ///
+    /// Lowering the interval is itself subject to the current cooldown, so it
+    /// cannot be used to bypass it.
+    pub fn set_weight_update_interval(
+        ctx: Context<UpdateHaiConfig>,
+        min_weight_update_interval: i64,
+    ) -> Result<()> {
+        require!(min_weight_update_interval >= 0, HaiError::InvalidWeightUpdateInterval);
+
+        let hai = &mut ctx.accounts.hai;
+        if min_weight_update_interval < hai.min_weight_update_interval {
+            let now = Clock::get()?.unix_timestamp;
+            require!(
+                now >= hai.last_weight_update_at.saturating_add(hai.min_weight_update_interval),
+                HaiError::WeightUpdateTooSoon
+            );
+        }
+        hai.min_weight_update_interval = min_weight_update_interval;
+
+        emit!(WeightUpdateIntervalUpdatedEvent { min_weight_update_interval });
+
+        ctx.accounts.audit_log.record(AuditAction::SetWeightUpdateInterval, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Switch the score denominator, rescaling the stored score
+    ///
+    /// Scores are out of `MAX_SCORE_BPS` in `Bps` mode and `MAX_SCORE_PPM` in
+    /// `Ppm` mode. Existing snapshots keep the mode they were taken in.
+    pub fn set_precision_mode(
+        ctx: Context<UpdateHaiConfig>,
+        precision_mode: PrecisionMode,
+    ) -> Result<()> {
+        let hai = &mut ctx.accounts.hai;
+        require!(!hai.is_frozen, HaiError::Frozen);
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+
+        hai.current_score = hai.precision_mode.rescale(hai.current_score, precision_mode)?;
+        hai.precision_mode = precision_mode;
+
+        emit!(PrecisionModeUpdatedEvent {
+            precision_mode,
+            current_score: hai.current_score,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetPrecisionMode, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Emit the weight configuration and each weighted score component
+    ///
+    /// Read-only. The weighted components sum to the score produced by
+    /// `calculate_hai_score`; `current_score` is included alongside so any
+    /// divergence introduced by the realtime/VRF paths is visible.
+    pub fn explain_score(ctx: Context<ExplainScore>) -> Result<()> {
+        let hai = &ctx.accounts.hai;
+        let components = calculate_score_components(hai)?;
+
+        emit!(ScoreExplanationEvent {
+            compliance_weight: hai.compliance_weight,
+            asset_backing_weight: hai.asset_backing_weight,
+            economic_value_weight: hai.economic_value_weight,
+            validator_participation_weight: hai.validator_participation_weight,
+            compliance_score: components.compliance_score,
+            asset_backing_score: components.asset_backing_score,
+            economic_value_score: components.economic_value_score,
+            validator_participation_score: components.validator_participation_score,
+            compliance_component: components.compliance_component,
+            asset_backing_component: components.asset_backing_component,
+            economic_value_component: components.economic_value_component,
+            validator_participation_component: components.validator_participation_component,
+            computed_score: components.total()?,
+            current_score: hai.current_score,
+        });
+
+        Ok(())
+    }
+
+    /// Emit the current weight sum and whether it is the required 10000
+    ///
+    /// Read-only. Scoring fails with `InvalidWeights` while the sum is off, so
+    /// this lets monitoring spot corrupted weights before an update does.
+    pub fn validate_weights(ctx: Context<ExplainScore>) -> Result<()> {
+        let weight_sum = ctx.accounts.hai.weight_sum()?;
+
+        emit!(WeightsValidatedEvent {
+            weight_sum,
+            is_valid: weight_sum == 10000,
+        });
+
+        Ok(())
+    }
+
+    /// Recompute the score from the tracked counters and check it against `current_score`
+    ///
+    /// Read-only. Fails with `ScoreMismatch` when the two differ by more than
+    /// `SCORE_VERIFY_TOLERANCE_BPS`, which exposes drift from the realtime and
+    /// VRF paths that adjust the score without `calculate_hai_score`.
+    pub fn verify_hai_score(ctx: Context<ExplainScore>) -> Result<()> {
+        let hai = &ctx.accounts.hai;
+        let computed_score = calculate_hai_score(hai)?;
+        let tolerance = PrecisionMode::Bps.rescale(SCORE_VERIFY_TOLERANCE_BPS, hai.precision_mode)?;
+
+        require!(
+            computed_score.abs_diff(hai.current_score) <= tolerance,
+            HaiError::ScoreMismatch
+        );
+
+        emit!(HaiVerifiedEvent {
+            computed_score,
+            stored_score: hai.current_score,
+        });
+
+        Ok(())
+    }
+
+    /// Emit the compliance sub-score of every activity category
+    ///
+    /// Read-only. Each sub-score is the share of the category's tracked
+    /// activities that were compliant, scaled to the current precision mode;
+    /// categories with nothing tracked report 0. `current_score` remains the
+    /// headline figure.
+    pub fn explain_category_scores(ctx: Context<ExplainScore>) -> Result<()> {
+        let hai = &ctx.accounts.hai;
+        let max_score = hai.precision_mode.max_score();
+
+        let mut scores = Vec::with_capacity(ActivityCategory::COUNT);
+        for category in ActivityCategory::ALL {
+            let index = category.index();
+            let total_activities = hai.category_activities[index];
+            let compliant_activities = hai.category_compliant[index];
+            let score = if total_activities == 0 {
+                0
+            } else {
+                ratio_score(compliant_activities, total_activities, max_score)? as u32
+            };
+
+            scores.push(CategoryScore {
+                category,
+                total_activities,
+                compliant_activities,
+                score,
+            });
+        }
+
+        emit!(CategoryScoresEvent {
+            scores,
+            current_score: hai.current_score,
+        });
+
+        Ok(())
+    }
+
+    /// Emit a capital-weighted aggregate of several HAI accounts
+    ///
+    /// Read-only. Each reserve runs its own HAI deployment, whose `Hai` lives
+    /// at the `[b"hai"]` PDA of that program. Between 1 and
+    /// `MAX_AGGREGATE_HAIS` distinct such accounts are passed via
+    /// `remaining_accounts`, each owned by this program or one listed in
+    /// `aggregate_programs`. Each score is converted to basis points and
+    /// weighted by its tracked capital; if none has tracked capital the scores
+    /// are averaged instead.
+    pub fn aggregate_hai(ctx: Context<AggregateHai>) -> Result<()> {
+        let reserve_count = ctx.remaining_accounts.len();
+        require!(
+            reserve_count > 0 && reserve_count <= MAX_AGGREGATE_HAIS,
+            HaiError::InvalidAggregateCount
+        );
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(reserve_count);
+        let mut weighted_sum: u128 = 0;
+        let mut total_capital: u128 = 0;
+        let mut score_sum: u64 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), HaiError::InvalidRemainingAccount);
+            seen.push(info.key());
+            require!(
+                *info.owner == crate::ID || ctx.accounts.hai.aggregate_programs.contains(info.owner),
+                HaiError::InvalidRemainingAccount
+            );
+            let (expected, _) = Pubkey::find_program_address(&[b"hai"], info.owner);
+            require_keys_eq!(info.key(), expected, HaiError::InvalidRemainingAccount);
+
+            let hai = Hai::try_deserialize(&mut &info.try_borrow_data()?[..])
+                .map_err(|_| HaiError::InvalidRemainingAccount)?;
+            let score = hai.precision_mode.rescale(hai.current_score, PrecisionMode::Bps)?;
+
+            weighted_sum = weighted_sum
+                .checked_add(score as u128 * hai.total_capital as u128)
+                .ok_or(HaiError::MathOverflow)?;
+            total_capital = total_capital
+                .checked_add(hai.total_capital as u128)
+                .ok_or(HaiError::MathOverflow)?;
+            score_sum = score_sum
+                .checked_add(score as u64)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+
+        let aggregate_score = if total_capital > 0 {
+            (weighted_sum / total_capital) as u32
+        } else {
+            (score_sum / reserve_count as u64) as u32
+        };
+
+        emit!(AggregateHaiEvent {
+            aggregate_score,
+            reserve_count: reserve_count as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Set the other HAI deployments `aggregate_hai` may read from
+    ///
+    /// At most `MAX_AGGREGATE_HAIS` distinct program ids; this program is
+    /// always accepted and need not be listed. An empty list limits
+    /// aggregation to this deployment.
+    pub fn set_aggregate_programs(
+        ctx: Context<UpdateHaiConfig>,
+        aggregate_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            aggregate_programs.len() <= MAX_AGGREGATE_HAIS,
+            HaiError::InvalidAggregateCount
+        );
+        for (i, program) in aggregate_programs.iter().enumerate() {
+            require!(
+                !aggregate_programs[..i].contains(program),
+                HaiError::DuplicateAggregateProgram
+            );
+        }
+
+        ctx.accounts.hai.aggregate_programs = aggregate_programs.clone();
+
+        emit!(AggregateProgramsUpdatedEvent { aggregate_programs });
+
+        ctx.accounts.audit_log.record(AuditAction::SetAggregatePrograms, ctx.accounts.admin.key())?;
+
         Ok(())
     }
 
@@ -181,6 +730,8 @@ pub mod amana_hai {
             updater,
         });
 
+        ctx.accounts.audit_log.record(AuditAction::AuthorizeUpdater, ctx.accounts.admin.key())?;
+
         Ok(())
     }
 
@@ -193,36 +744,115 @@ pub mod amana_hai {
             updater: updater_account.updater,
         });
 
+        ctx.accounts.audit_log.record(AuditAction::RevokeUpdater, ctx.accounts.admin.key())?;
+
         Ok(())
     }
 
     // ========== MagicBlock VRF and Real-time Integration ==========
 
-    /// Update HAI score with verifiable randomness for data source sampling
-    pub fn update_hai_score_with_vrf(
-        ctx: Context<UpdateHaiScoreVrf>,
+    /// Ask the MagicBlock VRF oracle for randomness to sample data sources
+    ///
+    /// Records the candidate `data_sources` in a `VrfRequest` PDA keyed by
+    /// `activity_id`; the score is only updated once the oracle answers
+    /// through `callback_hai_randomness`, which closes the request so the
+    /// activity can be sampled again. Restricted to the HAI admin or an
+    /// authorized updater.
+    pub fn request_hai_randomness(
+        ctx: Context<RequestHaiRandomness>,
         activity_id: [u8; 32],
         data_sources: Vec<u8>,
+        caller_seed: [u8; 32],
+    ) -> Result<()> {
+        require!(!ctx.accounts.hai.is_frozen, HaiError::Frozen);
+        require!(!ctx.accounts.hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+        require_admin_or_updater(
+            &ctx.accounts.hai,
+            &ctx.accounts.payer.key(),
+            ctx.accounts.updater_account.as_ref(),
+        )?;
+        require!(
+            data_sources.len() <= MAX_VRF_DATA_SOURCES,
+            HaiError::TooManyDataSources
+        );
+
+        let request = &mut ctx.accounts.vrf_request;
+        request.activity_id = activity_id;
+        request.requester = ctx.accounts.payer.key();
+        request.data_sources = data_sources;
+        request.caller_seed = caller_seed;
+        request.requested_at = Clock::get()?.unix_timestamp;
+        request.bump = ctx.bumps.vrf_request;
+
+        let ix = create_request_randomness_ix(RequestRandomnessParams {
+            payer: ctx.accounts.payer.key(),
+            oracle_queue: ctx.accounts.oracle_queue.key(),
+            callback_program_id: crate::ID,
+            callback_discriminator: instruction::CallbackHaiRandomness::DISCRIMINATOR.to_vec(),
+            caller_seed,
+            accounts_metas: Some(vec![
+                SerializableAccountMeta {
+                    pubkey: ctx.accounts.hai.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                SerializableAccountMeta {
+                    pubkey: ctx.accounts.vrf_request.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                SerializableAccountMeta {
+                    pubkey: ctx.accounts.payer.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+            ]),
+            ..Default::default()
+        });
+        ctx.accounts.invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
+
+        emit!(HaiRandomnessRequestedEvent {
+            activity_id,
+            requester: ctx.accounts.payer.key(),
+            caller_seed,
+        });
+
+        Ok(())
+    }
+
+    /// VRF oracle callback: sample data sources and update the HAI score
+    ///
+    /// Only the VRF program identity can sign, so `randomness` is the
+    /// oracle's verified output. The randomness, selected sources and
+    /// resulting score go into `HaiScoreUpdatedWithVrfEvent` so the update can
+    /// be replayed, and the `VrfRequest` is closed with its rent returned to
+    /// the requester; a second callback for the same request finds no account.
+    pub fn callback_hai_randomness(
+        ctx: Context<CallbackHaiRandomness>,
+        randomness: [u8; 32],
     ) -> Result<()> {
         let hai = &mut ctx.accounts.hai;
-        
-        // Simulate VRF randomness (in production, integrate with MagicBlock VRF)
-        let clock = Clock::get()?;
-        let pseudo_randomness = (clock.unix_timestamp as u64) % 100;
-        
-        // Use randomness to select data sources
-        let selected_sources = select_data_sources_with_randomness(data_sources, pseudo_randomness)?;
-        
-        // Calculate HAI score with selected sources
-        let new_score = calculate_hai_score_with_sources(hai, selected_sources)?;
+        let request = &ctx.accounts.vrf_request;
+        require!(!hai.is_frozen, HaiError::Frozen);
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+
+        let mut sample = [0u8; 8];
+        sample.copy_from_slice(&randomness[..8]);
+        let selected_sources = select_data_sources_with_randomness(
+            request.data_sources.clone(),
+            u64::from_le_bytes(sample),
+        )?;
+
+        let new_score = calculate_hai_score_with_sources(hai, selected_sources.clone())?;
         hai.current_score = new_score;
-        
+
         emit!(HaiScoreUpdatedWithVrfEvent {
-            activity_id,
+            activity_id: request.activity_id,
             new_score,
-            randomness: pseudo_randomness,
+            randomness,
+            selected_sources,
         });
-        
+
         Ok(())
     }
 
@@ -244,26 +874,93 @@ pub mod amana_hai {
         Ok(())
     }
 
+    /// End an ER session, handing the HAI back to the base layer
+    ///
+    /// Commits and undelegates the `Hai` account with `final_snapshot_pending`
+    /// set. An account created on the ER is never committed, so the final
+    /// `HaiSnapshot` is taken by `record_final_snapshot` on the base layer
+    /// instead; until then every score-changing instruction fails with
+    /// `FinalSnapshotPending`, so the snapshot shows the session's end state.
+    /// Restricted to the HAI admin or an authorized updater.
+    pub fn finalize_hai_session(ctx: Context<FinalizeHaiSession>) -> Result<()> {
+        require_admin_or_updater(
+            &ctx.accounts.hai,
+            &ctx.accounts.payer.key(),
+            ctx.accounts.updater_account.as_ref(),
+        )?;
+        require!(
+            !ctx.accounts.hai.final_snapshot_pending,
+            HaiError::FinalSnapshotPending
+        );
+
+        ctx.accounts.hai.final_snapshot_pending = true;
+
+        emit!(HaiUndelegatedEvent {
+            snapshot_id: ctx.accounts.hai.snapshot_count,
+            triggered_by: ctx.accounts.payer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        // Serialize the pending flag before the account leaves the ER
+        ctx.accounts.hai.exit(&crate::ID)?;
+        commit_and_undelegate_accounts(
+            &ctx.accounts.payer,
+            vec![&ctx.accounts.hai.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        Ok(())
+    }
+
+    /// Take the snapshot a finalized ER session left pending
+    ///
+    /// Runs on the base layer once `finalize_hai_session` has undelegated the
+    /// `Hai`, and re-enables score updates. Restricted to the HAI admin or an
+    /// authorized updater.
+    pub fn record_final_snapshot(ctx: Context<CreateSnapshot>) -> Result<()> {
+        require_admin_or_updater(
+            &ctx.accounts.hai,
+            &ctx.accounts.payer.key(),
+            ctx.accounts.updater_account.as_ref(),
+        )?;
+        require!(
+            ctx.accounts.hai.final_snapshot_pending,
+            HaiError::NoFinalSnapshotPending
+        );
+
+        ctx.accounts.hai.final_snapshot_pending = false;
+        record_snapshot(
+            &mut ctx.accounts.hai,
+            &mut ctx.accounts.snapshot,
+            ctx.bumps.snapshot,
+        )
+    }
+
     /// Real-time HAI score update on Ephemeral Rollup
+    ///
+    /// Restricted to the HAI admin or an authorized updater, like tracking.
+    /// `compliance_delta` is in basis points and is rescaled to the current
+    /// precision mode before it is applied.
     pub fn update_hai_realtime(
         ctx: Context<UpdateHaiRealtime>,
         activity_id: [u8; 32],
         compliance_delta: i16,
     ) -> Result<()> {
         let hai = &mut ctx.accounts.hai;
+        require!(!hai.is_frozen, HaiError::Frozen);
+        require!(!hai.final_snapshot_pending, HaiError::FinalSnapshotPending);
+        require_admin_or_updater(hai, &ctx.accounts.payer.key(), ctx.accounts.updater_account.as_ref())?;
         
-        // Apply real-time score adjustment
-        let new_score = if compliance_delta >= 0 {
-            hai.current_score.saturating_add(compliance_delta as u16)
-        } else {
-            hai.current_score.saturating_sub((-compliance_delta) as u16)
-        };
+        // Apply real-time score adjustment, clamped to [0, max score of the mode]
+        let old_score = hai.current_score;
+        let new_score = apply_score_delta(old_score, compliance_delta, hai.precision_mode)?;
         
-        hai.current_score = new_score.min(10000); // Cap at 100%
+        hai.current_score = new_score;
         
         emit!(HaiRealtimeUpdateEvent {
             activity_id,
-            old_score: hai.current_score,
+            old_score,
             new_score,
             delta: compliance_delta,
         });
@@ -272,64 +969,300 @@ pub mod amana_hai {
     }
 }
 
-/// Calculate HAI score based on current metrics
-fn calculate_hai_score(hai: &Hai) -> Result<u16> {
-    if hai.total_activities == 0 {
-        return Ok(hai.current_score);
+/// Maximum activities per `track_activities_batch` call (compute budget)
+pub const MAX_TRACK_BATCH: usize = 8;
+
+/// Upper bound of the HAI score in `Bps` mode (100.00%)
+pub const MAX_SCORE_BPS: u32 = 10_000;
+
+/// Upper bound of the HAI score in `Ppm` mode (100.0000%)
+pub const MAX_SCORE_PPM: u32 = 1_000_000;
+
+/// Most `Hai` accounts a single `aggregate_hai` call may combine
+pub const MAX_AGGREGATE_HAIS: usize = 8;
+
+/// Largest gap, in basis points, `verify_hai_score` accepts between the recomputed and stored score
+pub const SCORE_VERIFY_TOLERANCE_BPS: u32 = 1;
+
+/// Maximum candidate data sources held by one `VrfRequest`
+pub const MAX_VRF_DATA_SOURCES: usize = 16;
+
+/// Most data sources a single VRF sample selects
+pub const MAX_VRF_SELECTED_SOURCES: usize = 3;
+
+/// Apply a signed basis-point delta to a score in `mode`, clamping at both ends
+///
+/// The delta's magnitude is rescaled from `Bps` into `mode` first. Negative
+/// deltas floor at 0 and positive deltas cap at the mode's maximum, so any
+/// `i16` (including `i16::MIN`, whose negation is not representable) moves the
+/// score monotonically in the delta's direction without overflowing.
+fn apply_score_delta(score: u32, delta: i16, mode: PrecisionMode) -> Result<u32> {
+    let magnitude = PrecisionMode::Bps.rescale(delta.unsigned_abs() as u32, mode)?;
+    let adjusted = if delta >= 0 {
+        score.saturating_add(magnitude)
+    } else {
+        score.saturating_sub(magnitude)
+    };
+    Ok(adjusted.min(mode.max_score()))
+}
+
+/// Add one tracked activity to the HAI counters and capital-weighted sums
+fn count_tracked_activity(
+    hai: &mut Hai,
+    is_compliant: bool,
+    is_asset_backed: bool,
+    has_real_economic_value: bool,
+    validator_count: u32,
+    positive_votes: u32,
+    category: Option<ActivityCategory>,
+    capital: u64,
+) -> Result<()> {
+    require!(positive_votes <= validator_count, HaiError::InvalidVotes);
+
+    hai.total_activities = hai.total_activities
+        .checked_add(1)
+        .ok_or(HaiError::MathOverflow)?;
+    hai.total_validator_count = hai.total_validator_count
+        .checked_add(validator_count as u64)
+        .ok_or(HaiError::MathOverflow)?;
+    hai.total_positive_votes = hai.total_positive_votes
+        .checked_add(positive_votes as u64)
+        .ok_or(HaiError::MathOverflow)?;
+    hai.total_capital = hai.total_capital
+        .checked_add(capital)
+        .ok_or(HaiError::MathOverflow)?;
+
+    if is_compliant {
+        hai.compliant_activities = hai.compliant_activities
+            .checked_add(1)
+            .ok_or(HaiError::MathOverflow)?;
+        hai.compliant_capital = hai.compliant_capital
+            .checked_add(capital)
+            .ok_or(HaiError::MathOverflow)?;
     }
 
-    let total = hai.total_activities as u64;
-    let max_score: u64 = 10000;
+    if is_asset_backed {
+        hai.asset_backed_activities = hai.asset_backed_activities
+            .checked_add(1)
+            .ok_or(HaiError::MathOverflow)?;
+        hai.asset_backed_capital = hai.asset_backed_capital
+            .checked_add(capital)
+            .ok_or(HaiError::MathOverflow)?;
+    }
 
-    // Compliance component
-    let compliance_score = (hai.compliant_activities as u64)
-        .checked_mul(max_score)
-        .and_then(|v| v.checked_div(total))
-        .ok_or(HaiError::MathOverflow)?;
+    if has_real_economic_value {
+        hai.economic_value_activities = hai.economic_value_activities
+            .checked_add(1)
+            .ok_or(HaiError::MathOverflow)?;
+        hai.economic_value_capital = hai.economic_value_capital
+            .checked_add(capital)
+            .ok_or(HaiError::MathOverflow)?;
+    }
+
+    if let Some(category) = category {
+        let index = category.index();
+        hai.category_activities[index] = hai.category_activities[index]
+            .checked_add(1)
+            .ok_or(HaiError::MathOverflow)?;
+
+        if is_compliant {
+            hai.category_compliant[index] = hai.category_compliant[index]
+                .checked_add(1)
+                .ok_or(HaiError::MathOverflow)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// When tracking requires validators, check that `authority` is a bonded one
+fn require_validator(
+    hai: &Hai,
+    validator: Option<&Account<Validator>>,
+    authority: &Pubkey,
+) -> Result<()> {
+    if hai.min_validator_stake == 0 {
+        return Ok(());
+    }
+
+    let validator = validator.ok_or(HaiError::ValidatorRequired)?;
+    require_keys_eq!(validator.authority, *authority, HaiError::ValidatorRequired);
+    require!(
+        validator.is_bonded(hai.min_validator_stake),
+        HaiError::ValidatorRequired
+    );
 
-    // Asset backing component
-    let asset_backing_score = (hai.asset_backed_activities as u64)
-        .checked_mul(max_score)
-        .and_then(|v| v.checked_div(total))
+    Ok(())
+}
+
+/// Check a tracked `capital` against the default reserve's books
+///
+/// Zero capital stays count-only and needs no reserve account. Any other
+/// amount must equal the `capital_required` of the reserve `Activity` with the
+/// same id, so the capital weighting cannot be inflated by the caller.
+fn require_booked_capital(
+    activity_id: &[u8; 32],
+    capital: u64,
+    activity: Option<&Account<Activity>>,
+) -> Result<()> {
+    if capital == 0 {
+        return Ok(());
+    }
+
+    let activity = activity.ok_or(HaiError::CapitalNotBooked)?;
+    require!(activity.activity_id == *activity_id, HaiError::CapitalNotBooked);
+    require!(activity.capital_required == capital, HaiError::CapitalNotBooked);
+
+    Ok(())
+}
+
+/// Check that `authority` is the HAI admin or holds an authorized `Updater`
+fn require_admin_or_updater(
+    hai: &Hai,
+    authority: &Pubkey,
+    updater: Option<&Account<Updater>>,
+) -> Result<()> {
+    if *authority == hai.admin {
+        return Ok(());
+    }
+
+    let updater = updater.ok_or(HaiError::Unauthorized)?;
+    require_keys_eq!(updater.updater, *authority, HaiError::Unauthorized);
+    require!(updater.is_authorized, HaiError::Unauthorized);
+
+    Ok(())
+}
+
+/// Copy the current metrics into a fresh snapshot and advance the counter
+fn record_snapshot(hai: &mut Hai, snapshot: &mut HaiSnapshot, bump: u8) -> Result<()> {
+    snapshot.score = hai.current_score;
+    snapshot.precision_mode = hai.precision_mode;
+    snapshot.total_activities = hai.total_activities;
+    snapshot.compliant_activities = hai.compliant_activities;
+    snapshot.asset_backed_activities = hai.asset_backed_activities;
+    snapshot.timestamp = Clock::get()?.unix_timestamp;
+    snapshot.snapshot_id = hai.snapshot_count;
+    snapshot.creation_nonce = next_nonce(&mut hai.creation_nonce)?;
+    snapshot.bump = bump;
+
+    // Advance the counter so the next snapshot gets a fresh PDA
+    hai.snapshot_count = hai.snapshot_count
+        .checked_add(1)
         .ok_or(HaiError::MathOverflow)?;
 
-    // Economic value component
-    let economic_value_score = (hai.economic_value_activities as u64)
-        .checked_mul(max_score)
-        .and_then(|v| v.checked_div(total))
+    emit!(SnapshotCreatedEvent {
+        snapshot_id: snapshot.snapshot_id,
+        score: snapshot.score,
+        creation_nonce: snapshot.creation_nonce,
+    });
+
+    Ok(())
+}
+
+/// Raw and weighted components of the HAI score
+pub struct ScoreComponents {
+    pub compliance_score: u64,
+    pub asset_backing_score: u64,
+    pub economic_value_score: u64,
+    pub validator_participation_score: u64,
+    pub compliance_component: u64,
+    pub asset_backing_component: u64,
+    pub economic_value_component: u64,
+    pub validator_participation_component: u64,
+    pub max_score: u32,
+}
+
+impl ScoreComponents {
+    /// Sum of the weighted components, capped at `max_score`
+    pub fn total(&self) -> Result<u32> {
+        let score = self
+            .compliance_component
+            .checked_add(self.asset_backing_component)
+            .and_then(|v| v.checked_add(self.economic_value_component))
+            .and_then(|v| v.checked_add(self.validator_participation_component))
+            .ok_or(HaiError::MathOverflow)?;
+        Ok(score.min(self.max_score as u64) as u32)
+    }
+}
+
+/// Share of `part` in `total`, scaled to `max_score`
+fn ratio_score(part: u64, total: u64, max_score: u32) -> Result<u64> {
+    let score = (part as u128)
+        .checked_mul(max_score as u128)
+        .and_then(|v| v.checked_div(total as u128))
         .ok_or(HaiError::MathOverflow)?;
+    Ok(score as u64)
+}
 
-    // Validator participation (baseline for now)
-    let validator_participation_score: u64 = 8000;
-
-    // Weighted calculation
-    let score = (compliance_score
-        .checked_mul(hai.compliance_weight as u64))
-        .and_then(|v| v.checked_div(10000))
-        .and_then(|v| {
-            v.checked_add(
-                (asset_backing_score
-                    .checked_mul(hai.asset_backing_weight as u64))
-                .unwrap_or(0) / 10000,
-            )
-        })
-        .and_then(|v| {
-            v.checked_add(
-                (economic_value_score
-                    .checked_mul(hai.economic_value_weight as u64))
-                .unwrap_or(0) / 10000,
-            )
-        })
-        .and_then(|v| {
-            v.checked_add(
-                (validator_participation_score
-                    .checked_mul(hai.validator_participation_weight as u64))
-                .unwrap_or(0) / 10000,
-            )
-        })
+/// Apply a basis-point weight to a raw component score
+fn weighted(score: u64, weight: u16) -> Result<u64> {
+    let component = score
+        .checked_mul(weight as u64)
+        .map(|v| v / 10000)
         .ok_or(HaiError::MathOverflow)?;
+    Ok(component)
+}
 
-    Ok(score.min(max_score) as u16)
+/// Compute each component of the HAI score from the tracked counters
+///
+/// Ratios are taken over tracked capital once any has been recorded, and over
+/// activity counts otherwise. Validator participation is the share of positive
+/// votes among all validator votes tracked, or 0 before any were tracked.
+///
+/// Fails with `InvalidWeights` unless the weights sum to exactly 10000, so a
+/// corrupted configuration surfaces instead of skewing the score.
+fn calculate_score_components(hai: &Hai) -> Result<ScoreComponents> {
+    require!(hai.weight_sum()? == 10000, HaiError::InvalidWeights);
+
+    let max_score = hai.precision_mode.max_score();
+    let (compliance_score, asset_backing_score, economic_value_score) = if hai.total_capital > 0 {
+        let total = hai.total_capital;
+        (
+            ratio_score(hai.compliant_capital, total, max_score)?,
+            ratio_score(hai.asset_backed_capital, total, max_score)?,
+            ratio_score(hai.economic_value_capital, total, max_score)?,
+        )
+    } else if hai.total_activities > 0 {
+        let total = hai.total_activities;
+        (
+            ratio_score(hai.compliant_activities, total, max_score)?,
+            ratio_score(hai.asset_backed_activities, total, max_score)?,
+            ratio_score(hai.economic_value_activities, total, max_score)?,
+        )
+    } else {
+        (0, 0, 0)
+    };
+
+    // Share of validator votes cast in favour across all tracked activities
+    let validator_participation_score = if hai.total_validator_count > 0 {
+        ratio_score(hai.total_positive_votes, hai.total_validator_count, max_score)?
+    } else {
+        0
+    };
+
+    Ok(ScoreComponents {
+        compliance_score,
+        asset_backing_score,
+        economic_value_score,
+        validator_participation_score,
+        compliance_component: weighted(compliance_score, hai.compliance_weight)?,
+        asset_backing_component: weighted(asset_backing_score, hai.asset_backing_weight)?,
+        economic_value_component: weighted(economic_value_score, hai.economic_value_weight)?,
+        validator_participation_component: weighted(
+            validator_participation_score,
+            hai.validator_participation_weight,
+        )?,
+        max_score,
+    })
+}
+
+/// Calculate HAI score based on current metrics
+fn calculate_hai_score(hai: &Hai) -> Result<u32> {
+    if hai.total_activities == 0 {
+        return Ok(hai.current_score);
+    }
+
+    calculate_score_components(hai)?.total()
 }
 
 /// Select data sources using verifiable randomness
@@ -338,11 +1271,12 @@ fn select_data_sources_with_randomness(sources: Vec<u8>, randomness: u64) -> Res
         return Ok(vec![]);
     }
     
-    let selection_count = ((randomness % 3) + 1) as usize; // Select 1-3 sources
+    let selection_count = ((randomness % MAX_VRF_SELECTED_SOURCES as u64) + 1) as usize; // Select 1-3 sources
+    let start = (randomness % sources.len() as u64) as usize;
     let mut selected = Vec::new();
     
     for i in 0..selection_count.min(sources.len()) {
-        let index = ((randomness + i as u64) % sources.len() as u64) as usize;
+        let index = (start + i) % sources.len();
         if !selected.contains(&sources[index]) {
             selected.push(sources[index]);
         }
@@ -352,24 +1286,52 @@ fn select_data_sources_with_randomness(sources: Vec<u8>, randomness: u64) -> Res
 }
 
 /// Calculate HAI score with specific data sources
-fn calculate_hai_score_with_sources(hai: &Hai, sources: Vec<u8>) -> Result<u16> {
+fn calculate_hai_score_with_sources(hai: &Hai, sources: Vec<u8>) -> Result<u32> {
     if sources.is_empty() {
         return calculate_hai_score(hai);
     }
     
     // Apply source-specific weighting (simplified)
     let base_score = calculate_hai_score(hai)?;
-    let source_modifier = sources.len() as u16 * 50; // Bonus for multiple sources
+    let per_source = PrecisionMode::Bps.rescale(50, hai.precision_mode)?; // Bonus for multiple sources
+    let source_modifier = sources.len() as u32 * per_source;
     
-    Ok((base_score + source_modifier).min(10000))
+    Ok((base_score + source_modifier).min(hai.precision_mode.max_score()))
 }
 
 // Account structs
 
+/// Bounded ring of the most recent HAI admin actions; every entry is
+/// also emitted as an `AuditEntryEvent`, which is the complete history
+#[account]
+pub struct AuditLog {
+    pub next_sequence: u64,
+    pub entries: Vec<AuditEntry>,
+    pub bump: u8,
+}
+
+impl AuditLog {
+    pub const SPACE: usize = AUDIT_LOG_SPACE;
+
+    /// Append an entry, overwriting the oldest one once the ring is full
+    pub fn record(&mut self, action: AuditAction, actor: Pubkey) -> Result<()> {
+        let entry = push_audit_entry(&mut self.entries, &mut self.next_sequence, action as u8, actor)?;
+
+        emit!(AuditEntryEvent {
+            sequence: entry.sequence,
+            action: entry.action,
+            actor,
+            timestamp: entry.timestamp,
+        });
+
+        Ok(())
+    }
+}
+
 #[account]
 pub struct Hai {
     pub admin: Pubkey,
-    pub current_score: u16,        // 0-10000 (0.00%-100.00%)
+    pub current_score: u32,        // 0 to precision_mode.max_score() (0%-100%)
     pub total_activities: u64,
     pub compliant_activities: u64,
     pub asset_backed_activities: u64,
@@ -380,9 +1342,44 @@ pub struct Hai {
     pub asset_backing_weight: u16,     // Default 2500 (25%)
     pub economic_value_weight: u16,    // Default 2000 (20%)
     pub validator_participation_weight: u16, // Default 1500 (15%)
+    pub is_frozen: bool,           // Emergency stop for score updates
+    pub min_validator_stake: u64,  // Bonded stake required to track (0 = anyone may track)
+    pub creation_nonce: u64,       // Last nonce stamped onto a metrics or snapshot account
+    pub precision_mode: PrecisionMode, // Denominator of current_score
+    pub category_activities: [u64; ActivityCategory::COUNT], // Tracked activities per category
+    pub category_compliant: [u64; ActivityCategory::COUNT],  // Compliant activities per category
+    // Capital-weighted sums backing the blended score
+    pub total_capital: u64,
+    pub compliant_capital: u64,
+    pub asset_backed_capital: u64,
+    pub economic_value_capital: u64,
+    pub min_weight_update_interval: i64, // Cooldown between weight updates (0 = none)
+    pub last_weight_update_at: i64,
+    // Running validator vote totals backing the participation component
+    pub total_validator_count: u64,
+    pub total_positive_votes: u64,
+    pub aggregate_programs: Vec<Pubkey>, // Other HAI deployments aggregate_hai accepts
+    pub final_snapshot_pending: bool, // Set by finalize_hai_session until record_final_snapshot
     pub bump: u8,
 }
 
+impl Hai {
+    pub const SPACE: usize = 8 + 32 + 4 + 8 + 8 + 8 + 8 + 8 + 2 + 2 + 2 + 2 + 1 + 8 + 8 + 1
+        + 8 * ActivityCategory::COUNT + 8 * ActivityCategory::COUNT + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8
+        + 4 + 32 * MAX_AGGREGATE_HAIS + 1 + 1;
+
+    /// Sum of the four score weights, in basis points
+    pub fn weight_sum(&self) -> Result<u32> {
+        let sum = (self.compliance_weight as u32)
+            .checked_add(self.asset_backing_weight as u32)
+            .and_then(|v| v.checked_add(self.economic_value_weight as u32))
+            .and_then(|v| v.checked_add(self.validator_participation_weight as u32))
+            .ok_or(HaiError::MathOverflow)?;
+        Ok(sum)
+    }
+
+}
+
 #[account]
 pub struct ActivityMetrics {
     pub activity_id: [u8; 32],
@@ -392,20 +1389,55 @@ pub struct ActivityMetrics {
     pub validator_count: u32,
     pub positive_votes: u32,
     pub timestamp: i64,
+    pub creation_nonce: u64,    // Distinguishes a recreated account under the same seeds
+    pub category: Option<ActivityCategory>,
+    pub capital: u64,           // Weight of this activity in the blended score
     pub bump: u8,
 }
 
+impl ActivityMetrics {
+    pub const SPACE: usize = 8 + 32 + 1 + 1 + 1 + 4 + 4 + 8 + 8 + 2 + 8 + 1;
+}
+
+/// One entry of a `track_activities_batch` call
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ActivityMetricInput {
+    pub activity_id: [u8; 32],
+    pub is_compliant: bool,
+    pub is_asset_backed: bool,
+    pub has_real_economic_value: bool,
+    pub validator_count: u32,
+    pub positive_votes: u32,
+    pub category: Option<ActivityCategory>,
+    pub capital: u64,
+}
+
+/// Compliance sub-score of one category, as reported by `explain_category_scores`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CategoryScore {
+    pub category: ActivityCategory,
+    pub total_activities: u64,
+    pub compliant_activities: u64,
+    pub score: u32,
+}
+
 #[account]
 pub struct HaiSnapshot {
     pub snapshot_id: u64,
-    pub score: u16,
+    pub score: u32,
     pub total_activities: u64,
     pub compliant_activities: u64,
     pub asset_backed_activities: u64,
     pub timestamp: i64,
+    pub creation_nonce: u64,
+    pub precision_mode: PrecisionMode, // Denominator of score when the snapshot was taken
     pub bump: u8,
 }
 
+impl HaiSnapshot {
+    pub const SPACE: usize = 8 + 8 + 4 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
+}
+
 #[account]
 pub struct Updater {
     pub updater: Pubkey,
@@ -413,24 +1445,63 @@ pub struct Updater {
     pub bump: u8,
 }
 
-// Context structs
-
+/// A VRF randomness request awaiting the oracle, closed by the callback
+#[account]
+pub struct VrfRequest {
+    pub activity_id: [u8; 32],
+    pub requester: Pubkey,          // Paid the rent; refunded when the callback closes the request
+    pub data_sources: Vec<u8>,      // Candidates the randomness samples from
+    pub caller_seed: [u8; 32],
+    pub requested_at: i64,
+    pub bump: u8,
+}
+
+impl VrfRequest {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + MAX_VRF_DATA_SOURCES + 32 + 8 + 1;
+}
+
+// Context structs
+
+#[derive(Accounts)]
+pub struct InitializeHai<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Hai::SPACE,
+        seeds = [b"hai"],
+        bump
+    )]
+    pub hai: Account<'info, Hai>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
-pub struct InitializeHai<'info> {
+pub struct InitializeAuditLog<'info> {
+    #[account(
+        seeds = [b"hai"],
+        bump = hai.bump,
+        constraint = hai.admin == admin.key() @ HaiError::Unauthorized
+    )]
+    pub hai: Account<'info, Hai>,
+
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 2 + 8 + 8 + 8 + 8 + 8 + 2 + 2 + 2 + 2 + 1,
-        seeds = [b"hai"],
+        space = AuditLog::SPACE,
+        seeds = [b"audit_log"],
         bump
     )]
-    pub hai: Account<'info, Hai>,
+    pub audit_log: Account<'info, AuditLog>,
+
     #[account(mut)]
     pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
 pub struct TrackActivity<'info> {
     #[account(
         mut,
@@ -442,17 +1513,99 @@ pub struct TrackActivity<'info> {
     #[account(
         init,
         payer = payer,
-        space = 8 + 32 + 1 + 1 + 1 + 4 + 4 + 8 + 1,
+        space = ActivityMetrics::SPACE,
         seeds = [b"metrics", activity_id.as_ref()],
         bump
     )]
     pub metrics: Account<'info, ActivityMetrics>,
 
+    /// Validator of the payer in the default amana-reserve reserve; required while `min_validator_stake` is set
+    #[account(
+        seeds = [b"validator", validator.authority.as_ref()],
+        bump = validator.bump,
+        seeds::program = amana_reserve::ID
+    )]
+    pub validator: Option<Account<'info, Validator>>,
+
+    /// The activity in the default amana-reserve reserve; required when `capital` is non-zero
+    #[account(
+        seeds = [b"activity", activity_id.as_ref()],
+        bump = activity.bump,
+        seeds::program = amana_reserve::ID
+    )]
+    pub activity: Option<Account<'info, Activity>>,
+
+    /// Updater record of `payer`; required unless `payer` is the HAI admin
+    #[account(
+        seeds = [b"updater", payer.key().as_ref()],
+        bump = updater_account.bump
+    )]
+    pub updater_account: Option<Account<'info, Updater>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TrackActivitiesBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"hai"],
+        bump = hai.bump
+    )]
+    pub hai: Account<'info, Hai>,
+
+    /// Validator of the payer in the default amana-reserve reserve; required while `min_validator_stake` is set
+    #[account(
+        seeds = [b"validator", validator.authority.as_ref()],
+        bump = validator.bump,
+        seeds::program = amana_reserve::ID
+    )]
+    pub validator: Option<Account<'info, Validator>>,
+
+    /// Updater record of `payer`; required unless `payer` is the HAI admin
+    #[account(
+        seeds = [b"updater", payer.key().as_ref()],
+        bump = updater_account.bump
+    )]
+    pub updater_account: Option<Account<'info, Updater>>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct VoidActivityMetric<'info> {
+    #[account(
+        mut,
+        seeds = [b"hai"],
+        bump = hai.bump,
+        constraint = hai.admin == admin.key() @ HaiError::Unauthorized
+    )]
+    pub hai: Account<'info, Hai>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"metrics", activity_id.as_ref()],
+        bump = metrics.bump
+    )]
+    pub metrics: Account<'info, ActivityMetrics>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct CreateSnapshot<'info> {
     #[account(
@@ -465,12 +1618,19 @@ pub struct CreateSnapshot<'info> {
     #[account(
         init,
         payer = payer,
-        space = 8 + 8 + 2 + 8 + 8 + 8 + 8 + 1,
+        space = HaiSnapshot::SPACE,
         seeds = [b"snapshot", hai.snapshot_count.to_le_bytes().as_ref()],
         bump
     )]
     pub snapshot: Account<'info, HaiSnapshot>,
 
+    /// Updater record of `payer`; required unless `payer` is the HAI admin
+    #[account(
+        seeds = [b"updater", payer.key().as_ref()],
+        bump = updater_account.bump
+    )]
+    pub updater_account: Option<Account<'info, Updater>>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -485,10 +1645,77 @@ pub struct UpdateWeights<'info> {
         constraint = hai.admin == admin.key() @ HaiError::Unauthorized
     )]
     pub hai: Account<'info, Hai>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetHaiFrozen<'info> {
+    #[account(
+        mut,
+        seeds = [b"hai"],
+        bump = hai.bump,
+        constraint = hai.admin == admin.key() @ HaiError::Unauthorized
+    )]
+    pub hai: Account<'info, Hai>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHaiConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"hai"],
+        bump = hai.bump,
+        constraint = hai.admin == admin.key() @ HaiError::Unauthorized
+    )]
+    pub hai: Account<'info, Hai>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
+pub struct AggregateHai<'info> {
+    #[account(
+        seeds = [b"hai"],
+        bump = hai.bump
+    )]
+    pub hai: Account<'info, Hai>,
+}
+
+#[derive(Accounts)]
+pub struct ExplainScore<'info> {
+    #[account(
+        seeds = [b"hai"],
+        bump = hai.bump
+    )]
+    pub hai: Account<'info, Hai>,
+}
+
+#[derive(Accounts)]
+#[instruction(updater: Pubkey)]
 pub struct AuthorizeUpdater<'info> {
     #[account(
         seeds = [b"hai"],
@@ -506,6 +1733,13 @@ pub struct AuthorizeUpdater<'info> {
     )]
     pub updater_account: Account<'info, Updater>,
 
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
     #[account(mut)]
     pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -527,24 +1761,80 @@ pub struct RevokeUpdater<'info> {
     )]
     pub hai: Account<'info, Hai>,
 
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
     pub admin: Signer<'info>,
 }
 
 // ========== MagicBlock Context Structs ==========
 
+/// `#[vrf]` adds the program identity, VRF program, slot hashes and system
+/// program accounts the randomness request needs.
+#[vrf]
 #[derive(Accounts)]
-pub struct UpdateHaiScoreVrf<'info> {
+#[instruction(activity_id: [u8; 32])]
+pub struct RequestHaiRandomness<'info> {
     #[account(
-        mut,
         seeds = [b"hai"],
         bump = hai.bump
     )]
     pub hai: Account<'info, Hai>,
-    
+
+    #[account(
+        init,
+        payer = payer,
+        space = VrfRequest::SPACE,
+        seeds = [b"vrf_request", activity_id.as_ref()],
+        bump
+    )]
+    pub vrf_request: Account<'info, VrfRequest>,
+
+    /// Updater record of `payer`; required unless `payer` is the HAI admin
+    #[account(
+        seeds = [b"updater", payer.key().as_ref()],
+        bump = updater_account.bump
+    )]
+    pub updater_account: Option<Account<'info, Updater>>,
+
+    /// CHECK: MagicBlock VRF oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CallbackHaiRandomness<'info> {
+    /// Signs only when the VRF program invokes the callback
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"hai"],
+        bump = hai.bump
+    )]
+    pub hai: Account<'info, Hai>,
+
+    #[account(
+        mut,
+        seeds = [b"vrf_request", vrf_request.activity_id.as_ref()],
+        bump = vrf_request.bump,
+        close = requester
+    )]
+    pub vrf_request: Account<'info, VrfRequest>,
+
+    /// Receives the request's rent back
+    #[account(mut, address = vrf_request.requester)]
+    pub requester: SystemAccount<'info>,
+}
+
 #[commit]
 #[derive(Accounts)]
 pub struct CommitHaiScores<'info> {
@@ -564,6 +1854,32 @@ pub struct CommitHaiScores<'info> {
     pub magic_program: AccountInfo<'info>,
 }
 
+#[commit]
+#[derive(Accounts)]
+pub struct FinalizeHaiSession<'info> {
+    #[account(
+        mut,
+        seeds = [b"hai"],
+        bump = hai.bump
+    )]
+    pub hai: Account<'info, Hai>,
+
+    /// Updater record of `payer`; required unless `payer` is the HAI admin
+    #[account(
+        seeds = [b"updater", payer.key().as_ref()],
+        bump = updater_account.bump
+    )]
+    pub updater_account: Option<Account<'info, Updater>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: MagicBlock context account
+    pub magic_context: AccountInfo<'info>,
+    /// CHECK: MagicBlock program
+    pub magic_program: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateHaiRealtime<'info> {
     #[account(
@@ -572,16 +1888,103 @@ pub struct UpdateHaiRealtime<'info> {
         bump = hai.bump
     )]
     pub hai: Account<'info, Hai>,
-    
+
+    /// Updater record of `payer`; required unless `payer` is the HAI admin
+    #[account(
+        seeds = [b"updater", payer.key().as_ref()],
+        bump = updater_account.bump
+    )]
+    pub updater_account: Option<Account<'info, Updater>>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
 }
 
+// Enums
+
+/// Admin actions recorded in the audit log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    UpdateWeights = 1,
+    AuthorizeUpdater = 2,
+    RevokeUpdater = 3,
+    VoidActivityMetric = 4,
+    FreezeHai = 5,
+    UnfreezeHai = 6,
+    SetMinValidatorStake = 7,
+    SetPrecisionMode = 8,
+    SetWeightUpdateInterval = 9,
+    SetAggregatePrograms = 10,
+}
+
+/// Denominator of the HAI score
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PrecisionMode {
+    Bps,    // Out of MAX_SCORE_BPS (10_000)
+    Ppm,    // Out of MAX_SCORE_PPM (1_000_000)
+}
+
+impl PrecisionMode {
+    /// Score representing 100% in this mode
+    pub fn max_score(self) -> u32 {
+        match self {
+            PrecisionMode::Bps => MAX_SCORE_BPS,
+            PrecisionMode::Ppm => MAX_SCORE_PPM,
+        }
+    }
+
+    /// Convert `score` from this mode to `target`, rounding down
+    pub fn rescale(self, score: u32, target: PrecisionMode) -> Result<u32> {
+        let rescaled = (score as u64)
+            .checked_mul(target.max_score() as u64)
+            .ok_or(HaiError::MathOverflow)?
+            / self.max_score() as u64;
+        Ok(rescaled as u32)
+    }
+}
+
+/// Sector an activity belongs to, for per-category sub-scores
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityCategory {
+    RealEstate,
+    Trade,
+    Agriculture,
+    Manufacturing,
+    Services,
+    Other,
+}
+
+impl ActivityCategory {
+    pub const COUNT: usize = 6;
+
+    pub const ALL: [ActivityCategory; ActivityCategory::COUNT] = [
+        ActivityCategory::RealEstate,
+        ActivityCategory::Trade,
+        ActivityCategory::Agriculture,
+        ActivityCategory::Manufacturing,
+        ActivityCategory::Services,
+        ActivityCategory::Other,
+    ];
+
+    /// Slot of this category in the `Hai` per-category counters
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
 // Events
 
+#[event]
+pub struct AuditEntryEvent {
+    pub sequence: u64,
+    pub action: u8,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct HaiInitializedEvent {
-    pub initial_score: u16,
+    pub initial_score: u32,
 }
 
 #[event]
@@ -589,13 +1992,29 @@ pub struct ActivityTrackedEvent {
     pub activity_id: [u8; 32],
     pub is_compliant: bool,
     pub is_asset_backed: bool,
-    pub new_score: u16,
+    pub category: Option<ActivityCategory>,
+    pub capital: u64,
+    pub new_score: u32,
+    pub creation_nonce: u64,
+}
+
+#[event]
+pub struct ActivitiesBatchTrackedEvent {
+    pub activity_ids: Vec<[u8; 32]>,
+    pub new_score: u32,
+}
+
+#[event]
+pub struct MetricVoidedEvent {
+    pub activity_id: [u8; 32],
+    pub new_score: u32,
 }
 
 #[event]
 pub struct SnapshotCreatedEvent {
     pub snapshot_id: u64,
-    pub score: u16,
+    pub score: u32,
+    pub creation_nonce: u64,
 }
 
 #[event]
@@ -606,6 +2025,74 @@ pub struct WeightsUpdatedEvent {
     pub validator_participation_weight: u16,
 }
 
+#[event]
+pub struct HaiFreezeUpdatedEvent {
+    pub is_frozen: bool,
+}
+
+#[event]
+pub struct MinValidatorStakeUpdatedEvent {
+    pub min_validator_stake: u64,
+}
+
+#[event]
+pub struct WeightUpdateIntervalUpdatedEvent {
+    pub min_weight_update_interval: i64,
+}
+
+#[event]
+pub struct PrecisionModeUpdatedEvent {
+    pub precision_mode: PrecisionMode,
+    pub current_score: u32,
+}
+
+#[event]
+pub struct WeightsValidatedEvent {
+    pub weight_sum: u32,
+    pub is_valid: bool,
+}
+
+#[event]
+pub struct HaiVerifiedEvent {
+    pub computed_score: u32,
+    pub stored_score: u32,
+}
+
+#[event]
+pub struct AggregateHaiEvent {
+    pub aggregate_score: u32,   // Basis points
+    pub reserve_count: u8,
+}
+
+#[event]
+pub struct AggregateProgramsUpdatedEvent {
+    pub aggregate_programs: Vec<Pubkey>,
+}
+
+#[event]
+pub struct ScoreExplanationEvent {
+    pub compliance_weight: u16,
+    pub asset_backing_weight: u16,
+    pub economic_value_weight: u16,
+    pub validator_participation_weight: u16,
+    pub compliance_score: u64,
+    pub asset_backing_score: u64,
+    pub economic_value_score: u64,
+    pub validator_participation_score: u64,
+    pub compliance_component: u64,
+    pub asset_backing_component: u64,
+    pub economic_value_component: u64,
+    pub validator_participation_component: u64,
+    pub computed_score: u32,
+    pub current_score: u32,
+}
+
+#[event]
+pub struct CategoryScoresEvent {
+    pub scores: Vec<CategoryScore>,
+    pub current_score: u32,
+}
+
 #[event]
 pub struct UpdaterAuthorizedEvent {
     pub updater: Pubkey,
@@ -618,11 +2105,19 @@ pub struct UpdaterRevokedEvent {
 
 // ========== MagicBlock Events ==========
 
+#[event]
+pub struct HaiRandomnessRequestedEvent {
+    pub activity_id: [u8; 32],
+    pub requester: Pubkey,
+    pub caller_seed: [u8; 32],
+}
+
 #[event]
 pub struct HaiScoreUpdatedWithVrfEvent {
     pub activity_id: [u8; 32],
-    pub new_score: u16,
-    pub randomness: u64,
+    pub new_score: u32,
+    pub randomness: [u8; 32],
+    pub selected_sources: Vec<u8>,
 }
 
 #[event]
@@ -630,11 +2125,18 @@ pub struct HaiScoresCommittedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct HaiUndelegatedEvent {
+    pub snapshot_id: u64,       // Id record_final_snapshot will give the pending snapshot
+    pub triggered_by: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct HaiRealtimeUpdateEvent {
     pub activity_id: [u8; 32],
-    pub old_score: u16,
-    pub new_score: u16,
+    pub old_score: u32,
+    pub new_score: u32,
     pub delta: i16,
 }
 
@@ -648,4 +2150,34 @@ pub enum HaiError {
     InvalidWeights,
     #[msg("Unauthorized access")]
     Unauthorized,
+    #[msg("Batch must hold between 1 and MAX_TRACK_BATCH entries")]
+    InvalidBatchSize,
+    #[msg("Invalid account passed in remaining accounts")]
+    InvalidRemainingAccount,
+    #[msg("Activity referenced more than once")]
+    DuplicateActivity,
+    #[msg("HAI updates are frozen")]
+    Frozen,
+    #[msg("A bonded validator must sign")]
+    ValidatorRequired,
+    #[msg("Weights were updated too recently")]
+    WeightUpdateTooSoon,
+    #[msg("Weight update interval cannot be negative")]
+    InvalidWeightUpdateInterval,
+    #[msg("Too many data sources for one VRF request")]
+    TooManyDataSources,
+    #[msg("Stored score differs from the score recomputed from tracked metrics")]
+    ScoreMismatch,
+    #[msg("Aggregation must cover between 1 and MAX_AGGREGATE_HAIS accounts")]
+    InvalidAggregateCount,
+    #[msg("Positive votes cannot exceed the validator count")]
+    InvalidVotes,
+    #[msg("Aggregate program listed more than once")]
+    DuplicateAggregateProgram,
+    #[msg("A finalized session's snapshot must be recorded first")]
+    FinalSnapshotPending,
+    #[msg("No finalized session is waiting for its snapshot")]
+    NoFinalSnapshotPending,
+    #[msg("Tracked capital must match the reserve activity's capital_required")]
+    CapitalNotBooked,
 }
@@ -15,7 +15,19 @@
 //!
 //! This program handles sensitive operations that require privacy
 //! using MagicBlock's Private Ephemeral Rollups (PER) and TEE.
-
+//!
+//! Private deployments are reconciled with the public reserve through the
+//! `[b"reserve_bridge"]` PDA of this program. The amana-reserve admin registers
+//! that PDA with `set_private_bridge`, after which `deploy_capital_private`
+//! signs a CPI into `debit_private_deployment` to move the committed amount out
+//! of the reserve's free capital, and `settle_capital_private` signs the
+//! matching `credit_private_deployment` to move it back. The reserve only
+//! accepts either from its admin or a deployer it has authorized, and caps
+//! deployments at its free capital. The activity's details stay encrypted;
+//! only the fact that capital left the public pool is visible.
+
+use amana_reserve::program::AmanaReserve;
+use amana_reserve::Reserve;
 use anchor_lang::prelude::*;
 use ephemeral_rollups_sdk::anchor::{commit, ephemeral};
 use ephemeral_rollups_sdk::ephem::commit_accounts;
@@ -33,16 +45,22 @@ pub mod amana_private {
         private_state.admin = ctx.accounts.admin.key();
         private_state.total_private_capital = 0;
         private_state.private_activities_count = 0;
+        private_state.max_reveal_delay_seconds = 0;
         private_state.bump = ctx.bumps.private_state;
         Ok(())
     }
 
     /// Deploy capital privately (amount hidden)
+    ///
+    /// `committed_amount` is debited from the public reserve's free capital
+    /// by CPI so the two ledgers reconcile. The deployer must be the reserve
+    /// admin or pass its authorized `private_deployer` record.
     pub fn deploy_capital_private(
         ctx: Context<DeployCapitalPrivate>,
         encrypted_amount: [u8; 32], // Encrypted amount
         activity_hash: [u8; 32],    // Hash of activity details
         tee_attestation: [u8; 64],  // TEE attestation
+        committed_amount: u64,      // Amount debited from the public reserve
     ) -> Result<()> {
         let private_state = &mut ctx.accounts.private_state;
         let private_activity = &mut ctx.accounts.private_activity;
@@ -57,10 +75,31 @@ pub mod amana_private {
         private_activity.deployer = ctx.accounts.deployer.key();
         private_activity.timestamp = Clock::get()?.unix_timestamp;
         private_activity.is_active = true;
+        private_activity.reveal_deadline = 0;
+        private_activity.committed_amount = committed_amount;
         private_activity.bump = ctx.bumps.private_activity;
 
         private_state.private_activities_count += 1;
 
+        let bridge_seeds: &[&[u8]] = &[b"reserve_bridge", &[ctx.bumps.bridge_authority]];
+        amana_reserve::cpi::debit_private_deployment(
+            CpiContext::new_with_signer(
+                ctx.accounts.amana_reserve_program.to_account_info(),
+                amana_reserve::cpi::accounts::DebitPrivateDeployment {
+                    reserve: ctx.accounts.reserve.to_account_info(),
+                    bridge_authority: ctx.accounts.bridge_authority.to_account_info(),
+                    deployer: ctx.accounts.deployer.to_account_info(),
+                    private_deployer: ctx
+                        .accounts
+                        .private_deployer
+                        .as_ref()
+                        .map(|record| record.to_account_info()),
+                },
+                &[bridge_seeds],
+            ),
+            committed_amount,
+        )?;
+
         emit!(PrivateCapitalDeployedEvent {
             activity_hash,
             deployer: ctx.accounts.deployer.key(),
@@ -69,6 +108,43 @@ pub mod amana_private {
         Ok(())
     }
 
+    /// Settle a private deployment, returning its capital to the public reserve
+    ///
+    /// Signs a CPI into `credit_private_deployment` for the activity's
+    /// `committed_amount`, which the reserve accepts under the same
+    /// admin-or-deployer check as the deployment. Settles once.
+    pub fn settle_capital_private(ctx: Context<SettleCapitalPrivate>) -> Result<()> {
+        let private_activity = &mut ctx.accounts.private_activity;
+        require!(private_activity.is_active, PrivateError::ActivityNotActive);
+        private_activity.is_active = false;
+
+        let bridge_seeds: &[&[u8]] = &[b"reserve_bridge", &[ctx.bumps.bridge_authority]];
+        amana_reserve::cpi::credit_private_deployment(
+            CpiContext::new_with_signer(
+                ctx.accounts.amana_reserve_program.to_account_info(),
+                amana_reserve::cpi::accounts::DebitPrivateDeployment {
+                    reserve: ctx.accounts.reserve.to_account_info(),
+                    bridge_authority: ctx.accounts.bridge_authority.to_account_info(),
+                    deployer: ctx.accounts.authority.to_account_info(),
+                    private_deployer: ctx
+                        .accounts
+                        .private_deployer
+                        .as_ref()
+                        .map(|record| record.to_account_info()),
+                },
+                &[bridge_seeds],
+            ),
+            private_activity.committed_amount,
+        )?;
+
+        emit!(PrivateCapitalSettledEvent {
+            activity_hash: private_activity.activity_hash,
+            settled_by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
     /// Calculate private HAI score with TEE
     pub fn calculate_private_hai(
         ctx: Context<CalculatePrivateHai>,
@@ -116,17 +192,66 @@ pub mod amana_private {
         Ok(())
     }
 
+    /// Set the maximum delay between a reveal request and the reveal
+    ///
+    /// Zero leaves reveals unbounded; otherwise every reveal needs a pending
+    /// `request_reveal` whose deadline has not passed.
+    pub fn set_max_reveal_delay(
+        ctx: Context<UpdatePrivateConfig>,
+        max_reveal_delay_seconds: i64,
+    ) -> Result<()> {
+        require!(max_reveal_delay_seconds >= 0, PrivateError::InvalidRevealDelay);
+
+        ctx.accounts.private_state.max_reveal_delay_seconds = max_reveal_delay_seconds;
+
+        emit!(RevealDelayUpdatedEvent { max_reveal_delay_seconds });
+
+        Ok(())
+    }
+
+    /// Open a reveal window for a private activity (e.g. on an audit request)
+    ///
+    /// Re-requesting replaces any earlier deadline, which is how an expired
+    /// window is re-authorized.
+    pub fn request_reveal(ctx: Context<RequestReveal>) -> Result<()> {
+        let max_delay = ctx.accounts.private_state.max_reveal_delay_seconds;
+        require!(max_delay > 0, PrivateError::InvalidRevealDelay);
+
+        let private_activity = &mut ctx.accounts.private_activity;
+        private_activity.reveal_deadline = Clock::get()?
+            .unix_timestamp
+            .checked_add(max_delay)
+            .ok_or(PrivateError::MathOverflow)?;
+
+        emit!(RevealRequestedEvent {
+            activity_hash: private_activity.activity_hash,
+            reveal_deadline: private_activity.reveal_deadline,
+        });
+
+        Ok(())
+    }
+
     /// Reveal private data with authorization
     pub fn reveal_private_data(
         ctx: Context<RevealPrivateData>,
         decryption_key: [u8; 32],
         authorization_proof: [u8; 64],
     ) -> Result<()> {
-        let private_activity = &ctx.accounts.private_activity;
+        let private_activity = &mut ctx.accounts.private_activity;
 
         // Verify authorization (simplified - would check Sharia Board approval)
         require!(authorization_proof != [0; 64], PrivateError::UnauthorizedReveal);
 
+        // Disclosure SLA: the reveal must land inside the requested window
+        if ctx.accounts.private_state.max_reveal_delay_seconds > 0 {
+            require!(private_activity.reveal_deadline != 0, PrivateError::RevealNotRequested);
+            require!(
+                Clock::get()?.unix_timestamp <= private_activity.reveal_deadline,
+                PrivateError::RevealWindowExpired
+            );
+            private_activity.reveal_deadline = 0;
+        }
+
         // In production, this would decrypt and return the actual amount
         emit!(PrivateDataRevealedEvent {
             activity_hash: private_activity.activity_hash,
@@ -144,6 +269,7 @@ pub struct PrivateState {
     pub admin: Pubkey,
     pub total_private_capital: u64, // This remains encrypted/hidden
     pub private_activities_count: u64,
+    pub max_reveal_delay_seconds: i64, // Reveal window after request_reveal (0 = unbounded)
     pub bump: u8,
 }
 
@@ -155,6 +281,8 @@ pub struct PrivateActivity {
     pub deployer: Pubkey,
     pub timestamp: i64,
     pub is_active: bool,
+    pub reveal_deadline: i64,   // Last moment a requested reveal is allowed (0 = none pending)
+    pub committed_amount: u64,  // Debited from the public reserve; credited back on settlement
     pub bump: u8,
 }
 
@@ -173,7 +301,7 @@ pub struct InitializePrivate<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 8 + 8 + 1,
+        space = 8 + 32 + 8 + 8 + 8 + 1,
         seeds = [b"private_state"],
         bump
     )]
@@ -184,6 +312,7 @@ pub struct InitializePrivate<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(encrypted_amount: [u8; 32], activity_hash: [u8; 32])]
 pub struct DeployCapitalPrivate<'info> {
     #[account(
         mut,
@@ -195,17 +324,65 @@ pub struct DeployCapitalPrivate<'info> {
     #[account(
         init,
         payer = deployer,
-        space = 8 + 32 + 32 + 64 + 32 + 8 + 1 + 1,
-        seeds = [b"private_activity", deployer.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()],
+        space = 8 + 32 + 32 + 64 + 32 + 8 + 1 + 8 + 8 + 1,
+        seeds = [b"private_activity", deployer.key().as_ref(), activity_hash.as_ref()],
         bump
     )]
     pub private_activity: Account<'info, PrivateActivity>,
 
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        seeds::program = amana_reserve_program.key()
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// CHECK: PDA signer registered as the reserve's private bridge
+    #[account(seeds = [b"reserve_bridge"], bump)]
+    pub bridge_authority: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub deployer: Signer<'info>,
+
+    /// CHECK: The deployer's `PrivateDeployer` record, validated by amana-reserve;
+    /// required unless the deployer is the reserve admin
+    pub private_deployer: Option<UncheckedAccount<'info>>,
+
+    pub amana_reserve_program: Program<'info, AmanaReserve>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SettleCapitalPrivate<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_activity", private_activity.deployer.as_ref(), private_activity.activity_hash.as_ref()],
+        bump = private_activity.bump
+    )]
+    pub private_activity: Account<'info, PrivateActivity>,
+
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        seeds::program = amana_reserve_program.key()
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// CHECK: PDA signer registered as the reserve's private bridge
+    #[account(seeds = [b"reserve_bridge"], bump)]
+    pub bridge_authority: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: The authority's `PrivateDeployer` record, validated by amana-reserve;
+    /// required unless the authority is the reserve admin
+    pub private_deployer: Option<UncheckedAccount<'info>>,
+
+    pub amana_reserve_program: Program<'info, AmanaReserve>,
+}
+
 #[derive(Accounts)]
 pub struct CalculatePrivateHai<'info> {
     #[account(
@@ -236,10 +413,47 @@ pub struct CommitPrivateState<'info> {
     pub magic_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct UpdatePrivateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"private_state"],
+        bump = private_state.bump,
+        constraint = private_state.admin == admin.key() @ PrivateError::Unauthorized
+    )]
+    pub private_state: Account<'info, PrivateState>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestReveal<'info> {
+    #[account(
+        seeds = [b"private_state"],
+        bump = private_state.bump,
+        constraint = private_state.admin == admin.key() @ PrivateError::Unauthorized
+    )]
+    pub private_state: Account<'info, PrivateState>,
+
+    #[account(
+        mut,
+        seeds = [b"private_activity", private_activity.deployer.as_ref(), private_activity.activity_hash.as_ref()],
+        bump = private_activity.bump
+    )]
+    pub private_activity: Account<'info, PrivateActivity>,
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct RevealPrivateData<'info> {
     #[account(
-        seeds = [b"private_activity", private_activity.deployer.as_ref()],
+        seeds = [b"private_state"],
+        bump = private_state.bump
+    )]
+    pub private_state: Account<'info, PrivateState>,
+
+    #[account(
+        mut,
+        seeds = [b"private_activity", private_activity.deployer.as_ref(), private_activity.activity_hash.as_ref()],
         bump = private_activity.bump
     )]
     pub private_activity: Account<'info, PrivateActivity>,
@@ -254,6 +468,12 @@ pub struct PrivateCapitalDeployedEvent {
     pub deployer: Pubkey,
 }
 
+#[event]
+pub struct PrivateCapitalSettledEvent {
+    pub activity_hash: [u8; 32],
+    pub settled_by: Pubkey,
+}
+
 #[event]
 pub struct PrivateHaiCalculatedEvent {
     pub timestamp: i64,
@@ -264,6 +484,17 @@ pub struct PrivateStateCommittedEvent {
     pub proof_hash: [u8; 32],
 }
 
+#[event]
+pub struct RevealDelayUpdatedEvent {
+    pub max_reveal_delay_seconds: i64,
+}
+
+#[event]
+pub struct RevealRequestedEvent {
+    pub activity_hash: [u8; 32],
+    pub reveal_deadline: i64,
+}
+
 #[event]
 pub struct PrivateDataRevealedEvent {
     pub activity_hash: [u8; 32],
@@ -282,4 +513,16 @@ pub enum PrivateError {
     InvalidZkProof,
     #[msg("Unauthorized data reveal")]
     UnauthorizedReveal,
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    #[msg("Math operation overflow")]
+    MathOverflow,
+    #[msg("Invalid reveal delay")]
+    InvalidRevealDelay,
+    #[msg("No reveal has been requested for this activity")]
+    RevealNotRequested,
+    #[msg("Reveal window has expired - request the reveal again")]
+    RevealWindowExpired,
+    #[msg("Private activity has already been settled")]
+    ActivityNotActive,
 }
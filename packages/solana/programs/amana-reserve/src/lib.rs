@@ -24,7 +24,13 @@
 //! - Real-time operations via Ephemeral Rollups
 //! - Zero-fee micro-transactions
 
+use amana_common::{
+    create_pda_account, push_audit_entry, AuditEntry, AUDIT_LOG_CAPACITY, AUDIT_LOG_SPACE,
+};
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
 use ephemeral_rollups_sdk::anchor::{delegate, commit, ephemeral};
 use ephemeral_rollups_sdk::cpi::DelegateConfig;
 use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
@@ -38,43 +44,153 @@ pub mod amana_reserve {
         ctx: Context<Initialize>,
         min_capital_contribution: u64,
         max_participants: u64,
+    ) -> Result<()> {
+        init_reserve(
+            &mut ctx.accounts.reserve,
+            ctx.accounts.admin.key(),
+            [0; 32],
+            String::new(),
+            min_capital_contribution,
+            max_participants,
+            ctx.bumps.reserve,
+        );
+        Ok(())
+    }
+
+    /// Initialize the reserve unless it already exists
+    ///
+    /// Safe for deploy scripts to re-run. A fresh account is set up exactly as
+    /// `initialize` would; an existing one is left untouched, provided the
+    /// signer is its admin and the parameters match what is stored, so a re-run
+    /// can never reset state or take over the reserve.
+    pub fn initialize_if_needed(
+        ctx: Context<InitializeIfNeeded>,
+        min_capital_contribution: u64,
+        max_participants: u64,
     ) -> Result<()> {
         let reserve = &mut ctx.accounts.reserve;
-        reserve.admin = ctx.accounts.admin.key();
-        reserve.min_capital_contribution = min_capital_contribution;
-        reserve.max_participants = max_participants;
-        reserve.total_capital = 0;
-        reserve.participant_count = 0;
-        reserve.is_initialized = true;
-        reserve.bump = ctx.bumps.reserve;
+
+        if reserve.is_initialized {
+            require_keys_eq!(reserve.admin, ctx.accounts.admin.key(), AmanaError::Unauthorized);
+            require!(
+                reserve.min_capital_contribution == min_capital_contribution
+                    && reserve.max_participants == max_participants,
+                AmanaError::InitializationMismatch
+            );
+            return Ok(());
+        }
+
+        init_reserve(
+            reserve,
+            ctx.accounts.admin.key(),
+            [0; 32],
+            String::new(),
+            min_capital_contribution,
+            max_participants,
+            ctx.bumps.reserve,
+        );
+        Ok(())
+    }
+
+    /// Initialize an additional reserve identified by `name`
+    ///
+    /// The reserve lives at `[b"reserve", name]` (trailing zero bytes of
+    /// `name` dropped) and every account belonging to it is seeded with the
+    /// same id after its prefix, so reserves under one deployment never share
+    /// participants, activities or funds.
+    pub fn initialize_named_reserve(
+        ctx: Context<InitializeNamedReserve>,
+        name: [u8; 32],
+        metadata_uri: String,
+        min_capital_contribution: u64,
+        max_participants: u64,
+    ) -> Result<()> {
+        require!(!name_seed(&name).is_empty(), AmanaError::InvalidReserveName);
+        require!(
+            metadata_uri.len() <= MAX_METADATA_URI_LEN,
+            AmanaError::MetadataUriTooLong
+        );
+
+        init_reserve(
+            &mut ctx.accounts.reserve,
+            ctx.accounts.admin.key(),
+            name,
+            metadata_uri,
+            min_capital_contribution,
+            max_participants,
+            ctx.bumps.reserve,
+        );
+        Ok(())
+    }
+
+    /// Create the reserve's audit log of admin actions
+    pub fn initialize_audit_log(ctx: Context<InitializeAuditLog>) -> Result<()> {
+        let audit_log = &mut ctx.accounts.audit_log;
+        audit_log.next_sequence = 0;
+        audit_log.entries = Vec::with_capacity(AUDIT_LOG_CAPACITY);
+        audit_log.bump = ctx.bumps.audit_log;
         Ok(())
     }
 
     /// Join the reserve as a participant
-    pub fn join_reserve(ctx: Context<JoinReserve>, amount: u64) -> Result<()> {
+    ///
+    /// When `entry_fee_bps` is set, that share of `amount` is a one-off
+    /// sadaqah donation sent straight to the charity; only the remainder
+    /// becomes capital. Nothing is ever taken from returns.
+    ///
+    /// Every denylist page must be passed via `remaining_accounts`, in page
+    /// order, so the joiner can be screened against the full list.
+    ///
+    /// The contribution must also satisfy the `tier`'s bounds, and the tier's
+    /// lockup applies from the join. Anyone may join as `Retail`; any other
+    /// tier must be vouched for by the admin co-signing as `tier_approver`.
+    /// Tiers never change how profit or loss is shared.
+    pub fn join_reserve<'info>(
+        ctx: Context<'_, '_, '_, 'info, JoinReserve<'info>>,
+        amount: u64,
+        tier: ParticipantTier,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+        require_not_denied(
+            &ctx.accounts.reserve,
+            &ctx.accounts.user.key(),
+            ctx.remaining_accounts,
+        )?;
+
         let reserve = &mut ctx.accounts.reserve;
         let participant = &mut ctx.accounts.participant;
 
+        let donation = ((amount as u128)
+            .checked_mul(reserve.entry_fee_bps as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / 10000) as u64;
+        let amount = amount
+            .checked_sub(donation)
+            .ok_or(AmanaError::MathOverflow)?;
+
         // Check minimum contribution
         require!(
             amount >= reserve.min_capital_contribution,
             AmanaError::InsufficientContribution
         );
+        require!(
+            tier == ParticipantTier::Retail || ctx.accounts.tier_approver.is_some(),
+            AmanaError::Unauthorized
+        );
+        let tier_config = reserve.tier_config(tier);
+        tier_config.check_contribution(amount)?;
 
         // Check max participants
         require!(
             reserve.participant_count < reserve.max_participants,
             AmanaError::MaxParticipantsReached
         );
+        reserve.require_capacity(amount)?;
 
         // Transfer capital to reserve
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::solana_program::system_instruction::Transfer {
-                from: ctx.accounts.user.to_account_info(),
-                to: ctx.accounts.reserve.to_account_info(),
-            },
-        );
         anchor_lang::solana_program::program::invoke(
             &anchor_lang::solana_program::system_instruction::transfer(
                 &ctx.accounts.user.key(),
@@ -88,63 +204,264 @@ pub mod amana_reserve {
             ],
         )?;
 
+        // Route the entry donation to the charity
+        if donation > 0 {
+            let charity = ctx.accounts.charity.as_ref().ok_or(AmanaError::InvalidCharity)?;
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.user.key(),
+                    &charity.key(),
+                    donation,
+                ),
+                &[
+                    ctx.accounts.user.to_account_info(),
+                    charity.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+
+            emit!(EntryDonationEvent {
+                agent: ctx.accounts.user.key(),
+                charity: charity.key(),
+                amount: donation,
+            });
+        }
+
         // Initialize participant state
         participant.agent = ctx.accounts.user.key();
         participant.capital_contributed = amount;
         participant.profit_share = 0;
         participant.loss_share = 0;
+        participant.pending_profit = 0;
         participant.is_active = true;
+        participant.is_frozen = false;
         participant.joined_at = Clock::get()?.unix_timestamp;
+        participant.preferred_mint = Pubkey::default();
+        participant.tier = tier;
+        participant.locked_until = participant.joined_at
+            .checked_add(tier_config.lockup_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        participant.pending_withdrawal = 0;
+        participant.withdrawal_requested_at = 0;
+        participant.settled_through = reserve.settlement_count;
+        participant.loss_carried = 0;
+        participant.profit_index = reserve.profit_per_unit;
+        participant.accrued_released = 0;
+        participant.accrued_unreleased = 0;
         participant.bump = ctx.bumps.participant;
 
         // Update reserve state
-        reserve.total_capital += amount;
-        reserve.participant_count += 1;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.participant_count = reserve.participant_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
 
         emit!(ParticipantJoinedEvent {
             agent: ctx.accounts.user.key(),
             capital_contributed: amount,
+            tier,
+            locked_until: participant.locked_until,
         });
 
         Ok(())
     }
 
     /// Propose a new economic activity
+    ///
+    /// `risk_rating` runs from 1 (lowest) to 5 (highest) and weights the
+    /// capital against the reserve's risk budget once approved.
+    /// `expected_duration` (seconds from approval, 0 = open-ended) sets the
+    /// maturity after which an unreported activity may be auto-completed, and
+    /// must fall within the reserve's activity duration bounds.
+    ///
+    /// `capital_required` must fit in the free capital left after other
+    /// pending proposals: `total_capital` (which already excludes deployed
+    /// capital) minus `pending_proposed_capital`. The request counts as
+    /// pending until the activity is approved or rejected, so no single one
+    /// may take more than `max_proposal_bps` of that free capital.
+    ///
+    /// While `activity_fee` is set, the proposer pays it into the compliance
+    /// fund on top of the request; the fee never touches `capital_required`.
     pub fn propose_activity(
         ctx: Context<ProposeActivity>,
         activity_id: [u8; 32],
         capital_required: u64,
+        risk_rating: u8,
+        expected_duration: i64,
     ) -> Result<()> {
-        let reserve = &ctx.accounts.reserve;
+        let reserve = &mut ctx.accounts.reserve;
         let activity = &mut ctx.accounts.activity;
 
+        require!(reserve.status == ReserveStatus::Active, AmanaError::ReserveWindingDown);
+
+        require!(capital_required > 0, AmanaError::InvalidCapitalAmount);
+        let free_capital = reserve.total_capital.saturating_sub(reserve.pending_proposed_capital);
+        require!(capital_required <= free_capital, AmanaError::InsufficientCapital);
+        require!(
+            (capital_required as u128)
+                .checked_mul(10000)
+                .ok_or(AmanaError::MathOverflow)?
+                <= (free_capital as u128)
+                    .checked_mul(reserve.max_proposal_bps as u128)
+                    .ok_or(AmanaError::MathOverflow)?,
+            AmanaError::ProposalTooLarge
+        );
+        require!(
+            capital_required >= reserve.min_activity_capital,
+            AmanaError::BelowMinimumActivityCapital
+        );
+        require!(
+            (MIN_RISK_RATING..=MAX_RISK_RATING).contains(&risk_rating),
+            AmanaError::InvalidRiskRating
+        );
+        require!(expected_duration >= 0, AmanaError::InvalidDuration);
         require!(
-            capital_required > 0 && capital_required <= reserve.total_capital,
-            AmanaError::InvalidCapitalAmount
+            expected_duration >= reserve.min_activity_duration_seconds
+                && (reserve.max_activity_duration_seconds == 0
+                    || expected_duration <= reserve.max_activity_duration_seconds),
+            AmanaError::DurationOutOfBounds
+        );
+        require!(
+            !reserve.unique_activity_ids || !reserve.may_have_closed_id(&activity_id),
+            AmanaError::ActivityIdReused
         );
 
         activity.activity_id = activity_id;
         activity.initiator = ctx.accounts.participant.agent;
         activity.capital_required = capital_required;
         activity.capital_deployed = 0;
+        activity.risk_rating = risk_rating;
+        activity.expected_duration = expected_duration;
+        activity.maturity_at = 0;
         activity.status = ActivityStatus::Proposed;
         activity.created_at = Clock::get()?.unix_timestamp;
         activity.completed_at = 0;
         activity.outcome = 0;
         activity.is_validated = false;
+        activity.is_settled = false;
+        activity.settled_outcome = 0;
+        activity.distribution_base = 0;
+        activity.parent_id = [0; 32];
+        activity.settlement_mint = Pubkey::default();
+        activity.approved_at = 0;
+        activity.settlement_seq = 0;
+        activity.unapplied_count = 0;
+        activity.allocated = 0;
+        activity.unallocated = 0;
         activity.bump = ctx.bumps.activity;
 
+        reserve.pending_proposed_capital = reserve.pending_proposed_capital
+            .checked_add(capital_required)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.record_proposal(activity.created_at)?;
+
+        if reserve.activity_fee > 0 {
+            let fee = reserve.activity_fee;
+            let fund = ctx.accounts.compliance_fund.as_mut()
+                .ok_or(AmanaError::ComplianceFundRequired)?;
+
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.user.key(),
+                    &fund.key(),
+                    fee,
+                ),
+                &[
+                    ctx.accounts.user.to_account_info(),
+                    fund.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+
+            fund.balance = fund.balance
+                .checked_add(fee)
+                .ok_or(AmanaError::MathOverflow)?;
+            fund.total_collected = fund.total_collected
+                .checked_add(fee)
+                .ok_or(AmanaError::MathOverflow)?;
+
+            emit!(ActivityFeeCollectedEvent {
+                activity_id,
+                payer: ctx.accounts.user.key(),
+                fee,
+                fund_balance: fund.balance,
+            });
+        }
+
+        let index = &mut ctx.accounts.initiator_index;
+        if index.initiator == Pubkey::default() {
+            index.initiator = ctx.accounts.user.key();
+            index.bump = ctx.bumps.initiator_index;
+        }
+        require!(
+            index.activity_ids.len() < MAX_INDEXED_ACTIVITIES,
+            AmanaError::InitiatorIndexFull
+        );
+        index.activity_ids.push(activity_id);
+
         emit!(ActivityProposedEvent {
             activity_id,
             initiator: ctx.accounts.participant.agent,
             capital_required,
+            risk_rating,
         });
 
         Ok(())
     }
 
     /// Approve an activity
-    pub fn approve_activity(ctx: Context<ApproveActivity>) -> Result<()> {
+    ///
+    /// Only the reserve admin may approve, since approval moves free capital
+    /// into the activity. While `min_validator_stake` is set, a bonded
+    /// validator must also sign.
+    /// `approved_amount` (at most `capital_required`, default the full
+    /// request) is the capital earmarked and recorded as `capital_deployed`.
+    pub fn approve_activity(
+        ctx: Context<ApproveActivity>,
+        approved_amount: Option<u64>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+        require_validator(
+            &ctx.accounts.reserve,
+            ctx.accounts.validator.as_ref(),
+            ctx.accounts.validator_authority.as_ref(),
+        )?;
+        if !solvency_interlock(&mut ctx.accounts.reserve)? {
+            return Ok(());
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        let activity = &mut ctx.accounts.activity;
+
+        let approved_amount = approved_amount.unwrap_or(activity.capital_required);
+        approve_into_escrow(reserve, activity, approved_amount, Clock::get()?.unix_timestamp)?;
+
+        emit!(ActivityApprovedEvent {
+            activity_id: activity.activity_id,
+            capital_required: activity.capital_required,
+            approved_amount,
+            approved_by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Reject a proposed activity, releasing its pending capital
+    ///
+    /// The admin or the activity's initiator may reject, and only while the
+    /// activity is still proposed. A proposal never deducted `total_capital`,
+    /// so only its pending request is released. `reason_code` is passed
+    /// through to `ActivityRejectedEvent` for indexers. Rejections by the
+    /// admin are recorded in the audit log.
+    pub fn reject_activity(ctx: Context<RejectActivity>, reason_code: u8) -> Result<()> {
         let reserve = &mut ctx.accounts.reserve;
         let activity = &mut ctx.accounts.activity;
 
@@ -153,57 +470,357 @@ pub mod amana_reserve {
             AmanaError::InvalidActivityStatus
         );
 
-        activity.status = ActivityStatus::Approved;
-        activity.capital_deployed = activity.capital_required;
-        reserve.total_capital = reserve.total_capital
+        activity.status = ActivityStatus::Rejected;
+        reserve.pending_proposed_capital = reserve.pending_proposed_capital
             .checked_sub(activity.capital_required)
             .ok_or(AmanaError::MathOverflow)?;
 
-        emit!(ActivityApprovedEvent {
+        emit!(ActivityRejectedEvent {
             activity_id: activity.activity_id,
+            capital_required: activity.capital_required,
+            reason_code,
+            rejected_by: ctx.accounts.authority.key(),
+        });
+
+        if ctx.accounts.authority.key() == reserve.admin {
+            ctx.accounts.audit_log.record(AuditAction::RejectActivity, reserve.admin)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reject a proposal that has waited too long for a decision
+    ///
+    /// Callable by anyone once `now > created_at + proposal_expiry_seconds`
+    /// while the activity is still proposed. Like `reject_activity`, only the
+    /// pending request is released. Disabled while `proposal_expiry_seconds`
+    /// is 0.
+    pub fn expire_proposed_activity(
+        ctx: Context<ExpireProposedActivity>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        let activity = &mut ctx.accounts.activity;
+
+        require!(
+            activity.status == ActivityStatus::Proposed,
+            AmanaError::InvalidActivityStatus
+        );
+        require!(reserve.proposal_expiry_seconds > 0, AmanaError::ProposalNotExpired);
+
+        let now = Clock::get()?.unix_timestamp;
+        let expires_at = activity.created_at
+            .checked_add(reserve.proposal_expiry_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(now > expires_at, AmanaError::ProposalNotExpired);
+
+        activity.status = ActivityStatus::Rejected;
+        reserve.pending_proposed_capital = reserve.pending_proposed_capital
+            .checked_sub(activity.capital_required)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(ProposalExpiredEvent {
+            activity_id,
+            capital_required: activity.capital_required,
+            created_at: activity.created_at,
+            expired_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Split an approved, not yet deployed activity into sub-activities
+    ///
+    /// Child activity PDAs are passed via `remaining_accounts` in split order.
+    /// Each child id is `sha256(parent_id || "split" || index)`, so clients can
+    /// derive the child addresses ahead of time. The split amounts must sum to
+    /// the parent's approved `capital_deployed`; the earmarked capital moves to the
+    /// children, which are then deployed and completed individually.
+    pub fn split_activity<'info>(
+        ctx: Context<'_, '_, '_, 'info, SplitActivity<'info>>,
+        activity_id: [u8; 32],
+        splits: Vec<u64>,
+    ) -> Result<()> {
+        let parent = &mut ctx.accounts.activity;
+
+        require!(
+            parent.status == ActivityStatus::Approved,
+            AmanaError::InvalidActivityStatus
+        );
+        require!(
+            splits.len() >= 2 && splits.len() <= MAX_ACTIVITY_SPLITS,
+            AmanaError::InvalidSplit
+        );
+        require!(
+            ctx.remaining_accounts.len() == splits.len(),
+            AmanaError::InvalidSplit
+        );
+
+        let mut split_total: u64 = 0;
+        for amount in splits.iter() {
+            require!(*amount > 0, AmanaError::InvalidSplit);
+            split_total = split_total
+                .checked_add(*amount)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+        require!(
+            split_total == parent.capital_deployed,
+            AmanaError::SplitAmountMismatch
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let reserve_seed = ctx.accounts.reserve.id_seed();
+        let mut child_ids = Vec::with_capacity(splits.len());
+
+        for (index, (child_info, amount)) in ctx
+            .remaining_accounts
+            .iter()
+            .zip(splits.iter())
+            .enumerate()
+        {
+            let child_id = derive_child_activity_id(&activity_id, index as u8);
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"activity", reserve_seed, child_id.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(child_info.key(), expected, AmanaError::InvalidSplit);
+
+            create_pda_account(
+                &ctx.accounts.user.to_account_info(),
+                child_info,
+                &ctx.accounts.system_program.to_account_info(),
+                Activity::SPACE,
+                &[b"activity", reserve_seed, child_id.as_ref(), &[bump]],
+                &crate::ID,
+            )?;
+
+            let child = Activity {
+                activity_id: child_id,
+                initiator: parent.initiator,
+                capital_required: *amount,
+                capital_deployed: *amount,
+                risk_rating: parent.risk_rating,
+                expected_duration: parent.expected_duration,
+                maturity_at: parent.maturity_at,
+                status: ActivityStatus::Approved,
+                created_at: now,
+                completed_at: 0,
+                outcome: 0,
+                is_validated: false,
+                is_settled: false,
+                settled_outcome: 0,
+                distribution_base: 0,
+                parent_id: activity_id,
+                settlement_mint: parent.settlement_mint,
+                approved_at: now,
+                settlement_seq: 0,
+                unapplied_count: 0,
+                allocated: 0,
+                unallocated: 0,
+                bump,
+            };
+            let mut data = child_info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            child.try_serialize(&mut writer)?;
+
+            child_ids.push(child_id);
+        }
+
+        parent.status = ActivityStatus::Split;
+        parent.capital_deployed = 0;
+
+        // The earmarked capital is unchanged; only the number of open activities grows
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.open_activity_count = reserve.open_activity_count
+            .checked_add(splits.len() as u64 - 1)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(ActivitySplitEvent {
+            parent_id: activity_id,
+            child_ids,
+            amounts: splits,
         });
 
         Ok(())
     }
 
     /// Complete an activity with profit/loss outcome
+    ///
+    /// `activity_id` must name the activity being completed, guarding against
+    /// a substituted activity account. `outcome_timestamp` is when the outcome
+    /// was computed. It may not lie in the future, nor be older than
+    /// `max_outcome_age_seconds` when that is set.
+    ///
+    /// `settlement_mint` is the mint the outcome is denominated in and must
+    /// match the activity's (`Pubkey::default()` for SOL). Token-settled
+    /// outcomes are booked in that mint's vault, which must be passed along
+    /// with its `MintVault`; they are always settled immediately, never netted.
+    ///
+    /// While `min_validator_stake` is set, a bonded validator must sign. A SOL
+    /// loss that draws on the guarantee fund must be reported by the admin or
+    /// an authorized reporter, signing as `reporter`.
     pub fn complete_activity(
         ctx: Context<CompleteActivity>,
+        activity_id: [u8; 32],
         outcome: i64,
+        outcome_timestamp: i64,
+        settlement_mint: Pubkey,
     ) -> Result<()> {
+        require_validator(
+            &ctx.accounts.reserve,
+            ctx.accounts.validator.as_ref(),
+            ctx.accounts.validator_authority.as_ref(),
+        )?;
+
         let reserve = &mut ctx.accounts.reserve;
         let activity = &mut ctx.accounts.activity;
 
         require!(
-            activity.status == ActivityStatus::Approved,
+            activity.activity_id == activity_id,
+            AmanaError::ActivityIdMismatch
+        );
+        require_keys_eq!(
+            settlement_mint,
+            activity.settlement_mint,
+            AmanaError::InvalidSettlementMint
+        );
+        let token_settled = settlement_mint != Pubkey::default();
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(outcome_timestamp <= now, AmanaError::FutureDatedOutcome);
+        require!(
+            reserve.max_outcome_age_seconds == 0
+                || now.saturating_sub(outcome_timestamp) <= reserve.max_outcome_age_seconds,
+            AmanaError::StaleOutcome
+        );
+
+        require!(
+            activity.status == ActivityStatus::Approved
+                || activity.status == ActivityStatus::Active,
             AmanaError::InvalidActivityStatus
         );
 
+        match ctx.accounts.completion_bond.as_mut() {
+            Some(bond) => {
+                require!(
+                    bond.amount >= reserve.completion_bond,
+                    AmanaError::CompletionBondRequired
+                );
+                // Only the reporter who posted the bond may report against it
+                let reporter = ctx.accounts.reporter.as_ref().ok_or(AmanaError::Unauthorized)?;
+                require_keys_eq!(reporter.key(), bond.reporter, AmanaError::Unauthorized);
+                bond.completed_at = now;
+            }
+            None => require!(reserve.completion_bond == 0, AmanaError::CompletionBondRequired),
+        }
+
         activity.status = ActivityStatus::Completed;
-        activity.completed_at = Clock::get()?.unix_timestamp;
+        activity.completed_at = now;
         activity.outcome = outcome;
         activity.is_validated = true;
 
-        let returned_capital = activity.capital_deployed;
+        let returned_capital = release_escrow(reserve, activity)?;
+        let mut settled_outcome = outcome;
+        let accrued = !token_settled
+            && !reserve.allow_netting
+            && outcome > 0
+            && reserve.distribution_interval_seconds > 0;
 
-        if outcome > 0 {
-            // Profit case
+        if token_settled {
+            // The principal is booked in SOL; only the outcome settles in the mint
+            settle_in_mint(
+                ctx.accounts.mint_vault.as_mut(),
+                ctx.accounts.vault.as_ref(),
+                activity,
+                outcome,
+            )?;
             reserve.total_capital = reserve.total_capital
                 .checked_add(returned_capital)
-                .and_then(|v| v.checked_add(outcome as u64))
                 .ok_or(AmanaError::MathOverflow)?;
-
-            // Distribute profit (simplified - in production would iterate participants)
-        } else if outcome < 0 {
-            // Loss case
-            let loss = outcome.unsigned_abs();
-            if loss < returned_capital {
-                reserve.total_capital = reserve.total_capital
-                    .checked_add(returned_capital)
-                    .and_then(|v| v.checked_sub(loss))
+        } else if reserve.allow_netting {
+            // Only the principal returns now; the outcome is settled later by
+            // net_settle_initiator together with the initiator's other activities
+            reserve.total_capital = reserve.total_capital
+                .checked_add(returned_capital)
+                .ok_or(AmanaError::MathOverflow)?;
+        } else if outcome > 0 {
+            // Profit case: the management fee is only booked as owed, since
+            // the reported profit may not be in the reserve yet, and
+            // participants share the remainder
+            let fee = u64::try_from(
+                (outcome as u128)
+                    .checked_mul(reserve.management_fee_bps as u128)
+                    .ok_or(AmanaError::MathOverflow)?
+                    / 10000,
+            )
+            .map_err(|_| error!(AmanaError::MathOverflow))?;
+            if fee > 0 {
+                reserve.fees_owed = reserve.fees_owed
+                    .checked_add(fee)
                     .ok_or(AmanaError::MathOverflow)?;
+
+                emit!(ManagementFeeAccruedEvent {
+                    activity_id: activity.activity_id,
+                    outcome,
+                    fee,
+                    fees_owed: reserve.fees_owed,
+                });
+            }
+            let profit = (outcome as u64)
+                .checked_sub(fee)
+                .ok_or(AmanaError::MathOverflow)?;
+            settled_outcome = profit as i64;
+
+            reserve.total_capital = reserve.total_capital
+                .checked_add(returned_capital)
+                .and_then(|v| v.checked_add(profit))
+                .ok_or(AmanaError::MathOverflow)?;
+
+            // On a distribution schedule the profit is shared now and released
+            // by distribute_pending
+            if accrued {
+                reserve.accrue_distribution(profit)?;
             }
-            // Distribute loss
+        } else if outcome < 0 {
+            // Loss case: liability is limited to the capital deployed, the
+            // guarantee fund covers its share, and participants bear the rest
+            // pro rata via realize_loss, each capped there at their capital
+            let gross_loss = outcome.unsigned_abs().min(returned_capital);
+            let covered = match ctx.accounts.guarantee_fund.as_mut() {
+                Some(fund) => {
+                    // The fund pays out on the reported outcome alone
+                    require_admin_or_reporter(
+                        reserve,
+                        ctx.accounts.reporter.as_ref(),
+                        ctx.accounts.reporter_record.as_ref(),
+                    )?;
+                    let covered = fund.cover(gross_loss, reserve.guarantee_coverage_bps)?;
+                    if covered > 0 {
+                        **fund.to_account_info().try_borrow_mut_lamports()? -= covered;
+                        **reserve.to_account_info().try_borrow_mut_lamports()? += covered;
+
+                        emit!(LossCoveredEvent {
+                            activity_id: activity.activity_id,
+                            loss: gross_loss,
+                            covered,
+                            fund_balance: fund.balance,
+                        });
+                    }
+                    covered
+                }
+                None => {
+                    require!(
+                        reserve.guarantee_coverage_bps == 0,
+                        AmanaError::GuaranteeFundRequired
+                    );
+                    0
+                }
+            };
+            let loss = gross_loss - covered;
+            reserve.total_capital = reserve.total_capital
+                .checked_add(returned_capital - loss)
+                .ok_or(AmanaError::MathOverflow)?;
+            settled_outcome = -(loss as i64);
         } else {
             // No profit or loss
             reserve.total_capital = reserve.total_capital
@@ -211,6 +828,17 @@ pub mod amana_reserve {
                 .ok_or(AmanaError::MathOverflow)?;
         }
 
+        if token_settled || !reserve.allow_netting {
+            activity.is_settled = true;
+            activity.settled_outcome = settled_outcome;
+            // A zero base keeps accrued profit out of allocate_profit_share
+            activity.distribution_base = if accrued { 0 } else { reserve.total_contributed };
+            if activity.distribution_base > 0 && activity.settled_outcome != 0 {
+                activity.unallocated = activity.settled_outcome.unsigned_abs();
+                reserve.sequence_settlement(&mut **activity)?;
+            }
+        }
+
         emit!(ActivityCompletedEvent {
             activity_id: activity.activity_id,
             outcome,
@@ -219,434 +847,9815 @@ pub mod amana_reserve {
         Ok(())
     }
 
-    /// Deposit additional capital
-    pub fn deposit_capital(ctx: Context<DepositCapital>, amount: u64) -> Result<()> {
-        require!(amount > 0, AmanaError::InvalidAmount);
-
+    /// Complete an overdue, unreported activity at break-even
+    ///
+    /// Callable by anyone once `now > maturity_at + maturity_grace_seconds`.
+    /// The outcome is recorded as 0 and the escrowed capital returns to the
+    /// reserve in full. Open-ended activities (no maturity) never qualify.
+    pub fn auto_complete_overdue(
+        ctx: Context<AutoCompleteOverdue>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
         let reserve = &mut ctx.accounts.reserve;
-        let participant = &mut ctx.accounts.participant;
+        let activity = &mut ctx.accounts.activity;
 
-        // Transfer SOL
-        anchor_lang::solana_program::program::invoke(
-            &anchor_lang::solana_program::system_instruction::transfer(
-                &ctx.accounts.user.key(),
-                &ctx.accounts.reserve.key(),
-                amount,
-            ),
-            &[
-                ctx.accounts.user.to_account_info(),
-                ctx.accounts.reserve.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+        require!(
+            activity.status == ActivityStatus::Approved
+                || activity.status == ActivityStatus::Active,
+            AmanaError::InvalidActivityStatus
+        );
+        require!(activity.maturity_at > 0, AmanaError::ActivityNotOverdue);
 
-        participant.capital_contributed = participant.capital_contributed
-            .checked_add(amount)
+        let now = Clock::get()?.unix_timestamp;
+        let deadline = activity.maturity_at
+            .checked_add(reserve.maturity_grace_seconds)
             .ok_or(AmanaError::MathOverflow)?;
+        require!(now > deadline, AmanaError::ActivityNotOverdue);
+
+        let returned_capital = release_escrow(reserve, activity)?;
         reserve.total_capital = reserve.total_capital
-            .checked_add(amount)
+            .checked_add(returned_capital)
             .ok_or(AmanaError::MathOverflow)?;
 
-        emit!(CapitalDepositedEvent {
-            agent: ctx.accounts.user.key(),
-            amount,
+        activity.status = ActivityStatus::Completed;
+        activity.completed_at = now;
+        activity.outcome = 0;
+        activity.is_settled = true;
+        activity.settled_outcome = 0;
+        activity.distribution_base = reserve.total_contributed;
+
+        emit!(ActivityAutoCompletedEvent {
+            activity_id,
+            maturity_at: activity.maturity_at,
+            returned_capital,
         });
 
         Ok(())
     }
 
-    /// Withdraw capital from the reserve
-    pub fn withdraw_capital(ctx: Context<WithdrawCapital>, amount: u64) -> Result<()> {
-        require!(amount > 0, AmanaError::InvalidAmount);
-
+    /// Net the outcomes of an initiator's completed activities and settle once
+    ///
+    /// Requires `allow_netting`. All activities must share one initiator and be
+    /// completed but not yet settled; they are passed writable via
+    /// `remaining_accounts` in the order of `activity_ids`. A set of one
+    /// settles that activity on its own outcome, so none is left unsettled
+    /// for want of a partner to net against.
+    ///
+    /// Sharia note: netting is only applied within a single mudarabah
+    /// relationship (one mudarib managing the pool's capital), where losses
+    /// are first made good from profits before any profit is recognised.
+    /// Netting across different mudaribs would make one manager's capital
+    /// providers absorb another's losses and is therefore not supported.
+    ///
+    /// The net result is booked against `total_capital` and recorded per
+    /// activity in `settled_outcome`: on a net gain the losses are zeroed and
+    /// the profits scaled down pro-rata, on a net loss the reverse.
+    pub fn net_settle_initiator(
+        ctx: Context<NetSettleInitiator>,
+        activity_ids: Vec<[u8; 32]>,
+    ) -> Result<()> {
         let reserve = &mut ctx.accounts.reserve;
-        let participant = &mut ctx.accounts.participant;
 
+        require!(reserve.allow_netting, AmanaError::NettingDisabled);
         require!(
-            amount <= participant.capital_contributed,
-            AmanaError::InsufficientBalance
+            !activity_ids.is_empty() && activity_ids.len() <= MAX_MERGED_ACTIVITIES,
+            AmanaError::InvalidRemainingAccount
+        );
+        require!(
+            ctx.remaining_accounts.len() == activity_ids.len(),
+            AmanaError::InvalidRemainingAccount
         );
 
-        // Calculate reserve SOL balance
-        let reserve_balance = ctx.accounts.reserve.lamports();
-        require!(amount <= reserve_balance, AmanaError::InsufficientLiquidity);
+        let mut activities: Vec<Activity> = Vec::with_capacity(activity_ids.len());
+        let mut gross_profit: u64 = 0;
+        let mut gross_loss: u64 = 0;
 
-        participant.capital_contributed = participant.capital_contributed
-            .checked_sub(amount)
-            .ok_or(AmanaError::MathOverflow)?;
-        reserve.total_capital = reserve.total_capital
-            .checked_sub(amount)
-            .ok_or(AmanaError::MathOverflow)?;
+        for (index, (info, activity_id)) in ctx
+            .remaining_accounts
+            .iter()
+            .zip(activity_ids.iter())
+            .enumerate()
+        {
+            require!(
+                !activity_ids[..index].contains(activity_id),
+                AmanaError::DuplicateActivity
+            );
+            require!(info.is_writable, AmanaError::InvalidRemainingAccount);
 
-        // Transfer SOL back to user
-        **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += amount;
+            let activity: Activity = load_remaining(info)?;
+            require_activity_pda(info, reserve.id_seed(), &activity)?;
+            require!(
+                activity.activity_id == *activity_id,
+                AmanaError::InvalidRemainingAccount
+            );
+            require!(
+                activity.status == ActivityStatus::Completed && !activity.is_settled,
+                AmanaError::InvalidActivityStatus
+            );
+            if let Some(first) = activities.first() {
+                require_keys_eq!(
+                    activity.initiator,
+                    first.initiator,
+                    AmanaError::InitiatorMismatch
+                );
+            }
 
-        emit!(CapitalWithdrawnEvent {
-            agent: ctx.accounts.user.key(),
-            amount,
-        });
+            if activity.outcome > 0 {
+                gross_profit = gross_profit
+                    .checked_add(activity.outcome as u64)
+                    .ok_or(AmanaError::MathOverflow)?;
+            } else {
+                gross_loss = gross_loss
+                    .checked_add(activity.outcome.unsigned_abs())
+                    .ok_or(AmanaError::MathOverflow)?;
+            }
+            activities.push(activity);
+        }
 
-        Ok(())
-    }
+        let net_gain = gross_profit >= gross_loss;
+        let (net_amount, gross_side) = if net_gain {
+            (gross_profit - gross_loss, gross_profit)
+        } else {
+            (gross_loss - gross_profit, gross_loss)
+        };
 
-    // ========== MagicBlock Ephemeral Rollup Integration ==========
+        // Scale the winning side down to the net amount, zero the other side
+        let mut allocated: u64 = 0;
+        for activity in activities.iter_mut() {
+            let on_net_side = if net_gain { activity.outcome > 0 } else { activity.outcome < 0 };
+            let share = if on_net_side && gross_side > 0 {
+                ((activity.outcome.unsigned_abs() as u128) * (net_amount as u128)
+                    / (gross_side as u128)) as u64
+            } else {
+                0
+            };
+            allocated = allocated.checked_add(share).ok_or(AmanaError::MathOverflow)?;
+            activity.settled_outcome = signed_amount(share, net_gain)?;
+            activity.distribution_base = reserve.total_contributed;
+            activity.is_settled = true;
+        }
 
-    /// Delegate reserve to Ephemeral Rollup for real-time operations
-    pub fn delegate_reserve(ctx: Context<DelegateReserve>) -> Result<()> {
-        ctx.accounts.delegate_pda(
-            &ctx.accounts.payer,
-            &[b"reserve"],
-            DelegateConfig {
-                validator: Some(pubkey!("MAS1Dt9qreoRMQ14YQuhg8UTZMMzDdKhmkZMECCzk57")), // Asia ER Validator
-                ..Default::default()
-            },
-        )?;
-        Ok(())
+        // Assign the rounding remainder to the first activity on the net side
+        let remainder = net_amount.checked_sub(allocated).ok_or(AmanaError::MathOverflow)?;
+        if remainder > 0 {
+            if let Some(activity) = activities.iter_mut().find(|a| {
+                if net_gain { a.outcome > 0 } else { a.outcome < 0 }
+            }) {
+                let adjusted = activity
+                    .settled_outcome
+                    .unsigned_abs()
+                    .checked_add(remainder)
+                    .ok_or(AmanaError::MathOverflow)?;
+                activity.settled_outcome = signed_amount(adjusted, net_gain)?;
+            }
+        }
+        for activity in activities.iter_mut() {
+            if activity.settled_outcome != 0 {
+                activity.unallocated = activity.settled_outcome.unsigned_abs();
+                reserve.sequence_settlement(activity)?;
+            }
+        }
+
+        // The settled outcomes must add back up to the net result
+        let mut settled_sum: u64 = 0;
+        for activity in activities.iter() {
+            settled_sum = settled_sum
+                .checked_add(activity.settled_outcome.unsigned_abs())
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+        audit_distribution(net_amount, settled_sum, 0)?;
+
+        for (info, activity) in ctx.remaining_accounts.iter().zip(activities.iter()) {
+            let mut data = info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            activity.try_serialize(&mut writer)?;
+        }
+
+        reserve.total_capital = if net_gain {
+            reserve.total_capital.checked_add(net_amount)
+        } else {
+            reserve.total_capital.checked_sub(net_amount)
+        }
+        .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(NetSettlementEvent {
+            initiator: activities[0].initiator,
+            activity_ids,
+            gross_profit,
+            gross_loss,
+            net_outcome: signed_amount(net_amount, net_gain)?,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::NetSettle, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Enable or disable netting of an initiator's outcomes before settlement
+    pub fn set_allow_netting(ctx: Context<UpdateReserveConfig>, allow_netting: bool) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.allow_netting = allow_netting;
+
+        emit!(NettingConfigUpdatedEvent { allow_netting });
+
+        ctx.accounts.audit_log.record(AuditAction::SetAllowNetting, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Enable or disable profit distribution in kind through `distribute_in_kind`
+    pub fn set_allow_in_kind(ctx: Context<UpdateReserveConfig>, allow_in_kind: bool) -> Result<()> {
+        ctx.accounts.reserve.allow_in_kind = allow_in_kind;
+
+        emit!(InKindConfigUpdatedEvent { allow_in_kind });
+
+        ctx.accounts.audit_log.record(AuditAction::SetAllowInKind, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Enable or disable rejecting activity ids that were used and closed before
+    ///
+    /// `close_activity` records every closed id in `closed_id_filter`, whether
+    /// or not this is enabled, so enabling it also covers earlier closures.
+    /// The filter is probabilistic and rotated as it fills: see
+    /// `Reserve::may_have_closed_id`.
+    pub fn set_unique_activity_ids(
+        ctx: Context<UpdateReserveConfig>,
+        unique_activity_ids: bool,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.unique_activity_ids = unique_activity_ids;
+
+        emit!(UniqueActivityIdsUpdatedEvent {
+            unique_activity_ids,
+            closed_id_count: reserve.closed_id_count,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetUniqueActivityIds, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the solvency ratio below which the reserve auto-pauses (0 = disabled)
+    ///
+    /// The ratio is the reserve's lamports above its rent-exempt minimum per
+    /// 10000 of capital it accounts for holding: free, escrowed and privately
+    /// deployed capital. Withdrawals, profit claims, approvals and realtime
+    /// deployments check it first; on a breach the reserve is paused instead.
+    pub fn set_min_solvency(ctx: Context<UpdateReserveConfig>, min_solvency_bps: u16) -> Result<()> {
+        require!(min_solvency_bps <= 10000, AmanaError::InvalidSolvencyThreshold);
+
+        ctx.accounts.reserve.min_solvency_bps = min_solvency_bps;
+
+        emit!(MinSolvencyUpdatedEvent { min_solvency_bps });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMinSolvency, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Lift a solvency pause once the shortfall has been made good
+    ///
+    /// The ratio must be back at or above `min_solvency_bps`, e.g. after the
+    /// missing lamports were returned or `reconcile_capital` corrected the books.
+    pub fn reset_after_solvency_fix(ctx: Context<UpdateReserveConfig>) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        require!(reserve.is_paused, AmanaError::ReserveNotPaused);
+
+        let (solvency_bps, _, _) = solvency_ratio(reserve)?;
+        require!(
+            solvency_bps >= reserve.min_solvency_bps as u64,
+            AmanaError::SolvencyNotRestored
+        );
+        reserve.is_paused = false;
+
+        emit!(SolvencyRestoredEvent {
+            solvency_bps,
+            reset_by: ctx.accounts.admin.key(),
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::ResetAfterSolvencyFix, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the minimum capital an activity proposal may request
+    ///
+    /// The same floor applies to a partial approval and to a deployment, so
+    /// neither can shrink an activity below it after proposal.
+    pub fn set_min_activity_capital(
+        ctx: Context<UpdateReserveConfig>,
+        min_activity_capital: u64,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.min_activity_capital = min_activity_capital;
+
+        emit!(MinActivityCapitalUpdatedEvent { min_activity_capital });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMinActivityCapital, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Cap a single proposal at `max_proposal_bps` of the free capital
+    ///
+    /// A pending proposal holds its capital until it is approved, rejected or
+    /// expired, so the cap keeps one oversized request from locking the
+    /// reserve. 10000 lets a proposal take all free capital.
+    pub fn set_max_proposal_bps(
+        ctx: Context<UpdateReserveConfig>,
+        max_proposal_bps: u16,
+    ) -> Result<()> {
+        require!(
+            max_proposal_bps > 0 && max_proposal_bps <= 10000,
+            AmanaError::InvalidProposalCap
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.max_proposal_bps = max_proposal_bps;
+
+        emit!(MaxProposalUpdatedEvent { max_proposal_bps });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMaxProposal, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the maximum age of an outcome accepted by complete_activity (0 = no limit)
+    pub fn set_max_outcome_age(
+        ctx: Context<UpdateReserveConfig>,
+        max_outcome_age_seconds: i64,
+    ) -> Result<()> {
+        require!(max_outcome_age_seconds >= 0, AmanaError::InvalidAmount);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.max_outcome_age_seconds = max_outcome_age_seconds;
+
+        emit!(MaxOutcomeAgeUpdatedEvent { max_outcome_age_seconds });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMaxOutcomeAge, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the risk budget approvals may consume (0 = unlimited)
+    pub fn set_max_risk_exposure(
+        ctx: Context<UpdateReserveConfig>,
+        max_risk_exposure: u64,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.max_risk_exposure = max_risk_exposure;
+
+        emit!(MaxRiskExposureUpdatedEvent { max_risk_exposure });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMaxRiskExposure, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Configure the entry donation taken on join and its charity recipient
+    pub fn set_entry_donation(
+        ctx: Context<UpdateReserveConfig>,
+        entry_fee_bps: u16,
+        charity: Pubkey,
+    ) -> Result<()> {
+        require!(
+            entry_fee_bps <= MAX_ENTRY_FEE_BPS,
+            AmanaError::InvalidEntryFee
+        );
+        require!(
+            entry_fee_bps == 0 || charity != Pubkey::default(),
+            AmanaError::InvalidCharity
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.entry_fee_bps = entry_fee_bps;
+        reserve.charity = charity;
+
+        emit!(EntryDonationUpdatedEvent {
+            entry_fee_bps,
+            charity,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetEntryDonation, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the grace period after maturity before auto-completion is allowed
+    pub fn set_maturity_grace(
+        ctx: Context<UpdateReserveConfig>,
+        maturity_grace_seconds: i64,
+    ) -> Result<()> {
+        require!(maturity_grace_seconds >= 0, AmanaError::InvalidDuration);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.maturity_grace_seconds = maturity_grace_seconds;
+
+        emit!(MaturityGraceUpdatedEvent { maturity_grace_seconds });
+
+        ctx.accounts.audit_log.record(AuditAction::SetMaturityGrace, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set how long a proposal may await approval before it can be expired
+    ///
+    /// A `proposal_expiry_seconds` of 0 lets proposals wait indefinitely.
+    pub fn set_proposal_expiry(
+        ctx: Context<UpdateReserveConfig>,
+        proposal_expiry_seconds: i64,
+    ) -> Result<()> {
+        require!(proposal_expiry_seconds >= 0, AmanaError::InvalidDuration);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.proposal_expiry_seconds = proposal_expiry_seconds;
+
+        emit!(ProposalExpiryUpdatedEvent { proposal_expiry_seconds });
+
+        ctx.accounts.audit_log.record(AuditAction::SetProposalExpiry, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Bound the `expected_duration` a proposal may declare
+    ///
+    /// Too short a duration suggests speculation (gharar); too long locks
+    /// capital up illiquidly. A `max_activity_duration_seconds` of 0 leaves
+    /// the upper end open.
+    pub fn set_activity_duration_bounds(
+        ctx: Context<UpdateReserveConfig>,
+        min_activity_duration_seconds: i64,
+        max_activity_duration_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            min_activity_duration_seconds >= 0
+                && max_activity_duration_seconds >= 0
+                && (max_activity_duration_seconds == 0
+                    || min_activity_duration_seconds <= max_activity_duration_seconds),
+            AmanaError::InvalidDuration
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.min_activity_duration_seconds = min_activity_duration_seconds;
+        reserve.max_activity_duration_seconds = max_activity_duration_seconds;
+
+        emit!(ActivityDurationBoundsUpdatedEvent {
+            min_activity_duration_seconds,
+            max_activity_duration_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetActivityDurationBounds, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Batch SOL profits into distributions at most every `distribution_interval_seconds`
+    ///
+    /// While non-zero, profits booked by `complete_activity` accrue to
+    /// `pending_distribution` and the `profit_per_unit` index instead of being
+    /// allocated per activity. Turning
+    /// the schedule on starts its first interval now. Setting 0 returns to
+    /// per-activity allocation; anything already accrued can still be
+    /// distributed.
+    pub fn set_distribution_interval(
+        ctx: Context<UpdateReserveConfig>,
+        distribution_interval_seconds: i64,
+    ) -> Result<()> {
+        require!(distribution_interval_seconds >= 0, AmanaError::InvalidDuration);
+
+        let reserve = &mut ctx.accounts.reserve;
+        if reserve.distribution_interval_seconds == 0 && distribution_interval_seconds > 0 {
+            reserve.last_distribution_at = Clock::get()?.unix_timestamp;
+        }
+        reserve.distribution_interval_seconds = distribution_interval_seconds;
+
+        emit!(DistributionIntervalUpdatedEvent { distribution_interval_seconds });
+
+        ctx.accounts.audit_log.record(AuditAction::SetDistributionInterval, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Select how profit reaches participants
+    ///
+    /// Returns may never be guaranteed, so only outcome-contingent modes are
+    /// accepted; `FixedReturn` fails with `GuaranteedReturnForbidden`. There is
+    /// deliberately no fixed-rate parameter on `join_reserve` or anywhere else.
+    pub fn set_distribution_mode(
+        ctx: Context<UpdateReserveConfig>,
+        distribution_mode: DistributionMode,
+    ) -> Result<()> {
+        require!(
+            distribution_mode.is_outcome_contingent(),
+            AmanaError::GuaranteedReturnForbidden
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.distribution_mode = distribution_mode;
+
+        emit!(DistributionModeUpdatedEvent { distribution_mode });
+
+        ctx.accounts.audit_log.record(AuditAction::SetDistributionMode, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the contribution bounds and lockup of a participant tier
+    ///
+    /// Applies to joins and deposits from now on; positions already held keep
+    /// the lockup they joined with.
+    pub fn set_tier_config(
+        ctx: Context<UpdateReserveConfig>,
+        tier: ParticipantTier,
+        min_contribution: u64,
+        max_contribution: u64,
+        lockup_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            max_contribution == 0 || max_contribution >= min_contribution,
+            AmanaError::InvalidTierConfig
+        );
+        require!(lockup_seconds >= 0, AmanaError::InvalidDuration);
+
+        let config = TierConfig {
+            min_contribution,
+            max_contribution,
+            lockup_seconds,
+        };
+        ctx.accounts.reserve.tier_configs[tier.index()] = config;
+
+        emit!(TierConfigUpdatedEvent {
+            tier,
+            min_contribution,
+            max_contribution,
+            lockup_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetTierConfig, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Register the swap venue used for profit conversions and its slippage tolerance
+    ///
+    /// The venue must be screened as halal before it is registered: spot
+    /// exchange only, no interest-bearing lending or leveraged pools behind
+    /// the route. `Pubkey::default()` disables conversions.
+    pub fn set_conversion_policy(
+        ctx: Context<UpdateReserveConfig>,
+        swap_program: Pubkey,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        require!(max_slippage_bps <= 10000, AmanaError::InvalidSlippage);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.swap_program = swap_program;
+        reserve.max_slippage_bps = max_slippage_bps;
+
+        emit!(ConversionPolicyUpdatedEvent {
+            swap_program,
+            max_slippage_bps,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetConversionPolicy, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the rate conversions from `from_mint` into `to_mint` are held to
+    ///
+    /// `rate` is `to_mint` base units per `CONVERSION_RATE_SCALE` base units
+    /// of `from_mint`. Conversions refuse a rate older than
+    /// `MAX_CONVERSION_RATE_AGE_SECONDS`, so the admin keeps it current.
+    pub fn set_conversion_rate(
+        ctx: Context<SetConversionRate>,
+        from_mint: Pubkey,
+        to_mint: Pubkey,
+        rate: u64,
+    ) -> Result<()> {
+        require!(rate > 0, AmanaError::InvalidQuote);
+
+        let now = Clock::get()?.unix_timestamp;
+        let conversion_rate = &mut ctx.accounts.conversion_rate;
+        conversion_rate.from_mint = from_mint;
+        conversion_rate.to_mint = to_mint;
+        conversion_rate.rate = rate;
+        conversion_rate.updated_at = now;
+        conversion_rate.bump = ctx.bumps.conversion_rate;
+
+        emit!(ConversionRateUpdatedEvent {
+            from_mint,
+            to_mint,
+            rate,
+            updated_at: now,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetConversionRate, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Recommend ER delegation once `delegation_threshold` proposals land within
+    /// `delegation_window_seconds`
+    ///
+    /// Changing the policy restarts the window. A threshold of 0 disables the
+    /// recommendation; a window of 0 also stops proposals being counted.
+    pub fn set_delegation_policy(
+        ctx: Context<UpdateReserveConfig>,
+        delegation_window_seconds: i64,
+        delegation_threshold: u32,
+    ) -> Result<()> {
+        require!(delegation_window_seconds >= 0, AmanaError::InvalidDuration);
+        require!(
+            delegation_threshold == 0 || delegation_window_seconds > 0,
+            AmanaError::InvalidDuration
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.delegation_window_seconds = delegation_window_seconds;
+        reserve.delegation_threshold = delegation_threshold;
+        reserve.window_started_at = Clock::get()?.unix_timestamp;
+        reserve.window_proposal_count = 0;
+
+        emit!(DelegationPolicyUpdatedEvent {
+            delegation_window_seconds,
+            delegation_threshold,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetDelegationPolicy, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Change the participant and contributed-capital caps
+    ///
+    /// Lowering a cap is always up to the admin, but never below what the
+    /// reserve already holds. Once `capacity_governed` is set, raising either
+    /// cap (including lifting `max_total_capital` to 0, unlimited) also needs
+    /// the amana-dao `Dao` PDA to sign, which it only does from
+    /// `apply_capacity_proposal` for an executed proposal whose
+    /// `target_account` is `capacity_commitment(max_participants,
+    /// max_total_capital)`. `proposal_id` names that proposal and is ignored
+    /// otherwise.
+    pub fn set_capacity(
+        ctx: Context<SetCapacity>,
+        max_participants: u64,
+        max_total_capital: u64,
+        proposal_id: Option<u64>,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        require!(
+            max_participants >= reserve.participant_count
+                && (max_total_capital == 0 || max_total_capital >= reserve.total_contributed),
+            AmanaError::InvalidCapacity
+        );
+
+        let raises_capital = reserve.max_total_capital != 0
+            && (max_total_capital == 0 || max_total_capital > reserve.max_total_capital);
+        let raises = max_participants > reserve.max_participants || raises_capital;
+
+        let mut approved_by = None;
+        if reserve.capacity_governed && raises {
+            require!(ctx.accounts.dao.is_some(), AmanaError::CapacityProposalRequired);
+            let id = proposal_id.ok_or(AmanaError::CapacityProposalRequired)?;
+            require!(
+                id >= reserve.next_capacity_proposal_id,
+                AmanaError::InvalidCapacityProposal
+            );
+            reserve.next_capacity_proposal_id = id
+                .checked_add(1)
+                .ok_or(AmanaError::MathOverflow)?;
+            approved_by = Some(id);
+        }
+
+        reserve.max_participants = max_participants;
+        reserve.max_total_capital = max_total_capital;
+
+        emit!(CapacityUpdatedEvent {
+            max_participants,
+            max_total_capital,
+            proposal_id: approved_by,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetCapacity, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Require DAO approval for every future capacity increase
+    ///
+    /// One-way: an admin able to switch it off could expand unilaterally.
+    pub fn enable_capacity_governance(ctx: Context<UpdateReserveConfig>) -> Result<()> {
+        ctx.accounts.reserve.capacity_governed = true;
+
+        emit!(CapacityGovernanceEnabledEvent {
+            admin: ctx.accounts.admin.key(),
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::EnableCapacityGovernance, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set when unclaimed SOL profit counts as dormant and how long a sweep
+    /// stays reversible
+    ///
+    /// A non-zero `dormancy_seconds` must be at least `MIN_DORMANCY_SECONDS`,
+    /// so only genuinely abandoned profit is ever swept, and
+    /// `sweep_reversal_seconds` at least `MIN_SWEEP_REVERSAL_SECONDS`, so a
+    /// participant always has time to take a sweep back. 0 disables sweeping.
+    pub fn set_dormancy_policy(
+        ctx: Context<UpdateReserveConfig>,
+        dormancy_seconds: i64,
+        sweep_reversal_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            dormancy_seconds == 0 || dormancy_seconds >= MIN_DORMANCY_SECONDS,
+            AmanaError::InvalidDuration
+        );
+        require!(
+            dormancy_seconds == 0 || sweep_reversal_seconds >= MIN_SWEEP_REVERSAL_SECONDS,
+            AmanaError::InvalidDuration
+        );
+        require!(sweep_reversal_seconds >= 0, AmanaError::InvalidDuration);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.dormancy_seconds = dormancy_seconds;
+        reserve.sweep_reversal_seconds = sweep_reversal_seconds;
+
+        emit!(DormancyPolicyUpdatedEvent {
+            dormancy_seconds,
+            sweep_reversal_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetDormancyPolicy, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Let `operator` commit and undelegate the reserve alongside the admin
+    ///
+    /// Re-authorizing a previously revoked operator reuses its record.
+    pub fn authorize_operator(ctx: Context<AuthorizeOperator>, operator: Pubkey) -> Result<()> {
+        let record = &mut ctx.accounts.operator;
+        record.operator = operator;
+        record.is_authorized = true;
+        record.bump = ctx.bumps.operator;
+
+        emit!(OperatorAuthorizedEvent { operator });
+
+        ctx.accounts.audit_log.record(AuditAction::AuthorizeOperator, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Withdraw an operator's authorization
+    pub fn revoke_operator(ctx: Context<RevokeOperator>) -> Result<()> {
+        let record = &mut ctx.accounts.operator;
+        record.is_authorized = false;
+
+        emit!(OperatorRevokedEvent { operator: record.operator });
+
+        ctx.accounts.audit_log.record(AuditAction::RevokeOperator, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the validator stake requirement and unbonding delay
+    ///
+    /// A non-zero `min_validator_stake` gates approval and completion on a
+    /// registered validator with at least that much stake still bonded.
+    pub fn set_validator_policy(
+        ctx: Context<UpdateReserveConfig>,
+        min_validator_stake: u64,
+        validator_unbonding_seconds: i64,
+    ) -> Result<()> {
+        require!(validator_unbonding_seconds >= 0, AmanaError::InvalidDuration);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.min_validator_stake = min_validator_stake;
+        reserve.validator_unbonding_seconds = validator_unbonding_seconds;
+
+        emit!(ValidatorPolicyUpdatedEvent {
+            min_validator_stake,
+            validator_unbonding_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetValidatorPolicy, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the vesting schedule applied to newly allocated profit shares
+    ///
+    /// Shares vest linearly over `vesting_duration_seconds` from allocation,
+    /// with nothing claimable before `vesting_cliff_seconds`. A duration of 0
+    /// makes new shares claimable immediately. Existing shares keep the
+    /// schedule they were allocated with.
+    pub fn set_vesting_schedule(
+        ctx: Context<UpdateReserveConfig>,
+        vesting_cliff_seconds: i64,
+        vesting_duration_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            vesting_cliff_seconds >= 0
+                && vesting_duration_seconds >= 0
+                && vesting_cliff_seconds <= vesting_duration_seconds,
+            AmanaError::InvalidDuration
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.vesting_cliff_seconds = vesting_cliff_seconds;
+        reserve.vesting_duration_seconds = vesting_duration_seconds;
+
+        emit!(VestingScheduleUpdatedEvent {
+            vesting_cliff_seconds,
+            vesting_duration_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetVestingSchedule, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Cap outstanding Qard Hasan at `max_qard_outstanding_bps` of capital
+    ///
+    /// Capital here is `total_capital` plus the Qard already lent out, so the
+    /// cap does not shrink as loans are issued. 0 disables new Qard; loans
+    /// already issued can still be repaid.
+    pub fn set_qard_limit(
+        ctx: Context<UpdateReserveConfig>,
+        max_qard_outstanding_bps: u16,
+    ) -> Result<()> {
+        require!(max_qard_outstanding_bps <= 10000, AmanaError::InvalidQardLimit);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.max_qard_outstanding_bps = max_qard_outstanding_bps;
+
+        emit!(QardLimitUpdatedEvent { max_qard_outstanding_bps });
+
+        ctx.accounts.audit_log.record(AuditAction::SetQardLimit, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Require a bond from whoever reports an activity's outcome
+    ///
+    /// While `completion_bond` is non-zero, `complete_activity` needs a
+    /// `CompletionBond` posted for the activity. The bond is refundable once
+    /// `dispute_window_seconds` have passed after completion, and can be
+    /// slashed to the reserve by the admin within that window.
+    pub fn set_completion_bond(
+        ctx: Context<UpdateReserveConfig>,
+        completion_bond: u64,
+        dispute_window_seconds: i64,
+    ) -> Result<()> {
+        require!(dispute_window_seconds >= 0, AmanaError::InvalidDuration);
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.completion_bond = completion_bond;
+        reserve.dispute_window_seconds = dispute_window_seconds;
+
+        emit!(CompletionBondUpdatedEvent {
+            completion_bond,
+            dispute_window_seconds,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetCompletionBond, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Create the guarantee fund that covers part of each SOL loss
+    ///
+    /// The fund at `[b"guarantee_fund"]` holds donated lamports only; it is
+    /// kept apart from participant capital and has no withdrawal path.
+    pub fn initialize_guarantee_fund(ctx: Context<InitializeGuaranteeFund>) -> Result<()> {
+        let fund = &mut ctx.accounts.guarantee_fund;
+        fund.balance = 0;
+        fund.total_donated = 0;
+        fund.total_covered = 0;
+        fund.bump = ctx.bumps.guarantee_fund;
+
+        ctx.accounts.audit_log.record(AuditAction::InitializeGuaranteeFund, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Donate lamports to the guarantee fund
+    ///
+    /// Anyone may donate. The donation is tabarru' (a gift), not a premium:
+    /// it buys the donor no claim on the fund or on any covered loss.
+    pub fn fund_guarantee(ctx: Context<FundGuarantee>, amount: u64) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.donor.key(),
+                &ctx.accounts.guarantee_fund.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.donor.to_account_info(),
+                ctx.accounts.guarantee_fund.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let fund = &mut ctx.accounts.guarantee_fund;
+        fund.balance = fund.balance
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        fund.total_donated = fund.total_donated
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(GuaranteeFundedEvent {
+            donor: ctx.accounts.donor.key(),
+            amount,
+            balance: fund.balance,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of each SOL loss the guarantee fund covers
+    ///
+    /// While non-zero, `complete_activity` must be given the fund to settle a
+    /// SOL loss, and draws `loss * guarantee_coverage_bps / 10000` from it (as
+    /// far as its balance allows) before the rest reaches participants.
+    pub fn set_guarantee_coverage(
+        ctx: Context<UpdateReserveConfig>,
+        guarantee_coverage_bps: u16,
+    ) -> Result<()> {
+        require!(guarantee_coverage_bps <= 10000, AmanaError::InvalidGuaranteeCoverage);
+
+        ctx.accounts.reserve.guarantee_coverage_bps = guarantee_coverage_bps;
+
+        emit!(GuaranteeCoverageUpdatedEvent { guarantee_coverage_bps });
+
+        ctx.accounts.audit_log.record(AuditAction::SetGuaranteeCoverage, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Enable or disable Mudarabah limited liability on SOL losses
+    ///
+    /// While enabled, `realize_loss` never charges a participant more than
+    /// their `capital_contributed`: the capital providers lose at most what
+    /// they put in and the initiator (the mudarib) loses only their effort.
+    /// The guarantee fund absorbs what it can of any excess and the rest is
+    /// written off. While disabled the excess is carried on the participant
+    /// and taken from their later profit.
+    pub fn set_mudarabah_limited_liability(
+        ctx: Context<UpdateReserveConfig>,
+        mudarabah_limited_liability: bool,
+    ) -> Result<()> {
+        ctx.accounts.reserve.mudarabah_limited_liability = mudarabah_limited_liability;
+
+        emit!(LimitedLiabilityUpdatedEvent { mudarabah_limited_liability });
+
+        ctx.accounts.audit_log.record(AuditAction::SetLimitedLiability, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Create the compliance fund that collects activity fees
+    ///
+    /// The fund at `[b"compliance_fund"]` pays for compliance checks and
+    /// validation. It is kept apart from participant capital.
+    pub fn initialize_compliance_fund(ctx: Context<InitializeComplianceFund>) -> Result<()> {
+        let fund = &mut ctx.accounts.compliance_fund;
+        fund.balance = 0;
+        fund.total_collected = 0;
+        fund.total_paid = 0;
+        fund.bump = ctx.bumps.compliance_fund;
+
+        ctx.accounts.audit_log.record(AuditAction::InitializeComplianceFund, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the flat fee a proposer pays into the compliance fund
+    ///
+    /// The fee is a charge for the compliance and validation service
+    /// (ujrah), not a return on capital: it is paid by the proposer, never
+    /// drawn from pooled capital and never distributed as profit. Once set,
+    /// `propose_activity` needs the compliance fund (`ComplianceFundRequired`).
+    pub fn set_activity_fee(ctx: Context<UpdateReserveConfig>, activity_fee: u64) -> Result<()> {
+        ctx.accounts.reserve.activity_fee = activity_fee;
+
+        emit!(ActivityFeeUpdatedEvent { activity_fee });
+
+        ctx.accounts.audit_log.record(AuditAction::SetActivityFee, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Compensate a validator or compliance keeper from the compliance fund
+    pub fn pay_compliance_keeper(ctx: Context<PayComplianceKeeper>, amount: u64) -> Result<()> {
+        let fund = &mut ctx.accounts.compliance_fund;
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(amount <= fund.balance, AmanaError::InsufficientLiquidity);
+
+        fund.balance -= amount;
+        fund.total_paid = fund.total_paid
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **fund.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.keeper.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(ComplianceKeeperPaidEvent {
+            keeper: ctx.accounts.keeper.key(),
+            amount,
+            fund_balance: fund.balance,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::PayComplianceKeeper, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Create the treasury that collects management fees
+    ///
+    /// The treasury at `[b"treasury"]` receives the management fee taken from
+    /// each SOL profit. It is kept apart from participant capital.
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.balance = 0;
+        treasury.total_collected = 0;
+        treasury.total_withdrawn = 0;
+        treasury.bump = ctx.bumps.treasury;
+
+        ctx.accounts.audit_log.record(AuditAction::InitializeTreasury, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Set the management fee taken from each SOL profit, in basis points
+    ///
+    /// The fee is the manager's agreed share of realized profit, never a
+    /// charge on capital: losses and break-even outcomes pay nothing. It is
+    /// capped at `MAX_MANAGEMENT_FEE_BPS`. `complete_activity` books it in
+    /// `fees_owed` and `collect_management_fee` pays it to the treasury.
+    pub fn set_management_fee(
+        ctx: Context<UpdateReserveConfig>,
+        management_fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            management_fee_bps <= MAX_MANAGEMENT_FEE_BPS,
+            AmanaError::InvalidManagementFee
+        );
+
+        ctx.accounts.reserve.management_fee_bps = management_fee_bps;
+
+        emit!(ManagementFeeUpdatedEvent { management_fee_bps });
+
+        ctx.accounts.audit_log.record(AuditAction::SetManagementFee, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Pay owed management fees into the treasury
+    ///
+    /// Fees are only paid from lamports the reserve holds beyond what it
+    /// accounts to participants, so a profit that was reported but never
+    /// deposited is not taken out of principal. Whatever that surplus cannot
+    /// cover stays in `fees_owed` for a later call.
+    pub fn collect_management_fee(ctx: Context<CollectManagementFee>) -> Result<()> {
+        if !solvency_interlock(&mut ctx.accounts.reserve)? {
+            return Ok(());
+        }
+
+        let (_, accounted, available) = solvency_ratio(&ctx.accounts.reserve)?;
+        let reserve = &mut ctx.accounts.reserve;
+        let amount = reserve.fees_owed.min(available.saturating_sub(accounted));
+        require!(amount > 0, AmanaError::InsufficientLiquidity);
+
+        reserve.fees_owed = reserve.fees_owed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.balance = treasury.balance
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        treasury.total_collected = treasury.total_collected
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(ManagementFeeCollectedEvent {
+            amount,
+            fees_owed: ctx.accounts.reserve.fees_owed,
+            treasury_balance: ctx.accounts.treasury.balance,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::CollectManagementFee, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Pay collected management fees out of the treasury
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(amount <= treasury.balance, AmanaError::InsufficientLiquidity);
+
+        treasury.balance -= amount;
+        treasury.total_withdrawn = treasury.total_withdrawn
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **treasury.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(TreasuryWithdrawnEvent {
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            treasury_balance: treasury.balance,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::WithdrawTreasury, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Point front-ends at an updated off-chain description of the reserve
+    pub fn set_reserve_metadata(
+        ctx: Context<UpdateReserveConfig>,
+        metadata_uri: String,
+    ) -> Result<()> {
+        require!(
+            metadata_uri.len() <= MAX_METADATA_URI_LEN,
+            AmanaError::MetadataUriTooLong
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.metadata_uri = metadata_uri;
+
+        emit!(ReserveMetadataUpdatedEvent {
+            name: reserve.name,
+            metadata_uri: reserve.metadata_uri.clone(),
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetReserveMetadata, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Open a token vault for activities settled in `mint`
+    ///
+    /// The vault is a token account at `[b"vault", mint]` whose authority is
+    /// the `MintVault` PDA `[b"mint_vault", mint]`, which keeps that mint's
+    /// books separate from the reserve's SOL capital.
+    pub fn initialize_mint_vault(ctx: Context<InitializeMintVault>) -> Result<()> {
+        let mint_vault = &mut ctx.accounts.mint_vault;
+        mint_vault.mint = ctx.accounts.mint.key();
+        mint_vault.vault = ctx.accounts.vault.key();
+        mint_vault.booked_balance = 0;
+        mint_vault.realized_loss = 0;
+        mint_vault.bump = ctx.bumps.mint_vault;
+
+        emit!(MintVaultInitializedEvent {
+            mint: mint_vault.mint,
+            vault: mint_vault.vault,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::InitializeMintVault, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Settle a proposed activity's outcome in an SPL token instead of SOL
+    ///
+    /// Only the initiator may choose, and only before approval; the mint must
+    /// already have a vault.
+    pub fn set_settlement_mint(
+        ctx: Context<SetSettlementMint>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let activity = &mut ctx.accounts.activity;
+        require!(
+            activity.status == ActivityStatus::Proposed,
+            AmanaError::InvalidActivityStatus
+        );
+
+        activity.settlement_mint = ctx.accounts.mint_vault.mint;
+
+        emit!(SettlementMintSetEvent {
+            activity_id,
+            mint: activity.settlement_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Authorize the amana-private bridge PDA to debit private deployments
+    ///
+    /// `private_bridge` should be the `[b"reserve_bridge"]` PDA of the
+    /// amana-private program; `Pubkey::default()` disables the bridge.
+    pub fn set_private_bridge(
+        ctx: Context<UpdateReserveConfig>,
+        private_bridge: Pubkey,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.private_bridge = private_bridge;
+
+        emit!(PrivateBridgeUpdatedEvent { private_bridge });
+
+        ctx.accounts.audit_log.record(AuditAction::SetPrivateBridge, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Let `deployer` deploy capital privately alongside the admin
+    ///
+    /// Re-authorizing a previously revoked deployer reuses its record.
+    pub fn authorize_private_deployer(
+        ctx: Context<AuthorizePrivateDeployer>,
+        deployer: Pubkey,
+    ) -> Result<()> {
+        let record = &mut ctx.accounts.private_deployer;
+        record.deployer = deployer;
+        record.is_authorized = true;
+        record.bump = ctx.bumps.private_deployer;
+
+        emit!(PrivateDeployerAuthorizedEvent { deployer });
+
+        ctx.accounts.audit_log.record(AuditAction::AuthorizePrivateDeployer, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Withdraw a private deployer's authorization
+    pub fn revoke_private_deployer(ctx: Context<RevokePrivateDeployer>) -> Result<()> {
+        let record = &mut ctx.accounts.private_deployer;
+        record.is_authorized = false;
+
+        emit!(PrivateDeployerRevokedEvent { deployer: record.deployer });
+
+        ctx.accounts.audit_log.record(AuditAction::RevokePrivateDeployer, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Let `reporter` complete activities that draw on the guarantee fund
+    ///
+    /// Re-authorizing a previously revoked reporter reuses its record.
+    pub fn authorize_reporter(ctx: Context<AuthorizeReporter>, reporter: Pubkey) -> Result<()> {
+        let record = &mut ctx.accounts.reporter_record;
+        record.reporter = reporter;
+        record.is_authorized = true;
+        record.bump = ctx.bumps.reporter_record;
+
+        emit!(ReporterAuthorizedEvent { reporter });
+
+        ctx.accounts.audit_log.record(AuditAction::AuthorizeReporter, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Withdraw a reporter's authorization
+    pub fn revoke_reporter(ctx: Context<RevokeReporter>) -> Result<()> {
+        let record = &mut ctx.accounts.reporter_record;
+        record.is_authorized = false;
+
+        emit!(ReporterRevokedEvent { reporter: record.reporter });
+
+        ctx.accounts.audit_log.record(AuditAction::RevokeReporter, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Move free capital into the private deployment bucket
+    ///
+    /// Only callable by CPI from amana-private, signed by its bridge PDA, so
+    /// public free capital reflects capital deployed privately. The private
+    /// activity's details stay encrypted on the private side.
+    ///
+    /// The deployer, whose signature carries through the CPI, must be the
+    /// reserve admin or hold an authorized `PrivateDeployer` record. Like a
+    /// proposal, the amount must fit in the free capital left after pending
+    /// proposals (`total_capital - pending_proposed_capital`).
+    pub fn debit_private_deployment(
+        ctx: Context<DebitPrivateDeployment>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require_admin_or_private_deployer(
+            &ctx.accounts.reserve,
+            &ctx.accounts.deployer.key(),
+            ctx.accounts.private_deployer.as_ref(),
+        )?;
+
+        let reserve = &mut ctx.accounts.reserve;
+        require!(reserve.status == ReserveStatus::Active, AmanaError::ReserveWindingDown);
+        let free_capital = reserve.total_capital.saturating_sub(reserve.pending_proposed_capital);
+        require!(amount <= free_capital, AmanaError::InsufficientCapital);
+
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::InsufficientCapital)?;
+        reserve.private_deployed_capital = reserve.private_deployed_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(PrivateDeploymentDebitedEvent {
+            total_capital: reserve.total_capital,
+            private_deployed_capital: reserve.private_deployed_capital,
+        });
+
+        Ok(())
+    }
+
+    /// Return a settled private deployment to free capital
+    ///
+    /// The counterpart of `debit_private_deployment`, under the same bridge
+    /// and deployer checks. The debit only moved capital between buckets and
+    /// the lamports never left the reserve, so `amount` moves back from
+    /// `private_deployed_capital` into `total_capital`.
+    pub fn credit_private_deployment(
+        ctx: Context<DebitPrivateDeployment>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require_admin_or_private_deployer(
+            &ctx.accounts.reserve,
+            &ctx.accounts.deployer.key(),
+            ctx.accounts.private_deployer.as_ref(),
+        )?;
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.private_deployed_capital = reserve.private_deployed_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::InsufficientCapital)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(PrivateDeploymentCreditedEvent {
+            total_capital: reserve.total_capital,
+            private_deployed_capital: reserve.private_deployed_capital,
+        });
+
+        Ok(())
+    }
+
+    /// Add an address to the denylist
+    ///
+    /// Compliance control for sanctioned or barred addresses: a denied key
+    /// cannot join, and passing its participant account freezes the existing
+    /// position (no deposits, withdrawals, claims or delegation). Entries are
+    /// stored in up to `MAX_DENYLIST_PAGES` pages of
+    /// `MAX_DENYLIST_PAGE_ENTRIES`; `page` must be an existing page with room
+    /// or `denylist_page_count` to open a new one. Every existing page must
+    /// be passed via `remaining_accounts`, so an address is listed on one
+    /// page only.
+    pub fn deny_address<'info>(
+        ctx: Context<'_, '_, '_, 'info, DenyAddress<'info>>,
+        page: u32,
+        agent: Pubkey,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        let denylist = &mut ctx.accounts.denylist_page;
+
+        require!(page <= reserve.denylist_page_count, AmanaError::InvalidDenylistPage);
+        require!(page < MAX_DENYLIST_PAGES, AmanaError::DenylistFull);
+        require_not_listed(reserve, &agent, ctx.remaining_accounts, Some(page))?;
+        if page == reserve.denylist_page_count {
+            denylist.page = page;
+            denylist.bump = ctx.bumps.denylist_page;
+            reserve.denylist_page_count = reserve.denylist_page_count
+                .checked_add(1)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+
+        require!(!denylist.entries.contains(&agent), AmanaError::Denied);
+        require!(
+            denylist.entries.len() < MAX_DENYLIST_PAGE_ENTRIES,
+            AmanaError::DenylistPageFull
+        );
+        denylist.entries.push(agent);
+
+        let frozen = if let Some(participant) = ctx.accounts.participant.as_mut() {
+            participant.is_frozen = true;
+            true
+        } else {
+            false
+        };
+
+        emit!(DenylistUpdatedEvent {
+            agent,
+            page,
+            denied: true,
+            frozen,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::DenyAddress, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Remove an address from a denylist page, unfreezing its position if passed
+    ///
+    /// Every denylist page must be passed via `remaining_accounts`. If the
+    /// address is still listed on another page the removal is refused, so a
+    /// position is never unfrozen while its owner remains denied.
+    pub fn allow_address<'info>(
+        ctx: Context<'_, '_, '_, 'info, AllowAddress<'info>>,
+        page: u32,
+        agent: Pubkey,
+    ) -> Result<()> {
+        require_not_listed(&ctx.accounts.reserve, &agent, ctx.remaining_accounts, Some(page))?;
+        let denylist = &mut ctx.accounts.denylist_page;
+
+        let position = denylist
+            .entries
+            .iter()
+            .position(|entry| *entry == agent)
+            .ok_or(AmanaError::NotDenied)?;
+        denylist.entries.swap_remove(position);
+
+        if let Some(participant) = ctx.accounts.participant.as_mut() {
+            participant.is_frozen = false;
+        }
+
+        emit!(DenylistUpdatedEvent {
+            agent,
+            page,
+            denied: false,
+            frozen: false,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::AllowAddress, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Recompute the reserve's free capital from its lamport balance
+    ///
+    /// Every open (approved or active) activity must be passed via
+    /// `remaining_accounts`; together they are the escrow evidence and must
+    /// match `deployed_capital` and `open_activity_count` exactly. Free capital
+    /// is then the reserve's lamports less its rent-exempt minimum, the
+    /// escrowed capital, the capital deployed privately and the management
+    /// fees owed, so booked fees never become participant capital.
+    pub fn reconcile_capital(ctx: Context<ReconcileCapital>) -> Result<()> {
+        let reserve_info = ctx.accounts.reserve.to_account_info();
+        let reserve = &mut ctx.accounts.reserve;
+
+        require!(
+            ctx.remaining_accounts.len() as u64 == reserve.open_activity_count,
+            AmanaError::ReconciliationMismatch
+        );
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut escrowed: u64 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), AmanaError::DuplicateActivity);
+            seen.push(info.key());
+
+            let activity: Activity = load_remaining(info)?;
+            require_activity_pda(info, reserve.id_seed(), &activity)?;
+            require!(
+                activity.status == ActivityStatus::Approved
+                    || activity.status == ActivityStatus::Active,
+                AmanaError::InvalidActivityStatus
+            );
+            escrowed = escrowed
+                .checked_add(activity.capital_deployed)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+        require!(
+            escrowed == reserve.deployed_capital,
+            AmanaError::ReconciliationMismatch
+        );
+
+        let rent_floor = Rent::get()?.minimum_balance(reserve_info.data_len());
+        let free_capital = reserve_info
+            .lamports()
+            .checked_sub(rent_floor)
+            .and_then(|v| v.checked_sub(escrowed))
+            .and_then(|v| v.checked_sub(reserve.private_deployed_capital))
+            .ok_or(AmanaError::ReconciliationMismatch)?
+            // Owed fees the lamports cannot cover yet stay owed
+            .saturating_sub(reserve.fees_owed);
+
+        let old = reserve.total_capital;
+        reserve.total_capital = free_capital;
+
+        emit!(CapitalReconciledEvent {
+            old,
+            new: free_capital,
+            escrowed,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::ReconcileCapital, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Aggregate several completed activities into one consolidated report
+    ///
+    /// Read-only: the activities are passed via `remaining_accounts` in the
+    /// same order as `activity_ids` and are never modified, so history stays
+    /// intact while reporting can treat them as a single venture.
+    pub fn merge_activity_reports(
+        ctx: Context<MergeActivityReports>,
+        activity_ids: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            activity_ids.len() >= 2 && activity_ids.len() <= MAX_MERGED_ACTIVITIES,
+            AmanaError::InvalidRemainingAccount
+        );
+        require!(
+            ctx.remaining_accounts.len() == activity_ids.len(),
+            AmanaError::InvalidRemainingAccount
+        );
+
+        let mut total_capital_deployed: u64 = 0;
+        let mut total_profit: u64 = 0;
+        let mut total_loss: u64 = 0;
+
+        for (index, (info, activity_id)) in ctx
+            .remaining_accounts
+            .iter()
+            .zip(activity_ids.iter())
+            .enumerate()
+        {
+            require!(
+                !activity_ids[..index].contains(activity_id),
+                AmanaError::DuplicateActivity
+            );
+
+            let activity: Activity = load_remaining(info)?;
+            require_activity_pda(info, ctx.accounts.reserve.id_seed(), &activity)?;
+            require!(
+                activity.activity_id == *activity_id,
+                AmanaError::InvalidRemainingAccount
+            );
+            require!(
+                activity.status == ActivityStatus::Completed,
+                AmanaError::InvalidActivityStatus
+            );
+
+            total_capital_deployed = total_capital_deployed
+                .checked_add(activity.capital_deployed)
+                .ok_or(AmanaError::MathOverflow)?;
+            if activity.outcome > 0 {
+                total_profit = total_profit
+                    .checked_add(activity.outcome as u64)
+                    .ok_or(AmanaError::MathOverflow)?;
+            } else {
+                total_loss = total_loss
+                    .checked_add(activity.outcome.unsigned_abs())
+                    .ok_or(AmanaError::MathOverflow)?;
+            }
+        }
+
+        let net_outcome = (total_profit as i128)
+            .checked_sub(total_loss as i128)
+            .and_then(|v| i64::try_from(v).ok())
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(MergedReportEvent {
+            activity_ids,
+            total_capital_deployed,
+            total_profit,
+            total_loss,
+            net_outcome,
+        });
+
+        Ok(())
+    }
+
+    /// Check that an activity's lifecycle fields are internally consistent
+    ///
+    /// Read-only. Emits `IntegrityCheckEvent` with the first violated
+    /// invariant as one of the `INTEGRITY_*` codes, or `INTEGRITY_OK`.
+    pub fn verify_activity_integrity(
+        ctx: Context<VerifyActivityIntegrity>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let violation_code = activity_integrity_violation(&ctx.accounts.activity, &activity_id);
+
+        emit!(IntegrityCheckEvent {
+            activity_id,
+            passed: violation_code == INTEGRITY_OK,
+            violation_code,
+        });
+
+        Ok(())
+    }
+
+    /// Emit the capital-weighted average risk rating of deployed activities
+    ///
+    /// Read-only. Active activities are passed via
+    /// `remaining_accounts`, at most `MAX_PORTFOLIO_ACTIVITIES` of them; each
+    /// is weighted by its `capital_deployed`. The average is scaled by 10000,
+    /// so a rating of 2.5 is reported as 25000.
+    pub fn compute_portfolio_risk(ctx: Context<ComputePortfolioRisk>) -> Result<()> {
+        let activity_count = ctx.remaining_accounts.len();
+        require!(
+            activity_count > 0 && activity_count <= MAX_PORTFOLIO_ACTIVITIES,
+            AmanaError::InvalidRemainingAccount
+        );
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(activity_count);
+        let mut total_capital_deployed: u64 = 0;
+        let mut total_risk_weighted: u128 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), AmanaError::DuplicateActivity);
+            seen.push(info.key());
+
+            let activity: Activity = load_remaining(info)?;
+            require_activity_pda(info, ctx.accounts.reserve.id_seed(), &activity)?;
+            require!(
+                activity.status == ActivityStatus::Active,
+                AmanaError::InvalidActivityStatus
+            );
+
+            total_capital_deployed = total_capital_deployed
+                .checked_add(activity.capital_deployed)
+                .ok_or(AmanaError::MathOverflow)?;
+            total_risk_weighted = total_risk_weighted
+                .checked_add(risk_weighted(activity.capital_deployed, activity.risk_rating)? as u128)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+
+        let average_risk_rating_bps = if total_capital_deployed == 0 {
+            0
+        } else {
+            (total_risk_weighted
+                .checked_mul(10000)
+                .ok_or(AmanaError::MathOverflow)?
+                / total_capital_deployed as u128) as u32
+        };
+
+        emit!(PortfolioRiskEvent {
+            activity_count: activity_count as u32,
+            total_capital_deployed,
+            average_risk_rating_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Emit a Gini coefficient of `capital_contributed` across participants
+    ///
+    /// Read-only. Participant accounts are passed via `remaining_accounts`,
+    /// at most `MAX_DISTRIBUTION_SAMPLE` of them. When the reserve has more
+    /// participants the coefficient describes the sample only, so callers
+    /// should pass the largest positions to bound the concentration from
+    /// below. The event reports the sample size next to `participant_count`.
+    pub fn compute_distribution_metrics(ctx: Context<ComputeDistributionMetrics>) -> Result<()> {
+        let sample_size = ctx.remaining_accounts.len();
+        require!(
+            sample_size > 0 && sample_size <= MAX_DISTRIBUTION_SAMPLE,
+            AmanaError::InvalidRemainingAccount
+        );
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(sample_size);
+        let mut capitals: Vec<u64> = Vec::with_capacity(sample_size);
+        for info in ctx.remaining_accounts.iter() {
+            let participant: Participant = load_remaining(info)?;
+            let expected = Pubkey::create_program_address(
+                &[
+                    b"participant",
+                    ctx.accounts.reserve.id_seed(),
+                    participant.agent.as_ref(),
+                    &[participant.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(AmanaError::InvalidRemainingAccount))?;
+            require_keys_eq!(info.key(), expected, AmanaError::InvalidRemainingAccount);
+            require!(!seen.contains(&participant.agent), AmanaError::DuplicateParticipant);
+            seen.push(participant.agent);
+            capitals.push(participant.capital_contributed);
+        }
+
+        let total_sampled_capital = capitals
+            .iter()
+            .try_fold(0u64, |acc, c| acc.checked_add(*c))
+            .ok_or(AmanaError::MathOverflow)?;
+        let gini_bps = gini_coefficient_bps(&mut capitals)?;
+
+        emit!(DistributionMetricsEvent {
+            sample_size: sample_size as u32,
+            participant_count: ctx.accounts.reserve.participant_count,
+            total_sampled_capital,
+            gini_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Emit how gross capital splits between deployed, idle and buffer
+    ///
+    /// Read-only. Deployed capital is escrowed in activities, deployed
+    /// privately or lent as Qard; the buffer is the part of free capital
+    /// already reserved by pending proposals (`pending_proposed_capital`);
+    /// the rest of free capital is idle. The three shares always sum to
+    /// 10000, and a reserve with no capital reports everything as idle.
+    pub fn compute_utilization(ctx: Context<ComputeUtilization>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        let gross_capital = reserve.gross_capital()?;
+        let deployed = gross_capital
+            .checked_sub(reserve.total_capital)
+            .ok_or(AmanaError::MathOverflow)?;
+        let buffer = reserve.pending_proposed_capital.min(reserve.total_capital);
+
+        let (deployed_bps, buffer_bps) = if gross_capital == 0 {
+            (0, 0)
+        } else {
+            let share = |amount: u64| {
+                ((amount as u128 * 10000) / gross_capital as u128) as u16
+            };
+            (share(deployed), share(buffer))
+        };
+
+        emit!(UtilizationEvent {
+            deployed_bps,
+            idle_bps: 10000 - deployed_bps - buffer_bps,
+            buffer_bps,
+            gross_capital,
+            deployed_capital: deployed,
+            buffer_capital: buffer,
+        });
+
+        Ok(())
+    }
+
+    /// Estimate when free capital will cover a pending withdrawal
+    ///
+    /// Read-only. Requests from `request_withdrawal` are recorded per
+    /// participant and settle in any order once their cooldown passes, so the
+    /// reserve keeps only their count, not their order. `queue_position` is
+    /// the lamports that must be free for the caller to be paid: the pending
+    /// withdrawals it expects to settle first plus its own amount. Upcoming
+    /// activities are passed via
+    /// `remaining_accounts`, at most `MAX_LIQUIDITY_FORECAST_ACTIVITIES`.
+    ///
+    /// The estimate assumes each activity returns its full `capital_deployed`
+    /// at `maturity_at` (or now, if already overdue), with no loss and no new
+    /// deployments, deposits or withdrawals in the meantime. Open-ended
+    /// activities have no maturity and are ignored. When even every passed
+    /// maturity falls short, `satisfiable` is false and `estimated_at` is 0.
+    pub fn estimate_withdrawal_time(
+        ctx: Context<EstimateWithdrawalTime>,
+        queue_position: u64,
+    ) -> Result<()> {
+        let activity_count = ctx.remaining_accounts.len();
+        require!(
+            activity_count <= MAX_LIQUIDITY_FORECAST_ACTIVITIES,
+            AmanaError::InvalidRemainingAccount
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(activity_count);
+        let mut maturities: Vec<(i64, u64)> = Vec::with_capacity(activity_count);
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), AmanaError::DuplicateActivity);
+            seen.push(info.key());
+
+            let activity: Activity = load_remaining(info)?;
+            require_activity_pda(info, ctx.accounts.reserve.id_seed(), &activity)?;
+            require!(
+                activity.status == ActivityStatus::Approved
+                    || activity.status == ActivityStatus::Active,
+                AmanaError::InvalidActivityStatus
+            );
+
+            if activity.maturity_at > 0 {
+                maturities.push((activity.maturity_at.max(now), activity.capital_deployed));
+            }
+        }
+        maturities.sort_unstable_by_key(|&(maturity_at, _)| maturity_at);
+
+        let available_now = ctx.accounts.reserve.total_capital;
+        let mut projected_liquidity = available_now;
+        let mut activities_counted: u32 = 0;
+        let mut estimated_at = 0;
+        if projected_liquidity >= queue_position {
+            estimated_at = now;
+        } else {
+            for (maturity_at, capital) in maturities {
+                projected_liquidity = projected_liquidity
+                    .checked_add(capital)
+                    .ok_or(AmanaError::MathOverflow)?;
+                activities_counted += 1;
+                if projected_liquidity >= queue_position {
+                    estimated_at = maturity_at;
+                    break;
+                }
+            }
+        }
+
+        emit!(EstimatedLiquidityEvent {
+            queue_position,
+            available_now,
+            projected_liquidity,
+            activities_counted,
+            satisfiable: estimated_at != 0,
+            estimated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Report everything a participant can claim right now
+    ///
+    /// Read-only. The participant's `ParticipantActivityShare` accounts are
+    /// passed via `remaining_accounts`, at most `MAX_PENDING_CLAIMS`; each must
+    /// be the participant's own share PDA. `total_claimable` sums the vested,
+    /// unclaimed lamports of SOL shares; token-settled shares are listed in
+    /// the breakdown with their mint but not added to the total. Shares with
+    /// nothing claimable are counted but left out of the breakdown.
+    pub fn list_pending_claims(ctx: Context<ListPendingClaims>) -> Result<()> {
+        let share_count = ctx.remaining_accounts.len();
+        require!(share_count <= MAX_PENDING_CLAIMS, AmanaError::InvalidRemainingAccount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let agent = ctx.accounts.participant.agent;
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(share_count);
+        let mut activity_ids: Vec<[u8; 32]> = Vec::with_capacity(share_count);
+        let mut mints: Vec<Pubkey> = Vec::with_capacity(share_count);
+        let mut amounts: Vec<u64> = Vec::with_capacity(share_count);
+        let mut total_claimable: u64 = 0;
+
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), AmanaError::InvalidRemainingAccount);
+            seen.push(info.key());
+
+            let share: ParticipantActivityShare = load_remaining(info)?;
+            require_keys_eq!(share.agent, agent, AmanaError::InvalidRemainingAccount);
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"share", ctx.accounts.reserve.id_seed(), share.activity_id.as_ref(), agent.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(info.key(), expected, AmanaError::InvalidRemainingAccount);
+
+            let claimable = share.claimable(now)?;
+            if claimable == 0 {
+                continue;
+            }
+            if share.mint == Pubkey::default() {
+                total_claimable = total_claimable
+                    .checked_add(claimable)
+                    .ok_or(AmanaError::MathOverflow)?;
+            }
+            activity_ids.push(share.activity_id);
+            mints.push(share.mint);
+            amounts.push(claimable);
+        }
+
+        emit!(PendingClaimsEvent {
+            agent,
+            total_claimable,
+            claim_count: activity_ids.len() as u32,
+            shares_checked: share_count as u32,
+            activity_ids,
+            mints,
+            amounts,
+        });
+
+        Ok(())
+    }
+
+    /// Summarise the keeper work currently pending on the reserve
+    ///
+    /// Read-only, so a keeper learns in one call whether anything needs doing:
+    /// - `overdue_count`: activities past `maturity_at + maturity_grace_seconds`
+    ///   that `auto_complete_overdue` would accept, among those passed via
+    ///   `remaining_accounts` (at most `MAX_POLL_WORK_ACTIVITIES`; others are
+    ///   skipped rather than rejected)
+    /// - `distributions_due`: `distribute_pending` would distribute now
+    /// - `withdrawals_queued`: participants still to `settle_and_exit` while
+    ///   winding down; otherwise `request_withdrawal` requests not yet settled
+    ///   or cancelled
+    /// - `breaker_tripped`: the solvency breaker has paused the reserve and
+    ///   waits for `reset_after_solvency_fix`
+    pub fn poll_work(ctx: Context<PollWork>) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() <= MAX_POLL_WORK_ACTIVITIES,
+            AmanaError::InvalidRemainingAccount
+        );
+
+        let reserve = &ctx.accounts.reserve;
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut overdue_count: u32 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), AmanaError::DuplicateActivity);
+            seen.push(info.key());
+
+            let activity: Activity = load_remaining(info)?;
+            require_activity_pda(info, reserve.id_seed(), &activity)?;
+            let open = activity.status == ActivityStatus::Approved
+                || activity.status == ActivityStatus::Active;
+            if open
+                && activity.maturity_at > 0
+                && now > activity.maturity_at.saturating_add(reserve.maturity_grace_seconds)
+            {
+                overdue_count += 1;
+            }
+        }
+
+        let distributions_due = reserve.distribution_interval_seconds > 0
+            && reserve.pending_distribution > 0
+            && now >= reserve.last_distribution_at
+                .saturating_add(reserve.distribution_interval_seconds);
+
+        let withdrawals_queued = if reserve.status == ReserveStatus::WindingDown {
+            reserve.participant_count
+        } else {
+            reserve.pending_withdrawal_count
+        };
+
+        emit!(WorkAvailableEvent {
+            overdue_count,
+            distributions_due,
+            withdrawals_queued,
+            breaker_tripped: reserve.is_paused,
+        });
+
+        Ok(())
+    }
+
+    /// Emit a consolidated snapshot of one participant's position
+    ///
+    /// Read-only, for tax and reporting. `reserve_share_bps` is the
+    /// participant's `capital_contributed` over the reserve's
+    /// `total_contributed`. A participant that has left (inactive, or with no
+    /// capital remaining) still gets a snapshot, with a zero share.
+    pub fn snapshot_participant(ctx: Context<SnapshotParticipant>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        let participant = &ctx.accounts.participant;
+        let now = Clock::get()?.unix_timestamp;
+
+        let reserve_share_bps = if !participant.is_active || reserve.total_contributed == 0 {
+            0
+        } else {
+            ((participant.capital_contributed as u128)
+                .checked_mul(10000)
+                .ok_or(AmanaError::MathOverflow)?
+                / reserve.total_contributed as u128)
+                .min(10000) as u16
+        };
+
+        emit!(ParticipantSnapshotEvent {
+            agent: participant.agent,
+            capital_contributed: participant.capital_contributed,
+            profit_share: participant.profit_share,
+            loss_share: participant.loss_share,
+            pending_profit: participant.pending_profit,
+            joined_at: participant.joined_at,
+            tenure_seconds: now.saturating_sub(participant.joined_at),
+            is_active: participant.is_active,
+            reserve_share_bps,
+            total_contributed: reserve.total_contributed,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Check a participant's `capital_contributed` against the reserve's books
+    ///
+    /// Read-only. The reserve only tracks contributions in aggregate, so every
+    /// other participant must be passed via `remaining_accounts`; the capital
+    /// the books leave for this participant is `total_contributed` less their
+    /// contributions. `discrepancy` is the participant's recorded capital
+    /// minus that amount, so a positive value means they appear to own more
+    /// than the reserve holds for them.
+    pub fn verify_participant_balance(ctx: Context<VerifyParticipantBalance>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        let participant = &ctx.accounts.participant;
+
+        require!(
+            ctx.remaining_accounts.len() as u64 + 1 == reserve.participant_count,
+            AmanaError::InvalidRemainingAccount
+        );
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len() + 1);
+        seen.push(participant.key());
+        let mut others_contributed: u64 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), AmanaError::InvalidRemainingAccount);
+            seen.push(info.key());
+
+            let other: Participant = load_remaining(info)?;
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"participant", reserve.id_seed(), other.agent.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(info.key(), expected, AmanaError::InvalidRemainingAccount);
+            others_contributed = others_contributed
+                .checked_add(other.capital_contributed)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+
+        let recorded_capital = reserve.total_contributed as i128 - others_contributed as i128;
+        let discrepancy = i64::try_from(participant.capital_contributed as i128 - recorded_capital)
+            .map_err(|_| AmanaError::MathOverflow)?;
+
+        emit!(BalanceVerifiedEvent {
+            agent: participant.agent,
+            capital_contributed: participant.capital_contributed,
+            total_contributed: reserve.total_contributed,
+            others_contributed,
+            consistent: discrepancy == 0,
+            discrepancy,
+        });
+
+        Ok(())
+    }
+
+    /// Emit every configurable reserve parameter as one `ReserveConfigEvent`
+    ///
+    /// Read-only, so off-chain tooling can audit the configuration from a
+    /// single simulated call instead of decoding the account.
+    pub fn dump_config(ctx: Context<DumpConfig>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+
+        emit!(ReserveConfigEvent {
+            admin: reserve.admin,
+            min_capital_contribution: reserve.min_capital_contribution,
+            max_participants: reserve.max_participants,
+            allow_netting: reserve.allow_netting,
+            min_activity_capital: reserve.min_activity_capital,
+            max_outcome_age_seconds: reserve.max_outcome_age_seconds,
+            max_risk_exposure: reserve.max_risk_exposure,
+            entry_fee_bps: reserve.entry_fee_bps,
+            charity: reserve.charity,
+            maturity_grace_seconds: reserve.maturity_grace_seconds,
+            private_bridge: reserve.private_bridge,
+            min_validator_stake: reserve.min_validator_stake,
+            validator_unbonding_seconds: reserve.validator_unbonding_seconds,
+            vesting_cliff_seconds: reserve.vesting_cliff_seconds,
+            vesting_duration_seconds: reserve.vesting_duration_seconds,
+            max_qard_outstanding_bps: reserve.max_qard_outstanding_bps,
+            completion_bond: reserve.completion_bond,
+            dispute_window_seconds: reserve.dispute_window_seconds,
+            min_activity_duration_seconds: reserve.min_activity_duration_seconds,
+            max_activity_duration_seconds: reserve.max_activity_duration_seconds,
+            distribution_interval_seconds: reserve.distribution_interval_seconds,
+            swap_program: reserve.swap_program,
+            max_slippage_bps: reserve.max_slippage_bps,
+            delegation_window_seconds: reserve.delegation_window_seconds,
+            delegation_threshold: reserve.delegation_threshold,
+            max_total_capital: reserve.max_total_capital,
+            capacity_governed: reserve.capacity_governed,
+            dormancy_seconds: reserve.dormancy_seconds,
+            sweep_reversal_seconds: reserve.sweep_reversal_seconds,
+            guarantee_coverage_bps: reserve.guarantee_coverage_bps,
+            name: reserve.name,
+            metadata_uri: reserve.metadata_uri.clone(),
+            proposal_expiry_seconds: reserve.proposal_expiry_seconds,
+            distribution_mode: reserve.distribution_mode,
+            activity_fee: reserve.activity_fee,
+            tier_configs: reserve.tier_configs,
+            allow_in_kind: reserve.allow_in_kind,
+            min_solvency_bps: reserve.min_solvency_bps,
+            unique_activity_ids: reserve.unique_activity_ids,
+            management_fee_bps: reserve.management_fee_bps,
+            withdrawal_cooldown_seconds: reserve.withdrawal_cooldown_seconds,
+            mudarabah_limited_liability: reserve.mudarabah_limited_liability,
+            max_proposal_bps: reserve.max_proposal_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Emit the reserve's event cursor: the sequence of the next `AuditEntryEvent`
+    ///
+    /// Read-only. Every audited action is emitted with a gapless sequence, so an
+    /// indexer that has processed everything below `next_sequence` is caught up.
+    pub fn get_cursor(ctx: Context<GetCursor>) -> Result<()> {
+        emit!(ReserveCursorEvent {
+            next_sequence: ctx.accounts.audit_log.next_sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Authorize an indexer to record its progress in an `IndexCursor` PDA
+    pub fn authorize_indexer(ctx: Context<AuthorizeIndexer>, indexer: Pubkey) -> Result<()> {
+        let cursor = &mut ctx.accounts.cursor;
+        cursor.indexer = indexer;
+        cursor.next_sequence = 0;
+        cursor.updated_at = Clock::get()?.unix_timestamp;
+        cursor.bump = ctx.bumps.cursor;
+
+        emit!(IndexerAuthorizedEvent { indexer });
+
+        ctx.accounts.audit_log.record(AuditAction::AuthorizeIndexer, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Revoke an indexer, closing its cursor
+    pub fn revoke_indexer(ctx: Context<RevokeIndexer>) -> Result<()> {
+        emit!(IndexerRevokedEvent {
+            indexer: ctx.accounts.cursor.indexer,
+            next_sequence: ctx.accounts.cursor.next_sequence,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::RevokeIndexer, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Record that the indexer has processed every event below `next_sequence`
+    ///
+    /// The cursor only moves forward and never past the reserve's own cursor,
+    /// so a restarting indexer resumes exactly where it left off.
+    pub fn advance_cursor(ctx: Context<AdvanceCursor>, next_sequence: u64) -> Result<()> {
+        let cursor = &mut ctx.accounts.cursor;
+        require!(
+            next_sequence >= cursor.next_sequence
+                && next_sequence <= ctx.accounts.audit_log.next_sequence,
+            AmanaError::InvalidCursor
+        );
+
+        cursor.next_sequence = next_sequence;
+        cursor.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(IndexCursorAdvancedEvent {
+            indexer: cursor.indexer,
+            next_sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished activity and reclaim its rent
+    ///
+    /// Only completed, rejected or split activities can be closed. A completed
+    /// one must be settled, through `net_settle_initiator` while netting is
+    /// on, and every participant must have allocated or realized the
+    /// outcome. The id is removed from the initiator's head index page;
+    /// archived overflow pages are left untouched as a historical record. The
+    /// id is also recorded in the reserve's closed-id filter for
+    /// `unique_activity_ids`.
+    pub fn close_activity(ctx: Context<CloseActivity>, activity_id: [u8; 32]) -> Result<()> {
+        let activity = &ctx.accounts.activity;
+
+        require!(
+            matches!(
+                activity.status,
+                ActivityStatus::Completed | ActivityStatus::Rejected | ActivityStatus::Split
+            ),
+            AmanaError::InvalidActivityStatus
+        );
+        require!(
+            activity.status != ActivityStatus::Completed || activity.is_settled,
+            AmanaError::OutcomeNotSettled
+        );
+        require!(activity.unapplied_count == 0, AmanaError::OutcomeNotFullyApplied);
+
+        ctx.accounts
+            .initiator_index
+            .activity_ids
+            .retain(|id| *id != activity_id);
+        if ctx.accounts.reserve.record_closed_id(&activity_id)? {
+            emit!(ClosedIdFilterRotatedEvent { activity_id });
+        }
+
+        emit!(ActivityClosedEvent {
+            activity_id,
+            initiator: activity.initiator,
+        });
+
+        Ok(())
+    }
+
+    /// Archive a full initiator index into a linked overflow page
+    ///
+    /// The head index at `["initiator_index", initiator]` holds at most
+    /// `MAX_INDEXED_ACTIVITIES` ids and `propose_activity` fails with
+    /// `InitiatorIndexFull` once it is full. Rolling moves the head's ids into
+    /// page `page_count` at `["initiator_index", initiator, page_count]`,
+    /// clears the head and increments `page_count`, so a full portfolio is the
+    /// head plus pages `0..page_count`.
+    pub fn roll_initiator_index(ctx: Context<RollInitiatorIndex>) -> Result<()> {
+        let index = &mut ctx.accounts.initiator_index;
+        let page = &mut ctx.accounts.page;
+
+        page.initiator = index.initiator;
+        page.page = index.page_count;
+        page.activity_ids = std::mem::take(&mut index.activity_ids);
+        page.bump = ctx.bumps.page;
+
+        index.page_count = index
+            .page_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(InitiatorIndexRolledEvent {
+            initiator: index.initiator,
+            page: page.page,
+            archived: page.activity_ids.len() as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit additional capital
+    ///
+    /// May be signed by the participant or by a delegate holding the deposit
+    /// scope; the funds always come from the signer. Like every instruction
+    /// that changes a participant's capital, it is refused until their
+    /// settled outcomes are all allocated or realized.
+    pub fn deposit_capital(ctx: Context<DepositCapital>, amount: u64) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+
+        require_participant_authority(
+            &ctx.accounts.participant,
+            &ctx.accounts.user.key(),
+            ctx.accounts.delegate.as_ref(),
+            DELEGATE_SCOPE_DEPOSIT,
+        )?;
+        ctx.accounts.participant.require_settled(&ctx.accounts.reserve)?;
+
+        let reserve = &mut ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+        reserve.require_capacity(amount)?;
+        reserve.tier_config(participant.tier).check_contribution_cap(
+            participant.capital_contributed
+                .checked_add(amount)
+                .ok_or(AmanaError::MathOverflow)?,
+        )?;
+
+        // Transfer SOL
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.user.key(),
+                &ctx.accounts.reserve.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.reserve.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        participant.accrue_profit(reserve)?;
+        participant.capital_contributed = participant.capital_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(CapitalDepositedEvent {
+            agent: ctx.accounts.user.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw capital from the reserve
+    ///
+    /// Closed while `withdrawal_cooldown_seconds` is set; withdrawals then go
+    /// through `request_withdrawal` and `settle_withdrawal`.
+    pub fn withdraw_capital(ctx: Context<WithdrawCapital>, amount: u64) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(
+            ctx.accounts.reserve.withdrawal_cooldown_seconds == 0,
+            AmanaError::TwoPhaseWithdrawalRequired
+        );
+        if !check_capital_payout(&mut ctx.accounts.reserve, &ctx.accounts.participant, amount, amount)? {
+            return Ok(());
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+
+        participant.accrue_profit(reserve)?;
+        participant.capital_contributed = participant.capital_contributed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        // Transfer SOL back to user
+        **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(CapitalWithdrawnEvent {
+            agent: ctx.accounts.user.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Request a withdrawal, to be paid by `settle_withdrawal` after the cooldown
+    ///
+    /// Nothing moves yet: the amount and time are recorded on the participant.
+    /// Splitting the withdrawal in two keeps a participant from draining
+    /// capital that an ER session is committing to an activity at the same
+    /// time. One request may be pending at a time.
+    pub fn request_withdrawal(ctx: Context<WithdrawCapital>, amount: u64) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(!ctx.accounts.reserve.is_paused, AmanaError::ReservePaused);
+
+        let now = Clock::get()?.unix_timestamp;
+        let participant = &mut ctx.accounts.participant;
+        participant.require_unlocked(now)?;
+        require!(participant.pending_withdrawal == 0, AmanaError::WithdrawalPending);
+        require!(
+            amount <= participant.capital_contributed,
+            AmanaError::InsufficientBalance
+        );
+
+        participant.pending_withdrawal = amount;
+        participant.withdrawal_requested_at = now;
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.pending_withdrawal_count = reserve.pending_withdrawal_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let settle_after = now
+            .checked_add(reserve.withdrawal_cooldown_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        emit!(WithdrawalRequestedEvent {
+            agent: participant.agent,
+            amount,
+            settle_after,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out a pending withdrawal once its cooldown has passed
+    ///
+    /// The amount must still be covered by the participant's capital, by their
+    /// share of free capital (so nothing committed to an activity is paid out)
+    /// and by the reserve's lamports above its rent-exempt minimum.
+    pub fn settle_withdrawal(ctx: Context<WithdrawCapital>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let amount = ctx.accounts.participant.pending_withdrawal;
+        require!(amount > 0, AmanaError::NoWithdrawalPending);
+        require!(
+            now >= ctx.accounts.participant.withdrawal_requested_at
+                .saturating_add(ctx.accounts.reserve.withdrawal_cooldown_seconds),
+            AmanaError::WithdrawalCooldownActive
+        );
+        if !check_capital_payout(&mut ctx.accounts.reserve, &ctx.accounts.participant, amount, amount)? {
+            return Ok(());
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+
+        participant.pending_withdrawal = 0;
+        participant.withdrawal_requested_at = 0;
+        reserve.pending_withdrawal_count = reserve.pending_withdrawal_count
+            .checked_sub(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        participant.accrue_profit(reserve)?;
+        participant.capital_contributed = participant.capital_contributed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(WithdrawalSettledEvent {
+            agent: ctx.accounts.user.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Drop a pending withdrawal request
+    pub fn cancel_withdrawal(ctx: Context<WithdrawCapital>) -> Result<()> {
+        let participant = &mut ctx.accounts.participant;
+        let amount = participant.pending_withdrawal;
+        require!(amount > 0, AmanaError::NoWithdrawalPending);
+
+        participant.pending_withdrawal = 0;
+        participant.withdrawal_requested_at = 0;
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.pending_withdrawal_count = reserve.pending_withdrawal_count
+            .checked_sub(1)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(WithdrawalCancelledEvent {
+            agent: participant.agent,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Set the cooldown between requesting and settling a withdrawal
+    ///
+    /// While non-zero, `withdraw_capital` is closed and `leave_reserve` only
+    /// closes a participant whose capital was already withdrawn in two phases.
+    pub fn set_withdrawal_cooldown(
+        ctx: Context<UpdateReserveConfig>,
+        withdrawal_cooldown_seconds: i64,
+    ) -> Result<()> {
+        require!(withdrawal_cooldown_seconds >= 0, AmanaError::InvalidAmount);
+
+        ctx.accounts.reserve.withdrawal_cooldown_seconds = withdrawal_cooldown_seconds;
+
+        emit!(WithdrawalCooldownUpdatedEvent { withdrawal_cooldown_seconds });
+
+        ctx.accounts.audit_log.record(AuditAction::SetWithdrawalCooldown, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Leave the reserve, returning remaining capital and closing the participant
+    ///
+    /// `dust_threshold` is the participant's opt-in consent to donate: a
+    /// non-zero residual strictly below it goes to the reserve charity instead
+    /// of back to the participant. Pass 0 to always have the residual returned.
+    /// Allocated profit must be claimed or compounded first. The residual
+    /// passes the same checks as `withdraw_capital`: the tier lockup, the
+    /// solvency interlock and the participant's share of free capital. On a
+    /// fresh solvency breach the participant is kept open and nothing is paid.
+    /// Scheduled profit accrued but not yet claimed is paid out with the exit.
+    pub fn leave_reserve(ctx: Context<LeaveReserve>, dust_threshold: u64) -> Result<()> {
+        let participant = &ctx.accounts.participant;
+        require!(participant.pending_profit == 0, AmanaError::PendingProfitUnclaimed);
+
+        let residual = participant.capital_contributed;
+        let had_request = participant.pending_withdrawal > 0;
+        require!(
+            residual == 0 || ctx.accounts.reserve.withdrawal_cooldown_seconds == 0,
+            AmanaError::TwoPhaseWithdrawalRequired
+        );
+        // Scheduled profit already accrued leaves with the participant
+        let profit = participant.unclaimed_distribution(&ctx.accounts.reserve)?;
+        let payout = residual.checked_add(profit).ok_or(AmanaError::MathOverflow)?;
+        if !check_capital_payout(&mut ctx.accounts.reserve, &ctx.accounts.participant, residual, payout)? {
+            return Ok(());
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.pay_distribution(profit)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(payout)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_sub(residual)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.participant_count = reserve.participant_count
+            .checked_sub(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        if had_request {
+            reserve.pending_withdrawal_count = reserve.pending_withdrawal_count
+                .checked_sub(1)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+
+        if residual > 0 && residual < dust_threshold {
+            let charity = ctx.accounts.charity.as_ref().ok_or(AmanaError::InvalidCharity)?;
+            require_keys_neq!(charity.key(), Pubkey::default(), AmanaError::InvalidCharity);
+
+            **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= residual;
+            **charity.to_account_info().try_borrow_mut_lamports()? += residual;
+
+            emit!(DustDonatedEvent {
+                agent: ctx.accounts.user.key(),
+                charity: charity.key(),
+                amount: residual,
+            });
+        } else if residual > 0 {
+            **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= residual;
+            **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += residual;
+
+            emit!(CapitalWithdrawnEvent {
+                agent: ctx.accounts.user.key(),
+                amount: residual,
+            });
+        }
+        if profit > 0 {
+            **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= profit;
+            **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += profit;
+
+            emit!(DistributionPaidOnExitEvent {
+                agent: ctx.accounts.user.key(),
+                amount: profit,
+            });
+        }
+
+        emit!(ParticipantLeftEvent {
+            agent: ctx.accounts.user.key(),
+        });
+
+        ctx.accounts.participant.close(ctx.accounts.user.to_account_info())
+
+    }
+
+    /// Move a whole position to `new_owner`, who must also sign
+    ///
+    /// The participant PDA is seeded by its owner, so the position is copied
+    /// into a fresh PDA for `new_owner` and the old one is closed. Capital,
+    /// profit and loss totals, `joined_at` and the preferred mint carry over
+    /// unchanged. Profit shares are seeded by the owner too, so allocated
+    /// profit must be claimed or compounded first. Every settled outcome must
+    /// be applied before the move, and `settled_through` carries over, so the
+    /// new owner can never allocate, realize or claim one a second time. As
+    /// on join, every denylist page must be passed via `remaining_accounts`
+    /// to screen the new owner.
+    pub fn transfer_position<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransferPosition<'info>>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        require_not_denied(
+            &ctx.accounts.reserve,
+            &new_owner,
+            ctx.remaining_accounts,
+        )?;
+
+        let old = &ctx.accounts.participant;
+        old.require_settled(&ctx.accounts.reserve)?;
+        require!(old.pending_profit == 0, AmanaError::PendingProfitUnclaimed);
+        require!(old.pending_withdrawal == 0, AmanaError::WithdrawalPending);
+
+        let new = &mut ctx.accounts.new_participant;
+        new.agent = new_owner;
+        new.capital_contributed = old.capital_contributed;
+        new.profit_share = old.profit_share;
+        new.loss_share = old.loss_share;
+        new.pending_profit = 0;
+        new.is_active = old.is_active;
+        new.is_frozen = old.is_frozen;
+        new.joined_at = old.joined_at;
+        new.preferred_mint = old.preferred_mint;
+        new.tier = old.tier;
+        new.locked_until = old.locked_until;
+        new.pending_withdrawal = 0;
+        new.withdrawal_requested_at = 0;
+        new.settled_through = old.settled_through;
+        new.loss_carried = old.loss_carried;
+        new.profit_index = old.profit_index;
+        new.accrued_released = old.accrued_released;
+        new.accrued_unreleased = old.accrued_unreleased;
+        new.bump = ctx.bumps.new_participant;
+
+        emit!(PositionTransferredEvent {
+            from: old.agent,
+            to: new_owner,
+            capital_contributed: new.capital_contributed,
+            joined_at: new.joined_at,
+        });
+
+        Ok(())
+    }
+
+    /// Start winding the reserve down
+    ///
+    /// Requires every activity to be closed out (none approved or active).
+    /// From here on joins, deposits, proposals, approvals, deployments and
+    /// Qard issuance are refused; participants leave through
+    /// `settle_and_exit` and the admin finishes with `close_reserve`.
+    pub fn initiate_shutdown(ctx: Context<UpdateReserveConfig>) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        require!(reserve.status == ReserveStatus::Active, AmanaError::ReserveWindingDown);
+        require!(reserve.open_activity_count == 0, AmanaError::ActivitiesStillOpen);
+
+        reserve.status = ReserveStatus::WindingDown;
+
+        emit!(ShutdownInitiatedEvent {
+            total_capital: reserve.total_capital,
+            participant_count: reserve.participant_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::InitiateShutdown, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Pay out a participant's capital and SOL profit and close it
+    ///
+    /// Only while winding down. All allocated SOL profit is paid, vested or
+    /// not, together with any scheduled profit accrued, since no further
+    /// claims are possible once the reserve closes.
+    ///
+    /// Loss and profit shares are rounded down, so the participants' books
+    /// can end a few lamports above or below `total_capital`. The last
+    /// participant to exit is paid whatever free capital is left instead,
+    /// so neither rounding dust nor a rounded-down loss can strand the close.
+    pub fn settle_and_exit(ctx: Context<SettleAndExit>) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::WindingDown,
+            AmanaError::ReserveNotWindingDown
+        );
+
+        let participant = &ctx.accounts.participant;
+        participant.require_settled(&ctx.accounts.reserve)?;
+        let mut capital = participant.capital_contributed;
+        let scheduled = participant.unclaimed_distribution(&ctx.accounts.reserve)?;
+        let mut profit = participant.pending_profit
+            .checked_add(scheduled)
+            .ok_or(AmanaError::MathOverflow)?;
+        let last = ctx.accounts.reserve.participant_count == 1;
+        if last {
+            let remaining = ctx.accounts.reserve.total_capital;
+            profit = profit.min(remaining);
+            capital = remaining.checked_sub(profit).ok_or(AmanaError::MathOverflow)?;
+        }
+        let payout = capital.checked_add(profit).ok_or(AmanaError::MathOverflow)?;
+        let had_request = participant.pending_withdrawal > 0;
+
+        let reserve_info = ctx.accounts.reserve.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(reserve_info.data_len());
+        require!(
+            payout <= reserve_info.lamports().saturating_sub(rent_floor),
+            AmanaError::InsufficientLiquidity
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.pay_distribution(scheduled)?;
+        if last {
+            reserve.total_capital = 0;
+            reserve.total_contributed = 0;
+        } else {
+            reserve.total_capital = reserve.total_capital
+                .checked_sub(payout)
+                .ok_or(AmanaError::MathOverflow)?;
+            reserve.total_contributed = reserve.total_contributed
+                .checked_sub(capital)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+        reserve.participant_count = reserve.participant_count
+            .checked_sub(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        if had_request {
+            reserve.pending_withdrawal_count = reserve.pending_withdrawal_count
+                .checked_sub(1)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+
+        move_lamports(
+            &ctx.accounts.reserve.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            payout,
+        )?;
+
+        emit!(ParticipantSettledEvent {
+            agent: ctx.accounts.user.key(),
+            capital,
+            profit,
+        });
+
+        Ok(())
+    }
+
+    /// Close a wound-down reserve, returning its rent to the admin
+    ///
+    /// Only once every participant has exited and no Qard or private
+    /// deployment is outstanding. Free capital still booked at that point is
+    /// rounding dust nobody can claim; it is donated to the reserve's
+    /// `charity`, which must then be passed. The treasury, guarantee fund and
+    /// mint vaults are addressed through the reserve, so sweep them first.
+    pub fn close_reserve(ctx: Context<CloseReserve>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        require!(
+            reserve.status == ReserveStatus::WindingDown,
+            AmanaError::ReserveNotWindingDown
+        );
+        require!(
+            reserve.participant_count == 0
+                && reserve.total_qard_outstanding == 0
+                && reserve.deployed_capital == 0
+                && reserve.private_deployed_capital == 0,
+            AmanaError::ReserveNotEmpty
+        );
+
+        let dust = reserve.total_capital;
+        if dust > 0 {
+            let charity = ctx.accounts.charity.as_ref().ok_or(AmanaError::InvalidCharity)?;
+            require_keys_neq!(charity.key(), Pubkey::default(), AmanaError::InvalidCharity);
+
+            ctx.accounts.reserve.total_capital = 0;
+            move_lamports(
+                &ctx.accounts.reserve.to_account_info(),
+                &charity.to_account_info(),
+                dust,
+            )?;
+
+            emit!(DustDonatedEvent {
+                agent: ctx.accounts.admin.key(),
+                charity: charity.key(),
+                amount: dust,
+            });
+        }
+
+        emit!(ReserveClosedEvent {
+            admin: ctx.accounts.admin.key(),
+            dust,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::CloseReserve, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Close the treasury during a wind-down, paying its fees and rent to the admin
+    pub fn sweep_treasury(ctx: Context<SweepTreasury>) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::WindingDown,
+            AmanaError::ReserveNotWindingDown
+        );
+
+        emit!(TreasurySweptEvent {
+            admin: ctx.accounts.admin.key(),
+            amount: ctx.accounts.treasury.balance,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SweepTreasury, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Close the guarantee fund once the last participant has exited
+    ///
+    /// No loss is left to cover, and the donations were tabarru', so the
+    /// balance goes to the reserve's `charity` rather than back to the admin.
+    /// Only the rent the admin paid is returned to them.
+    pub fn sweep_guarantee_fund(ctx: Context<SweepGuaranteeFund>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        require!(
+            reserve.status == ReserveStatus::WindingDown,
+            AmanaError::ReserveNotWindingDown
+        );
+        require!(reserve.participant_count == 0, AmanaError::ReserveNotEmpty);
+        require_keys_neq!(reserve.charity, Pubkey::default(), AmanaError::InvalidCharity);
+
+        let amount = ctx.accounts.guarantee_fund.balance;
+        ctx.accounts.guarantee_fund.balance = 0;
+        move_lamports(
+            &ctx.accounts.guarantee_fund.to_account_info(),
+            &ctx.accounts.charity.to_account_info(),
+            amount,
+        )?;
+
+        emit!(GuaranteeFundSweptEvent {
+            charity: ctx.accounts.charity.key(),
+            amount,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SweepGuaranteeFund, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Close a mint vault once the last participant has exited
+    ///
+    /// Any tokens left, unclaimed profit or rounding dust, go to the
+    /// charity's token account for the mint. The vault token account and
+    /// the `MintVault` return their rent to the admin.
+    pub fn sweep_mint_vault(ctx: Context<SweepMintVault>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        require!(
+            reserve.status == ReserveStatus::WindingDown,
+            AmanaError::ReserveNotWindingDown
+        );
+        require!(reserve.participant_count == 0, AmanaError::ReserveNotEmpty);
+        require_keys_neq!(reserve.charity, Pubkey::default(), AmanaError::InvalidCharity);
+
+        let mint_vault = &ctx.accounts.mint_vault;
+        let vault_seeds: &[&[u8]] = &[
+            b"mint_vault",
+            reserve.id_seed(),
+            mint_vault.mint.as_ref(),
+            &[mint_vault.bump],
+        ];
+        let amount = ctx.accounts.vault.amount;
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: ctx.accounts.charity_token_account.to_account_info(),
+                        authority: mint_vault.to_account_info(),
+                    },
+                    &[vault_seeds],
+                ),
+                amount,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.admin.to_account_info(),
+                authority: mint_vault.to_account_info(),
+            },
+            &[vault_seeds],
+        ))?;
+
+        emit!(MintVaultSweptEvent {
+            mint: mint_vault.mint,
+            charity: reserve.charity,
+            amount,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SweepMintVault, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Allocate a participant's pro-rata share of a settled activity's profit
+    ///
+    /// The share is `settled_outcome * capital_contributed / distribution_base`,
+    /// where `distribution_base` is the reserve's total contributed capital at
+    /// settlement. A participant's capital cannot change while they have
+    /// settled outcomes left to apply, and outcomes are applied in
+    /// `settlement_seq` order, so `capital_contributed` here is still what the
+    /// participant held at settlement. Participants who joined after
+    /// settlement get no share. The share vests on the reserve's current
+    /// vesting schedule. Any `loss_carried` is taken out of a SOL share first.
+    pub fn allocate_profit_share(
+        ctx: Context<AllocateProfitShare>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let activity = &mut ctx.accounts.activity;
+        let participant = &mut ctx.accounts.participant;
+        let share = &mut ctx.accounts.share;
+
+        ctx.accounts.reserve.require_outcome_contingent()?;
+        require!(
+            activity.status == ActivityStatus::Completed && activity.is_settled,
+            AmanaError::InvalidActivityStatus
+        );
+        require!(activity.settled_outcome > 0, AmanaError::NoProfitToAllocate);
+        require!(activity.distribution_base > 0, AmanaError::NoProfitToAllocate);
+        require!(
+            activity.settlement_seq > participant.settled_through,
+            AmanaError::NoProfitToAllocate
+        );
+        participant.apply_settlement(&mut **activity)?;
+
+        let amount = ((activity.settled_outcome as u128)
+            .checked_mul(participant.capital_contributed as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / (activity.distribution_base as u128)) as u64;
+
+        // The shares of one outcome can never add up to more than it settled
+        activity.allocate_outcome(amount)?;
+
+        share.agent = participant.agent;
+        share.activity_id = activity_id;
+        share.amount = amount;
+        share.claimed = 0;
+        share.allocated_at = Clock::get()?.unix_timestamp;
+        share.last_claimed_at = share.allocated_at;
+        share.swept_amount = 0;
+        share.sweep_releasable_at = 0;
+        share.mint = activity.settlement_mint;
+        share.vesting = VestingSchedule {
+            start: share.allocated_at,
+            cliff_seconds: ctx.accounts.reserve.vesting_cliff_seconds,
+            duration_seconds: ctx.accounts.reserve.vesting_duration_seconds,
+        };
+        share.bump = ctx.bumps.share;
+
+        // Participant totals are SOL-denominated; token shares live on the share
+        if share.mint == Pubkey::default() {
+            share.amount = participant.book_profit(amount)?;
+        }
+
+        emit!(ProfitShareAllocatedEvent {
+            activity_id,
+            agent: participant.agent,
+            amount: share.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Apply a participant's pro-rata share of a settled activity's loss
+    ///
+    /// Callable by anyone. The loss is `-settled_outcome * capital_contributed /
+    /// distribution_base`, capped at the participant's `capital_contributed`.
+    /// Any excess is written off under `mudarabah_limited_liability`, after
+    /// the guarantee fund absorbs what it can, and otherwise added to
+    /// `loss_carried` and taken from later profit. The capped part is taken
+    /// off `capital_contributed` and `total_contributed` and added to
+    /// `loss_share`. The `LossRealization` PDA makes each (activity,
+    /// participant) pair apply once. Losses take their place in the settlement
+    /// sequence like profits, so until it is realized the participant can
+    /// neither exit nor move capital, and the share is sized on the capital
+    /// held at settlement. Participants who joined after settlement bear none
+    /// of it. A token-settled loss follows the same path in its own mint: the
+    /// share is added to the mint vault's `realized_loss` and recorded on the
+    /// `LossRealization`, while SOL capital is untouched because the principal
+    /// was returned in SOL.
+    pub fn realize_loss(ctx: Context<RealizeLoss>, activity_id: [u8; 32]) -> Result<()> {
+        let activity = &mut ctx.accounts.activity;
+        let participant = &mut ctx.accounts.participant;
+
+        require!(
+            activity.status == ActivityStatus::Completed && activity.is_settled,
+            AmanaError::InvalidActivityStatus
+        );
+        require!(
+            activity.settled_outcome < 0 && activity.distribution_base > 0,
+            AmanaError::NoLossToRealize
+        );
+        require!(
+            activity.settlement_seq > participant.settled_through,
+            AmanaError::NoLossToRealize
+        );
+        participant.apply_settlement(&mut **activity)?;
+
+        let share = ((activity.settled_outcome.unsigned_abs() as u128)
+            .checked_mul(participant.capital_contributed as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / (activity.distribution_base as u128)) as u64;
+        activity.allocate_outcome(share)?;
+
+        let realization = &mut ctx.accounts.loss_realization;
+        realization.agent = participant.agent;
+        realization.activity_id = activity_id;
+        realization.mint = activity.settlement_mint;
+        realization.realized_at = Clock::get()?.unix_timestamp;
+        realization.bump = ctx.bumps.loss_realization;
+
+        // A token loss is borne in its mint; the SOL principal came back whole
+        if activity.settlement_mint != Pubkey::default() {
+            let mint_vault = ctx.accounts.mint_vault.as_mut()
+                .ok_or(AmanaError::InvalidSettlementMint)?;
+            require_keys_eq!(mint_vault.mint, activity.settlement_mint, AmanaError::InvalidSettlementMint);
+            mint_vault.realized_loss = mint_vault.realized_loss
+                .checked_add(share)
+                .ok_or(AmanaError::MathOverflow)?;
+            realization.amount = share;
+
+            emit!(LossRealizedEvent {
+                activity_id,
+                agent: participant.agent,
+                mint: activity.settlement_mint,
+                amount: share,
+                carried: 0,
+                capital_remaining: participant.capital_contributed,
+            });
+            return Ok(());
+        }
+        let amount = share.min(participant.capital_contributed);
+
+        // A loss larger than the participant's capital can only come from
+        // capital that had grown with booked profit
+        let excess = share - amount;
+        let mut carried = 0;
+        if excess > 0 {
+            if ctx.accounts.reserve.mudarabah_limited_liability {
+                // The fund absorbs what it can and the rest is written off; it
+                // already left total_capital at completion and no one bears it
+                let absorbed = match ctx.accounts.guarantee_fund.as_mut() {
+                    Some(fund) => {
+                        let absorbed = fund.cover(excess, 10000)?;
+                        if absorbed > 0 {
+                            **fund.to_account_info().try_borrow_mut_lamports()? -= absorbed;
+                            **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? += absorbed;
+                        }
+                        absorbed
+                    }
+                    None => 0,
+                };
+                let reserve = &mut ctx.accounts.reserve;
+                reserve.total_capital = reserve.total_capital
+                    .checked_add(absorbed)
+                    .ok_or(AmanaError::MathOverflow)?;
+
+                emit!(LossWrittenOffEvent {
+                    activity_id,
+                    agent: participant.agent,
+                    excess,
+                    absorbed,
+                    written_off: excess - absorbed,
+                });
+            } else {
+                carried = excess;
+                participant.loss_carried = participant.loss_carried
+                    .checked_add(excess)
+                    .ok_or(AmanaError::MathOverflow)?;
+            }
+        }
+
+        participant.accrue_profit(&ctx.accounts.reserve)?;
+        participant.capital_contributed = participant.capital_contributed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        participant.loss_share = participant.loss_share
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        ctx.accounts.loss_realization.amount = amount;
+
+        emit!(LossRealizedEvent {
+            activity_id,
+            agent: participant.agent,
+            mint: Pubkey::default(),
+            amount,
+            carried,
+            capital_remaining: participant.capital_contributed,
+        });
+
+        Ok(())
+    }
+
+    /// Release the scheduled profit accrued since the last distribution
+    ///
+    /// Callable by anyone. Before `last_distribution_at +
+    /// distribution_interval_seconds`, or with nothing accrued, it is a no-op.
+    /// Scheduled profit is shared as it accrues, through the reserve's
+    /// cumulative `profit_per_unit` index on the capital held at that moment,
+    /// so capital deposited later earns none of it. This only releases it:
+    /// `released_per_unit` catches up with the index, and each participant
+    /// then takes their part with `claim_distribution`, in any transaction.
+    pub fn distribute_pending(ctx: Context<DistributePending>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let reserve = &mut ctx.accounts.reserve;
+        let due_at = reserve.last_distribution_at
+            .checked_add(reserve.distribution_interval_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        if now < due_at || reserve.pending_distribution == 0 {
+            return Ok(());
+        }
+        reserve.require_outcome_contingent()?;
+
+        let round = reserve.distribution_round
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        let amount = reserve.pending_distribution;
+        reserve.released_per_unit = reserve.profit_per_unit;
+        reserve.pending_distribution = 0;
+        reserve.last_distribution_at = now;
+        reserve.distribution_round = round;
+        reserve.audit_distributions()?;
+
+        emit!(PendingDistributedEvent {
+            round,
+            distribution_id: derive_distribution_id(round),
+            amount,
+            released_per_unit: reserve.released_per_unit,
+        });
+
+        Ok(())
+    }
+
+    /// Turn a participant's released scheduled profit into a profit share
+    ///
+    /// Callable by anyone. Brings the participant's accrual up to date and
+    /// records everything released so far as a `ParticipantActivityShare`
+    /// under `derive_distribution_id(distribution_round)`, claimed and
+    /// compounded like any other share and vesting on the current schedule.
+    /// A participant takes at most one such share per round.
+    pub fn claim_distribution(ctx: Context<ClaimDistribution>) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.require_outcome_contingent()?;
+
+        let participant = &mut ctx.accounts.participant;
+        participant.accrue_profit(reserve)?;
+        let released = participant.accrued_released;
+        require!(released > 0, AmanaError::NothingToClaim);
+        participant.accrued_released = 0;
+        reserve.pay_distribution(released)?;
+        let amount = participant.book_profit(released)?;
+
+        let distribution_id = derive_distribution_id(reserve.distribution_round);
+        let now = Clock::get()?.unix_timestamp;
+        let share = &mut ctx.accounts.share;
+        share.agent = participant.agent;
+        share.activity_id = distribution_id;
+        share.amount = amount;
+        share.claimed = 0;
+        share.allocated_at = now;
+        share.last_claimed_at = now;
+        share.swept_amount = 0;
+        share.sweep_releasable_at = 0;
+        share.mint = Pubkey::default();
+        share.vesting = VestingSchedule {
+            start: now,
+            cliff_seconds: reserve.vesting_cliff_seconds,
+            duration_seconds: reserve.vesting_duration_seconds,
+        };
+        share.bump = ctx.bumps.share;
+
+        emit!(ProfitShareAllocatedEvent {
+            activity_id: distribution_id,
+            agent: participant.agent,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out the unclaimed part of a profit share to the participant
+    ///
+    /// May be signed by the participant or a delegate with the claim scope;
+    /// the lamports always go to the participant's own key. Only the vested
+    /// part of the share is released.
+    pub fn claim_profit(ctx: Context<ClaimProfit>, activity_id: [u8; 32]) -> Result<()> {
+        require_participant_authority(
+            &ctx.accounts.participant,
+            &ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_ref(),
+            DELEGATE_SCOPE_CLAIM,
+        )?;
+        if !solvency_interlock(&mut ctx.accounts.reserve)? {
+            return Ok(());
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+        let share = &mut ctx.accounts.share;
+
+        require_keys_eq!(share.mint, Pubkey::default(), AmanaError::InvalidSettlementMint);
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = share.claimable(now)?;
+        require!(amount > 0, AmanaError::NothingToClaim);
+        require!(
+            amount <= reserve.to_account_info().lamports(),
+            AmanaError::InsufficientLiquidity
+        );
+
+        share.record_claim(amount, now)?;
+        participant.pending_profit = participant.pending_profit
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.agent.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(ProfitClaimedEvent {
+            activity_id,
+            agent: participant.agent,
+            claimed_by: ctx.accounts.authority.key(),
+            amount,
+            vested: share.vesting.vested(share.amount, now)?,
+        });
+
+        Ok(())
+    }
+
+    /// Sweep the unclaimed remainder of a dormant SOL profit share
+    ///
+    /// A share is dormant once `dormancy_seconds` have passed since its
+    /// allocation or last claim. Only the vested, unclaimed part is swept;
+    /// what vests later stays claimable. It is only earmarked here: the
+    /// participant may take it back with `restore_swept_share` until
+    /// `sweep_reversal_seconds` have passed, after which
+    /// `release_dormant_sweep` donates it to the charity.
+    pub fn sweep_dormant(ctx: Context<SweepDormant>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+        let share = &mut ctx.accounts.share;
+
+        require_keys_eq!(share.mint, Pubkey::default(), AmanaError::InvalidSettlementMint);
+        require!(reserve.dormancy_seconds > 0, AmanaError::NotDormant);
+        require!(share.swept_amount == 0, AmanaError::AlreadySwept);
+        require!(reserve.charity != Pubkey::default(), AmanaError::InvalidCharity);
+
+        let now = Clock::get()?.unix_timestamp;
+        let dormant_at = share.last_claimed_at
+            .checked_add(reserve.dormancy_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(now >= dormant_at, AmanaError::NotDormant);
+
+        let amount = share.claimable(now)?;
+        require!(amount > 0, AmanaError::NothingToClaim);
+
+        // Marking the swept part claimed stops it being claimed twice
+        share.claimed = share.claimed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        share.swept_amount = amount;
+        share.sweep_releasable_at = now
+            .checked_add(reserve.sweep_reversal_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        participant.pending_profit = participant.pending_profit
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(DormantShareSweptEvent {
+            activity_id: share.activity_id,
+            agent: share.agent,
+            amount,
+            dormant_since: share.last_claimed_at,
+            releasable_at: share.sweep_releasable_at,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SweepDormant, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Reverse a sweep of the participant's own share while the window is open
+    pub fn restore_swept_share(ctx: Context<RestoreSweptShare>) -> Result<()> {
+        let participant = &mut ctx.accounts.participant;
+        let share = &mut ctx.accounts.share;
+
+        let amount = share.swept_amount;
+        require!(amount > 0, AmanaError::NothingSwept);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < share.sweep_releasable_at, AmanaError::SweepWindowClosed);
+
+        share.claimed = share.claimed
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        share.swept_amount = 0;
+        share.sweep_releasable_at = 0;
+        share.last_claimed_at = now;
+        participant.pending_profit = participant.pending_profit
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(DormantSweepReversedEvent {
+            activity_id: share.activity_id,
+            agent: share.agent,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Donate a swept share to the charity once its reversal window has passed
+    pub fn release_dormant_sweep(ctx: Context<ReleaseDormantSweep>) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        let share = &mut ctx.accounts.share;
+
+        let amount = share.swept_amount;
+        require!(amount > 0, AmanaError::NothingSwept);
+        require!(
+            Clock::get()?.unix_timestamp >= share.sweep_releasable_at,
+            AmanaError::SweepWindowOpen
+        );
+        require!(
+            amount <= reserve.to_account_info().lamports(),
+            AmanaError::InsufficientLiquidity
+        );
+
+        share.swept_amount = 0;
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.charity.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(DormantSweepReleasedEvent {
+            activity_id: share.activity_id,
+            agent: share.agent,
+            charity: ctx.accounts.charity.key(),
+            amount,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::ReleaseDormantSweep, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Pay out the unclaimed part of a token-settled profit share
+    ///
+    /// The tokens come from the share's mint vault and always go to a token
+    /// account owned by the participant. Token profit cannot be compounded.
+    pub fn claim_profit_token(ctx: Context<ClaimProfitToken>, activity_id: [u8; 32]) -> Result<()> {
+        require_participant_authority(
+            &ctx.accounts.participant,
+            &ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_ref(),
+            DELEGATE_SCOPE_CLAIM,
+        )?;
+
+        let share = &mut ctx.accounts.share;
+        let mint_vault = &mut ctx.accounts.mint_vault;
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = share.claimable(now)?;
+        require!(amount > 0, AmanaError::NothingToClaim);
+
+        share.record_claim(amount, now)?;
+        mint_vault.booked_balance = mint_vault.booked_balance
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let vault_seeds: &[&[u8]] = &[
+            b"mint_vault",
+            ctx.accounts.reserve.id_seed(),
+            mint_vault.mint.as_ref(),
+            &[mint_vault.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.agent_token_account.to_account_info(),
+                    authority: mint_vault.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            amount,
+        )?;
+
+        emit!(TokenProfitClaimedEvent {
+            activity_id,
+            agent: ctx.accounts.participant.agent,
+            mint: share.mint,
+            claimed_by: ctx.accounts.authority.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Distribute profit an activity returned as an asset, without liquidating it
+    ///
+    /// Moves `amount` of the asset from the admin into the mint's vault and
+    /// records an `InKindDistribution` at `[b"in_kind", distribution_id]`.
+    /// Each participant may then claim `amount * capital_contributed /
+    /// distribution_base` of the asset itself with `claim_in_kind`, where
+    /// `distribution_base` is `total_contributed` now. The distribution takes
+    /// the next place in the settlement sequence, so capital stays as it is
+    /// now until the claim. Participants who join later get no claim.
+    pub fn distribute_in_kind(
+        ctx: Context<DistributeInKind>,
+        distribution_id: [u8; 32],
+        amount: u64,
+    ) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        require!(reserve.allow_in_kind, AmanaError::InKindDisabled);
+        reserve.require_outcome_contingent()?;
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(reserve.total_contributed > 0, AmanaError::NoProfitToAllocate);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.admin.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let mint_vault = &mut ctx.accounts.mint_vault;
+        mint_vault.booked_balance = mint_vault.booked_balance
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let distribution = &mut ctx.accounts.distribution;
+        distribution.distribution_id = distribution_id;
+        distribution.mint = mint_vault.mint;
+        distribution.amount = amount;
+        distribution.distribution_base = reserve.total_contributed;
+        distribution.claimed = 0;
+        distribution.unclaimed = amount;
+        distribution.created_at = Clock::get()?.unix_timestamp;
+        distribution.bump = ctx.bumps.distribution;
+        reserve.sequence_settlement(&mut **distribution)?;
+
+        emit!(InKindDistributedEvent {
+            distribution_id,
+            mint: distribution.mint,
+            amount,
+            distribution_base: distribution.distribution_base,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::DistributeInKind, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Claim a participant's pro-rata share of an in-kind distribution
+    ///
+    /// Creates the participant's `InKindEntitlement`, so each share is paid
+    /// once, and sends the asset to a token account the participant owns.
+    pub fn claim_in_kind(ctx: Context<ClaimInKind>, distribution_id: [u8; 32]) -> Result<()> {
+        require_participant_authority(
+            &ctx.accounts.participant,
+            &ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_ref(),
+            DELEGATE_SCOPE_CLAIM,
+        )?;
+
+        let participant = &mut ctx.accounts.participant;
+        let distribution = &mut ctx.accounts.distribution;
+        require!(
+            distribution.settlement_seq > participant.settled_through,
+            AmanaError::NoProfitToAllocate
+        );
+        participant.apply_settlement(&mut **distribution)?;
+
+        let amount = ((distribution.amount as u128)
+            .checked_mul(participant.capital_contributed as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / (distribution.distribution_base as u128)) as u64;
+        require!(amount > 0, AmanaError::NothingToClaim);
+
+        distribution.claimed = distribution.claimed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        distribution.unclaimed = distribution.unclaimed.saturating_sub(amount);
+        audit_distribution(distribution.amount, distribution.claimed, distribution.unclaimed)?;
+
+        let entitlement = &mut ctx.accounts.entitlement;
+        entitlement.agent = participant.agent;
+        entitlement.distribution_id = distribution_id;
+        entitlement.mint = distribution.mint;
+        entitlement.amount = amount;
+        entitlement.claimed_at = Clock::get()?.unix_timestamp;
+        entitlement.bump = ctx.bumps.entitlement;
+
+        let mint_vault = &mut ctx.accounts.mint_vault;
+        mint_vault.booked_balance = mint_vault.booked_balance
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let vault_seeds: &[&[u8]] = &[
+            b"mint_vault",
+            ctx.accounts.reserve.id_seed(),
+            mint_vault.mint.as_ref(),
+            &[mint_vault.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.agent_token_account.to_account_info(),
+                    authority: mint_vault.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            amount,
+        )?;
+
+        emit!(InKindClaimedEvent {
+            distribution_id,
+            agent: participant.agent,
+            mint: entitlement.mint,
+            claimed_by: ctx.accounts.authority.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Choose the mint token profit is converted to when claimed
+    ///
+    /// `Pubkey::default()` opts out, so token profit is paid in its own mint.
+    pub fn set_preferred_mint(ctx: Context<SetPreferredMint>, preferred_mint: Pubkey) -> Result<()> {
+        ctx.accounts.participant.preferred_mint = preferred_mint;
+
+        emit!(PreferredMintUpdatedEvent {
+            agent: ctx.accounts.user.key(),
+            preferred_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a token-settled profit share converted into the participant's preferred mint
+    ///
+    /// Only the participant may convert; delegates cannot route their profit
+    /// through a swap. The vested, unclaimed amount is swapped through the
+    /// reserve's registered `swap_program`, whose SPL Token Swap `Swap`
+    /// instruction is built here with the mint vault signing as the source's
+    /// authority. The minimum output comes from the admin-set
+    /// `ConversionRate` for the pair less `max_slippage_bps`, never from the
+    /// caller. Exactly the claimed amount must leave the vault and at least
+    /// that minimum must arrive, otherwise the whole claim reverts.
+    pub fn claim_profit_token_converted(
+        ctx: Context<ClaimProfitTokenConverted>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let preferred_mint = ctx.accounts.participant.preferred_mint;
+        require!(
+            preferred_mint != Pubkey::default() && preferred_mint != ctx.accounts.share.mint,
+            AmanaError::ConversionNotRequested
+        );
+
+        let share = &mut ctx.accounts.share;
+        let mint_vault = &mut ctx.accounts.mint_vault;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(ctx.accounts.conversion_rate.updated_at)
+                <= MAX_CONVERSION_RATE_AGE_SECONDS,
+            AmanaError::StaleConversionRate
+        );
+        let amount = share.claimable(now)?;
+        require!(amount > 0, AmanaError::NothingToClaim);
+
+        share.record_claim(amount, now)?;
+        mint_vault.booked_balance = mint_vault.booked_balance
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let min_amount_out = u64::try_from(
+            (amount as u128)
+                .checked_mul(ctx.accounts.conversion_rate.rate as u128)
+                .and_then(|v| v.checked_mul((10000 - ctx.accounts.reserve.max_slippage_bps) as u128))
+                .ok_or(AmanaError::MathOverflow)?
+                / CONVERSION_RATE_SCALE
+                / 10000,
+        )
+        .map_err(|_| error!(AmanaError::MathOverflow))?;
+        require!(min_amount_out > 0, AmanaError::InvalidQuote);
+        let vault_before = ctx.accounts.vault.amount;
+        let received_before = ctx.accounts.agent_token_account.amount;
+
+        let mut data = Vec::with_capacity(17);
+        data.push(TOKEN_SWAP_SWAP_TAG);
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&min_amount_out.to_le_bytes());
+        let swap = Instruction {
+            program_id: ctx.accounts.swap_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.swap_pool.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.swap_authority.key(), false),
+                AccountMeta::new_readonly(mint_vault.key(), true),
+                AccountMeta::new(ctx.accounts.vault.key(), false),
+                AccountMeta::new(ctx.accounts.pool_source.key(), false),
+                AccountMeta::new(ctx.accounts.pool_destination.key(), false),
+                AccountMeta::new(ctx.accounts.agent_token_account.key(), false),
+                AccountMeta::new(ctx.accounts.pool_mint.key(), false),
+                AccountMeta::new(ctx.accounts.pool_fee.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data,
+        };
+        let swap_accounts = [
+            ctx.accounts.swap_pool.to_account_info(),
+            ctx.accounts.swap_authority.to_account_info(),
+            mint_vault.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.pool_source.to_account_info(),
+            ctx.accounts.pool_destination.to_account_info(),
+            ctx.accounts.agent_token_account.to_account_info(),
+            ctx.accounts.pool_mint.to_account_info(),
+            ctx.accounts.pool_fee.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.swap_program.to_account_info(),
+        ];
+
+        let vault_seeds: &[&[u8]] = &[
+            b"mint_vault",
+            ctx.accounts.reserve.id_seed(),
+            mint_vault.mint.as_ref(),
+            &[mint_vault.bump],
+        ];
+        invoke_signed(&swap, &swap_accounts, &[vault_seeds])?;
+
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.agent_token_account.reload()?;
+
+        let amount_in = vault_before
+            .checked_sub(ctx.accounts.vault.amount)
+            .ok_or(AmanaError::InvalidSwap)?;
+        require!(amount_in == amount, AmanaError::InvalidSwap);
+
+        let amount_out = ctx.accounts.agent_token_account.amount
+            .checked_sub(received_before)
+            .ok_or(AmanaError::InvalidSwap)?;
+        require!(amount_out >= min_amount_out, AmanaError::SlippageExceeded);
+
+        emit!(ProfitConvertedEvent {
+            activity_id,
+            agent: ctx.accounts.participant.agent,
+            from_mint: ctx.accounts.share.mint,
+            to_mint: preferred_mint,
+            amount_in,
+            amount_out,
+            min_amount_out,
+        });
+
+        Ok(())
+    }
+
+    /// Reinvest the vested, unclaimed part of a profit share as contributed capital
+    pub fn compound_profit(ctx: Context<CompoundProfit>, activity_id: [u8; 32]) -> Result<()> {
+        require_participant_authority(
+            &ctx.accounts.participant,
+            &ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_ref(),
+            DELEGATE_SCOPE_COMPOUND,
+        )?;
+        ctx.accounts.participant.require_settled(&ctx.accounts.reserve)?;
+
+        let reserve = &mut ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+        let share = &mut ctx.accounts.share;
+
+        require_keys_eq!(share.mint, Pubkey::default(), AmanaError::InvalidSettlementMint);
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = share.claimable(now)?;
+        require!(amount > 0, AmanaError::NothingToClaim);
+
+        // The profit already sits in total_capital; it only changes owner class
+        share.record_claim(amount, now)?;
+        participant.pending_profit = participant.pending_profit
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        participant.accrue_profit(reserve)?;
+        participant.capital_contributed = participant.capital_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(ProfitCompoundedEvent {
+            activity_id,
+            agent: participant.agent,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Authorize a delegate key to act on the participant's behalf
+    ///
+    /// `scopes` is a bitmask of `DELEGATE_SCOPE_*` flags; withdrawals can never
+    /// be delegated. `expires_at` of 0 means the delegation does not expire.
+    pub fn authorize_delegate(
+        ctx: Context<AuthorizeDelegate>,
+        delegate: Pubkey,
+        scopes: u8,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            scopes != 0 && scopes & !DELEGATE_SCOPE_ALL == 0,
+            AmanaError::InvalidDelegateScope
+        );
+        require!(
+            expires_at == 0 || expires_at > Clock::get()?.unix_timestamp,
+            AmanaError::DelegateExpired
+        );
+
+        let record = &mut ctx.accounts.delegate;
+        record.agent = ctx.accounts.user.key();
+        record.delegate = delegate;
+        record.scopes = scopes;
+        record.expires_at = expires_at;
+        record.bump = ctx.bumps.delegate;
+
+        emit!(DelegateAuthorizedEvent {
+            agent: record.agent,
+            delegate,
+            scopes,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke the participant's delegate
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+        emit!(DelegateRevokedEvent {
+            agent: ctx.accounts.delegate.agent,
+            delegate: ctx.accounts.delegate.delegate,
+        });
+
+        Ok(())
+    }
+
+    /// Schedule a recurring contribution funded from a prefunded escrow
+    ///
+    /// The `RecurringContribution` PDA holds `prefund` lamports on the
+    /// participant's behalf; a keeper moves `amount` into the reserve every
+    /// `interval_seconds` via `execute_recurring`, starting one interval from now.
+    pub fn schedule_recurring(
+        ctx: Context<ScheduleRecurring>,
+        amount: u64,
+        interval_seconds: i64,
+        prefund: u64,
+    ) -> Result<()> {
+        require!(
+            amount > 0 && amount <= MAX_RECURRING_CONTRIBUTION,
+            AmanaError::InvalidAmount
+        );
+        require!(interval_seconds > 0, AmanaError::InvalidDuration);
+
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.user.key(),
+                &ctx.accounts.recurring.key(),
+                prefund,
+            ),
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.recurring.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let recurring = &mut ctx.accounts.recurring;
+        recurring.agent = ctx.accounts.user.key();
+        recurring.amount = amount;
+        recurring.interval_seconds = interval_seconds;
+        recurring.next_due_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(interval_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        recurring.bump = ctx.bumps.recurring;
+
+        emit!(RecurringScheduledEvent {
+            agent: recurring.agent,
+            amount,
+            interval_seconds,
+            next_due_at: recurring.next_due_at,
+        });
+
+        Ok(())
+    }
+
+    /// Execute a due recurring contribution (callable by any keeper)
+    ///
+    /// Moves one installment from the escrow into the reserve and advances
+    /// `next_due_at` by one interval.
+    pub fn execute_recurring(ctx: Context<ExecuteRecurring>) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let recurring_info = ctx.accounts.recurring.to_account_info();
+        let recurring = &mut ctx.accounts.recurring;
+
+        require!(now >= recurring.next_due_at, AmanaError::RecurringNotDue);
+
+        let amount = recurring.amount;
+        require!(amount <= MAX_RECURRING_CONTRIBUTION, AmanaError::InvalidAmount);
+        ctx.accounts.participant.require_settled(&ctx.accounts.reserve)?;
+
+        let rent_floor = Rent::get()?.minimum_balance(recurring_info.data_len());
+        let available = recurring_info
+            .lamports()
+            .checked_sub(rent_floor)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(amount <= available, AmanaError::InsufficientBalance);
+        ctx.accounts.reserve.require_capacity(amount)?;
+        ctx.accounts.reserve.tier_config(ctx.accounts.participant.tier).check_contribution_cap(
+            ctx.accounts.participant.capital_contributed
+                .checked_add(amount)
+                .ok_or(AmanaError::MathOverflow)?,
+        )?;
+
+        **recurring_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        let reserve = &mut ctx.accounts.reserve;
+        let participant = &mut ctx.accounts.participant;
+        participant.accrue_profit(reserve)?;
+        participant.capital_contributed = participant.capital_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_contributed = reserve.total_contributed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        recurring.next_due_at = recurring.next_due_at
+            .checked_add(recurring.interval_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(RecurringExecutedEvent {
+            agent: recurring.agent,
+            amount,
+            keeper: ctx.accounts.keeper.key(),
+            next_due_at: recurring.next_due_at,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a recurring contribution, refunding the unspent escrow
+    pub fn cancel_recurring(ctx: Context<CancelRecurring>) -> Result<()> {
+        emit!(RecurringCancelledEvent {
+            agent: ctx.accounts.recurring.agent,
+        });
+
+        Ok(())
+    }
+
+    /// Register the signer as a validator, bonding `stake` lamports in its PDA
+    ///
+    /// The stake must meet the reserve's `min_validator_stake`.
+    pub fn register_validator(ctx: Context<RegisterValidator>, stake: u64) -> Result<()> {
+        require!(
+            stake > 0 && stake >= ctx.accounts.reserve.min_validator_stake,
+            AmanaError::InsufficientStake
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.authority.key(),
+                &ctx.accounts.validator.key(),
+                stake,
+            ),
+            &[
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.validator.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let validator = &mut ctx.accounts.validator;
+        validator.authority = ctx.accounts.authority.key();
+        validator.stake = stake;
+        validator.registered_at = Clock::get()?.unix_timestamp;
+        validator.unbonding_ends_at = 0;
+        validator.bump = ctx.bumps.validator;
+
+        emit!(ValidatorRegisteredEvent {
+            authority: validator.authority,
+            stake,
+        });
+
+        Ok(())
+    }
+
+    /// Start unbonding; the validator stops counting as bonded immediately
+    pub fn unregister_validator(ctx: Context<UnregisterValidator>) -> Result<()> {
+        let validator = &mut ctx.accounts.validator;
+        require!(validator.unbonding_ends_at == 0, AmanaError::ValidatorUnbonding);
+
+        validator.unbonding_ends_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.reserve.validator_unbonding_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(ValidatorUnregisteredEvent {
+            authority: validator.authority,
+            unbonding_ends_at: validator.unbonding_ends_at,
+        });
+
+        Ok(())
+    }
+
+    /// Close an unbonded validator, returning its stake to the authority
+    pub fn withdraw_validator_stake(ctx: Context<WithdrawValidatorStake>) -> Result<()> {
+        let validator = &ctx.accounts.validator;
+        require!(
+            validator.unbonding_ends_at != 0
+                && Clock::get()?.unix_timestamp >= validator.unbonding_ends_at,
+            AmanaError::UnbondingNotElapsed
+        );
+
+        emit!(ValidatorStakeWithdrawnEvent {
+            authority: validator.authority,
+            stake: validator.stake,
+        });
+
+        Ok(())
+    }
+
+    /// Lend free capital to `borrower` as Qard Hasan (interest-free)
+    ///
+    /// The loan leaves free capital until repaid. Total outstanding Qard may
+    /// not exceed the reserve's `max_qard_outstanding_bps` limit. A borrower
+    /// past their due date is lent nothing more until they repay or are
+    /// granted an extension.
+    pub fn issue_qard(ctx: Context<IssueQard>, amount: u64) -> Result<()> {
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+        require!(
+            amount <= ctx.accounts.reserve.to_account_info().lamports(),
+            AmanaError::InsufficientLiquidity
+        );
+
+        let reserve = &mut ctx.accounts.reserve;
+        let limit = (reserve.total_capital as u128)
+            .checked_add(reserve.total_qard_outstanding as u128)
+            .and_then(|v| v.checked_mul(reserve.max_qard_outstanding_bps as u128))
+            .ok_or(AmanaError::MathOverflow)?
+            / 10000;
+        let outstanding = reserve.total_qard_outstanding
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(outstanding as u128 <= limit, AmanaError::QardLimitExceeded);
+
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(amount)
+            .ok_or(AmanaError::InsufficientCapital)?;
+        reserve.total_qard_outstanding = outstanding;
+
+        let now = Clock::get()?.unix_timestamp;
+        let loan = &mut ctx.accounts.qard_loan;
+        if loan.borrower == Pubkey::default() {
+            loan.borrower = ctx.accounts.borrower.key();
+            loan.issued_at = now;
+            loan.bump = ctx.bumps.qard_loan;
+        }
+        require!(!loan.is_overdue(now), AmanaError::QardOverdue);
+        loan.outstanding = loan.outstanding
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        **ctx.accounts.reserve.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.borrower.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(QardIssuedEvent {
+            borrower: ctx.accounts.qard_loan.borrower,
+            amount,
+            total_qard_outstanding: ctx.accounts.reserve.total_qard_outstanding,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::IssueQard, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Repay part or all of a Qard Hasan loan, returning it to free capital
+    pub fn repay_qard(ctx: Context<RepayQard>, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= ctx.accounts.qard_loan.outstanding,
+            AmanaError::InvalidAmount
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.borrower.key(),
+                &ctx.accounts.reserve.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.borrower.to_account_info(),
+                ctx.accounts.reserve.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let loan = &mut ctx.accounts.qard_loan;
+        loan.outstanding = loan.outstanding
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        if loan.outstanding == 0 {
+            // A repaid loan owes nothing by any date; the next one gets its own
+            loan.due_at = 0;
+            loan.requested_due_at = 0;
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.total_qard_outstanding = reserve.total_qard_outstanding
+            .checked_sub(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(QardRepaidEvent {
+            borrower: loan.borrower,
+            amount,
+            remaining: loan.outstanding,
+            total_qard_outstanding: reserve.total_qard_outstanding,
+        });
+
+        Ok(())
+    }
+
+    /// Fix the date an outstanding Qard Hasan loan is due
+    ///
+    /// Only a loan with no due date can be given one; after that only
+    /// `approve_qard_extension` moves it. The date is cleared once the loan
+    /// is fully repaid.
+    pub fn set_qard_due_date(ctx: Context<SetQardDueDate>, due_at: i64) -> Result<()> {
+        let loan = &mut ctx.accounts.qard_loan;
+        require!(loan.outstanding > 0, AmanaError::InvalidAmount);
+        require!(
+            loan.due_at == 0 && due_at > Clock::get()?.unix_timestamp,
+            AmanaError::InvalidQardDueDate
+        );
+
+        loan.due_at = due_at;
+
+        emit!(QardDueDateSetEvent {
+            borrower: loan.borrower,
+            due_at,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SetQardDueDate, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Ask for a later due date on a Qard Hasan loan
+    ///
+    /// A borrower in hardship is owed respite (nazirah), never a charge for
+    /// it: the request names only the new date, and approval moves nothing
+    /// but the date. `new_due_at` must be after the current due date.
+    pub fn request_qard_extension(ctx: Context<RequestQardExtension>, new_due_at: i64) -> Result<()> {
+        let loan = &mut ctx.accounts.qard_loan;
+        require!(loan.outstanding > 0, AmanaError::InvalidAmount);
+        require!(
+            loan.due_at != 0 && new_due_at > loan.due_at,
+            AmanaError::InvalidQardDueDate
+        );
+
+        loan.requested_due_at = new_due_at;
+
+        emit!(QardExtensionRequestedEvent {
+            borrower: loan.borrower,
+            due_at: loan.due_at,
+            requested_due_at: new_due_at,
+        });
+
+        Ok(())
+    }
+
+    /// Grant a pending Qard Hasan extension
+    ///
+    /// The admin or the reserve's Sharia board may approve. Only the due date
+    /// moves. The outstanding amount is left exactly as it was, and the
+    /// instruction takes no amount, so no fee or increase can be attached to
+    /// the extension.
+    pub fn approve_qard_extension(ctx: Context<ApproveQardExtension>) -> Result<()> {
+        let loan = &mut ctx.accounts.qard_loan;
+        require!(loan.requested_due_at != 0, AmanaError::NoQardExtensionRequested);
+
+        let old_due_at = loan.due_at;
+        loan.due_at = loan.requested_due_at;
+        loan.requested_due_at = 0;
+        loan.extension_count = loan.extension_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(QardExtendedEvent {
+            borrower: loan.borrower,
+            old_due_at,
+            new_due_at: loan.due_at,
+            outstanding: loan.outstanding,
+            extension_count: loan.extension_count,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::ApproveQardExtension, ctx.accounts.approver.key())?;
+
+        Ok(())
+    }
+
+    /// Bring a Qard loan created before due dates to the current layout
+    ///
+    /// Grows the account to `QardLoan::SPACE`, with the admin topping up its
+    /// rent, and rewrites it with no due date and no extensions. Loans in the
+    /// current layout are refused.
+    pub fn migrate_qard_loan(ctx: Context<MigrateQardLoan>) -> Result<()> {
+        let info = ctx.accounts.qard_loan.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, AmanaError::InvalidQardLoan);
+        require!(info.data_len() == LegacyQardLoan::SPACE, AmanaError::InvalidQardLoan);
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(data[..8] == QardLoan::DISCRIMINATOR[..], AmanaError::InvalidQardLoan);
+            LegacyQardLoan::deserialize(&mut &data[8..])
+                .map_err(|_| error!(AmanaError::InvalidQardLoan))?
+        };
+        let expected = Pubkey::create_program_address(
+            &[
+                b"qard",
+                ctx.accounts.reserve.id_seed(),
+                legacy.borrower.as_ref(),
+                &[legacy.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(AmanaError::InvalidQardLoan))?;
+        require_keys_eq!(expected, info.key(), AmanaError::InvalidQardLoan);
+
+        let rent = Rent::get()?.minimum_balance(QardLoan::SPACE);
+        let top_up = rent.saturating_sub(info.lamports());
+        if top_up > 0 {
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.admin.key(),
+                    &info.key(),
+                    top_up,
+                ),
+                &[
+                    ctx.accounts.admin.to_account_info(),
+                    info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        info.realloc(QardLoan::SPACE, false)?;
+
+        let loan = QardLoan {
+            borrower: legacy.borrower,
+            outstanding: legacy.outstanding,
+            issued_at: legacy.issued_at,
+            due_at: 0,
+            requested_due_at: 0,
+            extension_count: 0,
+            bump: legacy.bump,
+        };
+        loan.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(QardLoanMigratedEvent {
+            borrower: loan.borrower,
+            outstanding: loan.outstanding,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::MigrateQardLoan, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Name the key of the reserve's Sharia board
+    ///
+    /// The board may approve Qard Hasan extensions alongside the admin.
+    /// `Pubkey::default()` leaves approvals to the admin alone.
+    pub fn set_sharia_board(ctx: Context<UpdateReserveConfig>, sharia_board: Pubkey) -> Result<()> {
+        ctx.accounts.reserve.sharia_board = sharia_board;
+
+        emit!(ShariaBoardUpdatedEvent { sharia_board });
+
+        ctx.accounts.audit_log.record(AuditAction::SetShariaBoard, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    /// Post the reserve's current completion bond before reporting an outcome
+    ///
+    /// The bond is keyed by activity and reporter, so posting one never
+    /// blocks another reporter's, and `complete_activity` accepts it only
+    /// when signed by the same reporter.
+    pub fn post_completion_bond(
+        ctx: Context<PostCompletionBond>,
+        activity_id: [u8; 32],
+    ) -> Result<()> {
+        let amount = ctx.accounts.reserve.completion_bond;
+        require!(amount > 0, AmanaError::InvalidAmount);
+        require!(
+            ctx.accounts.activity.status == ActivityStatus::Approved
+                || ctx.accounts.activity.status == ActivityStatus::Active,
+            AmanaError::InvalidActivityStatus
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.reporter.key(),
+                &ctx.accounts.completion_bond.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.reporter.to_account_info(),
+                ctx.accounts.completion_bond.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let bond = &mut ctx.accounts.completion_bond;
+        bond.activity_id = activity_id;
+        bond.reporter = ctx.accounts.reporter.key();
+        bond.amount = amount;
+        bond.completed_at = 0;
+        bond.bump = ctx.bumps.completion_bond;
+
+        emit!(CompletionBondPostedEvent {
+            activity_id,
+            reporter: bond.reporter,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return a completion bond once its dispute window passed undisputed
+    pub fn refund_completion_bond(ctx: Context<RefundCompletionBond>) -> Result<()> {
+        let bond = &ctx.accounts.completion_bond;
+        let window_ends_at = bond.completed_at
+            .checked_add(ctx.accounts.reserve.dispute_window_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(
+            bond.completed_at != 0 && Clock::get()?.unix_timestamp >= window_ends_at,
+            AmanaError::DisputeWindowOpen
+        );
+
+        emit!(CompletionBondRefundedEvent {
+            activity_id: bond.activity_id,
+            reporter: bond.reporter,
+            amount: bond.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Slash a completion bond to the reserve after a successful dispute
+    ///
+    /// Admin-only and only within the dispute window. Correcting the booked
+    /// outcome itself is left to the reserve's reconciliation tools.
+    pub fn slash_completion_bond(ctx: Context<SlashCompletionBond>) -> Result<()> {
+        let bond = &ctx.accounts.completion_bond;
+        let window_ends_at = bond.completed_at
+            .checked_add(ctx.accounts.reserve.dispute_window_seconds)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(
+            bond.completed_at != 0 && Clock::get()?.unix_timestamp < window_ends_at,
+            AmanaError::DisputeWindowClosed
+        );
+
+        let amount = bond.amount;
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        emit!(CompletionBondSlashedEvent {
+            activity_id: ctx.accounts.completion_bond.activity_id,
+            reporter: ctx.accounts.completion_bond.reporter,
+            amount,
+        });
+
+        ctx.accounts.audit_log.record(AuditAction::SlashCompletionBond, ctx.accounts.admin.key())?;
+
+        Ok(())
+    }
+
+    // ========== MagicBlock Ephemeral Rollup Integration ==========
+
+    /// Delegate reserve to Ephemeral Rollup for real-time operations
+    pub fn delegate_reserve(ctx: Context<DelegateReserve>) -> Result<()> {
+        let reserve: Reserve = load_remaining(&ctx.accounts.reserve)?;
+        ctx.accounts.delegate_pda(
+            &ctx.accounts.payer,
+            &[b"reserve", reserve.id_seed()],
+            DelegateConfig {
+                validator: Some(pubkey!("MAS1Dt9qreoRMQ14YQuhg8UTZMMzDdKhmkZMECCzk57")), // Asia ER Validator
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Emit whether recent proposal volume recommends delegating to the ER
+    ///
+    /// Read-only, for keepers deciding when to call `delegate_if_recommended`.
+    pub fn should_delegate(ctx: Context<ShouldDelegate>) -> Result<()> {
+        let reserve = &ctx.accounts.reserve;
+        let now = Clock::get()?.unix_timestamp;
+
+        emit!(DelegationRecommendationEvent {
+            recommended: reserve.delegation_recommended(now),
+            proposals_in_window: reserve.proposals_in_window(now),
+            delegation_threshold: reserve.delegation_threshold,
+            delegation_window_seconds: reserve.delegation_window_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Delegate the reserve to the ER, but only while `should_delegate` recommends it
+    ///
+    /// Callable by anyone, so a keeper can simply call it on a schedule.
+    pub fn delegate_if_recommended(ctx: Context<DelegateIfRecommended>) -> Result<()> {
+        let reserve: Reserve = load_remaining(&ctx.accounts.reserve)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            reserve.delegation_recommended(now),
+            AmanaError::DelegationNotRecommended
+        );
+
+        ctx.accounts.delegate_pda(
+            &ctx.accounts.payer,
+            &[b"reserve", reserve.id_seed()],
+            DelegateConfig {
+                validator: Some(pubkey!("MAS1Dt9qreoRMQ14YQuhg8UTZMMzDdKhmkZMECCzk57")), // Asia ER Validator
+                ..Default::default()
+            },
+        )?;
+
+        emit!(ReserveAutoDelegatedEvent {
+            proposals_in_window: reserve.proposals_in_window(now),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Deploy capital to activity in real-time on ER
+    ///
+    /// The admin or the activity's initiator must sign. `amount` replaces the
+    /// approved earmark and may never exceed `capital_required`; only the
+    /// admin may deploy more than was approved, so a partial approval holds.
+    pub fn deploy_capital_realtime(
+        ctx: Context<DeployCapitalRealtime>,
+        activity_id: [u8; 32],
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+        require!(
+            ctx.accounts.payer.key() == ctx.accounts.reserve.admin
+                || amount <= ctx.accounts.activity.capital_deployed,
+            AmanaError::Unauthorized
+        );
+        if !solvency_interlock(&mut ctx.accounts.reserve)? {
+            return Ok(());
+        }
+
+        // Deploy capital instantly on ER
+        deploy_escrow(&mut ctx.accounts.reserve, &mut ctx.accounts.activity, amount)?;
+
+        // Auto-commit critical state changes
+        commit_accounts(
+            &ctx.accounts.payer,
+            vec![
+                &ctx.accounts.reserve.to_account_info(),
+                &ctx.accounts.activity.to_account_info(),
+            ],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        emit!(CapitalDeployedRealtimeEvent {
+            activity_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a proposed activity and deploy `amount` to it in one call on ER
+    ///
+    /// For trusted automated flows. Equivalent to `approve_activity` for the
+    /// full request followed by `deploy_capital_realtime(amount)`, with the
+    /// same guards (admin signature, validator, risk budget, free capital),
+    /// but with no window between the two. Both events are emitted
+    /// and the result is committed like a realtime deployment.
+    pub fn approve_and_deploy(ctx: Context<ApproveAndDeploy>, amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.reserve.status == ReserveStatus::Active,
+            AmanaError::ReserveWindingDown
+        );
+        require_validator(
+            &ctx.accounts.reserve,
+            ctx.accounts.validator.as_ref(),
+            ctx.accounts.validator_authority.as_ref(),
+        )?;
+        if !solvency_interlock(&mut ctx.accounts.reserve)? {
+            return Ok(());
+        }
+
+        let reserve = &mut ctx.accounts.reserve;
+        let activity = &mut ctx.accounts.activity;
+        let activity_id = activity.activity_id;
+        let approved_amount = activity.capital_required;
+
+        approve_into_escrow(reserve, activity, approved_amount, Clock::get()?.unix_timestamp)?;
+        emit!(ActivityApprovedEvent {
+            activity_id,
+            capital_required: activity.capital_required,
+            approved_amount,
+            approved_by: ctx.accounts.authority.key(),
+        });
+
+        deploy_escrow(reserve, activity, amount)?;
+
+        commit_accounts(
+            &ctx.accounts.payer,
+            vec![
+                &ctx.accounts.reserve.to_account_info(),
+                &ctx.accounts.activity.to_account_info(),
+            ],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        emit!(CapitalDeployedRealtimeEvent {
+            activity_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Commit and undelegate reserve state back to base layer
+    ///
+    /// Ends real-time operation, so only the admin or an authorized operator
+    /// may trigger it.
+    pub fn commit_and_undelegate_reserve(ctx: Context<CommitAndUndelegateReserve>) -> Result<()> {
+        require_admin_or_operator(
+            &ctx.accounts.reserve,
+            &ctx.accounts.payer.key(),
+            ctx.accounts.operator.as_ref(),
+        )?;
+
+        emit!(ReserveUndelegatedEvent {
+            triggered_by: ctx.accounts.payer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        commit_and_undelegate_accounts(
+            &ctx.accounts.payer,
+            vec![&ctx.accounts.reserve.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+        Ok(())
+    }
+}
+
+/// Maximum number of sub-activities a single split may create
+pub const MAX_ACTIVITY_SPLITS: usize = 8;
+
+/// Maximum number of activity ids held by one initiator index page
+pub const MAX_INDEXED_ACTIVITIES: usize = 32;
+
+/// Maximum number of activities a single merged report may aggregate
+pub const MAX_MERGED_ACTIVITIES: usize = 16;
+
+/// verify_activity_integrity result codes
+pub const INTEGRITY_OK: u8 = 0;
+/// Stored activity_id differs from the one the account is seeded by
+pub const INTEGRITY_ID_MISMATCH: u8 = 1;
+/// approved_at precedes created_at
+pub const INTEGRITY_APPROVED_BEFORE_CREATED: u8 = 2;
+/// Status is past approval but approved_at was never set
+pub const INTEGRITY_MISSING_APPROVAL: u8 = 3;
+/// completed_at precedes approved_at
+pub const INTEGRITY_COMPLETED_BEFORE_APPROVED: u8 = 4;
+/// Completion time, outcome or settlement recorded on an uncompleted activity
+pub const INTEGRITY_OUTCOME_WITHOUT_COMPLETION: u8 = 5;
+/// capital_deployed exceeds capital_required
+pub const INTEGRITY_DEPLOYED_EXCEEDS_REQUIRED: u8 = 6;
+
+/// Share of free capital a single proposal may request until the admin changes it
+pub const DEFAULT_MAX_PROPOSAL_BPS: u16 = 5000;
+
+/// Maximum number of activities weighed by compute_portfolio_risk
+pub const MAX_PORTFOLIO_ACTIVITIES: usize = 32;
+
+/// Shortest dormancy a reserve may configure before profit can be swept (2 years)
+pub const MIN_DORMANCY_SECONDS: i64 = 2 * 365 * 24 * 60 * 60;
+
+/// Shortest window in which a participant may reverse a sweep (30 days)
+pub const MIN_SWEEP_REVERSAL_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// Maximum number of activities estimate_withdrawal_time projects over
+pub const MAX_LIQUIDITY_FORECAST_ACTIVITIES: usize = 32;
+
+/// Maximum number of shares list_pending_claims reports in one call
+pub const MAX_PENDING_CLAIMS: usize = 16;
+
+/// Maximum number of activities poll_work checks for overdue maturities
+pub const MAX_POLL_WORK_ACTIVITIES: usize = 32;
+
+/// Longest metadata_uri a reserve may store
+pub const MAX_METADATA_URI_LEN: usize = 200;
+
+/// Number of `ParticipantTier` variants, each with a `TierConfig` on the reserve
+pub const PARTICIPANT_TIER_COUNT: usize = 2;
+
+/// Size of the Bloom filter of closed activity ids kept on the reserve (2048 bits)
+pub const CLOSED_ID_FILTER_BYTES: usize = 256;
+
+/// Bits set in the closed-id filter per activity id
+pub const CLOSED_ID_FILTER_HASHES: usize = 3;
+
+/// Closures recorded in one closed-id filter before it is rotated out
+///
+/// At 200 ids a 2048-bit filter with 3 hashes rejects about 1.6% of fresh
+/// ids, so checking it together with the previous filter stays near 3%.
+pub const CLOSED_ID_FILTER_CAPACITY: u64 = 200;
+
+/// Highest management fee the admin may set, in basis points of profit (10%)
+pub const MAX_MANAGEMENT_FEE_BPS: u16 = 1000;
+
+/// Base units of the source mint a `ConversionRate` is quoted per
+pub const CONVERSION_RATE_SCALE: u128 = 1_000_000_000;
+
+/// Oldest `ConversionRate` a conversion will still use (1 hour)
+pub const MAX_CONVERSION_RATE_AGE_SECONDS: i64 = 60 * 60;
+
+/// Instruction tag of `Swap` in the SPL Token Swap interface the venue implements
+const TOKEN_SWAP_SWAP_TAG: u8 = 1;
+
+/// Fixed-point scale of the reserve's cumulative profit-per-unit index
+pub const PROFIT_INDEX_SCALE: u128 = 1_000_000_000_000;
+
+/// Largest single installment of a recurring contribution (100 SOL)
+pub const MAX_RECURRING_CONTRIBUTION: u64 = 100_000_000_000;
+
+/// Maximum number of participants sampled by compute_distribution_metrics
+pub const MAX_DISTRIBUTION_SAMPLE: usize = 32;
+
+/// Number of addresses held by one denylist page
+pub const MAX_DENYLIST_PAGE_ENTRIES: usize = 64;
+
+/// Upper bound on denylist pages, so every join can pass them all
+pub const MAX_DENYLIST_PAGES: u32 = 8;
+
+/// Largest entry donation a reserve may configure (10%)
+pub const MAX_ENTRY_FEE_BPS: u16 = 1000;
+
+/// Lowest and highest activity risk ratings
+pub const MIN_RISK_RATING: u8 = 1;
+pub const MAX_RISK_RATING: u8 = 5;
+
+/// amana-dao program, whose `Dao` PDA signs governed capacity increases
+pub const AMANA_DAO_PROGRAM_ID: Pubkey = pubkey!("AMANAdao1111111111111111111111111111111");
+
+/// Delegate may claim profit shares on the participant's behalf
+pub const DELEGATE_SCOPE_CLAIM: u8 = 1 << 0;
+/// Delegate may compound profit shares into capital
+pub const DELEGATE_SCOPE_COMPOUND: u8 = 1 << 1;
+/// Delegate may deposit capital for the participant
+pub const DELEGATE_SCOPE_DEPOSIT: u8 = 1 << 2;
+/// Every delegable scope; withdrawals are deliberately excluded
+pub const DELEGATE_SCOPE_ALL: u8 =
+    DELEGATE_SCOPE_CLAIM | DELEGATE_SCOPE_COMPOUND | DELEGATE_SCOPE_DEPOSIT;
+
+/// Self-check that a distribution accounts for every unit it hands out
+///
+/// Emits `DistributionAuditEvent` and fails with `DistributionMismatch`
+/// unless `distributed_sum + remainder == total`. Callers keep the sum and
+/// the remainder in separate counters and run it on:
+/// - each profit share or loss realized from a settled activity
+/// - each in-kind claim
+/// - the scheduled profit ledger, on release, claim and exit
+/// - the net result of `net_settle_initiator` against its settled outcomes
+fn audit_distribution(total: u64, distributed_sum: u64, remainder: u64) -> Result<()> {
+    let accounted = (distributed_sum as u128) + (remainder as u128);
+    require!(accounted == total as u128, AmanaError::DistributionMismatch);
+
+    emit!(DistributionAuditEvent {
+        total,
+        distributed_sum,
+        remainder,
+    });
+
+    Ok(())
+}
+
+/// Set every field of a freshly created reserve
+fn init_reserve(
+    reserve: &mut Reserve,
+    admin: Pubkey,
+    name: [u8; 32],
+    metadata_uri: String,
+    min_capital_contribution: u64,
+    max_participants: u64,
+    bump: u8,
+) {
+    reserve.admin = admin;
+    reserve.min_capital_contribution = min_capital_contribution;
+    reserve.max_participants = max_participants;
+    reserve.total_capital = 0;
+    reserve.total_contributed = 0;
+    reserve.participant_count = 0;
+    reserve.allow_netting = false;
+    reserve.min_activity_capital = 0;
+    reserve.deployed_capital = 0;
+    reserve.open_activity_count = 0;
+    reserve.max_outcome_age_seconds = 0;
+    reserve.risk_exposure = 0;
+    reserve.max_risk_exposure = 0;
+    reserve.entry_fee_bps = 0;
+    reserve.charity = Pubkey::default();
+    reserve.denylist_page_count = 0;
+    reserve.maturity_grace_seconds = 0;
+    reserve.private_bridge = Pubkey::default();
+    reserve.private_deployed_capital = 0;
+    reserve.min_validator_stake = 0;
+    reserve.validator_unbonding_seconds = 0;
+    reserve.vesting_cliff_seconds = 0;
+    reserve.vesting_duration_seconds = 0;
+    reserve.max_qard_outstanding_bps = 0;
+    reserve.total_qard_outstanding = 0;
+    reserve.completion_bond = 0;
+    reserve.dispute_window_seconds = 0;
+    reserve.status = ReserveStatus::Active;
+    reserve.min_activity_duration_seconds = 0;
+    reserve.max_activity_duration_seconds = 0;
+    reserve.distribution_interval_seconds = 0;
+    reserve.pending_distribution = 0;
+    reserve.last_distribution_at = 0;
+    reserve.distribution_round = 0;
+    reserve.pending_proposed_capital = 0;
+    reserve.swap_program = Pubkey::default();
+    reserve.max_slippage_bps = 0;
+    reserve.delegation_window_seconds = 0;
+    reserve.delegation_threshold = 0;
+    reserve.window_started_at = 0;
+    reserve.window_proposal_count = 0;
+    reserve.max_total_capital = 0;
+    reserve.capacity_governed = false;
+    reserve.next_capacity_proposal_id = 0;
+    reserve.dormancy_seconds = 0;
+    reserve.sweep_reversal_seconds = 0;
+    reserve.guarantee_coverage_bps = 0;
+    reserve.name = name;
+    reserve.metadata_uri = metadata_uri;
+    reserve.proposal_expiry_seconds = 0;
+    reserve.distribution_mode = DistributionMode::OutcomeContingent;
+    reserve.activity_fee = 0;
+    reserve.tier_configs = [TierConfig::default(); PARTICIPANT_TIER_COUNT];
+    reserve.allow_in_kind = false;
+    reserve.min_solvency_bps = 0;
+    reserve.is_paused = false;
+    reserve.unique_activity_ids = false;
+    reserve.closed_id_filter = [0; CLOSED_ID_FILTER_BYTES];
+    reserve.closed_id_count = 0;
+    reserve.previous_closed_id_filter = [0; CLOSED_ID_FILTER_BYTES];
+    reserve.management_fee_bps = 0;
+    reserve.withdrawal_cooldown_seconds = 0;
+    reserve.mudarabah_limited_liability = false;
+    reserve.settlement_count = 0;
+    reserve.profit_per_unit = 0;
+    reserve.released_per_unit = 0;
+    reserve.fees_owed = 0;
+    reserve.sharia_board = Pubkey::default();
+    reserve.pending_withdrawal_count = 0;
+    reserve.max_proposal_bps = DEFAULT_MAX_PROPOSAL_BPS;
+    reserve.distribution_accrued = 0;
+    reserve.distribution_paid = 0;
+    reserve.distribution_outstanding = 0;
+    reserve.is_initialized = true;
+    reserve.bump = bump;
+}
+
+/// `name` without its zero padding, as used in PDA seeds
+///
+/// The default reserve has an all-zero name, so its seeds and those of its
+/// accounts are exactly the ones used before reserves could be named.
+pub fn name_seed(name: &[u8; 32]) -> &[u8] {
+    let len = name.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    &name[..len]
+}
+
+/// Check that `authority` is the participant or an unexpired delegate with `scope`
+fn require_participant_authority(
+    participant: &Participant,
+    authority: &Pubkey,
+    delegate: Option<&Account<ParticipantDelegate>>,
+    scope: u8,
+) -> Result<()> {
+    if *authority == participant.agent {
+        return Ok(());
+    }
+
+    let delegate = delegate.ok_or(AmanaError::Unauthorized)?;
+    require_keys_eq!(delegate.agent, participant.agent, AmanaError::Unauthorized);
+    require_keys_eq!(delegate.delegate, *authority, AmanaError::Unauthorized);
+    require!(delegate.scopes & scope == scope, AmanaError::InvalidDelegateScope);
+    require!(
+        delegate.expires_at == 0 || Clock::get()?.unix_timestamp < delegate.expires_at,
+        AmanaError::DelegateExpired
+    );
+
+    Ok(())
+}
+
+/// Reject `agent` if it appears on any denylist page
+///
+/// `pages` must hold exactly `page_count` denylist pages in page order, so a
+/// caller cannot skip the page that lists them.
+fn require_not_denied(
+    reserve: &Reserve,
+    agent: &Pubkey,
+    pages: &[AccountInfo],
+) -> Result<()> {
+    require_not_listed(reserve, agent, pages, None)
+}
+
+/// Reject `agent` if it appears on any denylist page other than `skip`
+///
+/// The page being edited is skipped, since the caller holds it as a typed
+/// account and checks it there; its address is still verified.
+fn require_not_listed(
+    reserve: &Reserve,
+    agent: &Pubkey,
+    pages: &[AccountInfo],
+    skip: Option<u32>,
+) -> Result<()> {
+    require!(
+        pages.len() == reserve.denylist_page_count as usize,
+        AmanaError::InvalidDenylistPage
+    );
+
+    for (index, info) in pages.iter().enumerate() {
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"denylist", reserve.id_seed(), (index as u32).to_le_bytes().as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(info.key(), expected, AmanaError::InvalidDenylistPage);
+        if skip == Some(index as u32) {
+            continue;
+        }
+
+        let page: DenylistPage = load_remaining(info)?;
+        require!(!page.entries.contains(agent), AmanaError::Denied);
+    }
+
+    Ok(())
+}
+
+/// Check that `info` is the address `activity` is seeded at under the reserve
+/// with id `reserve_seed`
+fn require_activity_pda(info: &AccountInfo, reserve_seed: &[u8], activity: &Activity) -> Result<()> {
+    let expected = Pubkey::create_program_address(
+        &[b"activity", reserve_seed, activity.activity_id.as_ref(), &[activity.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| error!(AmanaError::InvalidRemainingAccount))?;
+    require_keys_eq!(info.key(), expected, AmanaError::InvalidRemainingAccount);
+    Ok(())
+}
+
+/// Gini coefficient of `values` in basis points (0 = equal, 10000 = maximal)
+///
+/// Uses the sorted form `G = (2 * sum(i * x_i) - (n + 1) * S) / (n * S)`
+/// with 1-based ranks `i` over ascending `x_i` and `S = sum(x_i)`.
+fn gini_coefficient_bps(values: &mut [u64]) -> Result<u16> {
+    let n = values.len() as u128;
+    let total: u128 = values.iter().map(|v| *v as u128).sum();
+    if n == 0 || total == 0 {
+        return Ok(0);
+    }
+
+    values.sort_unstable();
+    let ranked: u128 = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i as u128 + 1) * (*v as u128))
+        .sum();
+
+    let numerator = (2 * ranked)
+        .checked_sub((n + 1) * total)
+        .ok_or(AmanaError::MathOverflow)?;
+    let gini = numerator
+        .checked_mul(10000)
+        .ok_or(AmanaError::MathOverflow)?
+        / (n * total);
+    Ok(gini as u16)
+}
+
+/// First lifecycle invariant `activity` violates, or `INTEGRITY_OK`
+fn activity_integrity_violation(activity: &Activity, activity_id: &[u8; 32]) -> u8 {
+    let approved = matches!(
+        activity.status,
+        ActivityStatus::Approved
+            | ActivityStatus::Active
+            | ActivityStatus::Completed
+            | ActivityStatus::Split
+    );
+    let completed = activity.status == ActivityStatus::Completed;
+
+    if activity.activity_id != *activity_id {
+        INTEGRITY_ID_MISMATCH
+    } else if activity.approved_at != 0 && activity.approved_at < activity.created_at {
+        INTEGRITY_APPROVED_BEFORE_CREATED
+    } else if approved && activity.approved_at == 0 {
+        INTEGRITY_MISSING_APPROVAL
+    } else if completed && activity.completed_at < activity.approved_at {
+        INTEGRITY_COMPLETED_BEFORE_APPROVED
+    } else if !completed
+        && (activity.completed_at != 0
+            || activity.outcome != 0
+            || activity.is_settled
+            || activity.settled_outcome != 0)
+    {
+        INTEGRITY_OUTCOME_WITHOUT_COMPLETION
+    } else if activity.capital_deployed > activity.capital_required {
+        INTEGRITY_DEPLOYED_EXCEEDS_REQUIRED
+    } else {
+        INTEGRITY_OK
+    }
+}
+
+/// When the reserve requires validators, check that a bonded one signed
+fn require_validator(
+    reserve: &Reserve,
+    validator: Option<&Account<Validator>>,
+    authority: Option<&Signer>,
+) -> Result<()> {
+    if reserve.min_validator_stake == 0 {
+        return Ok(());
+    }
+
+    let validator = validator.ok_or(AmanaError::ValidatorRequired)?;
+    let authority = authority.ok_or(AmanaError::ValidatorRequired)?;
+    require_keys_eq!(validator.authority, authority.key(), AmanaError::ValidatorRequired);
+    require!(
+        validator.is_bonded(reserve.min_validator_stake),
+        AmanaError::InsufficientStake
+    );
+
+    Ok(())
+}
+
+/// Key a DAO proposal must target to approve exactly this capacity change
+pub fn capacity_commitment(max_participants: u64, max_total_capital: u64) -> Pubkey {
+    Pubkey::new_from_array(
+        anchor_lang::solana_program::hash::hashv(&[
+            b"capacity",
+            &max_participants.to_le_bytes(),
+            &max_total_capital.to_le_bytes(),
+        ])
+        .to_bytes(),
+    )
+}
+
+/// Check that `deployer` is the reserve admin or an authorized private deployer
+fn require_admin_or_private_deployer(
+    reserve: &Reserve,
+    deployer: &Pubkey,
+    record: Option<&Account<PrivateDeployer>>,
+) -> Result<()> {
+    if reserve.admin == *deployer {
+        return Ok(());
+    }
+
+    let record = record.ok_or(AmanaError::Unauthorized)?;
+    require_keys_eq!(record.deployer, *deployer, AmanaError::Unauthorized);
+    require!(record.is_authorized, AmanaError::Unauthorized);
+
+    Ok(())
+}
+
+/// Check that `reporter` signed and is the reserve admin or an authorized reporter
+fn require_admin_or_reporter(
+    reserve: &Reserve,
+    reporter: Option<&Signer>,
+    record: Option<&Account<Reporter>>,
+) -> Result<()> {
+    let reporter = reporter.ok_or(AmanaError::Unauthorized)?.key();
+    if reserve.admin == reporter {
+        return Ok(());
+    }
+
+    let record = record.ok_or(AmanaError::Unauthorized)?;
+    require_keys_eq!(record.reporter, reporter, AmanaError::Unauthorized);
+    require!(record.is_authorized, AmanaError::Unauthorized);
+
+    Ok(())
+}
+
+/// Check that `authority` is the reserve admin or an authorized operator
+fn require_admin_or_operator(
+    reserve: &Reserve,
+    authority: &Pubkey,
+    operator: Option<&Account<Operator>>,
+) -> Result<()> {
+    if reserve.admin == *authority {
+        return Ok(());
+    }
+
+    let operator = operator.ok_or(AmanaError::Unauthorized)?;
+    require_keys_eq!(operator.operator, *authority, AmanaError::Unauthorized);
+    require!(operator.is_authorized, AmanaError::Unauthorized);
+
+    Ok(())
+}
+
+/// Remove an open activity's escrow from the reserve's books
+///
+/// Returns the capital that was deployed; the caller decides how much of it
+/// flows back into `total_capital`.
+fn release_escrow(reserve: &mut Reserve, activity: &Activity) -> Result<u64> {
+    let released = activity.capital_deployed;
+    reserve.deployed_capital = reserve.deployed_capital
+        .checked_sub(released)
+        .ok_or(AmanaError::MathOverflow)?;
+    reserve.risk_exposure = reserve.risk_exposure
+        .checked_sub(risk_weighted(released, activity.risk_rating)?)
+        .ok_or(AmanaError::MathOverflow)?;
+    reserve.open_activity_count = reserve.open_activity_count
+        .checked_sub(1)
+        .ok_or(AmanaError::MathOverflow)?;
+    Ok(released)
+}
+
+/// Solvency ratio in basis points, with the accounted and available lamports
+///
+/// Accounted capital is what the reserve should still hold: free, escrowed
+/// and privately deployed capital. Qard loans have left the account and are
+/// not counted. A reserve accounting for nothing is fully solvent.
+fn solvency_ratio(reserve: &Account<Reserve>) -> Result<(u64, u64, u64)> {
+    let info = reserve.to_account_info();
+    let rent_floor = Rent::get()?.minimum_balance(info.data_len());
+    let available = info.lamports().saturating_sub(rent_floor);
+    let accounted = reserve.total_capital
+        .checked_add(reserve.deployed_capital)
+        .and_then(|v| v.checked_add(reserve.private_deployed_capital))
+        .ok_or(AmanaError::MathOverflow)?;
+    if accounted == 0 {
+        return Ok((10000, accounted, available));
+    }
+    let solvency_bps = (available as u128)
+        .checked_mul(10000)
+        .ok_or(AmanaError::MathOverflow)?
+        / accounted as u128;
+    Ok((u64::try_from(solvency_bps).unwrap_or(u64::MAX), accounted, available))
+}
+
+/// Move lamports out of an account this program owns, with checked math
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let from_balance = from.lamports().checked_sub(amount).ok_or(AmanaError::MathOverflow)?;
+    let to_balance = to.lamports().checked_add(amount).ok_or(AmanaError::MathOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
+    Ok(())
+}
+
+/// Refuse to act while paused, and pause on a fresh solvency breach
+///
+/// Returns whether the caller may proceed. A breach is recorded by setting
+/// `is_paused` and the caller must then return `Ok` without acting, since an
+/// error would roll the pause back.
+fn solvency_interlock(reserve: &mut Account<Reserve>) -> Result<bool> {
+    require!(!reserve.is_paused, AmanaError::ReservePaused);
+    if reserve.min_solvency_bps == 0 {
+        return Ok(true);
+    }
+
+    let (solvency_bps, accounted, available) = solvency_ratio(reserve)?;
+    if solvency_bps >= reserve.min_solvency_bps as u64 {
+        return Ok(true);
+    }
+
+    reserve.is_paused = true;
+    emit!(SolvencyBreachEvent {
+        solvency_bps,
+        min_solvency_bps: reserve.min_solvency_bps,
+        accounted,
+        available,
+    });
+    Ok(false)
+}
+
+/// Checks shared by every path paying a participant's capital out
+///
+/// `capital` is the part of `payout` drawn from the participant's
+/// contribution and is capped by their share of free capital; the whole
+/// `payout` must fit in the reserve's lamports above its rent-exempt minimum.
+/// Like `solvency_interlock`, returns `false` when a fresh breach has just
+/// paused the reserve and the caller must return `Ok` without paying.
+fn check_capital_payout(
+    reserve: &mut Account<Reserve>,
+    participant: &Participant,
+    capital: u64,
+    payout: u64,
+) -> Result<bool> {
+    participant.require_unlocked(Clock::get()?.unix_timestamp)?;
+    participant.require_settled(reserve)?;
+    if !solvency_interlock(reserve)? {
+        return Ok(false);
+    }
+
+    require!(
+        capital <= participant.capital_contributed,
+        AmanaError::InsufficientBalance
+    );
+    require!(
+        capital <= reserve.free_capital_share(participant.capital_contributed)?,
+        AmanaError::CapitalLocked
+    );
+
+    let reserve_info = reserve.to_account_info();
+    let rent_floor = Rent::get()?.minimum_balance(reserve_info.data_len());
+    require!(
+        payout <= reserve_info.lamports().saturating_sub(rent_floor),
+        AmanaError::InsufficientLiquidity
+    );
+
+    Ok(true)
+}
+
+/// Approve a proposed activity, earmarking `approved_amount` of free capital
+///
+/// The amount must be positive and at most `capital_required` and fit the
+/// risk budget.
+fn approve_into_escrow(
+    reserve: &mut Reserve,
+    activity: &mut Activity,
+    approved_amount: u64,
+    now: i64,
+) -> Result<()> {
+    require!(
+        activity.status == ActivityStatus::Proposed,
+        AmanaError::InvalidActivityStatus
+    );
+    require!(
+        approved_amount > 0 && approved_amount <= activity.capital_required,
+        AmanaError::InvalidApprovedAmount
+    );
+    require!(
+        approved_amount >= reserve.min_activity_capital,
+        AmanaError::BelowMinimumActivityCapital
+    );
+
+    let exposure = reserve.risk_exposure
+        .checked_add(risk_weighted(approved_amount, activity.risk_rating)?)
+        .ok_or(AmanaError::MathOverflow)?;
+    require!(
+        reserve.max_risk_exposure == 0 || exposure <= reserve.max_risk_exposure,
+        AmanaError::RiskBudgetExceeded
+    );
+
+    activity.status = ActivityStatus::Approved;
+    activity.capital_deployed = approved_amount;
+    activity.approved_at = now;
+    if activity.expected_duration > 0 {
+        activity.maturity_at = now
+            .checked_add(activity.expected_duration)
+            .ok_or(AmanaError::MathOverflow)?;
+    }
+    reserve.risk_exposure = exposure;
+    reserve.pending_proposed_capital = reserve.pending_proposed_capital
+        .checked_sub(activity.capital_required)
+        .ok_or(AmanaError::MathOverflow)?;
+    reserve.total_capital = reserve.total_capital
+        .checked_sub(approved_amount)
+        .ok_or(AmanaError::MathOverflow)?;
+    reserve.deployed_capital = reserve.deployed_capital
+        .checked_add(approved_amount)
+        .ok_or(AmanaError::MathOverflow)?;
+    reserve.open_activity_count = reserve.open_activity_count
+        .checked_add(1)
+        .ok_or(AmanaError::MathOverflow)?;
+    Ok(())
+}
+
+/// Deploy an approved activity with exactly `amount`, activating it
+///
+/// Approval already earmarked `capital_deployed`; only the difference moves
+/// between free capital and escrow, under the same risk limit.
+/// The deployment can never exceed what the activity requested nor fall
+/// below `min_activity_capital`.
+fn deploy_escrow(reserve: &mut Reserve, activity: &mut Activity, amount: u64) -> Result<()> {
+    require!(
+        activity.status == ActivityStatus::Approved,
+        AmanaError::InvalidActivityStatus
+    );
+    require!(
+        amount <= activity.capital_required,
+        AmanaError::DeploymentExceedsRequest
+    );
+    require!(
+        amount >= reserve.min_activity_capital,
+        AmanaError::BelowMinimumActivityCapital
+    );
+
+    if amount > activity.capital_deployed {
+        let extra = amount - activity.capital_deployed;
+        require!(extra <= reserve.total_capital, AmanaError::InsufficientCapital);
+        let exposure = reserve.risk_exposure
+            .checked_add(risk_weighted(extra, activity.risk_rating)?)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(
+            reserve.max_risk_exposure == 0 || exposure <= reserve.max_risk_exposure,
+            AmanaError::RiskBudgetExceeded
+        );
+        reserve.risk_exposure = exposure;
+        reserve.total_capital = reserve.total_capital
+            .checked_sub(extra)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.deployed_capital = reserve.deployed_capital
+            .checked_add(extra)
+            .ok_or(AmanaError::MathOverflow)?;
+    } else {
+        let released = activity.capital_deployed - amount;
+        reserve.total_capital = reserve.total_capital
+            .checked_add(released)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.deployed_capital = reserve.deployed_capital
+            .checked_sub(released)
+            .ok_or(AmanaError::MathOverflow)?;
+        reserve.risk_exposure = reserve.risk_exposure
+            .checked_sub(risk_weighted(released, activity.risk_rating)?)
+            .ok_or(AmanaError::MathOverflow)?;
+    }
+
+    activity.capital_deployed = amount;
+    activity.status = ActivityStatus::Active;
+    Ok(())
+}
+
+/// Book a token-settled outcome in the vault of the activity's settlement mint
+///
+/// A profit must already sit unbooked in the vault and becomes claimable.
+/// Nothing is booked for a loss here: like a SOL loss it reaches each
+/// participant through `realize_loss`, which adds it to the vault's
+/// `realized_loss`.
+fn settle_in_mint(
+    mint_vault: Option<&mut Account<MintVault>>,
+    vault: Option<&Account<TokenAccount>>,
+    activity: &Activity,
+    outcome: i64,
+) -> Result<()> {
+    let mint_vault = mint_vault.ok_or(AmanaError::InvalidSettlementMint)?;
+    let vault = vault.ok_or(AmanaError::InvalidSettlementMint)?;
+    require_keys_neq!(activity.settlement_mint, Pubkey::default(), AmanaError::InvalidSettlementMint);
+    require_keys_eq!(mint_vault.mint, activity.settlement_mint, AmanaError::InvalidSettlementMint);
+    require_keys_eq!(vault.key(), mint_vault.vault, AmanaError::InvalidSettlementMint);
+    require_keys_eq!(vault.mint, activity.settlement_mint, AmanaError::InvalidSettlementMint);
+
+    if outcome > 0 {
+        let booked = mint_vault.booked_balance
+            .checked_add(outcome as u64)
+            .ok_or(AmanaError::MathOverflow)?;
+        require!(vault.amount >= booked, AmanaError::InsufficientLiquidity);
+        mint_vault.booked_balance = booked;
+    }
+
+    Ok(())
+}
+
+/// Weight an amount of capital by an activity's risk rating
+fn risk_weighted(amount: u64, risk_rating: u8) -> Result<u64> {
+    let weighted = amount
+        .checked_mul(risk_rating as u64)
+        .ok_or(AmanaError::MathOverflow)?;
+    Ok(weighted)
+}
+
+/// Convert an unsigned magnitude into a signed outcome
+fn signed_amount(amount: u64, positive: bool) -> Result<i64> {
+    let value = i64::try_from(amount).map_err(|_| error!(AmanaError::MathOverflow))?;
+    Ok(if positive { value } else { -value })
+}
+
+/// Deserialize a program-owned account passed via `remaining_accounts`
+fn load_remaining<T: AccountDeserialize>(info: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*info.owner, crate::ID, AmanaError::InvalidRemainingAccount);
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Derive the share id of the `round`-th scheduled distribution
+fn derive_distribution_id(round: u64) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[b"distribution", &round.to_le_bytes()])
+        .to_bytes()
+}
+
+/// Derive the id of the `index`-th child of a split activity
+fn derive_child_activity_id(parent_id: &[u8; 32], index: u8) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[parent_id.as_ref(), b"split", &[index]])
+        .to_bytes()
+}
+
+// Account structs
+
+/// Bounded ring of the most recent reserve admin actions; every entry is
+/// also emitted as an `AuditEntryEvent`, which is the complete history
+#[account]
+pub struct AuditLog {
+    pub next_sequence: u64,
+    pub entries: Vec<AuditEntry>,
+    pub bump: u8,
+}
+
+impl AuditLog {
+    pub const SPACE: usize = AUDIT_LOG_SPACE;
+
+    /// Append an entry, overwriting the oldest one once the ring is full
+    pub fn record(&mut self, action: AuditAction, actor: Pubkey) -> Result<()> {
+        let entry = push_audit_entry(&mut self.entries, &mut self.next_sequence, action as u8, actor)?;
+
+        emit!(AuditEntryEvent {
+            sequence: entry.sequence,
+            action: entry.action,
+            actor,
+            timestamp: entry.timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Configurable fields are mirrored in `ReserveConfigEvent`; extend
+/// `dump_config` when adding one.
+#[account]
+pub struct Reserve {
+    pub admin: Pubkey,
+    pub min_capital_contribution: u64,
+    pub max_participants: u64,
+    pub total_capital: u64,
+    pub total_contributed: u64,           // Sum of participants' capital_contributed
+    pub participant_count: u64,
+    pub allow_netting: bool,
+    pub min_activity_capital: u64,        // Floor for capital_required on proposals
+    pub deployed_capital: u64,            // Capital escrowed in approved/active activities
+    pub open_activity_count: u64,         // Number of approved/active activities
+    pub max_outcome_age_seconds: i64,     // Oldest outcome complete_activity accepts (0 = no limit)
+    pub risk_exposure: u64,               // Sum of capital_deployed * risk_rating over open activities
+    pub max_risk_exposure: u64,           // Risk budget for approvals (0 = unlimited)
+    pub entry_fee_bps: u16,               // Sadaqah share of a join routed to charity
+    pub charity: Pubkey,                  // Recipient of entry donations
+    pub denylist_page_count: u32,         // Number of DenylistPage accounts
+    pub maturity_grace_seconds: i64,      // Delay after maturity before auto-completion
+    pub private_bridge: Pubkey,           // amana-private PDA allowed to debit private deployments
+    pub private_deployed_capital: u64,    // Capital moved out of free capital by private deployments
+    pub min_validator_stake: u64,         // Stake a validator must keep bonded (0 = validators not required)
+    pub validator_unbonding_seconds: i64, // Delay between unregistering and withdrawing stake
+    pub vesting_cliff_seconds: i64,       // Nothing of a new profit share is claimable before this
+    pub vesting_duration_seconds: i64,    // Linear vesting period of new profit shares (0 = immediate)
+    pub max_qard_outstanding_bps: u16,    // Cap on outstanding Qard as a share of capital incl. Qard (0 = no Qard)
+    pub total_qard_outstanding: u64,      // Qard Hasan lent out and not yet repaid
+    pub completion_bond: u64,             // Bond a reporter posts to complete an activity (0 = none)
+    pub dispute_window_seconds: i64,      // Time after completion during which the bond can be slashed
+    pub status: ReserveStatus,
+    pub min_activity_duration_seconds: i64, // Shortest expected_duration a proposal may declare
+    pub max_activity_duration_seconds: i64, // Longest expected_duration a proposal may declare (0 = no limit)
+    pub distribution_interval_seconds: i64, // Batch SOL profits into scheduled distributions (0 = per activity)
+    pub pending_distribution: u64,        // Accrued profit awaiting distribute_pending
+    pub last_distribution_at: i64,        // Start of the current distribution interval
+    pub distribution_round: u64,          // Number of distributions performed
+    pub pending_proposed_capital: u64,    // Capital requested by proposals awaiting approval
+    pub swap_program: Pubkey,             // Halal-screened venue for profit conversions (default = disabled)
+    pub max_slippage_bps: u16,            // Tolerated shortfall of a conversion against its quote
+    pub delegation_window_seconds: i64,   // Window over which proposals are counted (0 = not tracked)
+    pub delegation_threshold: u32,        // Proposals per window that recommend ER delegation
+    pub window_started_at: i64,
+    pub window_proposal_count: u32,
+    pub max_total_capital: u64,           // Cap on total_contributed (0 = unlimited)
+    pub capacity_governed: bool,          // Capacity increases require an executed DAO proposal
+    pub next_capacity_proposal_id: u64,   // Lowest DAO proposal id set_capacity still accepts
+    pub dormancy_seconds: i64,            // Time without a claim before a SOL share may be swept (0 = never)
+    pub sweep_reversal_seconds: i64,      // Time a swept share stays reclaimable by its participant
+    pub guarantee_coverage_bps: u16,      // Share of a SOL loss drawn from the guarantee fund (0 = none)
+    pub name: [u8; 32],                   // Zero-padded id the reserve is seeded by (zero = default reserve)
+    pub metadata_uri: String,             // Off-chain description for front-ends
+    pub proposal_expiry_seconds: i64,     // Time a proposal may await a decision before anyone can expire it (0 = never)
+    pub distribution_mode: DistributionMode, // How profit reaches participants; always outcome-contingent
+    pub activity_fee: u64,                // Lamports a proposer pays into the compliance fund (0 = none)
+    pub tier_configs: [TierConfig; PARTICIPANT_TIER_COUNT], // Join bounds and lockup, indexed by ParticipantTier
+    pub allow_in_kind: bool,              // Profit may be distributed as an asset via distribute_in_kind
+    pub min_solvency_bps: u16,            // Solvency ratio below which the reserve auto-pauses (0 = disabled)
+    pub is_paused: bool,                  // Set on a solvency breach; cleared by reset_after_solvency_fix
+    pub unique_activity_ids: bool,        // propose_activity rejects ids found in closed_id_filter
+    pub closed_id_filter: [u8; CLOSED_ID_FILTER_BYTES], // Bloom filter of recently closed activity ids
+    pub closed_id_count: u64,             // Ids recorded in closed_id_filter
+    pub previous_closed_id_filter: [u8; CLOSED_ID_FILTER_BYTES], // closed_id_filter before its last rotation
+    pub management_fee_bps: u16,          // Share of each SOL profit routed to the treasury (0 = none)
+    pub withdrawal_cooldown_seconds: i64, // Wait between request_withdrawal and settle_withdrawal (0 = one-step withdrawals allowed)
+    pub mudarabah_limited_liability: bool, // Never charge a participant more SOL loss than their capital
+    pub settlement_count: u64,            // Settled outcomes participants allocate or realize in order
+    pub profit_per_unit: u128,            // Cumulative scheduled profit per unit of capital, scaled by PROFIT_INDEX_SCALE
+    pub released_per_unit: u128,          // profit_per_unit as of the last distribute_pending
+    pub fees_owed: u64,                   // Management fees booked at completion and not yet collected
+    pub sharia_board: Pubkey,             // Key that may approve Qard extensions besides the admin (default = none)
+    pub pending_withdrawal_count: u64,    // Participants with a request_withdrawal not yet settled or cancelled
+    pub max_proposal_bps: u16,            // Largest share of free capital one proposal may request
+    pub distribution_accrued: u64,        // Scheduled profit ever accrued through accrue_distribution
+    pub distribution_paid: u64,           // Scheduled profit paid to participants so far
+    pub distribution_outstanding: u64,    // Scheduled profit not yet paid, kept apart from distribution_paid
+    pub is_initialized: bool,
+    pub bump: u8,
+}
+
+impl Reserve {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 2 + 32 + 4 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 2 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 2 + 8 + 4 + 8 + 4 + 8 + 1 + 8 + 8 + 8 + 2 + 32 + 4 + MAX_METADATA_URI_LEN + 8 + 1 + 8 + TierConfig::SPACE * PARTICIPANT_TIER_COUNT + 1 + 2 + 1 + 1 + CLOSED_ID_FILTER_BYTES + 8 + CLOSED_ID_FILTER_BYTES + 2 + 8 + 1 + 8 + 16 + 16 + 8 + 32 + 8 + 2 + 8 + 8 + 8 + 1 + 1;
+
+    pub fn tier_config(&self, tier: ParticipantTier) -> TierConfig {
+        self.tier_configs[tier.index()]
+    }
+
+    /// Bit positions an activity id occupies in `closed_id_filter`
+    fn closed_id_bits(activity_id: &[u8; 32]) -> [usize; CLOSED_ID_FILTER_HASHES] {
+        let digest = anchor_lang::solana_program::hash::hashv(&[b"closed_id", activity_id.as_ref()])
+            .to_bytes();
+        let mut bits = [0usize; CLOSED_ID_FILTER_HASHES];
+        for (i, bit) in bits.iter_mut().enumerate() {
+            let word = u16::from_le_bytes([digest[2 * i], digest[2 * i + 1]]) as usize;
+            *bit = word % (CLOSED_ID_FILTER_BYTES * 8);
+        }
+        bits
+    }
+
+    /// Record a closed activity id in `closed_id_filter`
+    ///
+    /// Once the filter holds `CLOSED_ID_FILTER_CAPACITY` ids it becomes
+    /// `previous_closed_id_filter` and a fresh one is started, so the false
+    /// positive rate stays bounded however many activities close. Returns
+    /// whether the filter was rotated.
+    pub fn record_closed_id(&mut self, activity_id: &[u8; 32]) -> Result<bool> {
+        let rotated = self.closed_id_count >= CLOSED_ID_FILTER_CAPACITY;
+        if rotated {
+            self.previous_closed_id_filter = self.closed_id_filter;
+            self.closed_id_filter = [0; CLOSED_ID_FILTER_BYTES];
+            self.closed_id_count = 0;
+        }
+
+        for bit in Self::closed_id_bits(activity_id) {
+            self.closed_id_filter[bit / 8] |= 1 << (bit % 8);
+        }
+        self.closed_id_count = self.closed_id_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        Ok(rotated)
+    }
+
+    /// Whether an id may have been closed before
+    ///
+    /// Both the current and the previous filter are checked, so an id is
+    /// always caught for at least `CLOSED_ID_FILTER_CAPACITY` closures after
+    /// its own, and for up to twice that. A Bloom filter has no false
+    /// negatives within that window, but a fresh id can collide with closed
+    /// ones: with 2048 bits and 3 hashes, about 1.6% per full filter. A
+    /// rejected proposer simply picks another id.
+    pub fn may_have_closed_id(&self, activity_id: &[u8; 32]) -> bool {
+        let bits = Self::closed_id_bits(activity_id);
+        let in_filter = |filter: &[u8; CLOSED_ID_FILTER_BYTES]| {
+            bits.iter().all(|bit| filter[bit / 8] & (1 << (bit % 8)) != 0)
+        };
+        in_filter(&self.closed_id_filter) || in_filter(&self.previous_closed_id_filter)
+    }
+
+    /// Seed distinguishing this reserve and its accounts from other reserves
+    pub fn id_seed(&self) -> &[u8] {
+        name_seed(&self.name)
+    }
+
+    /// Part of free capital owned by a participant with `capital_contributed`
+    ///
+    /// `total_capital` already excludes capital escrowed in activities
+    /// (`deployed_capital`), deployed privately or lent as Qard, so this is
+    /// `total_capital * capital_contributed / total_contributed`. Withdrawals
+    /// are capped here so no participant takes more than their pro-rata part
+    /// of the liquidity left while capital is deployed.
+    pub fn free_capital_share(&self, capital_contributed: u64) -> Result<u64> {
+        if self.total_contributed == 0 {
+            return Ok(0);
+        }
+        let share = (self.total_capital as u128)
+            .checked_mul(capital_contributed as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / (self.total_contributed as u128);
+        u64::try_from(share).map_err(|_| error!(AmanaError::MathOverflow))
+    }
+
+    /// Free capital plus everything escrowed, deployed privately or lent
+    pub fn gross_capital(&self) -> Result<u64> {
+        let gross = self.total_capital
+            .checked_add(self.deployed_capital)
+            .and_then(|v| v.checked_add(self.private_deployed_capital))
+            .and_then(|v| v.checked_add(self.total_qard_outstanding))
+            .ok_or(AmanaError::MathOverflow)?;
+        Ok(gross)
+    }
+
+    /// Give a settled outcome the next place in the settlement sequence
+    ///
+    /// Every current participant must allocate or realize it, in sequence
+    /// order, before their capital may change again, so each share is
+    /// computed on the capital they held at settlement.
+    pub fn sequence_settlement(&mut self, outcome: &mut impl Sequenced) -> Result<()> {
+        self.settlement_count = self.settlement_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        let (seq, unapplied) = outcome.sequence_mut();
+        *seq = self.settlement_count;
+        *unapplied = self.participant_count;
+        Ok(())
+    }
+
+    /// Share scheduled profit across the capital contributed right now
+    ///
+    /// Moves the cumulative `profit_per_unit` index, which participants bring
+    /// their own accrual up to lazily with `Participant::accrue_profit`.
+    pub fn accrue_distribution(&mut self, profit: u64) -> Result<()> {
+        self.pending_distribution = self.pending_distribution
+            .checked_add(profit)
+            .ok_or(AmanaError::MathOverflow)?;
+        self.distribution_accrued = self.distribution_accrued
+            .checked_add(profit)
+            .ok_or(AmanaError::MathOverflow)?;
+        self.distribution_outstanding = self.distribution_outstanding
+            .checked_add(profit)
+            .ok_or(AmanaError::MathOverflow)?;
+        if self.total_contributed > 0 {
+            let step = (profit as u128)
+                .checked_mul(PROFIT_INDEX_SCALE)
+                .ok_or(AmanaError::MathOverflow)?
+                / (self.total_contributed as u128);
+            self.profit_per_unit = self.profit_per_unit
+                .checked_add(step)
+                .ok_or(AmanaError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Record scheduled profit paid to one participant and audit the ledger
+    pub fn pay_distribution(&mut self, amount: u64) -> Result<()> {
+        self.distribution_paid = self.distribution_paid
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        self.distribution_outstanding = self.distribution_outstanding.saturating_sub(amount);
+        self.audit_distributions()
+    }
+
+    /// Check every unit of scheduled profit accrued is paid or still outstanding
+    pub fn audit_distributions(&self) -> Result<()> {
+        audit_distribution(
+            self.distribution_accrued,
+            self.distribution_paid,
+            self.distribution_outstanding,
+        )
+    }
+
+    /// Check that profit is only ever allocated from realized outcomes
+    pub fn require_outcome_contingent(&self) -> Result<()> {
+        require!(
+            self.distribution_mode.is_outcome_contingent(),
+            AmanaError::GuaranteedReturnForbidden
+        );
+        Ok(())
+    }
+
+    /// Check that `amount` more contributed capital stays within `max_total_capital`
+    pub fn require_capacity(&self, amount: u64) -> Result<()> {
+        if self.max_total_capital > 0 {
+            let contributed = self.total_contributed
+                .checked_add(amount)
+                .ok_or(AmanaError::MathOverflow)?;
+            require!(
+                contributed <= self.max_total_capital,
+                AmanaError::CapacityExceeded
+            );
+        }
+        Ok(())
+    }
+
+    /// Proposals counted in the window that is current at `now`
+    pub fn proposals_in_window(&self, now: i64) -> u32 {
+        if self.delegation_window_seconds == 0
+            || now >= self.window_started_at.saturating_add(self.delegation_window_seconds)
+        {
+            0
+        } else {
+            self.window_proposal_count
+        }
+    }
+
+    /// Whether proposal volume at `now` calls for delegating to the ER
+    pub fn delegation_recommended(&self, now: i64) -> bool {
+        self.delegation_threshold > 0 && self.proposals_in_window(now) >= self.delegation_threshold
+    }
+
+    /// Count a proposal made at `now`, starting a new window once the last has lapsed
+    pub fn record_proposal(&mut self, now: i64) -> Result<()> {
+        if self.delegation_window_seconds == 0 {
+            return Ok(());
+        }
+        if now >= self.window_started_at.saturating_add(self.delegation_window_seconds) {
+            self.window_started_at = now;
+            self.window_proposal_count = 0;
+        }
+        self.window_proposal_count = self.window_proposal_count
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct Participant {
+    pub agent: Pubkey,
+    pub capital_contributed: u64,
+    pub profit_share: u64,
+    pub loss_share: u64,
+    pub pending_profit: u64,    // Allocated profit not yet claimed or compounded
+    pub is_active: bool,
+    pub is_frozen: bool,        // Set while the agent is on the denylist
+    pub joined_at: i64,
+    pub preferred_mint: Pubkey, // Mint token profit is converted to on claim (default = none)
+    pub tier: ParticipantTier,
+    pub locked_until: i64,      // Capital cannot be withdrawn before this time
+    pub pending_withdrawal: u64, // Requested via request_withdrawal, not yet settled (0 = none)
+    pub withdrawal_requested_at: i64,
+    pub settled_through: u64,   // Last settlement_seq allocated or realized; capital is fixed while behind
+    pub loss_carried: u64,      // Loss beyond capital, taken from later profit (limited liability off)
+    pub profit_index: u128,     // Reserve profit_per_unit the accrued amounts are brought up to
+    pub accrued_released: u64,  // Scheduled profit released and claimable via claim_distribution
+    pub accrued_unreleased: u64, // Scheduled profit awaiting the next distribute_pending
+    pub bump: u8,
+}
+
+impl Participant {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 8 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 1;
+
+    pub fn require_unlocked(&self, now: i64) -> Result<()> {
+        require!(now >= self.locked_until, AmanaError::PositionLocked);
+        Ok(())
+    }
+
+    /// Check that every settled outcome has been applied, so capital may change
+    pub fn require_settled(&self, reserve: &Reserve) -> Result<()> {
+        require!(
+            self.settled_through == reserve.settlement_count,
+            AmanaError::SettlementsPending
+        );
+        Ok(())
+    }
+
+    /// Apply a settled outcome, which must be next in sequence
+    pub fn apply_settlement(&mut self, outcome: &mut impl Sequenced) -> Result<()> {
+        let next = self.settled_through
+            .checked_add(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        let (seq, unapplied) = outcome.sequence_mut();
+        require!(*seq == next, AmanaError::SettlementOutOfOrder);
+
+        self.settled_through = next;
+        *unapplied = unapplied
+            .checked_sub(1)
+            .ok_or(AmanaError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Bring scheduled profit up to the reserve's current index
+    ///
+    /// Must run before `capital_contributed` changes, so every step of the
+    /// index is shared on the capital held when that profit accrued. The part
+    /// of the step already released by `distribute_pending` becomes claimable.
+    pub fn accrue_profit(&mut self, reserve: &Reserve) -> Result<()> {
+        // A release at or after the last accrual frees everything accrued before it
+        if reserve.released_per_unit >= self.profit_index && self.accrued_unreleased > 0 {
+            self.accrued_released = self.accrued_released
+                .checked_add(self.accrued_unreleased)
+                .ok_or(AmanaError::MathOverflow)?;
+            self.accrued_unreleased = 0;
+        }
+
+        let index = reserve.profit_per_unit;
+        if index > self.profit_index {
+            let released_to = reserve.released_per_unit.clamp(self.profit_index, index);
+            let released = self.profit_between(self.profit_index, released_to)?;
+            let unreleased = self.profit_between(released_to, index)?;
+            self.accrued_released = self.accrued_released
+                .checked_add(released)
+                .ok_or(AmanaError::MathOverflow)?;
+            self.accrued_unreleased = self.accrued_unreleased
+                .checked_add(unreleased)
+                .ok_or(AmanaError::MathOverflow)?;
+            self.profit_index = index;
+        }
+        Ok(())
+    }
+
+    /// Scheduled profit not yet claimed, released or not
+    pub fn unclaimed_distribution(&self, reserve: &Reserve) -> Result<u64> {
+        self.profit_between(self.profit_index, reserve.profit_per_unit.max(self.profit_index))?
+            .checked_add(self.accrued_released)
+            .and_then(|v| v.checked_add(self.accrued_unreleased))
+            .ok_or(error!(AmanaError::MathOverflow))
+    }
+
+    /// Profit the current capital earned while the index moved from `from` to `to`
+    fn profit_between(&self, from: u128, to: u128) -> Result<u64> {
+        let profit = (to - from)
+            .checked_mul(self.capital_contributed as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / PROFIT_INDEX_SCALE;
+        u64::try_from(profit).map_err(|_| error!(AmanaError::MathOverflow))
+    }
+
+    /// Add newly allocated SOL profit, first settling any carried loss out of it
+    ///
+    /// Returns the profit left for the participant.
+    pub fn book_profit(&mut self, amount: u64) -> Result<u64> {
+        let offset = amount.min(self.loss_carried);
+        self.loss_carried -= offset;
+        self.loss_share = self.loss_share
+            .checked_add(offset)
+            .ok_or(AmanaError::MathOverflow)?;
+
+        let amount = amount - offset;
+        self.profit_share = self.profit_share
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        self.pending_profit = self.pending_profit
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        Ok(amount)
+    }
+}
+
+/// Join bounds and lockup of one participant tier
+///
+/// Only gates entry and withdrawal timing; profit and loss are still shared
+/// by capital alone, whatever the tier.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct TierConfig {
+    pub min_contribution: u64,  // Checked on top of the reserve-wide minimum
+    pub max_contribution: u64,  // Cap on a participant's capital (0 = unlimited)
+    pub lockup_seconds: i64,    // Time after joining before capital can be withdrawn
+}
+
+impl TierConfig {
+    pub const SPACE: usize = 8 + 8 + 8;
+
+    pub fn check_contribution(&self, amount: u64) -> Result<()> {
+        require!(amount >= self.min_contribution, AmanaError::InsufficientContribution);
+        self.check_contribution_cap(amount)
+    }
+
+    pub fn check_contribution_cap(&self, capital: u64) -> Result<()> {
+        require!(
+            self.max_contribution == 0 || capital <= self.max_contribution,
+            AmanaError::TierContributionExceeded
+        );
+        Ok(())
+    }
+}
+
+#[account]
+pub struct DenylistPage {
+    pub page: u32,
+    pub entries: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl DenylistPage {
+    pub const SPACE: usize = 8 + 4 + 4 + MAX_DENYLIST_PAGE_ENTRIES * 32 + 1;
+}
+
+#[account]
+pub struct Activity {
+    pub activity_id: [u8; 32],
+    pub initiator: Pubkey,
+    pub capital_required: u64,
+    pub capital_deployed: u64,
+    pub risk_rating: u8,        // 1 (lowest) to 5 (highest)
+    pub expected_duration: i64, // Seconds from approval to maturity (0 = open-ended)
+    pub maturity_at: i64,       // Set on approval when expected_duration > 0
+    pub status: ActivityStatus,
+    pub created_at: i64,
+    pub completed_at: i64,
+    pub outcome: i64,
+    pub is_validated: bool,
+    pub is_settled: bool,       // False while the outcome awaits netting
+    pub settled_outcome: i64,   // Outcome actually booked to the pool
+    pub distribution_base: u64, // Total contributed capital at settlement
+    pub parent_id: [u8; 32],    // Zero unless created by split_activity
+    pub settlement_mint: Pubkey, // Mint the outcome settles in (default = SOL)
+    pub approved_at: i64,       // Zero until approved
+    pub settlement_seq: u64,    // Position in the reserve's settlement sequence (0 = nothing to apply)
+    pub unapplied_count: u64,   // Participants who have yet to allocate or realize the outcome
+    pub allocated: u64,         // Settled outcome allocated or realized so far, in the settlement mint
+    pub unallocated: u64,       // Settled outcome not yet allocated or realized, kept apart from allocated
+    pub bump: u8,
+}
+
+impl Activity {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1 + 8 + 8 + 1 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Hand one participant's share of the settled outcome out and audit it
+    ///
+    /// `allocated` and `unallocated` are moved separately, so a share past
+    /// what is left unbalances them and fails the audit.
+    pub fn allocate_outcome(&mut self, amount: u64) -> Result<()> {
+        self.allocated = self.allocated
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        self.unallocated = self.unallocated.saturating_sub(amount);
+        audit_distribution(self.settled_outcome.unsigned_abs(), self.allocated, self.unallocated)
+    }
+}
+
+/// A settled outcome participants apply one at a time, in sequence
+pub trait Sequenced {
+    /// `(settlement_seq, unapplied_count)`
+    fn sequence_mut(&mut self) -> (&mut u64, &mut u64);
+}
+
+impl Sequenced for Activity {
+    fn sequence_mut(&mut self) -> (&mut u64, &mut u64) {
+        (&mut self.settlement_seq, &mut self.unapplied_count)
+    }
+}
+
+impl Sequenced for InKindDistribution {
+    fn sequence_mut(&mut self) -> (&mut u64, &mut u64) {
+        (&mut self.settlement_seq, &mut self.unapplied_count)
+    }
+}
+
+/// Profit returned as an asset and shared pro-rata by capital
+#[account]
+pub struct InKindDistribution {
+    pub distribution_id: [u8; 32],
+    pub mint: Pubkey,
+    pub amount: u64,            // Asset units placed in the vault
+    pub distribution_base: u64, // total_contributed when distributed
+    pub claimed: u64,
+    pub unclaimed: u64,         // Units not yet claimed, kept apart from claimed
+    pub created_at: i64,
+    pub settlement_seq: u64,    // Position in the reserve's settlement sequence
+    pub unapplied_count: u64,   // Participants yet to claim or skip it
+    pub bump: u8,
+}
+
+impl InKindDistribution {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+/// A participant's claimed share of one in-kind distribution
+#[account]
+pub struct InKindEntitlement {
+    pub agent: Pubkey,
+    pub distribution_id: [u8; 32],
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+impl InKindEntitlement {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct ParticipantActivityShare {
+    pub agent: Pubkey,
+    pub activity_id: [u8; 32],
+    pub amount: u64,            // Profit allocated to the participant
+    pub claimed: u64,           // Portion already claimed or compounded
+    pub allocated_at: i64,
+    pub mint: Pubkey,           // Settlement mint of the activity (default = SOL)
+    pub vesting: VestingSchedule,
+    pub last_claimed_at: i64,   // Allocation or latest claim; dormancy runs from here
+    pub swept_amount: u64,      // Dormant profit swept and awaiting release to charity
+    pub sweep_releasable_at: i64, // End of the window in which a sweep can be reversed
+    pub bump: u8,
+}
+
+impl ParticipantActivityShare {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 32 + VestingSchedule::SPACE + 8 + 8 + 8 + 1;
+
+    /// Vested but not yet claimed part of the share at `now`
+    pub fn claimable(&self, now: i64) -> Result<u64> {
+        let vested = self.vesting.vested(self.amount, now)?;
+        Ok(vested.saturating_sub(self.claimed))
+    }
+
+    /// Book `amount` as claimed at `now`, restarting the dormancy clock
+    pub fn record_claim(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.claimed = self.claimed
+            .checked_add(amount)
+            .ok_or(AmanaError::MathOverflow)?;
+        self.last_claimed_at = now;
+        Ok(())
+    }
+}
+
+/// Record that a participant's share of an activity loss has been applied
+#[account]
+pub struct LossRealization {
+    pub agent: Pubkey,
+    pub activity_id: [u8; 32],
+    pub mint: Pubkey,           // Settlement mint of the loss (default = SOL)
+    pub amount: u64,
+    pub realized_at: i64,
+    pub bump: u8,
+}
+
+impl LossRealization {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Release schedule of an already-earned profit share
+///
+/// Vesting only defers when realized profit becomes claimable; the amount
+/// never grows with time, so no return accrues on the deferral.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct VestingSchedule {
+    pub start: i64,
+    pub cliff_seconds: i64,     // Nothing vests before start + cliff
+    pub duration_seconds: i64,  // Fully vested at start + duration (0 = immediately)
+}
+
+impl VestingSchedule {
+    pub const SPACE: usize = 8 + 8 + 8;
+
+    /// Portion of `total` vested at `now`, linear from `start` after the cliff
+    pub fn vested(&self, total: u64, now: i64) -> Result<u64> {
+        if self.duration_seconds == 0 {
+            return Ok(total);
+        }
+
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff_seconds {
+            return Ok(0);
+        }
+        if elapsed >= self.duration_seconds {
+            return Ok(total);
+        }
+
+        let vested = (total as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(AmanaError::MathOverflow)?
+            / self.duration_seconds as u128;
+        Ok(vested as u64)
+    }
+}
+
+/// A staked validator; the stake is held as lamports in this PDA
+#[account]
+pub struct Validator {
+    pub authority: Pubkey,
+    pub stake: u64,
+    pub registered_at: i64,
+    pub unbonding_ends_at: i64, // 0 while bonded
+    pub bump: u8,
+}
+
+impl Validator {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 1;
+
+    /// Whether the validator is still bonded with at least `min_stake`
+    pub fn is_bonded(&self, min_stake: u64) -> bool {
+        self.unbonding_ends_at == 0 && self.stake >= min_stake
+    }
+}
+
+/// A key allowed to commit and undelegate the reserve besides the admin
+#[account]
+pub struct Operator {
+    pub operator: Pubkey,
+    pub is_authorized: bool,
+    pub bump: u8,
+}
+
+impl Operator {
+    pub const SPACE: usize = 8 + 32 + 1 + 1;
+}
+
+/// A key allowed to deploy capital through the private bridge besides the admin
+#[account]
+pub struct PrivateDeployer {
+    pub deployer: Pubkey,
+    pub is_authorized: bool,
+    pub bump: u8,
+}
+
+impl PrivateDeployer {
+    pub const SPACE: usize = 8 + 32 + 1 + 1;
+}
+
+/// A key allowed to complete activities against the guarantee fund besides the admin
+#[account]
+pub struct Reporter {
+    pub reporter: Pubkey,
+    pub is_authorized: bool,
+    pub bump: u8,
+}
+
+impl Reporter {
+    pub const SPACE: usize = 8 + 32 + 1 + 1;
+}
+
+/// An interest-free Qard Hasan loan from the reserve to one borrower
+#[account]
+pub struct QardLoan {
+    pub borrower: Pubkey,
+    pub outstanding: u64,
+    pub issued_at: i64,
+    pub due_at: i64,            // 0 = no due date
+    pub requested_due_at: i64,  // Extension awaiting approval (0 = none)
+    pub extension_count: u32,
+    pub bump: u8,
+}
+
+impl QardLoan {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 4 + 1;
+
+    /// Past a due date with something still owed
+    pub fn is_overdue(&self, now: i64) -> bool {
+        self.outstanding > 0 && self.due_at != 0 && now > self.due_at
+    }
+}
+
+/// `QardLoan` as stored before due dates; read only by `migrate_qard_loan`
+#[derive(AnchorDeserialize)]
+pub struct LegacyQardLoan {
+    pub borrower: Pubkey,
+    pub outstanding: u64,
+    pub issued_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyQardLoan {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+/// Bond posted by the reporter of an activity's outcome
+#[account]
+pub struct CompletionBond {
+    pub activity_id: [u8; 32],
+    pub reporter: Pubkey,
+    pub amount: u64,
+    pub completed_at: i64,  // 0 until the activity is completed
+    pub bump: u8,
+}
+
+impl CompletionBond {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Management fees skimmed from SOL profit, held apart from participant capital
+#[account]
+pub struct Treasury {
+    pub balance: u64,         // Fees not yet withdrawn; the account's rent is excluded
+    pub total_collected: u64,
+    pub total_withdrawn: u64,
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub const SPACE: usize = 8 + 8 + 8 + 8 + 1;
+}
+
+/// Donated lamports that cover part of each SOL loss
+#[account]
+pub struct GuaranteeFund {
+    pub balance: u64,         // Donations not yet drawn; the account's rent is excluded
+    pub total_donated: u64,
+    pub total_covered: u64,
+    pub bump: u8,
+}
+
+/// Activity fees held to pay for compliance checks and validation
+#[account]
+pub struct ComplianceFund {
+    pub balance: u64,         // Fees not yet paid out; the account's rent is excluded
+    pub total_collected: u64,
+    pub total_paid: u64,
+    pub bump: u8,
+}
+
+impl ComplianceFund {
+    pub const SPACE: usize = 8 + 8 + 8 + 8 + 1;
+}
+
+impl GuaranteeFund {
+    pub const SPACE: usize = 8 + 8 + 8 + 8 + 1;
+
+    /// Draw `coverage_bps` of `loss`, limited to the balance, and return it
+    pub fn cover(&mut self, loss: u64, coverage_bps: u16) -> Result<u64> {
+        let share = ((loss as u128) * (coverage_bps as u128) / 10000) as u64;
+        let covered = share.min(self.balance);
+        self.balance -= covered;
+        self.total_covered = self.total_covered
+            .checked_add(covered)
+            .ok_or(AmanaError::MathOverflow)?;
+        Ok(covered)
+    }
+}
+
+/// Books of one settlement mint, separate from the reserve's SOL capital
+#[account]
+pub struct MintVault {
+    pub mint: Pubkey,
+    pub vault: Pubkey,          // Token account holding the mint's settlements
+    pub booked_balance: u64,    // Settled profit not yet claimed
+    pub realized_loss: u64,     // Cumulative token-settled losses
+    pub bump: u8,
+}
+
+impl MintVault {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Admin-set rate a profit conversion's minimum output is derived from
+#[account]
+pub struct ConversionRate {
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub rate: u64,              // to_mint units per CONVERSION_RATE_SCALE from_mint units
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl ConversionRate {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct ParticipantDelegate {
+    pub agent: Pubkey,
+    pub delegate: Pubkey,
+    pub scopes: u8,             // Bitmask of DELEGATE_SCOPE_* flags
+    pub expires_at: i64,        // 0 = no expiry
+    pub bump: u8,
+}
+
+impl ParticipantDelegate {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 1;
+}
+
+/// An authorized indexer's progress through the reserve's audited events
+#[account]
+pub struct IndexCursor {
+    pub indexer: Pubkey,
+    pub next_sequence: u64,     // Every AuditEntryEvent below this has been processed
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl IndexCursor {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct RecurringContribution {
+    pub agent: Pubkey,
+    pub amount: u64,            // Installment moved per execution
+    pub interval_seconds: i64,
+    pub next_due_at: i64,
+    pub bump: u8,
+}
+
+impl RecurringContribution {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct InitiatorActivityIndex {
+    pub initiator: Pubkey,
+    pub activity_ids: Vec<[u8; 32]>,   // Most recent ids, bounded by MAX_INDEXED_ACTIVITIES
+    pub page_count: u32,               // Number of archived overflow pages
+    pub bump: u8,
+}
+
+impl InitiatorActivityIndex {
+    pub const SPACE: usize = 8 + 32 + 4 + 32 * MAX_INDEXED_ACTIVITIES + 4 + 1;
+}
+
+#[account]
+pub struct InitiatorIndexPage {
+    pub initiator: Pubkey,
+    pub page: u32,
+    pub activity_ids: Vec<[u8; 32]>,
+    pub bump: u8,
+}
+
+impl InitiatorIndexPage {
+    pub const SPACE: usize = 8 + 32 + 4 + 4 + 32 * MAX_INDEXED_ACTIVITIES + 1;
+}
+
+// Context structs
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Reserve::SPACE,
+        seeds = [b"reserve"],
+        bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeIfNeeded<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Reserve::SPACE,
+        seeds = [b"reserve"],
+        bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: [u8; 32])]
+pub struct InitializeNamedReserve<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Reserve::SPACE,
+        seeds = [b"reserve", name_seed(&name)],
+        bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAuditLog<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AuditLog::SPACE,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinReserve<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = user,
+        space = Participant::SPACE,
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    /// Entry donation recipient; required when `entry_fee_bps` is non-zero
+    #[account(mut, address = reserve.charity @ AmanaError::InvalidCharity)]
+    pub charity: Option<SystemAccount<'info>>,
+
+    /// The admin, vouching for a tier other than `Retail`
+    #[account(address = reserve.admin @ AmanaError::Unauthorized)]
+    pub tier_approver: Option<Signer<'info>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied,
+        constraint = participant.agent == user.key() @ AmanaError::Unauthorized
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = user,
+        space = Activity::SPACE,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = InitiatorActivityIndex::SPACE,
+        seeds = [b"initiator_index", reserve.id_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub initiator_index: Account<'info, InitiatorActivityIndex>,
+
+    /// Receives the activity fee; required while `activity_fee` is set
+    #[account(
+        mut,
+        seeds = [b"compliance_fund", reserve.id_seed()],
+        bump = compliance_fund.bump
+    )]
+    pub compliance_fund: Option<Account<'info, ComplianceFund>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == authority.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity.activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    /// Bonded validator; required while `min_validator_stake` is set
+    #[account(
+        seeds = [b"validator", reserve.id_seed(), validator.authority.as_ref()],
+        bump = validator.bump
+    )]
+    pub validator: Option<Account<'info, Validator>>,
+
+    pub validator_authority: Option<Signer<'info>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RejectActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity.activity_id.as_ref()],
+        bump = activity.bump,
+        constraint = authority.key() == reserve.admin
+            || authority.key() == activity.initiator @ AmanaError::Unauthorized
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    /// Reserve admin or the activity's initiator
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct SplitActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump,
+        constraint = activity.initiator == user.key() @ AmanaError::Unauthorized
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity.activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    /// Books of the activity's settlement mint; required for token settlement
+    #[account(
+        mut,
+        seeds = [b"mint_vault", reserve.id_seed(), activity.settlement_mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Option<Account<'info, MintVault>>,
+
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Bonded validator; required while `min_validator_stake` is set
+    #[account(
+        seeds = [b"validator", reserve.id_seed(), validator.authority.as_ref()],
+        bump = validator.bump
+    )]
+    pub validator: Option<Account<'info, Validator>>,
+
+    pub validator_authority: Option<Signer<'info>>,
+
+    /// Reporter's bond; required while `completion_bond` is set
+    #[account(
+        mut,
+        seeds = [
+            b"completion_bond",
+            reserve.id_seed(),
+            activity.activity_id.as_ref(),
+            completion_bond.reporter.as_ref()
+        ],
+        bump = completion_bond.bump
+    )]
+    pub completion_bond: Option<Account<'info, CompletionBond>>,
+
+    /// Reporter who posted `completion_bond`; required with it, and to draw on
+    /// the guarantee fund, where it must be the admin or an authorized reporter
+    pub reporter: Option<Signer<'info>>,
+
+    /// Required to draw on the guarantee fund unless the reporter is the admin
+    #[account(
+        seeds = [b"reporter", reserve.id_seed(), reporter_record.reporter.as_ref()],
+        bump = reporter_record.bump
+    )]
+    pub reporter_record: Option<Account<'info, Reporter>>,
+
+    /// Guarantee fund; required to settle a SOL loss while `guarantee_coverage_bps` is set
+    #[account(
+        mut,
+        seeds = [b"guarantee_fund", reserve.id_seed()],
+        bump = guarantee_fund.bump
+    )]
+    pub guarantee_fund: Option<Account<'info, GuaranteeFund>>,
+}
+
+#[derive(Accounts)]
+pub struct NetSettleInitiator<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReserveConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCapacity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    /// amana-dao's `Dao` PDA, signing through `apply_capacity_proposal`.
+    /// Required for increases while capacity is governed.
+    #[account(
+        seeds = [b"dao"],
+        bump,
+        seeds::program = AMANA_DAO_PROGRAM_ID
+    )]
+    pub dao: Option<Signer<'info>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct AuthorizeOperator<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Operator::SPACE,
+        seeds = [b"operator", reserve.id_seed(), operator.as_ref()],
+        bump
+    )]
+    pub operator: Account<'info, Operator>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeOperator<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"operator", reserve.id_seed(), operator.operator.as_ref()],
+        bump = operator.bump
+    )]
+    pub operator: Account<'info, Operator>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGuaranteeFund<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = GuaranteeFund::SPACE,
+        seeds = [b"guarantee_fund", reserve.id_seed()],
+        bump
+    )]
+    pub guarantee_fund: Account<'info, GuaranteeFund>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundGuarantee<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"guarantee_fund", reserve.id_seed()],
+        bump = guarantee_fund.bump
+    )]
+    pub guarantee_fund: Account<'info, GuaranteeFund>,
+
+    #[account(mut)]
+    pub donor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeComplianceFund<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ComplianceFund::SPACE,
+        seeds = [b"compliance_fund", reserve.id_seed()],
+        bump
+    )]
+    pub compliance_fund: Account<'info, ComplianceFund>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PayComplianceKeeper<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"compliance_fund", reserve.id_seed()],
+        bump = compliance_fund.bump
+    )]
+    pub compliance_fund: Account<'info, ComplianceFund>,
+
+    /// CHECK: Only receives lamports
+    #[account(mut)]
+    pub keeper: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Treasury::SPACE,
+        seeds = [b"treasury", reserve.id_seed()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CollectManagementFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", reserve.id_seed()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", reserve.id_seed()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Only receives lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMintVault<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = MintVault::SPACE,
+        seeds = [b"mint_vault", reserve.id_seed(), mint.key().as_ref()],
+        bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = mint_vault,
+        seeds = [b"vault", reserve.id_seed(), mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct SetSettlementMint<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump,
+        constraint = activity.initiator == initiator.key() @ AmanaError::Unauthorized
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        seeds = [b"mint_vault", reserve.id_seed(), mint_vault.mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    pub initiator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DebitPrivateDeployment<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.private_bridge != Pubkey::default() @ AmanaError::Unauthorized,
+        constraint = reserve.private_bridge == bridge_authority.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    pub bridge_authority: Signer<'info>,
+
+    pub deployer: Signer<'info>,
+
+    /// Required unless the deployer is the reserve admin
+    #[account(
+        seeds = [b"private_deployer", reserve.id_seed(), deployer.key().as_ref()],
+        bump = private_deployer.bump
+    )]
+    pub private_deployer: Option<Account<'info, PrivateDeployer>>,
+}
+
+#[derive(Accounts)]
+#[instruction(deployer: Pubkey)]
+pub struct AuthorizePrivateDeployer<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PrivateDeployer::SPACE,
+        seeds = [b"private_deployer", reserve.id_seed(), deployer.as_ref()],
+        bump
+    )]
+    pub private_deployer: Account<'info, PrivateDeployer>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokePrivateDeployer<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"private_deployer", reserve.id_seed(), private_deployer.deployer.as_ref()],
+        bump = private_deployer.bump
+    )]
+    pub private_deployer: Account<'info, PrivateDeployer>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(reporter: Pubkey)]
+pub struct AuthorizeReporter<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Reporter::SPACE,
+        seeds = [b"reporter", reserve.id_seed(), reporter.as_ref()],
+        bump
+    )]
+    pub reporter_record: Account<'info, Reporter>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeReporter<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"reporter", reserve.id_seed(), reporter_record.reporter.as_ref()],
+        bump = reporter_record.bump
+    )]
+    pub reporter_record: Account<'info, Reporter>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32, agent: Pubkey)]
+pub struct DenyAddress<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = DenylistPage::SPACE,
+        seeds = [b"denylist", reserve.id_seed(), page.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub denylist_page: Account<'info, DenylistPage>,
+
+    /// Existing position of `agent`, frozen when passed
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Option<Account<'info, Participant>>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32, agent: Pubkey)]
+pub struct AllowAddress<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"denylist", reserve.id_seed(), page.to_le_bytes().as_ref()],
+        bump = denylist_page.bump
+    )]
+    pub denylist_page: Account<'info, DenylistPage>,
+
+    /// Existing position of `agent`, unfrozen when passed
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Option<Account<'info, Participant>>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReconcileCapital<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct ExpireProposedActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct AutoCompleteOverdue<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotParticipant<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyParticipantBalance<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+}
+
+#[derive(Accounts)]
+pub struct ComputeDistributionMetrics<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct EstimateWithdrawalTime<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct ListPendingClaims<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+}
+
+#[derive(Accounts)]
+pub struct PollWork<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct ComputeUtilization<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct DumpConfig<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct GetCursor<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(indexer: Pubkey)]
+pub struct AuthorizeIndexer<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = IndexCursor::SPACE,
+        seeds = [b"index_cursor", reserve.id_seed(), indexer.as_ref()],
+        bump
+    )]
+    pub cursor: Account<'info, IndexCursor>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeIndexer<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"index_cursor", reserve.id_seed(), cursor.indexer.as_ref()],
+        bump = cursor.bump
+    )]
+    pub cursor: Account<'info, IndexCursor>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdvanceCursor<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"index_cursor", reserve.id_seed(), indexer.key().as_ref()],
+        bump = cursor.bump
+    )]
+    pub cursor: Account<'info, IndexCursor>,
+
+    #[account(
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub indexer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct VerifyActivityIntegrity<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump
+    )]
+    pub activity: Account<'info, Activity>,
+}
+
+#[derive(Accounts)]
+pub struct ComputePortfolioRisk<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct MergeActivityReports<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct CloseActivity<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump,
+        constraint = activity.initiator == user.key() @ AmanaError::Unauthorized
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        mut,
+        seeds = [b"initiator_index", reserve.id_seed(), user.key().as_ref()],
+        bump = initiator_index.bump
+    )]
+    pub initiator_index: Account<'info, InitiatorActivityIndex>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RollInitiatorIndex<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"initiator_index", reserve.id_seed(), user.key().as_ref()],
+        bump = initiator_index.bump
+    )]
+    pub initiator_index: Account<'info, InitiatorActivityIndex>,
+
+    #[account(
+        init,
+        payer = user,
+        space = InitiatorIndexPage::SPACE,
+        seeds = [
+            b"initiator_index",
+            reserve.id_seed(),
+            user.key().as_ref(),
+            initiator_index.page_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub page: Account<'info, InitiatorIndexPage>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCapital<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        seeds = [b"delegate", reserve.id_seed(), participant.agent.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, ParticipantDelegate>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCapital<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied,
+        constraint = participant.agent == user.key() @ AmanaError::Unauthorized
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_owner: Pubkey)]
+pub struct TransferPosition<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = user,
+        space = Participant::SPACE,
+        seeds = [b"participant", reserve.id_seed(), new_owner.as_ref()],
+        bump
+    )]
+    pub new_participant: Account<'info, Participant>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(address = new_owner @ AmanaError::Unauthorized)]
+    pub new_agent: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveReserve<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// Closed by the handler rather than `close = user`, so a solvency
+    /// breach can pause the reserve without closing the position unpaid
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    /// Dust recipient; required only when the residual is donated
+    #[account(mut, address = reserve.charity @ AmanaError::InvalidCharity)]
+    pub charity: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAndExit<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseReserve<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    /// Receives leftover free capital; required when any is left
+    #[account(mut, address = reserve.charity @ AmanaError::InvalidCharity)]
+    pub charity: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepTreasury<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"treasury", reserve.id_seed()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepGuaranteeFund<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"guarantee_fund", reserve.id_seed()],
+        bump = guarantee_fund.bump
+    )]
+    pub guarantee_fund: Account<'info, GuaranteeFund>,
+
+    #[account(mut, address = reserve.charity @ AmanaError::InvalidCharity)]
+    pub charity: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepMintVault<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"mint_vault", reserve.id_seed(), mint_vault.mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    #[account(mut, address = mint_vault.vault @ AmanaError::InvalidSettlementMint)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint_vault.mint,
+        token::authority = reserve.charity
+    )]
+    pub charity_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct AllocateProfitShare<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ParticipantActivityShare::SPACE,
+        seeds = [b"share", reserve.id_seed(), activity_id.as_ref(), participant.agent.as_ref()],
+        bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct RealizeLoss<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = LossRealization::SPACE,
+        seeds = [b"loss", reserve.id_seed(), activity_id.as_ref(), participant.agent.as_ref()],
+        bump
+    )]
+    pub loss_realization: Account<'info, LossRealization>,
+
+    /// Guarantee fund; absorbs loss beyond capital under limited liability
+    #[account(
+        mut,
+        seeds = [b"guarantee_fund", reserve.id_seed()],
+        bump = guarantee_fund.bump
+    )]
+    pub guarantee_fund: Option<Account<'info, GuaranteeFund>>,
+
+    /// Vault of the activity's settlement mint; required for a token-settled loss
+    #[account(
+        mut,
+        seeds = [b"mint_vault", reserve.id_seed(), activity.settlement_mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Option<Account<'info, MintVault>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributePending<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDistribution<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ParticipantActivityShare::SPACE,
+        seeds = [
+            b"share",
+            reserve.id_seed(),
+            derive_distribution_id(reserve.distribution_round).as_ref(),
+            participant.agent.as_ref()
+        ],
+        bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct ClaimProfit<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), activity_id.as_ref(), participant.agent.as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(
+        seeds = [b"delegate", reserve.id_seed(), participant.agent.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, ParticipantDelegate>>,
+
+    /// CHECK: Payout destination, pinned to the participant's own key
+    #[account(mut, address = participant.agent @ AmanaError::Unauthorized)]
+    pub agent: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct ClaimProfitToken<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), activity_id.as_ref(), participant.agent.as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_vault", reserve.id_seed(), share.mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    #[account(mut, address = mint_vault.vault @ AmanaError::InvalidSettlementMint)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Payout destination, pinned to a token account the participant owns
+    #[account(
+        mut,
+        token::mint = share.mint,
+        token::authority = participant.agent
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"delegate", reserve.id_seed(), participant.agent.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, ParticipantDelegate>>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(distribution_id: [u8; 32])]
+pub struct DistributeInKind<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = InKindDistribution::SPACE,
+        seeds = [b"in_kind", reserve.id_seed(), distribution_id.as_ref()],
+        bump
+    )]
+    pub distribution: Account<'info, InKindDistribution>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_vault", reserve.id_seed(), mint_vault.mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    #[account(mut, address = mint_vault.vault @ AmanaError::InvalidSettlementMint)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Admin-owned token account the asset is moved from
+    #[account(
+        mut,
+        token::mint = mint_vault.mint,
+        token::authority = admin
+    )]
+    pub source: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(distribution_id: [u8; 32])]
+pub struct ClaimInKind<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"in_kind", reserve.id_seed(), distribution_id.as_ref()],
+        bump = distribution.bump
+    )]
+    pub distribution: Account<'info, InKindDistribution>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = InKindEntitlement::SPACE,
+        seeds = [b"in_kind_claim", reserve.id_seed(), distribution_id.as_ref(), participant.agent.as_ref()],
+        bump
+    )]
+    pub entitlement: Account<'info, InKindEntitlement>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_vault", reserve.id_seed(), distribution.mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    #[account(mut, address = mint_vault.vault @ AmanaError::InvalidSettlementMint)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Payout destination, pinned to a token account the participant owns
+    #[account(
+        mut,
+        token::mint = distribution.mint,
+        token::authority = participant.agent
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"delegate", reserve.id_seed(), participant.agent.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, ParticipantDelegate>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPreferredMint<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct ClaimProfitTokenConverted<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.swap_program != Pubkey::default() @ AmanaError::ConversionDisabled
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), activity_id.as_ref(), participant.agent.as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_vault", reserve.id_seed(), share.mint.as_ref()],
+        bump = mint_vault.bump
+    )]
+    pub mint_vault: Account<'info, MintVault>,
+
+    #[account(mut, address = mint_vault.vault @ AmanaError::InvalidSettlementMint)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Conversion destination, pinned to the participant's preferred-mint account
+    #[account(
+        mut,
+        token::mint = participant.preferred_mint,
+        token::authority = participant.agent
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+
+    /// Rate for the share's mint into the preferred mint
+    #[account(
+        seeds = [
+            b"conversion_rate",
+            reserve.id_seed(),
+            share.mint.as_ref(),
+            participant.preferred_mint.as_ref()
+        ],
+        bump = conversion_rate.bump
+    )]
+    pub conversion_rate: Account<'info, ConversionRate>,
+
+    /// CHECK: The reserve's registered swap venue
+    #[account(executable, address = reserve.swap_program @ AmanaError::ConversionDisabled)]
+    pub swap_program: UncheckedAccount<'info>,
+
+    /// CHECK: Pool state, validated by the venue
+    pub swap_pool: UncheckedAccount<'info>,
+
+    /// CHECK: Pool authority, validated by the venue
+    pub swap_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Pool's account in the share's mint, validated by the venue
+    #[account(mut)]
+    pub pool_source: UncheckedAccount<'info>,
+
+    /// CHECK: Pool's account in the preferred mint, validated by the venue
+    #[account(mut)]
+    pub pool_destination: UncheckedAccount<'info>,
+
+    /// CHECK: Pool token mint, validated by the venue
+    #[account(mut)]
+    pub pool_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pool fee account, validated by the venue
+    #[account(mut)]
+    pub pool_fee: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(from_mint: Pubkey, to_mint: Pubkey)]
+pub struct SetConversionRate<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ConversionRate::SPACE,
+        seeds = [b"conversion_rate", reserve.id_seed(), from_mint.as_ref(), to_mint.as_ref()],
+        bump
+    )]
+    pub conversion_rate: Account<'info, ConversionRate>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepDormant<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), share.activity_id.as_ref(), participant.agent.as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RestoreSweptShare<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), agent.key().as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), share.activity_id.as_ref(), agent.key().as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    pub agent: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseDormantSweep<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), share.activity_id.as_ref(), share.agent.as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(mut, address = reserve.charity @ AmanaError::InvalidCharity)]
+    pub charity: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct CompoundProfit<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), participant.agent.as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"share", reserve.id_seed(), activity_id.as_ref(), participant.agent.as_ref()],
+        bump = share.bump
+    )]
+    pub share: Account<'info, ParticipantActivityShare>,
+
+    #[account(
+        seeds = [b"delegate", reserve.id_seed(), participant.agent.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, ParticipantDelegate>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AuthorizeDelegate<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = user,
+        space = ParticipantDelegate::SPACE,
+        seeds = [b"delegate", reserve.id_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub delegate: Account<'info, ParticipantDelegate>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"delegate", reserve.id_seed(), user.key().as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Account<'info, ParticipantDelegate>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleRecurring<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"participant", reserve.id_seed(), user.key().as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        init,
+        payer = user,
+        space = RecurringContribution::SPACE,
+        seeds = [b"recurring", reserve.id_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub recurring: Account<'info, RecurringContribution>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRecurring<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", reserve.id_seed(), recurring.agent.as_ref()],
+        bump = participant.bump,
+        constraint = participant.is_active @ AmanaError::InactiveParticipant,
+        constraint = !participant.is_frozen @ AmanaError::Denied
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [b"recurring", reserve.id_seed(), recurring.agent.as_ref()],
+        bump = recurring.bump
+    )]
+    pub recurring: Account<'info, RecurringContribution>,
+
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRecurring<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"recurring", reserve.id_seed(), user.key().as_ref()],
+        bump = recurring.bump
+    )]
+    pub recurring: Account<'info, RecurringContribution>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterValidator<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Validator::SPACE,
+        seeds = [b"validator", reserve.id_seed(), authority.key().as_ref()],
+        bump
+    )]
+    pub validator: Account<'info, Validator>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnregisterValidator<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"validator", reserve.id_seed(), authority.key().as_ref()],
+        bump = validator.bump
+    )]
+    pub validator: Account<'info, Validator>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawValidatorStake<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"validator", reserve.id_seed(), authority.key().as_ref()],
+        bump = validator.bump
+    )]
+    pub validator: Account<'info, Validator>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueQard<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = QardLoan::SPACE,
+        seeds = [b"qard", reserve.id_seed(), borrower.key().as_ref()],
+        bump
+    )]
+    pub qard_loan: Account<'info, QardLoan>,
+
+    #[account(mut)]
+    pub borrower: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RepayQard<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"qard", reserve.id_seed(), borrower.key().as_ref()],
+        bump = qard_loan.bump
+    )]
+    pub qard_loan: Account<'info, QardLoan>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestQardExtension<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"qard", reserve.id_seed(), borrower.key().as_ref()],
+        bump = qard_loan.bump
+    )]
+    pub qard_loan: Account<'info, QardLoan>,
+
+    pub borrower: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetQardDueDate<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"qard", reserve.id_seed(), qard_loan.borrower.as_ref()],
+        bump = qard_loan.bump
+    )]
+    pub qard_loan: Account<'info, QardLoan>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveQardExtension<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == approver.key() || reserve.sharia_board == approver.key()
+            @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"qard", reserve.id_seed(), qard_loan.borrower.as_ref()],
+        bump = qard_loan.bump
+    )]
+    pub qard_loan: Account<'info, QardLoan>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    /// The reserve admin or its Sharia board
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateQardLoan<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    /// CHECK: A loan in the pre-due-date layout; owner, discriminator, size
+    /// and PDA are checked by the handler
+    #[account(mut)]
+    pub qard_loan: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(activity_id: [u8; 32])]
+pub struct PostCompletionBond<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        seeds = [b"activity", reserve.id_seed(), activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    #[account(
+        init,
+        payer = reporter,
+        space = CompletionBond::SPACE,
+        seeds = [
+            b"completion_bond",
+            reserve.id_seed(),
+            activity_id.as_ref(),
+            reporter.key().as_ref()
+        ],
+        bump
+    )]
+    pub completion_bond: Account<'info, CompletionBond>,
+
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundCompletionBond<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = reporter,
+        seeds = [
+            b"completion_bond",
+            reserve.id_seed(),
+            completion_bond.activity_id.as_ref(),
+            completion_bond.reporter.as_ref()
+        ],
+        bump = completion_bond.bump,
+        has_one = reporter
+    )]
+    pub completion_bond: Account<'info, CompletionBond>,
+
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SlashCompletionBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == admin.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        close = reserve,
+        seeds = [
+            b"completion_bond",
+            reserve.id_seed(),
+            completion_bond.activity_id.as_ref(),
+            completion_bond.reporter.as_ref()
+        ],
+        bump = completion_bond.bump
+    )]
+    pub completion_bond: Account<'info, CompletionBond>,
+
+    #[account(
+        mut,
+        seeds = [b"audit_log", reserve.id_seed()],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    pub admin: Signer<'info>,
+}
+
+// ========== MagicBlock Context Structs ==========
+
+#[delegate]
+#[derive(Accounts)]
+pub struct DelegateReserve<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Checked by the delegate program
+    pub validator: Option<AccountInfo<'info>>,
+    /// CHECK: The reserve PDA to delegate
+    #[account(mut, del)]
+    pub reserve: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ShouldDelegate<'info> {
+    #[account(
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+}
+
+#[delegate]
+#[derive(Accounts)]
+pub struct DelegateIfRecommended<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Checked by the delegate program
+    pub validator: Option<AccountInfo<'info>>,
+    /// CHECK: The reserve PDA to delegate; its state is read before delegating
+    #[account(mut, del)]
+    pub reserve: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeployCapitalRealtime<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity.activity_id.as_ref()],
+        bump = activity.bump,
+        constraint = reserve.admin == payer.key() || activity.initiator == payer.key()
+            @ AmanaError::Unauthorized
+    )]
+    pub activity: Account<'info, Activity>,
+
+    /// The reserve admin or the activity's initiator
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: MagicBlock context account
+    pub magic_context: AccountInfo<'info>,
+    /// CHECK: MagicBlock program
+    pub magic_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAndDeploy<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump,
+        constraint = reserve.admin == authority.key() @ AmanaError::Unauthorized
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(
+        mut,
+        seeds = [b"activity", reserve.id_seed(), activity.activity_id.as_ref()],
+        bump = activity.bump
+    )]
+    pub activity: Account<'info, Activity>,
+
+    /// Bonded validator; required while `min_validator_stake` is set
+    #[account(
+        seeds = [b"validator", reserve.id_seed(), validator.authority.as_ref()],
+        bump = validator.bump
+    )]
+    pub validator: Option<Account<'info, Validator>>,
+
+    pub validator_authority: Option<Signer<'info>>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: MagicBlock context account
+    pub magic_context: AccountInfo<'info>,
+    /// CHECK: MagicBlock program
+    pub magic_program: AccountInfo<'info>,
+}
+
+#[commit]
+#[derive(Accounts)]
+pub struct CommitAndUndelegateReserve<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.id_seed()],
+        bump = reserve.bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Required unless the payer is the reserve admin
+    #[account(
+        seeds = [b"operator", reserve.id_seed(), payer.key().as_ref()],
+        bump = operator.bump
+    )]
+    pub operator: Option<Account<'info, Operator>>,
+
+    /// CHECK: MagicBlock context account
+    pub magic_context: AccountInfo<'info>,
+    /// CHECK: MagicBlock program
+    pub magic_program: AccountInfo<'info>,
+}
+
+// Enums
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityStatus {
+    Proposed,
+    Approved,
+    Active,
+    Completed,
+    Rejected,
+    Split,
+}
+
+/// Lifecycle of the reserve itself
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ReserveStatus {
+    Active,
+    WindingDown,    // Shutdown initiated; only exits and settlement remain
+}
+
+/// How profit reaches participants
+///
+/// Only outcome-contingent distribution is permitted: a participant's share
+/// follows what activities actually earned. `FixedReturn` exists solely so a
+/// request for a promised rate can be named and refused.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DistributionMode {
+    OutcomeContingent,
+    FixedReturn,    // Guaranteed return (riba); never selectable
+}
+
+impl DistributionMode {
+    pub fn is_outcome_contingent(&self) -> bool {
+        matches!(self, DistributionMode::OutcomeContingent)
+    }
+}
+
+/// Participant class, each with its own `TierConfig` on the reserve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ParticipantTier {
+    Retail,
+    Institutional,
+}
+
+impl ParticipantTier {
+    pub fn index(&self) -> usize {
+        *self as usize
     }
+}
+
+/// Admin actions recorded in the audit log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    SetAllowNetting = 1,
+    SetMinActivityCapital = 2,
+    SetMaxOutcomeAge = 3,
+    SetMaxRiskExposure = 4,
+    NetSettle = 5,
+    ReconcileCapital = 6,
+    SetEntryDonation = 7,
+    DenyAddress = 8,
+    AllowAddress = 9,
+    SetMaturityGrace = 10,
+    SetPrivateBridge = 11,
+    InitializeMintVault = 12,
+    SetValidatorPolicy = 13,
+    SetVestingSchedule = 14,
+    SetQardLimit = 15,
+    IssueQard = 16,
+    SetCompletionBond = 17,
+    SlashCompletionBond = 18,
+    InitiateShutdown = 19,
+    CloseReserve = 20,
+    SetActivityDurationBounds = 21,
+    SetDistributionInterval = 23,
+    RejectActivity = 24,
+    SetConversionPolicy = 25,
+    SetDelegationPolicy = 26,
+    AuthorizeOperator = 27,
+    RevokeOperator = 28,
+    SetCapacity = 29,
+    EnableCapacityGovernance = 30,
+    SetDormancyPolicy = 31,
+    SweepDormant = 32,
+    ReleaseDormantSweep = 33,
+    InitializeGuaranteeFund = 34,
+    SetGuaranteeCoverage = 35,
+    SetReserveMetadata = 36,
+    SetProposalExpiry = 37,
+    SetDistributionMode = 38,
+    InitializeComplianceFund = 39,
+    SetActivityFee = 40,
+    PayComplianceKeeper = 41,
+    SetTierConfig = 42,
+    SetAllowInKind = 43,
+    DistributeInKind = 44,
+    SetMinSolvency = 45,
+    ResetAfterSolvencyFix = 46,
+    AuthorizeIndexer = 47,
+    RevokeIndexer = 48,
+    SetUniqueActivityIds = 49,
+    InitializeTreasury = 50,
+    SetManagementFee = 51,
+    WithdrawTreasury = 52,
+    ApproveQardExtension = 53,
+    SetWithdrawalCooldown = 54,
+    SetLimitedLiability = 55,
+    CollectManagementFee = 56,
+    SetConversionRate = 57,
+    SetQardDueDate = 58,
+    MigrateQardLoan = 59,
+    SetShariaBoard = 60,
+    SweepTreasury = 61,
+    SweepGuaranteeFund = 62,
+    SweepMintVault = 63,
+    SetMaxProposal = 64,
+    AuthorizePrivateDeployer = 65,
+    RevokePrivateDeployer = 66,
+    AuthorizeReporter = 67,
+    RevokeReporter = 68,
+}
+
+// Events
+
+#[event]
+pub struct AuditEntryEvent {
+    pub sequence: u64,
+    pub action: u8,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParticipantJoinedEvent {
+    pub agent: Pubkey,
+    pub capital_contributed: u64,
+    pub tier: ParticipantTier,
+    pub locked_until: i64,
+}
+
+#[event]
+pub struct PositionTransferredEvent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub capital_contributed: u64,
+    pub joined_at: i64,
+}
+
+#[event]
+pub struct ActivityProposedEvent {
+    pub activity_id: [u8; 32],
+    pub initiator: Pubkey,
+    pub capital_required: u64,
+    pub risk_rating: u8,
+}
+
+#[event]
+pub struct ActivityApprovedEvent {
+    pub activity_id: [u8; 32],
+    pub capital_required: u64,
+    pub approved_amount: u64,
+    pub approved_by: Pubkey,
+}
+
+#[event]
+pub struct ActivityRejectedEvent {
+    pub activity_id: [u8; 32],
+    pub capital_required: u64,
+    pub reason_code: u8,
+    pub rejected_by: Pubkey,
+}
+
+#[event]
+pub struct ActivitySplitEvent {
+    pub parent_id: [u8; 32],
+    pub child_ids: Vec<[u8; 32]>,
+    pub amounts: Vec<u64>,
+}
+
+#[event]
+pub struct ActivityClosedEvent {
+    pub activity_id: [u8; 32],
+    pub initiator: Pubkey,
+}
+
+#[event]
+pub struct ClosedIdFilterRotatedEvent {
+    pub activity_id: [u8; 32], // First id recorded in the fresh filter
+}
+
+#[event]
+pub struct InitiatorIndexRolledEvent {
+    pub initiator: Pubkey,
+    pub page: u32,
+    pub archived: u32,
+}
+
+#[event]
+pub struct ActivityCompletedEvent {
+    pub activity_id: [u8; 32],
+    pub outcome: i64,
+}
+
+#[event]
+pub struct NetSettlementEvent {
+    pub initiator: Pubkey,
+    pub activity_ids: Vec<[u8; 32]>,
+    pub gross_profit: u64,
+    pub gross_loss: u64,
+    pub net_outcome: i64,
+}
+
+#[event]
+pub struct NettingConfigUpdatedEvent {
+    pub allow_netting: bool,
+}
+
+#[event]
+pub struct InKindConfigUpdatedEvent {
+    pub allow_in_kind: bool,
+}
+
+#[event]
+pub struct UniqueActivityIdsUpdatedEvent {
+    pub unique_activity_ids: bool,
+    pub closed_id_count: u64,
+}
+
+#[event]
+pub struct MinSolvencyUpdatedEvent {
+    pub min_solvency_bps: u16,
+}
+
+#[event]
+pub struct SolvencyBreachEvent {
+    pub solvency_bps: u64,
+    pub min_solvency_bps: u16,
+    pub accounted: u64,
+    pub available: u64,
+}
+
+#[event]
+pub struct SolvencyRestoredEvent {
+    pub solvency_bps: u64,
+    pub reset_by: Pubkey,
+}
+
+#[event]
+pub struct ReserveCursorEvent {
+    pub next_sequence: u64,
+}
+
+#[event]
+pub struct IndexerAuthorizedEvent {
+    pub indexer: Pubkey,
+}
+
+#[event]
+pub struct IndexerRevokedEvent {
+    pub indexer: Pubkey,
+    pub next_sequence: u64,
+}
+
+#[event]
+pub struct IndexCursorAdvancedEvent {
+    pub indexer: Pubkey,
+    pub next_sequence: u64,
+}
+
+#[event]
+pub struct ActivityAutoCompletedEvent {
+    pub activity_id: [u8; 32],
+    pub maturity_at: i64,
+    pub returned_capital: u64,
+}
+
+#[event]
+pub struct ProposalExpiredEvent {
+    pub activity_id: [u8; 32],
+    pub capital_required: u64,
+    pub created_at: i64,
+    pub expired_at: i64,
+}
+
+#[event]
+pub struct ProposalExpiryUpdatedEvent {
+    pub proposal_expiry_seconds: i64,
+}
+
+#[event]
+pub struct MaturityGraceUpdatedEvent {
+    pub maturity_grace_seconds: i64,
+}
+
+#[event]
+pub struct ActivityDurationBoundsUpdatedEvent {
+    pub min_activity_duration_seconds: i64,
+    pub max_activity_duration_seconds: i64,
+}
+
+#[event]
+pub struct ValidatorPolicyUpdatedEvent {
+    pub min_validator_stake: u64,
+    pub validator_unbonding_seconds: i64,
+}
+
+#[event]
+pub struct VestingScheduleUpdatedEvent {
+    pub vesting_cliff_seconds: i64,
+    pub vesting_duration_seconds: i64,
+}
+
+#[event]
+pub struct DormancyPolicyUpdatedEvent {
+    pub dormancy_seconds: i64,
+    pub sweep_reversal_seconds: i64,
+}
+
+#[event]
+pub struct DormantShareSweptEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub dormant_since: i64,     // Allocation or last claim
+    pub releasable_at: i64,     // Reversible by the participant until then
+}
+
+#[event]
+pub struct DormantSweepReversedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DormantSweepReleasedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub charity: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CapacityUpdatedEvent {
+    pub max_participants: u64,
+    pub max_total_capital: u64,
+    pub proposal_id: Option<u64>, // Approving DAO proposal, when one was required
+}
+
+#[event]
+pub struct CapacityGovernanceEnabledEvent {
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct OperatorAuthorizedEvent {
+    pub operator: Pubkey,
+}
+
+#[event]
+pub struct OperatorRevokedEvent {
+    pub operator: Pubkey,
+}
+
+#[event]
+pub struct ValidatorRegisteredEvent {
+    pub authority: Pubkey,
+    pub stake: u64,
+}
+
+#[event]
+pub struct ValidatorUnregisteredEvent {
+    pub authority: Pubkey,
+    pub unbonding_ends_at: i64,
+}
+
+#[event]
+pub struct ValidatorStakeWithdrawnEvent {
+    pub authority: Pubkey,
+    pub stake: u64,
+}
+
+#[event]
+pub struct QardLimitUpdatedEvent {
+    pub max_qard_outstanding_bps: u16,
+}
+
+#[event]
+pub struct QardIssuedEvent {
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub total_qard_outstanding: u64,
+}
+
+#[event]
+pub struct QardRepaidEvent {
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub total_qard_outstanding: u64,
+}
+
+#[event]
+pub struct WithdrawalRequestedEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub settle_after: i64,
+}
+
+#[event]
+pub struct WithdrawalSettledEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+}
 
-    /// Deploy capital to activity in real-time on ER
-    pub fn deploy_capital_realtime(
-        ctx: Context<DeployCapitalRealtime>,
-        activity_id: [u8; 32],
-        amount: u64,
-    ) -> Result<()> {
-        let reserve = &mut ctx.accounts.reserve;
-        let activity = &mut ctx.accounts.activity;
+#[event]
+pub struct WithdrawalCancelledEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+}
 
-        require!(
-            amount <= reserve.total_capital,
-            AmanaError::InsufficientCapital
-        );
+#[event]
+pub struct WithdrawalCooldownUpdatedEvent {
+    pub withdrawal_cooldown_seconds: i64,
+}
 
-        // Deploy capital instantly on ER
-        activity.capital_deployed = amount;
-        activity.status = ActivityStatus::Active;
-        reserve.total_capital -= amount;
+#[event]
+pub struct QardExtensionRequestedEvent {
+    pub borrower: Pubkey,
+    pub due_at: i64,
+    pub requested_due_at: i64,
+}
 
-        // Auto-commit critical state changes
-        commit_accounts(
-            &ctx.accounts.payer,
-            vec![
-                &ctx.accounts.reserve.to_account_info(),
-                &ctx.accounts.activity.to_account_info(),
-            ],
-            &ctx.accounts.magic_context,
-            &ctx.accounts.magic_program,
-        )?;
+#[event]
+pub struct QardDueDateSetEvent {
+    pub borrower: Pubkey,
+    pub due_at: i64,
+}
 
-        emit!(CapitalDeployedRealtimeEvent {
-            activity_id,
-            amount,
-        });
+#[event]
+pub struct QardLoanMigratedEvent {
+    pub borrower: Pubkey,
+    pub outstanding: u64,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct ShariaBoardUpdatedEvent {
+    pub sharia_board: Pubkey,
+}
 
-    /// Commit and undelegate reserve state back to base layer
-    pub fn commit_and_undelegate_reserve(ctx: Context<CommitAndUndelegateReserve>) -> Result<()> {
-        commit_and_undelegate_accounts(
-            &ctx.accounts.payer,
-            vec![&ctx.accounts.reserve.to_account_info()],
-            &ctx.accounts.magic_context,
-            &ctx.accounts.magic_program,
-        )?;
-        Ok(())
-    }
+#[event]
+pub struct QardExtendedEvent {
+    pub borrower: Pubkey,
+    pub old_due_at: i64,
+    pub new_due_at: i64,
+    pub outstanding: u64,
+    pub extension_count: u32,
 }
 
-// Account structs
+#[event]
+pub struct CompletionBondUpdatedEvent {
+    pub completion_bond: u64,
+    pub dispute_window_seconds: i64,
+}
 
-#[account]
-pub struct Reserve {
-    pub admin: Pubkey,
-    pub min_capital_contribution: u64,
-    pub max_participants: u64,
-    pub total_capital: u64,
+#[event]
+pub struct CompletionBondPostedEvent {
+    pub activity_id: [u8; 32],
+    pub reporter: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CompletionBondRefundedEvent {
+    pub activity_id: [u8; 32],
+    pub reporter: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CompletionBondSlashedEvent {
+    pub activity_id: [u8; 32],
+    pub reporter: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DistributionMetricsEvent {
+    pub sample_size: u32,
     pub participant_count: u64,
-    pub is_initialized: bool,
-    pub bump: u8,
+    pub total_sampled_capital: u64,
+    pub gini_bps: u16,
 }
 
-#[account]
-pub struct Participant {
+#[event]
+pub struct BalanceVerifiedEvent {
+    pub agent: Pubkey,
+    pub capital_contributed: u64,
+    pub total_contributed: u64,
+    pub others_contributed: u64,
+    pub consistent: bool,
+    pub discrepancy: i64,       // Recorded capital minus what the books leave for the participant
+}
+
+#[event]
+pub struct UtilizationEvent {
+    pub deployed_bps: u16,
+    pub idle_bps: u16,
+    pub buffer_bps: u16,
+    pub gross_capital: u64,
+    pub deployed_capital: u64,  // Escrowed + private + Qard
+    pub buffer_capital: u64,
+}
+
+#[event]
+pub struct ParticipantSnapshotEvent {
     pub agent: Pubkey,
     pub capital_contributed: u64,
     pub profit_share: u64,
     pub loss_share: u64,
-    pub is_active: bool,
+    pub pending_profit: u64,
     pub joined_at: i64,
-    pub bump: u8,
+    pub tenure_seconds: i64,
+    pub is_active: bool,
+    pub reserve_share_bps: u16,
+    pub total_contributed: u64,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Activity {
+#[event]
+pub struct ReserveConfigEvent {
+    pub admin: Pubkey,
+    pub min_capital_contribution: u64,
+    pub max_participants: u64,
+    pub allow_netting: bool,
+    pub min_activity_capital: u64,
+    pub max_outcome_age_seconds: i64,
+    pub max_risk_exposure: u64,
+    pub entry_fee_bps: u16,
+    pub charity: Pubkey,
+    pub maturity_grace_seconds: i64,
+    pub private_bridge: Pubkey,
+    pub min_validator_stake: u64,
+    pub validator_unbonding_seconds: i64,
+    pub vesting_cliff_seconds: i64,
+    pub vesting_duration_seconds: i64,
+    pub max_qard_outstanding_bps: u16,
+    pub completion_bond: u64,
+    pub dispute_window_seconds: i64,
+    pub min_activity_duration_seconds: i64,
+    pub max_activity_duration_seconds: i64,
+    pub distribution_interval_seconds: i64,
+    pub swap_program: Pubkey,
+    pub max_slippage_bps: u16,
+    pub delegation_window_seconds: i64,
+    pub delegation_threshold: u32,
+    pub max_total_capital: u64,
+    pub capacity_governed: bool,
+    pub dormancy_seconds: i64,
+    pub sweep_reversal_seconds: i64,
+    pub guarantee_coverage_bps: u16,
+    pub name: [u8; 32],
+    pub metadata_uri: String,
+    pub proposal_expiry_seconds: i64,
+    pub distribution_mode: DistributionMode,
+    pub activity_fee: u64,
+    pub tier_configs: [TierConfig; PARTICIPANT_TIER_COUNT],
+    pub allow_in_kind: bool,
+    pub min_solvency_bps: u16,
+    pub unique_activity_ids: bool,
+    pub management_fee_bps: u16,
+    pub withdrawal_cooldown_seconds: i64,
+    pub mudarabah_limited_liability: bool,
+    pub max_proposal_bps: u16,
+}
+
+#[event]
+pub struct MaxProposalUpdatedEvent {
+    pub max_proposal_bps: u16,
+}
+
+#[event]
+pub struct DistributionIntervalUpdatedEvent {
+    pub distribution_interval_seconds: i64,
+}
+
+#[event]
+pub struct DistributionAuditEvent {
+    pub total: u64,
+    pub distributed_sum: u64,
+    pub remainder: u64,
+}
+
+#[event]
+pub struct DistributionModeUpdatedEvent {
+    pub distribution_mode: DistributionMode,
+}
+
+#[event]
+pub struct TierConfigUpdatedEvent {
+    pub tier: ParticipantTier,
+    pub min_contribution: u64,
+    pub max_contribution: u64,
+    pub lockup_seconds: i64,
+}
+
+#[event]
+pub struct ConversionPolicyUpdatedEvent {
+    pub swap_program: Pubkey,
+    pub max_slippage_bps: u16,
+}
+
+#[event]
+pub struct DelegationPolicyUpdatedEvent {
+    pub delegation_window_seconds: i64,
+    pub delegation_threshold: u32,
+}
+
+#[event]
+pub struct PreferredMintUpdatedEvent {
+    pub agent: Pubkey,
+    pub preferred_mint: Pubkey,
+}
+
+#[event]
+pub struct DenylistUpdatedEvent {
+    pub agent: Pubkey,
+    pub page: u32,
+    pub denied: bool,
+    pub frozen: bool,
+}
+
+#[event]
+pub struct EntryDonationEvent {
+    pub agent: Pubkey,
+    pub charity: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct EntryDonationUpdatedEvent {
+    pub entry_fee_bps: u16,
+    pub charity: Pubkey,
+}
+
+#[event]
+pub struct PrivateBridgeUpdatedEvent {
+    pub private_bridge: Pubkey,
+}
+
+#[event]
+pub struct PrivateDeploymentDebitedEvent {
+    pub total_capital: u64,
+    pub private_deployed_capital: u64,
+}
+
+#[event]
+pub struct PrivateDeploymentCreditedEvent {
+    pub total_capital: u64,
+    pub private_deployed_capital: u64,
+}
+
+#[event]
+pub struct PrivateDeployerAuthorizedEvent {
+    pub deployer: Pubkey,
+}
+
+#[event]
+pub struct PrivateDeployerRevokedEvent {
+    pub deployer: Pubkey,
+}
+
+#[event]
+pub struct ReporterAuthorizedEvent {
+    pub reporter: Pubkey,
+}
+
+#[event]
+pub struct ReporterRevokedEvent {
+    pub reporter: Pubkey,
+}
+
+#[event]
+pub struct MaxRiskExposureUpdatedEvent {
+    pub max_risk_exposure: u64,
+}
+
+#[event]
+pub struct MaxOutcomeAgeUpdatedEvent {
+    pub max_outcome_age_seconds: i64,
+}
+
+#[event]
+pub struct CapitalReconciledEvent {
+    pub old: u64,
+    pub new: u64,
+    pub escrowed: u64,
+}
+
+#[event]
+pub struct MinActivityCapitalUpdatedEvent {
+    pub min_activity_capital: u64,
+}
+
+#[event]
+pub struct MergedReportEvent {
+    pub activity_ids: Vec<[u8; 32]>,
+    pub total_capital_deployed: u64,
+    pub total_profit: u64,
+    pub total_loss: u64,
+    pub net_outcome: i64,
+}
+
+#[event]
+pub struct IntegrityCheckEvent {
     pub activity_id: [u8; 32],
-    pub initiator: Pubkey,
-    pub capital_required: u64,
-    pub capital_deployed: u64,
-    pub status: ActivityStatus,
-    pub created_at: i64,
-    pub completed_at: i64,
-    pub outcome: i64,
-    pub is_validated: bool,
-    pub bump: u8,
+    pub passed: bool,
+    pub violation_code: u8,     // INTEGRITY_* constant
 }
 
-// Context structs
+#[event]
+pub struct PendingClaimsEvent {
+    pub agent: Pubkey,
+    pub total_claimable: u64,   // SOL shares only
+    pub claim_count: u32,       // Shares with something claimable, one breakdown entry each
+    pub shares_checked: u32,
+    pub activity_ids: Vec<[u8; 32]>,
+    pub mints: Vec<Pubkey>,
+    pub amounts: Vec<u64>,
+}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + 32 + 8 + 8 + 8 + 8 + 1 + 1,
-        seeds = [b"reserve"],
-        bump
-    )]
-    pub reserve: Account<'info, Reserve>,
-    #[account(mut)]
-    pub admin: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct EstimatedLiquidityEvent {
+    pub queue_position: u64,
+    pub available_now: u64,       // Free capital at the time of the call
+    pub projected_liquidity: u64, // Free capital once the counted maturities return
+    pub activities_counted: u32,  // Maturities needed, in order, to reach the position
+    pub satisfiable: bool,
+    pub estimated_at: i64,        // 0 when the passed maturities fall short
 }
 
-#[derive(Accounts)]
-pub struct JoinReserve<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct WorkAvailableEvent {
+    pub overdue_count: u32,       // Passed activities auto_complete_overdue would accept
+    pub distributions_due: bool,
+    pub withdrawals_queued: u64,  // Participants awaiting settle_and_exit
+    pub breaker_tripped: bool,    // Solvency breaker has paused the reserve
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + 32 + 8 + 8 + 8 + 1 + 8 + 1,
-        seeds = [b"participant", user.key().as_ref()],
-        bump
-    )]
-    pub participant: Account<'info, Participant>,
+#[event]
+pub struct PortfolioRiskEvent {
+    pub activity_count: u32,
+    pub total_capital_deployed: u64,
+    pub average_risk_rating_bps: u32, // Capital-weighted risk rating x 10000
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct CapitalDepositedEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CapitalWithdrawnEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DustDonatedEvent {
+    pub agent: Pubkey,
+    pub charity: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ParticipantLeftEvent {
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct ShutdownInitiatedEvent {
+    pub total_capital: u64,
+    pub participant_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParticipantSettledEvent {
+    pub agent: Pubkey,
+    pub capital: u64,
+    pub profit: u64,
+}
+
+#[event]
+pub struct ReserveClosedEvent {
+    pub admin: Pubkey,
+    pub dust: u64,              // Leftover free capital donated to the charity
 }
 
-#[derive(Accounts)]
-pub struct ProposeActivity<'info> {
-    #[account(
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
-
-    #[account(
-        seeds = [b"participant", participant.agent.as_ref()],
-        bump = participant.bump,
-        constraint = participant.is_active @ AmanaError::InactiveParticipant,
-        constraint = participant.agent == user.key() @ AmanaError::Unauthorized
-    )]
-    pub participant: Account<'info, Participant>,
+#[event]
+pub struct TreasurySweptEvent {
+    pub admin: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + 32 + 32 + 8 + 8 + 1 + 8 + 8 + 8 + 1 + 1,
-        seeds = [b"activity", activity_id.as_ref()],
-        bump
-    )]
-    pub activity: Account<'info, Activity>,
+#[event]
+pub struct GuaranteeFundSweptEvent {
+    pub charity: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct MintVaultSweptEvent {
+    pub mint: Pubkey,
+    pub charity: Pubkey,
+    pub amount: u64,
 }
 
-#[derive(Accounts)]
-pub struct ApproveActivity<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct GuaranteeFundedEvent {
+    pub donor: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"activity", activity.activity_id.as_ref()],
-        bump = activity.bump
-    )]
-    pub activity: Account<'info, Activity>,
+#[event]
+pub struct GuaranteeCoverageUpdatedEvent {
+    pub guarantee_coverage_bps: u16,
 }
 
-#[derive(Accounts)]
-pub struct CompleteActivity<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct ActivityFeeCollectedEvent {
+    pub activity_id: [u8; 32],
+    pub payer: Pubkey,
+    pub fee: u64,
+    pub fund_balance: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"activity", activity.activity_id.as_ref()],
-        bump = activity.bump
-    )]
-    pub activity: Account<'info, Activity>,
+#[event]
+pub struct ActivityFeeUpdatedEvent {
+    pub activity_fee: u64,
 }
 
-#[derive(Accounts)]
-pub struct DepositCapital<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct ComplianceKeeperPaidEvent {
+    pub keeper: Pubkey,
+    pub amount: u64,
+    pub fund_balance: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"participant", participant.agent.as_ref()],
-        bump = participant.bump,
-        constraint = participant.is_active @ AmanaError::InactiveParticipant,
-        constraint = participant.agent == user.key() @ AmanaError::Unauthorized
-    )]
-    pub participant: Account<'info, Participant>,
+#[event]
+pub struct ManagementFeeUpdatedEvent {
+    pub management_fee_bps: u16,
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct ManagementFeeAccruedEvent {
+    pub activity_id: [u8; 32],
+    pub outcome: i64,
+    pub fee: u64,
+    pub fees_owed: u64,
 }
 
-#[derive(Accounts)]
-pub struct WithdrawCapital<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct ManagementFeeCollectedEvent {
+    pub amount: u64,
+    pub fees_owed: u64,
+    pub treasury_balance: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"participant", participant.agent.as_ref()],
-        bump = participant.bump,
-        constraint = participant.is_active @ AmanaError::InactiveParticipant,
-        constraint = participant.agent == user.key() @ AmanaError::Unauthorized
-    )]
-    pub participant: Account<'info, Participant>,
+#[event]
+pub struct TreasuryWithdrawnEvent {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub treasury_balance: u64,
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+#[event]
+pub struct ReserveMetadataUpdatedEvent {
+    pub name: [u8; 32],
+    pub metadata_uri: String,
 }
 
-// ========== MagicBlock Context Structs ==========
+#[event]
+pub struct LimitedLiabilityUpdatedEvent {
+    pub mudarabah_limited_liability: bool,
+}
 
-#[delegate]
-#[derive(Accounts)]
-pub struct DelegateReserve<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    /// CHECK: Checked by the delegate program
-    pub validator: Option<AccountInfo<'info>>,
-    /// CHECK: The reserve PDA to delegate
-    #[account(mut, del)]
-    pub reserve: AccountInfo<'info>,
+#[event]
+pub struct LossWrittenOffEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub excess: u64,        // Participant's loss share beyond their capital
+    pub absorbed: u64,      // Part of the excess drawn from the guarantee fund
+    pub written_off: u64,   // Part borne by no participant
 }
 
-#[derive(Accounts)]
-pub struct DeployCapitalRealtime<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct LossCoveredEvent {
+    pub activity_id: [u8; 32],
+    pub loss: u64,          // Loss after capping at the capital deployed
+    pub covered: u64,       // Part drawn from the guarantee fund
+    pub fund_balance: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"activity", activity.activity_id.as_ref()],
-        bump = activity.bump
-    )]
-    pub activity: Account<'info, Activity>,
+#[event]
+pub struct LossRealizedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub mint: Pubkey,           // Settlement mint of the loss (default = SOL)
+    pub amount: u64,
+    pub carried: u64,           // Excess over capital added to loss_carried
+    pub capital_remaining: u64,
+}
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+#[event]
+pub struct ProfitShareAllocatedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub amount: u64,
+}
 
-    /// CHECK: MagicBlock context account
-    pub magic_context: AccountInfo<'info>,
-    /// CHECK: MagicBlock program
-    pub magic_program: AccountInfo<'info>,
+#[event]
+pub struct ConversionRateUpdatedEvent {
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub rate: u64,
+    pub updated_at: i64,
 }
 
-#[commit]
-#[derive(Accounts)]
-pub struct CommitAndUndelegateReserve<'info> {
-    #[account(
-        mut,
-        seeds = [b"reserve"],
-        bump = reserve.bump
-    )]
-    pub reserve: Account<'info, Reserve>,
+#[event]
+pub struct ProfitConvertedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub min_amount_out: u64,
+}
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+#[event]
+pub struct DistributionPaidOnExitEvent {
+    pub agent: Pubkey,
+    pub amount: u64,            // Scheduled profit accrued but not yet claimed
+}
 
-    /// CHECK: MagicBlock context account
-    pub magic_context: AccountInfo<'info>,
-    /// CHECK: MagicBlock program
-    pub magic_program: AccountInfo<'info>,
+#[event]
+pub struct PendingDistributedEvent {
+    pub round: u64,
+    pub distribution_id: [u8; 32],  // Activity id of the round's shares
+    pub amount: u64,                // Profit accrued since the previous round
+    pub released_per_unit: u128,
 }
 
-// Enums
+#[event]
+pub struct ProfitClaimedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub claimed_by: Pubkey,
+    pub amount: u64,
+    pub vested: u64,            // Total of the share vested so far
+}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum ActivityStatus {
-    Proposed,
-    Approved,
-    Active,
-    Completed,
-    Rejected,
+#[event]
+pub struct TokenProfitClaimedEvent {
+    pub activity_id: [u8; 32],
+    pub agent: Pubkey,
+    pub mint: Pubkey,
+    pub claimed_by: Pubkey,
+    pub amount: u64,
 }
 
-// Events
+#[event]
+pub struct InKindDistributedEvent {
+    pub distribution_id: [u8; 32],
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub distribution_base: u64,
+}
 
 #[event]
-pub struct ParticipantJoinedEvent {
+pub struct InKindClaimedEvent {
+    pub distribution_id: [u8; 32],
     pub agent: Pubkey,
-    pub capital_contributed: u64,
+    pub mint: Pubkey,
+    pub claimed_by: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
-pub struct ActivityProposedEvent {
-    pub activity_id: [u8; 32],
-    pub initiator: Pubkey,
-    pub capital_required: u64,
+pub struct MintVaultInitializedEvent {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
 }
 
 #[event]
-pub struct ActivityApprovedEvent {
+pub struct SettlementMintSetEvent {
     pub activity_id: [u8; 32],
+    pub mint: Pubkey,
 }
 
 #[event]
-pub struct ActivityCompletedEvent {
+pub struct ProfitCompoundedEvent {
     pub activity_id: [u8; 32],
-    pub outcome: i64,
+    pub agent: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
-pub struct CapitalDepositedEvent {
+pub struct DelegateAuthorizedEvent {
+    pub agent: Pubkey,
+    pub delegate: Pubkey,
+    pub scopes: u8,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct DelegateRevokedEvent {
+    pub agent: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct RecurringScheduledEvent {
     pub agent: Pubkey,
     pub amount: u64,
+    pub interval_seconds: i64,
+    pub next_due_at: i64,
 }
 
 #[event]
-pub struct CapitalWithdrawnEvent {
+pub struct RecurringExecutedEvent {
     pub agent: Pubkey,
     pub amount: u64,
+    pub keeper: Pubkey,
+    pub next_due_at: i64,
+}
+
+#[event]
+pub struct RecurringCancelledEvent {
+    pub agent: Pubkey,
 }
 
 // ========== MagicBlock Events ==========
@@ -657,6 +10666,26 @@ pub struct CapitalDeployedRealtimeEvent {
     pub amount: u64,
 }
 
+#[event]
+pub struct DelegationRecommendationEvent {
+    pub recommended: bool,
+    pub proposals_in_window: u32,
+    pub delegation_threshold: u32,
+    pub delegation_window_seconds: i64,
+}
+
+#[event]
+pub struct ReserveAutoDelegatedEvent {
+    pub proposals_in_window: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReserveUndelegatedEvent {
+    pub triggered_by: Pubkey,
+    pub timestamp: i64,
+}
+
 // Errors
 
 #[error_code]
@@ -683,4 +10712,234 @@ pub enum AmanaError {
     Unauthorized,
     #[msg("Insufficient capital")]
     InsufficientCapital,
+    #[msg("Invalid activity split")]
+    InvalidSplit,
+    #[msg("Split amounts must sum to the parent's capital requirement")]
+    SplitAmountMismatch,
+    #[msg("Invalid account passed in remaining accounts")]
+    InvalidRemainingAccount,
+    #[msg("Activity referenced more than once")]
+    DuplicateActivity,
+    #[msg("Initiator index is full - roll it into an overflow page")]
+    InitiatorIndexFull,
+    #[msg("Netting is disabled for this reserve")]
+    NettingDisabled,
+    #[msg("Activities belong to different initiators")]
+    InitiatorMismatch,
+    #[msg("Activity capital is below the reserve's minimum")]
+    BelowMinimumActivityCapital,
+    #[msg("No profit to allocate for this participant")]
+    NoProfitToAllocate,
+    #[msg("Nothing left to claim")]
+    NothingToClaim,
+    #[msg("Delegate scope does not permit this action")]
+    InvalidDelegateScope,
+    #[msg("Delegation has expired")]
+    DelegateExpired,
+    #[msg("Escrow evidence does not match the reserve's books")]
+    ReconciliationMismatch,
+    #[msg("Outcome is older than the maximum allowed age")]
+    StaleOutcome,
+    #[msg("Outcome timestamp lies in the future")]
+    FutureDatedOutcome,
+    #[msg("Risk rating must be between 1 and 5")]
+    InvalidRiskRating,
+    #[msg("Approval would exceed the reserve's risk budget")]
+    RiskBudgetExceeded,
+    #[msg("Entry donation exceeds the maximum allowed")]
+    InvalidEntryFee,
+    #[msg("Charity account missing or does not match the reserve")]
+    InvalidCharity,
+    #[msg("Address is on the reserve denylist")]
+    Denied,
+    #[msg("Address is not on this denylist page")]
+    NotDenied,
+    #[msg("Denylist page is full")]
+    DenylistPageFull,
+    #[msg("Denylist pages missing, out of order or invalid")]
+    InvalidDenylistPage,
+    #[msg("Activity id does not match the activity account")]
+    ActivityIdMismatch,
+    #[msg("Participant passed more than once")]
+    DuplicateParticipant,
+    #[msg("Duration must not be negative")]
+    InvalidDuration,
+    #[msg("Activity is not past maturity and grace period")]
+    ActivityNotOverdue,
+    #[msg("Recurring contribution is not due yet")]
+    RecurringNotDue,
+    #[msg("Settlement mint does not match the activity")]
+    InvalidSettlementMint,
+    #[msg("Allocated profit must be claimed before leaving")]
+    PendingProfitUnclaimed,
+    #[msg("Stake is below the validator minimum")]
+    InsufficientStake,
+    #[msg("A registered validator must sign")]
+    ValidatorRequired,
+    #[msg("Validator is already unbonding")]
+    ValidatorUnbonding,
+    #[msg("Unbonding period has not elapsed")]
+    UnbondingNotElapsed,
+    #[msg("Qard limit must be at most 10000 bps")]
+    InvalidQardLimit,
+    #[msg("Outstanding Qard would exceed the reserve's limit")]
+    QardLimitExceeded,
+    #[msg("A completion bond of at least the configured amount is required")]
+    CompletionBondRequired,
+    #[msg("The dispute window is still open")]
+    DisputeWindowOpen,
+    #[msg("The dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("The reserve is winding down")]
+    ReserveWindingDown,
+    #[msg("The reserve is not winding down")]
+    ReserveNotWindingDown,
+    #[msg("Approved or active activities are still open")]
+    ActivitiesStillOpen,
+    #[msg("Participants, capital or Qard remain in the reserve")]
+    ReserveNotEmpty,
+    #[msg("Expected duration is outside the reserve's bounds")]
+    DurationOutOfBounds,
+    #[msg("Approved amount must be non-zero and at most the capital required")]
+    InvalidApprovedAmount,
+    #[msg("Reserve already exists with different parameters")]
+    InitializationMismatch,
+    #[msg("Slippage tolerance must be at most 10000 bps")]
+    InvalidSlippage,
+    #[msg("Profit conversions are disabled")]
+    ConversionDisabled,
+    #[msg("Participant has not chosen a different mint to convert to")]
+    ConversionNotRequested,
+    #[msg("Conversion rate and minimum output must be non-zero")]
+    InvalidQuote,
+    #[msg("Swap did not spend exactly the claimed amount")]
+    InvalidSwap,
+    #[msg("Conversion output is below the slippage tolerance")]
+    SlippageExceeded,
+    #[msg("Proposal volume does not recommend delegation")]
+    DelegationNotRecommended,
+    #[msg("Capacity is below what the reserve already holds")]
+    InvalidCapacity,
+    #[msg("Contribution would exceed the reserve's capital cap")]
+    CapacityExceeded,
+    #[msg("Capacity is governed; an executed DAO proposal is required")]
+    CapacityProposalRequired,
+    #[msg("DAO proposal does not approve this capacity change")]
+    InvalidCapacityProposal,
+    #[msg("Profit share is not dormant")]
+    NotDormant,
+    #[msg("Profit share has no swept profit")]
+    NothingSwept,
+    #[msg("Sweep reversal window has closed")]
+    SweepWindowClosed,
+    #[msg("Sweep is still within its reversal window")]
+    SweepWindowOpen,
+    #[msg("No loss to realize for this participant")]
+    NoLossToRealize,
+    #[msg("Guarantee fund must be passed to settle a covered loss")]
+    GuaranteeFundRequired,
+    #[msg("Guarantee coverage cannot exceed 100%")]
+    InvalidGuaranteeCoverage,
+    #[msg("Reserve name must not be empty")]
+    InvalidReserveName,
+    #[msg("Metadata URI is too long")]
+    MetadataUriTooLong,
+    #[msg("Proposal has not passed its expiry")]
+    ProposalNotExpired,
+    #[msg("Returns cannot be guaranteed; distribution must follow outcomes")]
+    GuaranteedReturnForbidden,
+    #[msg("Compliance fund account is required while an activity fee is set")]
+    ComplianceFundRequired,
+    #[msg("Distributed shares and remainder do not add up to the total")]
+    DistributionMismatch,
+    #[msg("Tier maximum contribution is below its minimum")]
+    InvalidTierConfig,
+    #[msg("Contribution exceeds the participant tier's maximum")]
+    TierContributionExceeded,
+    #[msg("Position is still within its tier lockup")]
+    PositionLocked,
+    #[msg("In-kind distribution is disabled for this reserve")]
+    InKindDisabled,
+    #[msg("Solvency threshold must be at most 10000 basis points")]
+    InvalidSolvencyThreshold,
+    #[msg("Reserve is paused after a solvency breach")]
+    ReservePaused,
+    #[msg("Reserve is not paused")]
+    ReserveNotPaused,
+    #[msg("Solvency ratio is still below the minimum")]
+    SolvencyNotRestored,
+    #[msg("Cursor may only advance, and not past the reserve's next sequence")]
+    InvalidCursor,
+    #[msg("Activity id was used by an activity that has since been closed")]
+    ActivityIdReused,
+    #[msg("Management fee exceeds MAX_MANAGEMENT_FEE_BPS")]
+    InvalidManagementFee,
+    #[msg("Treasury account required while a management fee is set")]
+    TreasuryRequired,
+    #[msg("Qard due date must be in the future and may only move later")]
+    InvalidQardDueDate,
+    #[msg("No Qard extension has been requested")]
+    NoQardExtensionRequested,
+    #[msg("A withdrawal is already pending")]
+    WithdrawalPending,
+    #[msg("No withdrawal is pending")]
+    NoWithdrawalPending,
+    #[msg("Withdrawal cooldown has not passed")]
+    WithdrawalCooldownActive,
+    #[msg("Withdrawals must go through request_withdrawal and settle_withdrawal")]
+    TwoPhaseWithdrawalRequired,
+    #[msg("Withdrawal exceeds the participant's share of free capital")]
+    CapitalLocked,
+    #[msg("Settled outcomes must be allocated or realized in sequence order")]
+    SettlementOutOfOrder,
+    #[msg("Participant has settled outcomes still to allocate or realize")]
+    SettlementsPending,
+    #[msg("Participants have yet to allocate or realize this outcome")]
+    OutcomeNotFullyApplied,
+    #[msg("Activity's outcome is still awaiting netting")]
+    OutcomeNotSettled,
+    #[msg("Deployment exceeds the capital the activity requested")]
+    DeploymentExceedsRequest,
+    #[msg("Conversion rate is too old to convert against")]
+    StaleConversionRate,
+    #[msg("Borrower is past their Qard due date")]
+    QardOverdue,
+    #[msg("Account is not a Qard loan awaiting migration")]
+    InvalidQardLoan,
+    #[msg("Every denylist page is full")]
+    DenylistFull,
+    #[msg("Share already has a sweep awaiting reversal or release")]
+    AlreadySwept,
+    #[msg("Proposal cap must be between 1 and 10000 basis points")]
+    InvalidProposalCap,
+    #[msg("Proposal takes more than max_proposal_bps of free capital")]
+    ProposalTooLarge,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_distribution_accepts_an_exact_split() {
+        assert!(audit_distribution(1_000, 997, 3).is_ok());
+        assert!(audit_distribution(1_000, 1_000, 0).is_ok());
+    }
+
+    #[test]
+    fn audit_distribution_rejects_over_allocation() {
+        // Running totals past the outcome leave a saturated remainder of 0
+        assert_eq!(
+            audit_distribution(1_000, 1_001, 0).unwrap_err(),
+            error!(AmanaError::DistributionMismatch)
+        );
+    }
+
+    #[test]
+    fn audit_distribution_rejects_lost_units() {
+        assert_eq!(
+            audit_distribution(1_000, 990, 5).unwrap_err(),
+            error!(AmanaError::DistributionMismatch)
+        );
+    }
 }
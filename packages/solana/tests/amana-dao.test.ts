@@ -0,0 +1,1508 @@
+// Copyright 2026 TAWF Labs
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  burn,
+  createMint,
+  getAccount,
+  getMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { expectRejected } from "./helpers";
+
+describe("amana-dao", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.AmanaDao as Program;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  let daoPda: PublicKey;
+  let treasuryPda: PublicKey;
+  let auditLogPda: PublicKey;
+  const votingDelay = 1;
+  const votingPeriod = 4;
+
+  const chainTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot()))!;
+
+  const proposalPdaFor = (index: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), index.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const voteRecordPdaFor = (proposalId: anchor.BN, voter: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("vote"), proposalId.toArrayLike(Buffer, "le", 8), voter.toBuffer()],
+      program.programId
+    )[0];
+
+  const voteEscrowPdaFor = (proposalId: anchor.BN, voter: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("vote_escrow"), proposalId.toArrayLike(Buffer, "le", 8), voter.toBuffer()],
+      program.programId
+    )[0];
+
+  const shariaMemberPdaFor = (member: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("sharia_member"), member.toBuffer()],
+      program.programId
+    )[0];
+
+  // Only members with a ShariaBoardMember record may review
+  const addBoardMember = async (member: PublicKey) => {
+    if ((await program.account.shariaBoardMember.fetchNullable(shariaMemberPdaFor(member))) !== null) {
+      return;
+    }
+    await program.methods
+      .addShariaBoardMember(member)
+      .accounts({
+        dao: daoPda,
+        shariaBoard: PublicKey.findProgramAddressSync([Buffer.from("sharia_board")], program.programId)[0],
+        boardMember: shariaMemberPdaFor(member),
+        auditLog: auditLogPda,
+        admin: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  };
+
+  const tokenAccountFor = async (owner: PublicKey) => {
+    const { tokenMint } = await program.account.dao.fetch(daoPda);
+    return (await getOrCreateAssociatedTokenAccount(provider.connection, payer, tokenMint, owner)).address;
+  };
+
+  // Voting power is the governance token balance, so voters need tokens minted to them
+  const fundVoter = async (owner: PublicKey, amount: number) => {
+    const { tokenMint } = await program.account.dao.fetch(daoPda);
+    await mintTo(provider.connection, payer, tokenMint, await tokenAccountFor(owner), payer, amount);
+  };
+
+  // A vote escrows the voter's whole balance until reclaim_vote_tokens
+  const voteAccounts = async (
+    proposalPda: PublicKey,
+    proposalId: anchor.BN,
+    voter: PublicKey,
+    voterTokenAccount?: PublicKey
+  ) => {
+    const { tokenMint } = await program.account.dao.fetch(daoPda);
+    return {
+      dao: daoPda,
+      proposal: proposalPda,
+      tokenMint,
+      voterTokenAccount: voterTokenAccount ?? (await tokenAccountFor(voter)),
+      voteEscrow: voteEscrowPdaFor(proposalId, voter),
+      voteRecord: voteRecordPdaFor(proposalId, voter),
+      voter,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+  };
+
+  const reclaimVoteTokens = async (proposalPda: PublicKey, voter: Keypair | null = null) => {
+    const { proposalId } = await program.account.proposal.fetch(proposalPda);
+    const voterKey = voter?.publicKey ?? provider.wallet.publicKey;
+    await program.methods
+      .reclaimVoteTokens()
+      .accounts({
+        dao: daoPda,
+        proposal: proposalPda,
+        voteEscrow: voteEscrowPdaFor(proposalId, voterKey),
+        voterTokenAccount: await tokenAccountFor(voterKey),
+        voter: voterKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers(voter ? [voter] : [])
+      .rpc();
+  };
+
+  // Quorum uses the DAO's cached supply, which execution requires to be fresh
+  const refreshSupply = async () => {
+    const { tokenMint } = await program.account.dao.fetch(daoPda);
+    await program.methods.refreshSupply().accounts({ dao: daoPda, tokenMint }).rpc();
+  };
+
+  const createProposal = async (
+    amount: number,
+    affectsSharia = false,
+    target: PublicKey = Keypair.generate().publicKey,
+    hai: PublicKey | null = null
+  ) => {
+    const { proposalCount } = await program.account.dao.fetch(daoPda);
+    const proposal = proposalPdaFor(proposalCount);
+
+    await program.methods
+      .createProposal(target, new anchor.BN(amount), affectsSharia)
+      .accounts({
+        dao: daoPda,
+        proposal,
+        treasury: treasuryPda,
+        hai,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Voting opens voting_delay after creation
+    const { votingStartsAt } = await program.account.proposal.fetch(proposal);
+    while ((await chainTime()) < votingStartsAt.toNumber()) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+
+    return proposal;
+  };
+
+  before(async () => {
+    [daoPda] = PublicKey.findProgramAddressSync([Buffer.from("dao")], program.programId);
+    [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+
+    [auditLogPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("audit_log")],
+      program.programId
+    );
+  });
+
+  describe("initialize", () => {
+    const initialize = async (votingDelay: number, period: number, quorumPercentage = 1000) => {
+      const tokenMint = await createMint(
+        provider.connection,
+        (provider.wallet as anchor.Wallet).payer,
+        provider.wallet.publicKey,
+        null,
+        6
+      );
+      await program.methods
+        .initialize(new anchor.BN(votingDelay), new anchor.BN(period), quorumPercentage)
+        .accounts({
+          dao: daoPda,
+          tokenMint,
+          timelock: Keypair.generate().publicKey,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    // The DAO is a singleton, so rejections can only be observed before it exists
+    beforeEach(async function () {
+      const title = this.currentTest!.title;
+      if (title.startsWith("Rejects") && (await program.account.dao.fetchNullable(daoPda)) !== null) {
+        this.skip();
+      }
+    });
+
+    it("Rejects a zero or negative voting delay", async () => {
+      await expectRejected(initialize(0, votingPeriod, 1000), "InvalidTiming");
+      await expectRejected(initialize(-1, votingPeriod, 1000), "InvalidTiming");
+    });
+
+    it("Rejects an oversized voting delay", async () => {
+      await expectRejected(initialize(8 * 24 * 60 * 60, votingPeriod, 1000), "InvalidTiming");
+    });
+
+    it("Rejects a zero or negative voting period", async () => {
+      await expectRejected(initialize(votingDelay, 0, 1000), "InvalidTiming");
+      await expectRejected(initialize(votingDelay, -votingPeriod, 1000), "InvalidTiming");
+    });
+
+    it("Rejects an oversized voting period", async () => {
+      await expectRejected(initialize(votingDelay, 31 * 24 * 60 * 60, 1000), "InvalidTiming");
+    });
+
+    it("Rejects a quorum above 10000 basis points", async () => {
+      await expectRejected(initialize(votingDelay, votingPeriod, 10001), "InvalidQuorum");
+    });
+
+    it("Initializes with valid parameters", async () => {
+      if ((await program.account.dao.fetchNullable(daoPda)) === null) {
+        await initialize(votingDelay, votingPeriod);
+      }
+
+      if ((await program.account.auditLog.fetchNullable(auditLogPda)) === null) {
+        await program.methods
+          .initializeAuditLog()
+          .accounts({
+            dao: daoPda,
+            auditLog: auditLogPda,
+            admin: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      const dao = await program.account.dao.fetch(daoPda);
+      expect(dao.votingDelay.toNumber()).to.equal(votingDelay);
+      expect(dao.votingPeriod.toNumber()).to.equal(votingPeriod);
+      expect(dao.baseQuorumBps).to.equal(1000);
+    });
+  });
+
+  describe("impact-scaled quorum", () => {
+    const treasuryBalance = 10_000_000_000;
+
+    before(async () => {
+      await program.methods
+        .setQuorumBounds(1000, 5000)
+        .accounts({ dao: daoPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+      const current = await provider.connection.getBalance(treasuryPda);
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: treasuryPda,
+            lamports: treasuryBalance - current,
+          })
+        )
+      );
+    });
+
+    it("Requires a higher quorum for a large-amount proposal", async () => {
+      const small = await program.account.proposal.fetch(await createProposal(100_000_000));
+      const large = await program.account.proposal.fetch(await createProposal(treasuryBalance));
+
+      // 1% of the treasury moves the quorum 1% of the way from base to max
+      expect(small.quorumBps).to.equal(1040);
+      expect(large.quorumBps).to.equal(5000);
+      expect(large.quorumBps).to.be.greaterThan(small.quorumBps);
+    });
+  });
+
+  describe("classify_proposal_sharia", () => {
+    const sensitiveTarget = Keypair.generate().publicKey;
+    let boardPda: PublicKey;
+
+    const sensitiveTargetPda = (target: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("sensitive_target"), target.toBuffer()],
+        program.programId
+      )[0];
+
+    before(async () => {
+      [boardPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sharia_board")],
+        program.programId
+      );
+
+      if ((await program.account.shariaBoard.fetchNullable(boardPda)) === null) {
+        await program.methods
+          .initShariaBoard()
+          .accounts({
+            shariaBoard: boardPda,
+            admin: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      await program.methods
+        .addSensitiveTarget(sensitiveTarget)
+        .accounts({
+          shariaBoard: boardPda,
+          sensitiveTarget: sensitiveTargetPda(sensitiveTarget),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Corrects a proposer's false affects_sharia = false", async () => {
+      const proposalPda = await createProposal(1_000_000, false, sensitiveTarget);
+      const created = await program.account.proposal.fetch(proposalPda);
+      expect(created.affectsSharia).to.be.false;
+      expect(created.shariaApproved).to.be.true;
+
+      await program.methods
+        .classifyProposalSharia(created.proposalId)
+        .accounts({
+          dao: daoPda,
+          shariaBoard: boardPda,
+          proposal: proposalPda,
+          sensitiveTarget: sensitiveTargetPda(sensitiveTarget),
+          auditLog: auditLogPda,
+          classifier: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const classified = await program.account.proposal.fetch(proposalPda);
+      expect(classified.affectsSharia).to.be.true;
+      expect(classified.shariaApproved).to.be.false;
+      expect(classified.shariaClassified).to.be.true;
+    });
+
+    it("Keeps a board rejection once the proposal has been reviewed", async () => {
+      // Claimed relevant, but the target is not on the registry
+      const target = Keypair.generate().publicKey;
+      const proposalPda = await createProposal(1_000_000, true, target);
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+
+      await addBoardMember(provider.wallet.publicKey);
+      await program.methods
+        .shariaReview(false)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          shariaBoard: boardPda,
+          review: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("review"),
+              proposalId.toArrayLike(Buffer, "le", 8),
+              provider.wallet.publicKey.toBuffer(),
+            ],
+            program.programId
+          )[0],
+          boardMemberRecord: shariaMemberPdaFor(provider.wallet.publicKey),
+          boardMember: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      try {
+        await program.methods
+          .classifyProposalSharia(proposalId)
+          .accounts({
+            dao: daoPda,
+            shariaBoard: boardPda,
+            proposal: proposalPda,
+            sensitiveTarget: sensitiveTargetPda(target),
+            auditLog: auditLogPda,
+            classifier: provider.wallet.publicKey,
+          })
+          .rpc();
+        expect.fail("classification should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ShariaAlreadyReviewed");
+      }
+
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.affectsSharia).to.be.true;
+      expect(proposal.shariaApproved).to.be.false;
+    });
+  });
+
+  describe("sharia review grace", () => {
+    const executionWindow = 1;
+    const shariaGrace = 60;
+
+    before(async () => {
+      await fundVoter(provider.wallet.publicKey, 100);
+      await addBoardMember(provider.wallet.publicKey);
+    });
+
+    const setExecutionWindow = async (window: number, grace: number) => {
+      await program.methods
+        .setExecutionWindow(new anchor.BN(window), new anchor.BN(grace))
+        .accounts({ dao: daoPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const passVote = async (proposalPda: PublicKey) => {
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .vote(proposal.proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposal.proposalId, provider.wallet.publicKey))
+        .rpc();
+
+      // Wait out voting and the general execution window
+      while ((await chainTime()) <= proposal.votingEndsAt.toNumber() + executionWindow) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(proposalPda);
+    };
+
+    const executeProposal = async (proposalPda: PublicKey) => {
+      const { targetAccount } = await program.account.proposal.fetch(proposalPda);
+      await refreshSupply();
+      await program.methods
+        .executeProposal()
+        .accounts({ dao: daoPda, proposal: proposalPda, recipient: targetAccount })
+        .rpc();
+    };
+
+    before(async () => setExecutionWindow(executionWindow, shariaGrace));
+
+    after(async () => setExecutionWindow(0, 0));
+
+    it("Expires an ordinary proposal after the execution window", async () => {
+      const proposalPda = await createProposal(1_000_000);
+      await passVote(proposalPda);
+
+      try {
+        await executeProposal(proposalPda);
+        expect.fail("execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ProposalExpired");
+      }
+    });
+
+    it("Executes a late-reviewed Sharia proposal within the grace", async () => {
+      const proposalPda = await createProposal(1_000_000, true);
+      await passVote(proposalPda);
+
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      const [reviewPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("review"),
+          proposalId.toArrayLike(Buffer, "le", 8),
+          provider.wallet.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .shariaReview(true)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          shariaBoard: PublicKey.findProgramAddressSync([Buffer.from("sharia_board")], program.programId)[0],
+          review: reviewPda,
+          boardMemberRecord: shariaMemberPdaFor(provider.wallet.publicKey),
+          boardMember: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await executeProposal(proposalPda);
+      const executed = await program.account.proposal.fetch(proposalPda);
+      expect(executed.status).to.deep.equal({ executed: {} });
+    });
+  });
+
+  describe("sharia board members", () => {
+    const member = Keypair.generate();
+    const [boardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sharia_board")],
+      program.programId
+    );
+    let approvedPda: PublicKey;
+
+    const reviewPdaFor = async (proposalPda: PublicKey, reviewer: PublicKey) => {
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("review"), proposalId.toArrayLike(Buffer, "le", 8), reviewer.toBuffer()],
+        program.programId
+      )[0];
+    };
+
+    const review = async (proposalPda: PublicKey, reviewer: Keypair) => {
+      await program.methods
+        .shariaReview(true)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          shariaBoard: boardPda,
+          review: await reviewPdaFor(proposalPda, reviewer.publicKey),
+          boardMemberRecord: shariaMemberPdaFor(reviewer.publicKey),
+          boardMember: reviewer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([reviewer])
+        .rpc();
+    };
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(member.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+    });
+
+    it("Rejects a review from a signer who is not on the board", async () => {
+      const proposalPda = await createProposal(0, true);
+
+      try {
+        await review(proposalPda, member);
+        expect.fail("review should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("AccountNotInitialized");
+      }
+    });
+
+    it("Records the added member and reports their key", async () => {
+      const { memberCount } = await program.account.shariaBoard.fetch(boardPda);
+      const { events } = await program.methods
+        .addShariaBoardMember(member.publicKey)
+        .accounts({
+          dao: daoPda,
+          shariaBoard: boardPda,
+          boardMember: shariaMemberPdaFor(member.publicKey),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .simulate();
+      const added = events.find((e) => e.name === "shariaBoardMemberAddedEvent")!.data as any;
+      expect(added.member.toBase58()).to.equal(member.publicKey.toBase58());
+
+      await addBoardMember(member.publicKey);
+
+      const record = await program.account.shariaBoardMember.fetch(shariaMemberPdaFor(member.publicKey));
+      expect(record.member.toBase58()).to.equal(member.publicKey.toBase58());
+      expect(record.isActive).to.be.true;
+      const board = await program.account.shariaBoard.fetch(boardPda);
+      expect(board.memberCount).to.equal(memberCount + 1);
+    });
+
+    it("Accepts a review from an active member", async () => {
+      approvedPda = await createProposal(0, true);
+      await review(approvedPda, member);
+
+      const proposal = await program.account.proposal.fetch(approvedPda);
+      expect(proposal.shariaApproved).to.be.true;
+      const record = await program.account.shariaBoardMember.fetch(shariaMemberPdaFor(member.publicKey));
+      expect(record.openApprovals).to.equal(1);
+    });
+
+    it("Closes a review only once its proposal is canceled", async () => {
+      const proposalPda = await createProposal(0, true);
+      await review(proposalPda, member);
+      const reviewPda = await reviewPdaFor(proposalPda, member.publicKey);
+
+      const close = () =>
+        program.methods
+          .closeShariaReview()
+          .accounts({
+            proposal: proposalPda,
+            review: reviewPda,
+            boardMemberRecord: shariaMemberPdaFor(member.publicKey),
+            boardMember: member.publicKey,
+          })
+          .signers([member])
+          .rpc();
+
+      try {
+        await close();
+        expect.fail("close should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReviewStillOpen");
+      }
+
+      await program.methods
+        .cancelProposal(7)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          boardMemberRecord: null,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+      await close();
+
+      expect(await program.account.shariaReview.fetchNullable(reviewPda)).to.be.null;
+      const record = await program.account.shariaBoardMember.fetch(shariaMemberPdaFor(member.publicKey));
+      expect(record.openApprovals).to.equal(1);
+    });
+
+    const removal = () =>
+      program.methods
+        .removeShariaBoardMember()
+        .accounts({
+          dao: daoPda,
+          shariaBoard: boardPda,
+          boardMember: shariaMemberPdaFor(member.publicKey),
+          member: member.publicKey,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        });
+
+    it("Refuses a removal that leaves an open approval counted", async () => {
+      try {
+        await removal().rpc();
+        expect.fail("removal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("OpenApprovalsRemain");
+      }
+    });
+
+    it("Drops the member's open approvals, closes the record and refuses the former member", async () => {
+      const reviewPda = await reviewPdaFor(approvedPda, member.publicKey);
+      const withApprovals = removal().remainingAccounts([
+        { pubkey: approvedPda, isSigner: false, isWritable: true },
+        { pubkey: reviewPda, isSigner: false, isWritable: true },
+      ]);
+      const { events } = await withApprovals.simulate();
+      const removed = events.find((e) => e.name === "shariaBoardMemberRemovedEvent")!.data as any;
+      expect(removed.member.toBase58()).to.equal(member.publicKey.toBase58());
+
+      await withApprovals.rpc();
+      expect(
+        await program.account.shariaBoardMember.fetchNullable(shariaMemberPdaFor(member.publicKey))
+      ).to.be.null;
+      expect(await program.account.shariaReview.fetchNullable(reviewPda)).to.be.null;
+      const dropped = await program.account.proposal.fetch(approvedPda);
+      expect(dropped.approvalCount).to.equal(0);
+      expect(dropped.shariaApproved).to.be.false;
+
+      const proposalPda = await createProposal(0, true);
+      try {
+        await review(proposalPda, member);
+        expect.fail("review should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("AccountNotInitialized");
+      }
+    });
+  });
+
+  describe("sharia approval threshold", () => {
+    const reviewers = [Keypair.generate(), Keypair.generate()];
+    const [boardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sharia_board")],
+      program.programId
+    );
+
+    const setRequiredApprovals = (requiredApprovals: number) =>
+      program.methods
+        .setRequiredApprovals(requiredApprovals)
+        .accounts({
+          dao: daoPda,
+          shariaBoard: boardPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    const review = (proposalPda: PublicKey, proposalId: anchor.BN, reviewer: Keypair) =>
+      program.methods
+        .shariaReview(true)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          shariaBoard: boardPda,
+          review: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("review"),
+              proposalId.toArrayLike(Buffer, "le", 8),
+              reviewer.publicKey.toBuffer(),
+            ],
+            program.programId
+          )[0],
+          boardMemberRecord: shariaMemberPdaFor(reviewer.publicKey),
+          boardMember: reviewer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([reviewer]);
+
+    before(async () => {
+      for (const reviewer of reviewers) {
+        const sig = await provider.connection.requestAirdrop(reviewer.publicKey, 1_000_000_000);
+        await provider.connection.confirmTransaction(sig);
+        await addBoardMember(reviewer.publicKey);
+      }
+    });
+
+    after(async () => setRequiredApprovals(1));
+
+    it("Refuses a removal that leaves fewer members than required approvals", async () => {
+      const { memberCount } = await program.account.shariaBoard.fetch(boardPda);
+      await setRequiredApprovals(memberCount);
+
+      try {
+        await program.methods
+          .removeShariaBoardMember()
+          .accounts({
+            dao: daoPda,
+            shariaBoard: boardPda,
+            boardMember: shariaMemberPdaFor(reviewers[0].publicKey),
+            member: reviewers[0].publicKey,
+            auditLog: auditLogPda,
+            admin: provider.wallet.publicKey,
+          })
+          .rpc();
+        expect.fail("removal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("BoardBelowThreshold");
+      }
+    });
+
+    it("Rejects a threshold of zero or above the member count", async () => {
+      const { memberCount } = await program.account.shariaBoard.fetch(boardPda);
+      for (const threshold of [0, memberCount + 1]) {
+        try {
+          await setRequiredApprovals(threshold);
+          expect.fail("threshold should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("InvalidApprovalThreshold");
+        }
+      }
+    });
+
+    it("Approves only once enough members have approved", async () => {
+      await setRequiredApprovals(2);
+      const proposalPda = await createProposal(0, true);
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+
+      await review(proposalPda, proposalId, reviewers[0]).rpc();
+      const pending = await program.account.proposal.fetch(proposalPda);
+      expect(pending.approvalCount).to.equal(1);
+      expect(pending.shariaApproved).to.be.false;
+
+      const { events } = await review(proposalPda, proposalId, reviewers[1]).simulate();
+      const met = events.find((e) => e.name === "shariaApprovalThresholdMetEvent")!.data as any;
+      expect(met.approvalCount).to.equal(2);
+      expect(met.requiredApprovals).to.equal(2);
+
+      await review(proposalPda, proposalId, reviewers[1]).rpc();
+      const approved = await program.account.proposal.fetch(proposalPda);
+      expect(approved.approvalCount).to.equal(2);
+      expect(approved.shariaApproved).to.be.true;
+    });
+  });
+
+  describe("cancel_proposal", () => {
+    const proposer = Keypair.generate();
+    const boardMember = Keypair.generate();
+    const outsider = Keypair.generate();
+
+    const cancel = (proposalPda: PublicKey, authority: Keypair | null, boardMemberRecord: PublicKey | null = null) =>
+      program.methods
+        .cancelProposal(7)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          boardMemberRecord,
+          authority: authority?.publicKey ?? provider.wallet.publicKey,
+        })
+        .signers(authority ? [authority] : []);
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(proposer.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await addBoardMember(boardMember.publicKey);
+    });
+
+    it("Rejects a signer who is neither admin, proposer nor board member", async () => {
+      const proposalPda = await createProposal(0);
+
+      try {
+        await cancel(proposalPda, outsider).rpc();
+        expect.fail("cancellation should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+
+    it("Lets the proposer cancel their own proposal", async () => {
+      const { proposalCount } = await program.account.dao.fetch(daoPda);
+      const proposalPda = proposalPdaFor(proposalCount);
+      await program.methods
+        .createProposal(Keypair.generate().publicKey, new anchor.BN(0), false)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          treasury: treasuryPda,
+          hai: null,
+          proposer: proposer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([proposer])
+        .rpc();
+
+      await cancel(proposalPda, proposer).rpc();
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.status).to.deep.equal({ canceled: {} });
+    });
+
+    it("Lets an active board member veto and reports who canceled", async () => {
+      const proposalPda = await createProposal(0);
+      const record = shariaMemberPdaFor(boardMember.publicKey);
+
+      const { events } = await cancel(proposalPda, boardMember, record).simulate();
+      const canceled = events.find((e) => e.name === "proposalCanceledEvent")!.data as any;
+      expect(canceled.canceledBy.toBase58()).to.equal(boardMember.publicKey.toBase58());
+      expect(canceled.reasonCode).to.equal(7);
+
+      await cancel(proposalPda, boardMember, record).rpc();
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.status).to.deep.equal({ canceled: {} });
+    });
+
+    it("Lets the DAO admin cancel any proposal", async () => {
+      const proposalPda = await createProposal(0);
+      await cancel(proposalPda, null).rpc();
+
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.status).to.deep.equal({ canceled: {} });
+    });
+  });
+
+  describe("HAI spending gate", () => {
+    const haiProgram = anchor.workspace.AmanaHai as Program;
+    const [haiPda] = PublicKey.findProgramAddressSync([Buffer.from("hai")], haiProgram.programId);
+    let score: number;
+
+    const setMinHai = async (minHai: number) => {
+      await program.methods
+        .setMinHaiForSpending(minHai)
+        .accounts({ dao: daoPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      if ((await haiProgram.account.hai.fetchNullable(haiPda)) === null) {
+        await haiProgram.methods
+          .initialize(5000)
+          .accounts({
+            hai: haiPda,
+            admin: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+      ({ currentScore: score } = await haiProgram.account.hai.fetch(haiPda));
+    });
+
+    after(async () => setMinHai(0));
+
+    it("Blocks spending proposals while the HAI is below the floor", async () => {
+      await setMinHai(score + 1);
+
+      try {
+        await createProposal(1_000_000, false, Keypair.generate().publicKey, haiPda);
+        expect.fail("spending proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("HaiTooLow");
+      }
+    });
+
+    it("Still accepts governance proposals under a low HAI", async () => {
+      await setMinHai(score + 1);
+
+      const proposalPda = await createProposal(0);
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.amount.toNumber()).to.equal(0);
+    });
+
+    it("Accepts spending proposals once the HAI meets the floor", async () => {
+      await setMinHai(score);
+
+      const proposalPda = await createProposal(1_000_000, false, Keypair.generate().publicKey, haiPda);
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.amount.toNumber()).to.equal(1_000_000);
+    });
+  });
+
+  describe("execution allowlist", () => {
+    const otherProgram = Keypair.generate().publicKey;
+    const payout = 1_000_000_000;
+    const [allowlistPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("execution_allowlist")],
+      program.programId
+    );
+
+    const passVote = async (proposalPda: PublicKey) => {
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .vote(proposal.proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposal.proposalId, provider.wallet.publicKey))
+        .rpc();
+
+      while ((await chainTime()) <= proposal.votingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(proposalPda);
+    };
+
+    const executeProposal = async (proposalPda: PublicKey) => {
+      const { targetAccount } = await program.account.proposal.fetch(proposalPda);
+      await refreshSupply();
+      await program.methods
+        .executeProposal()
+        .accounts({ dao: daoPda, proposal: proposalPda, recipient: targetAccount })
+        .rpc();
+    };
+
+    const setExecutionAllowlist = async (programs: PublicKey[]) => {
+      await program.methods
+        .setExecutionAllowlist(programs)
+        .accounts({
+          dao: daoPda,
+          executionAllowlist: allowlistPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      await fundVoter(provider.wallet.publicKey, 100);
+      await setExecutionAllowlist([otherProgram]);
+    });
+
+    after(async () => {
+      await program.methods
+        .clearExecutionAllowlist()
+        .accounts({
+          dao: daoPda,
+          executionAllowlist: allowlistPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Copies the allowlist onto a new proposal", async () => {
+      const proposal = await program.account.proposal.fetch(await createProposal(0));
+      expect(proposal.allowlistEnforced).to.be.true;
+      expect(proposal.allowedPrograms.map((key: PublicKey) => key.toBase58())).to.deep.equal([otherProgram.toBase58()]);
+    });
+
+    it("Executes a governance proposal, which invokes no program", async () => {
+      const proposalPda = await createProposal(0);
+      await passVote(proposalPda);
+
+      await executeProposal(proposalPda);
+      const executed = await program.account.proposal.fetch(proposalPda);
+      expect(executed.status).to.deep.equal({ executed: {} });
+    });
+
+    it("Rejects a payout while the System Program is off the proposal's allowlist", async () => {
+      const proposalPda = await createProposal(payout);
+      // Allowing the System Program later does not reach a proposal created before
+      await setExecutionAllowlist([otherProgram, SystemProgram.programId]);
+      await passVote(proposalPda);
+
+      try {
+        await executeProposal(proposalPda);
+        expect.fail("execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("TargetNotAllowed");
+      }
+    });
+
+    it("Pays out a proposal created while the System Program is allowlisted", async () => {
+      const tx = new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: treasuryPda,
+          lamports: payout,
+        })
+      );
+      await provider.sendAndConfirm(tx);
+
+      const proposalPda = await createProposal(payout);
+      // Removing the System Program later does not reach a proposal created before
+      await setExecutionAllowlist([otherProgram]);
+      await passVote(proposalPda);
+
+      await executeProposal(proposalPda);
+      const executed = await program.account.proposal.fetch(proposalPda);
+      expect(executed.status).to.deep.equal({ executed: {} });
+    });
+
+    it("Rejects an allowlist over the size limit", async () => {
+      const programs = Array.from({ length: 17 }, () => Keypair.generate().publicKey);
+      try {
+        await setExecutionAllowlist(programs);
+        expect.fail("oversized allowlist should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("AllowlistFull");
+      }
+    });
+  });
+
+  describe("quorum", () => {
+    const minority = Keypair.generate();
+
+    const voteFor = async (proposalPda: PublicKey, voter: Keypair | null) => {
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      const voterKey = voter?.publicKey ?? provider.wallet.publicKey;
+      await program.methods
+        .vote(proposal.proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposal.proposalId, voterKey))
+        .signers(voter ? [voter] : [])
+        .rpc();
+
+      while ((await chainTime()) <= proposal.votingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(proposalPda, voter);
+    };
+
+    const executeProposal = async (proposalPda: PublicKey) => {
+      const { targetAccount } = await program.account.proposal.fetch(proposalPda);
+      await refreshSupply();
+      await program.methods
+        .executeProposal()
+        .accounts({ dao: daoPda, proposal: proposalPda, recipient: targetAccount })
+        .rpc();
+    };
+
+    before(async () => {
+      await fundVoter(provider.wallet.publicKey, 1_000);
+      const sig = await provider.connection.requestAirdrop(minority.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await fundVoter(minority.publicKey, 1);
+    });
+
+    it("Rejects execution when too little of the supply voted", async () => {
+      const proposalPda = await createProposal(0);
+      await voteFor(proposalPda, minority);
+
+      try {
+        await executeProposal(proposalPda);
+        expect.fail("execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("QuorumNotMet");
+      }
+    });
+
+    it("Executes once the votes cast reach the quorum", async () => {
+      const proposalPda = await createProposal(0);
+      await voteFor(proposalPda, null);
+
+      await executeProposal(proposalPda);
+      const executed = await program.account.proposal.fetch(proposalPda);
+      expect(executed.status).to.deep.equal({ executed: {} });
+    });
+  });
+
+  describe("cached supply", () => {
+    const maxSupplyAge = 2;
+
+    const setMaxSupplyAge = async (seconds: number) => {
+      await program.methods
+        .setMaxSupplyAge(new anchor.BN(seconds))
+        .accounts({ dao: daoPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const passVote = async (proposalPda: PublicKey) => {
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .vote(proposal.proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposal.proposalId, provider.wallet.publicKey))
+        .rpc();
+
+      while ((await chainTime()) <= proposal.votingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(proposalPda);
+    };
+
+    const execute = async (proposalPda: PublicKey) => {
+      const { targetAccount } = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .executeProposal()
+        .accounts({ dao: daoPda, proposal: proposalPda, recipient: targetAccount })
+        .rpc();
+    };
+
+    before(async () => {
+      await fundVoter(provider.wallet.publicKey, 100);
+      await setMaxSupplyAge(maxSupplyAge);
+    });
+
+    after(async () => setMaxSupplyAge(60 * 60));
+
+    it("Caches the mint supply on refresh", async () => {
+      await refreshSupply();
+
+      const { tokenMint, totalSupply, supplyRefreshedAt } = await program.account.dao.fetch(daoPda);
+      const mint = await getMint(provider.connection, tokenMint);
+      expect(totalSupply.toString()).to.equal(mint.supply.toString());
+      expect(supplyRefreshedAt.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("Rejects execution with a stale supply until it is refreshed", async () => {
+      const proposalPda = await createProposal(0);
+      await refreshSupply();
+      await passVote(proposalPda);
+
+      const { supplyRefreshedAt } = await program.account.dao.fetch(daoPda);
+      while ((await chainTime()) <= supplyRefreshedAt.toNumber() + maxSupplyAge) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      try {
+        await execute(proposalPda);
+        expect.fail("execution with a stale supply should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("StaleSupply");
+      }
+
+      await refreshSupply();
+      await execute(proposalPda);
+      const executed = await program.account.proposal.fetch(proposalPda);
+      expect(executed.status).to.deep.equal({ executed: {} });
+    });
+
+    it("Rejects a zero max supply age", async () => {
+      try {
+        await setMaxSupplyAge(0);
+        expect.fail("zero max supply age should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidTiming");
+      }
+    });
+  });
+
+  describe("treasury payouts", () => {
+    const payout = 1_000_000_000;
+
+    before(async () => {
+      await fundVoter(provider.wallet.publicKey, 100);
+      const tx = new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: treasuryPda,
+          lamports: payout,
+        })
+      );
+      await provider.sendAndConfirm(tx);
+    });
+
+    const passVote = async (proposalPda: PublicKey) => {
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .vote(proposal.proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposal.proposalId, provider.wallet.publicKey))
+        .rpc();
+
+      while ((await chainTime()) <= proposal.votingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(proposalPda);
+    };
+
+    const execute = async (proposalPda: PublicKey, recipient: PublicKey) => {
+      await refreshSupply();
+      return program.methods
+        .executeProposal()
+        .accounts({ dao: daoPda, proposal: proposalPda, recipient });
+    };
+
+    it("Pays the proposal amount from the treasury to the target", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const proposalPda = await createProposal(payout, false, recipient);
+      await passVote(proposalPda);
+
+      const { events } = await (await execute(proposalPda, recipient)).simulate();
+      const executed = events.find((e) => e.name === "proposalExecutedEvent")!.data as any;
+      expect(executed.recipient.toBase58()).to.equal(recipient.toBase58());
+      expect(executed.amount.toNumber()).to.equal(payout);
+
+      const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+      await (await execute(proposalPda, recipient)).rpc();
+
+      expect(await provider.connection.getBalance(recipient)).to.equal(payout);
+      expect(treasuryBefore - (await provider.connection.getBalance(treasuryPda))).to.equal(payout);
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.status).to.deep.equal({ executed: {} });
+    });
+
+    it("Never pays out the same proposal twice", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const proposalPda = await createProposal(payout / 10, false, recipient);
+      await passVote(proposalPda);
+      await (await execute(proposalPda, recipient)).rpc();
+
+      try {
+        await (await execute(proposalPda, recipient)).rpc();
+        expect.fail("re-execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidProposalStatus");
+      }
+      expect(await provider.connection.getBalance(recipient)).to.equal(payout / 10);
+    });
+
+    it("Rejects a recipient other than the proposal's target", async () => {
+      const proposalPda = await createProposal(payout / 10);
+      await passVote(proposalPda);
+
+      try {
+        await (await execute(proposalPda, provider.wallet.publicKey)).rpc();
+        expect.fail("execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidRecipient");
+      }
+    });
+
+    it("Rejects a payout the treasury cannot cover", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const treasury = await provider.connection.getBalance(treasuryPda);
+      const proposalPda = await createProposal(treasury + 1, false, recipient);
+      await passVote(proposalPda);
+
+      try {
+        await (await execute(proposalPda, recipient)).rpc();
+        expect.fail("execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InsufficientTreasury");
+      }
+    });
+  });
+
+  describe("vote records", () => {
+    before(async () => fundVoter(provider.wallet.publicKey, 100));
+
+    const vote = async (proposalPda: PublicKey, proposalId: anchor.BN, choice: object) =>
+      program.methods
+        .vote(proposalId, choice)
+        .accounts(await voteAccounts(proposalPda, proposalId, provider.wallet.publicKey))
+        .rpc();
+
+    it("Records the voter's choice and weight", async () => {
+      const proposalPda = await createProposal(0);
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      const balance = (await getAccount(provider.connection, await tokenAccountFor(provider.wallet.publicKey))).amount;
+      await vote(proposalPda, proposalId, { against: {} });
+
+      const record = await program.account.voteRecord.fetch(
+        voteRecordPdaFor(proposalId, provider.wallet.publicKey)
+      );
+      expect(record.proposalId.toString()).to.equal(proposalId.toString());
+      expect(record.voter.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      expect(record.vote).to.deep.equal({ against: {} });
+      expect(record.weight.toString()).to.equal(balance.toString());
+    });
+
+    it("Rejects a second vote from the same voter", async () => {
+      // The first vote escrowed the whole balance
+      await fundVoter(provider.wallet.publicKey, 100);
+      const proposalPda = await createProposal(0);
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      await vote(proposalPda, proposalId, { for: {} });
+      const { forVotes } = await program.account.proposal.fetch(proposalPda);
+
+      try {
+        await vote(proposalPda, proposalId, { for: {} });
+        expect.fail("the second vote should have been rejected");
+      } catch (err: any) {
+        expect(err.logs.join("\n")).to.include("already in use");
+      }
+
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.forVotes.toString()).to.equal(forVotes.toString());
+    });
+  });
+
+  describe("vote escrow", () => {
+    const balance = 300;
+
+    const vote = async (proposalPda: PublicKey) => {
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .vote(proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposalId, provider.wallet.publicKey))
+        .rpc();
+      return proposalId;
+    };
+
+    const walletBalance = async () =>
+      Number((await getAccount(provider.connection, await tokenAccountFor(provider.wallet.publicKey))).amount);
+
+    it("Holds the voted tokens until voting ends", async () => {
+      await fundVoter(provider.wallet.publicKey, balance);
+      const before = await walletBalance();
+      const proposalPda = await createProposal(0);
+      const proposalId = await vote(proposalPda);
+
+      const escrow = await getAccount(provider.connection, voteEscrowPdaFor(proposalId, provider.wallet.publicKey));
+      expect(Number(escrow.amount)).to.equal(before);
+      expect(await walletBalance()).to.equal(0);
+
+      try {
+        await reclaimVoteTokens(proposalPda);
+        expect.fail("the tokens should stay escrowed while voting is open");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("VoteTokensLocked");
+      }
+
+      const { votingEndsAt } = await program.account.proposal.fetch(proposalPda);
+      while ((await chainTime()) <= votingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(proposalPda);
+
+      expect(await walletBalance()).to.equal(before);
+      expect(
+        await provider.connection.getAccountInfo(voteEscrowPdaFor(proposalId, provider.wallet.publicKey))
+      ).to.be.null;
+    });
+
+    it("Returns the tokens early from a canceled proposal", async () => {
+      const before = await walletBalance();
+      const proposalPda = await createProposal(0);
+      await vote(proposalPda);
+
+      await program.methods
+        .cancelProposal(0)
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPda,
+          boardMemberRecord: null,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+      await reclaimVoteTokens(proposalPda);
+
+      expect(await walletBalance()).to.equal(before);
+    });
+  });
+
+  describe("token-weighted votes", () => {
+    const voter = Keypair.generate();
+    const balance = 7_500;
+
+    const vote = async (proposalPda: PublicKey, voterTokenAccount: PublicKey) => {
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      await program.methods
+        .vote(proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposalId, voter.publicKey, voterTokenAccount))
+        .signers([voter])
+        .rpc();
+    };
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(voter.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+    });
+
+    it("Rejects a voter without governance tokens", async () => {
+      const proposalPda = await createProposal(0);
+      try {
+        await vote(proposalPda, await tokenAccountFor(voter.publicKey));
+        expect.fail("vote should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NoVotingPower");
+      }
+    });
+
+    it("Rejects a token account the voter does not own", async () => {
+      const proposalPda = await createProposal(0);
+      try {
+        await vote(proposalPda, await tokenAccountFor(provider.wallet.publicKey));
+        expect.fail("vote should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidTokenAccount");
+      }
+    });
+
+    it("Counts the voter's token balance as the weight", async () => {
+      await fundVoter(voter.publicKey, balance);
+      const proposalPda = await createProposal(0);
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      const { events } = await program.methods
+        .vote(proposalId, { for: {} })
+        .accounts(await voteAccounts(proposalPda, proposalId, voter.publicKey))
+        .signers([voter])
+        .simulate();
+      const cast = events.find((e) => e.name === "voteCastEvent")!.data as any;
+      expect(cast.weight.toNumber()).to.equal(balance);
+      expect(cast.balance.toNumber()).to.equal(balance);
+
+      await vote(proposalPda, await tokenAccountFor(voter.publicKey));
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.forVotes.toNumber()).to.equal(balance);
+    });
+  });
+
+  describe("vote weight cap", () => {
+    const supply = 1_000_000;
+    const smallBalance = 10_000;
+    const capBps = 500; // 5% of supply
+    let tokenMint: PublicKey;
+    let holderAccount: PublicKey;
+    let cappedProposalPda: PublicKey;
+
+    const setMaxVoteWeight = async (bps: number) => {
+      await program.methods
+        .setMaxVoteWeight(bps)
+        .accounts({ dao: daoPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const smallHolder = Keypair.generate();
+
+    const vote = async (proposalPda: PublicKey, choice: object, voter?: Keypair) => {
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      const voterKey = voter?.publicKey ?? provider.wallet.publicKey;
+      await program.methods
+        .vote(proposalId, choice)
+        .accounts(await voteAccounts(proposalPda, proposalId, voterKey))
+        .signers(voter ? [voter] : [])
+        .rpc();
+    };
+
+    before(async () => {
+      ({ tokenMint } = await program.account.dao.fetch(daoPda));
+      holderAccount = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, payer, tokenMint, provider.wallet.publicKey)
+      ).address;
+      await mintTo(provider.connection, payer, tokenMint, holderAccount, payer, supply);
+      await setMaxVoteWeight(capBps);
+      const sig = await provider.connection.requestAirdrop(smallHolder.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await fundVoter(smallHolder.publicKey, smallBalance);
+    });
+
+    // Other suites rely on the provider wallet holding most of the supply for their quorum
+    after(async () => {
+      const proposal = await program.account.proposal.fetch(cappedProposalPda);
+      while ((await chainTime()) <= proposal.votingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await reclaimVoteTokens(cappedProposalPda);
+      await reclaimVoteTokens(cappedProposalPda, smallHolder);
+      await setMaxVoteWeight(0);
+      await burn(provider.connection, payer, holderAccount, tokenMint, payer, supply);
+      await burn(
+        provider.connection,
+        payer,
+        await tokenAccountFor(smallHolder.publicKey),
+        tokenMint,
+        smallHolder,
+        smallBalance
+      );
+    });
+
+    it("Caps a whale's weight but not a small holder's", async () => {
+      cappedProposalPda = await createProposal(0);
+      await vote(cappedProposalPda, { for: {} });
+      await vote(cappedProposalPda, { against: {} }, smallHolder);
+
+      const totalSupply = Number((await getMint(provider.connection, tokenMint)).supply);
+      const proposal = await program.account.proposal.fetch(cappedProposalPda);
+      expect(proposal.forVotes.toNumber()).to.equal(Math.floor((totalSupply * capBps) / 10_000));
+      expect(proposal.againstVotes.toNumber()).to.equal(smallBalance);
+    });
+
+    it("Rejects a mint other than the DAO's governance token", async () => {
+      const otherMint = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 0);
+      const proposalPda = await createProposal(0);
+      const { proposalId } = await program.account.proposal.fetch(proposalPda);
+      try {
+        await program.methods
+          .vote(proposalId, { for: {} })
+          .accounts({
+            ...(await voteAccounts(proposalPda, proposalId, provider.wallet.publicKey, holderAccount)),
+            tokenMint: otherMint,
+          })
+          .rpc();
+        expect.fail("vote should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidTokenMint");
+      }
+    });
+  });
+});
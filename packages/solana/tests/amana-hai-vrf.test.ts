@@ -13,9 +13,17 @@
 
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey, Keypair } from "@solana/web3.js";
+import { PublicKey, Keypair, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
 import { expect } from "chai";
 
+// MagicBlock VRF program, its callback signer and the default oracle queue
+const VRF_PROGRAM_ID = new PublicKey("Vrf1RNUjXmQGjmQrQLvJHs9SNkvDJEsRVFPkfSQUwGz");
+const VRF_PROGRAM_IDENTITY = PublicKey.findProgramAddressSync(
+  [Buffer.from("identity")],
+  VRF_PROGRAM_ID
+)[0];
+const VRF_ORACLE_QUEUE = new PublicKey("Cuj97ggrhhidhbu39TijNVqE74xvKJ69gDervRUXAxGh");
+
 describe("amana-hai with VRF integration", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -46,21 +54,63 @@ describe("amana-hai with VRF integration", () => {
     expect(hai.currentScore).to.equal(8500);
   });
 
-  it("Updates HAI score with VRF", async () => {
+  it("Builds a VRF randomness request recorded in a VrfRequest PDA", async () => {
     const dataSources = [1, 2, 3, 4, 5]; // Mock data sources
+    const callerSeed = Array.from(Buffer.alloc(32, 7));
 
-    const tx = await program.methods
-      .updateHaiScoreWithVrf(activityId, dataSources)
+    const [vrfRequestPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vrf_request"), Buffer.from(activityId)],
+      program.programId
+    );
+    const [programIdentity] = PublicKey.findProgramAddressSync(
+      [Buffer.from("identity")],
+      program.programId
+    );
+
+    const ix = await program.methods
+      .requestHaiRandomness(activityId, Buffer.from(dataSources), callerSeed)
       .accounts({
         hai: haiPda,
+        vrfRequest: vrfRequestPda,
+        updaterAccount: null,
+        oracleQueue: VRF_ORACLE_QUEUE,
         payer: provider.wallet.publicKey,
+        programIdentity,
+        vrfProgram: VRF_PROGRAM_ID,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .rpc();
+      .instruction();
+
+    const meta = (key: PublicKey) => ix.keys.find((k) => k.pubkey.equals(key));
+    // The request is created by the same instruction and filled in by the callback
+    expect(meta(vrfRequestPda)?.isWritable).to.equal(true);
+    expect(meta(VRF_ORACLE_QUEUE)?.isWritable).to.equal(true);
+    expect(meta(VRF_PROGRAM_ID)).to.not.equal(undefined);
+    expect(meta(provider.wallet.publicKey)?.isSigner).to.equal(true);
+  });
 
-    console.log("✅ HAI score updated with VRF:", tx);
-    
-    const hai = await program.account.hai.fetch(haiPda);
-    console.log("New HAI score:", hai.currentScore);
+  it("Accepts randomness only from the VRF program identity", async () => {
+    const [vrfRequestPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vrf_request"), Buffer.from(activityId)],
+      program.programId
+    );
+
+    const ix = await program.methods
+      .callbackHaiRandomness(Array.from(Buffer.alloc(32, 9)))
+      .accounts({
+        vrfProgramIdentity: VRF_PROGRAM_IDENTITY,
+        hai: haiPda,
+        vrfRequest: vrfRequestPda,
+        requester: provider.wallet.publicKey,
+      })
+      .instruction();
+
+    expect(ix.keys[0].pubkey.equals(VRF_PROGRAM_IDENTITY)).to.equal(true);
+    expect(ix.keys[0].isSigner).to.equal(true);
+    // The callback closes the request and refunds the requester
+    const requester = ix.keys.find((k) => k.pubkey.equals(provider.wallet.publicKey));
+    expect(requester?.isWritable).to.equal(true);
   });
 
   it("Updates HAI in real-time on ER", async () => {
@@ -80,6 +130,7 @@ describe("amana-hai with VRF integration", () => {
       .updateHaiRealtime(activityId, 100) // +1% compliance boost
       .accounts({
         hai: haiPda,
+        updaterAccount: null,
         payer: provider.wallet.publicKey,
       })
       .rpc();
@@ -103,4 +154,34 @@ describe("amana-hai with VRF integration", () => {
 
     console.log("✅ HAI scores committed to base layer:", tx);
   });
+
+  it("Builds a session finalization that undelegates HAI", async () => {
+    const magicContext = Keypair.generate().publicKey; // Mock
+    const magicProgram = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+
+    const { snapshotCount } = await program.account.hai.fetch(haiPda);
+    const [snapshotPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("snapshot"), snapshotCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const ix = await program.methods
+      .finalizeHaiSession()
+      .accounts({
+        hai: haiPda,
+        updaterAccount: null,
+        payer: provider.wallet.publicKey,
+        magicContext,
+        magicProgram,
+      })
+      .instruction();
+
+    const meta = (key: PublicKey) => ix.keys.find((k) => k.pubkey.equals(key));
+    expect(meta(haiPda)?.isWritable).to.equal(true);
+    // The final snapshot is taken on the base layer by record_final_snapshot
+    expect(meta(snapshotPda)).to.equal(undefined);
+    expect(meta(magicContext)).to.not.equal(undefined);
+    expect(meta(magicProgram)).to.not.equal(undefined);
+    expect(meta(provider.wallet.publicKey)?.isSigner).to.equal(true);
+  });
 });
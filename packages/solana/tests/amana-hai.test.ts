@@ -0,0 +1,1224 @@
+// Copyright 2026 TAWF Labs
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { BankrunProvider } from "anchor-bankrun";
+import { expect } from "chai";
+import { expectRejected } from "./helpers";
+import { startAnchor } from "solana-bankrun";
+
+describe("amana-hai", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.AmanaHai as Program;
+
+  let haiPda: PublicKey;
+  let auditLogPda: PublicKey;
+  const activityId = Array.from(Buffer.alloc(32, 20));
+
+  const metricsPdaFor = (id: number[]) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("metrics"), Buffer.from(id)],
+      program.programId
+    )[0];
+
+  const trackActivity = async (
+    id: number[],
+    isCompliant: boolean,
+    isAssetBacked: boolean,
+    hasRealEconomicValue: boolean,
+    category: object | null = null
+  ) => {
+    await program.methods
+      .trackActivity(
+        id,
+        isCompliant,
+        isAssetBacked,
+        hasRealEconomicValue,
+        5,
+        4,
+        category,
+        new anchor.BN(0)
+      )
+      .accounts({
+        hai: haiPda,
+        metrics: metricsPdaFor(id),
+        validator: null,
+        activity: null,
+        updaterAccount: null,
+        payer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  };
+
+  before(async () => {
+    [haiPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("hai")],
+      program.programId
+    );
+
+    if ((await program.account.hai.fetchNullable(haiPda)) === null) {
+      await program.methods
+        .initialize(5000)
+        .accounts({
+          hai: haiPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    [auditLogPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("audit_log")],
+      program.programId
+    );
+
+    if ((await program.account.auditLog.fetchNullable(auditLogPda)) === null) {
+      await program.methods
+        .initializeAuditLog()
+        .accounts({
+          hai: haiPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  describe("initialize", () => {
+    it("Caps the initial score at the basis-point maximum", async () => {
+      const context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      const bankrunProgram = new Program(program.idl, bankrunProvider);
+      await bankrunProgram.methods
+        .initialize(20000)
+        .accounts({ hai: haiPda, admin: bankrunProvider.wallet.publicKey, systemProgram: SystemProgram.programId })
+        .rpc();
+
+      expect((await bankrunProgram.account.hai.fetch(haiPda)).currentScore).to.equal(10000);
+    });
+  });
+
+  describe("default weights", () => {
+    it("Starts with the documented weights", async () => {
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.complianceWeight).to.equal(4000);
+      expect(hai.assetBackingWeight).to.equal(2500);
+      expect(hai.economicValueWeight).to.equal(2000);
+      expect(hai.validatorParticipationWeight).to.equal(1500);
+    });
+
+    it("Yields a non-zero score from tracking without update_weights", async () => {
+      await trackActivity(Array.from(Buffer.alloc(32, 19)), true, true, true);
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.currentScore).to.be.greaterThan(0);
+    });
+  });
+
+  describe("update_hai_realtime", () => {
+    const applyDelta = async (delta: number) => {
+      await program.methods
+        .updateHaiRealtime(activityId, delta)
+        .accounts({
+          hai: haiPda,
+          updaterAccount: null,
+          payer: provider.wallet.publicKey,
+        })
+        .rpc();
+      return (await program.account.hai.fetch(haiPda)).currentScore;
+    };
+
+    it("Clamps i16::MAX at the maximum score", async () => {
+      expect(await applyDelta(32767)).to.equal(10000);
+    });
+
+    it("Clamps i16::MIN at zero without panicking", async () => {
+      expect(await applyDelta(-32768)).to.equal(0);
+    });
+
+    it("Reports the score before and after the delta", async () => {
+      const { currentScore } = await program.account.hai.fetch(haiPda);
+      const { events } = await program.methods
+        .updateHaiRealtime(activityId, 250)
+        .accounts({ hai: haiPda, updaterAccount: null, payer: provider.wallet.publicKey })
+        .simulate();
+
+      const update = events.find((e) => e.name === "haiRealtimeUpdateEvent")!.data as any;
+      expect(update.oldScore).to.equal(currentScore);
+      expect(update.newScore).to.equal(Math.min(currentScore + 250, 10000));
+      expect(update.newScore).to.not.equal(update.oldScore);
+    });
+  });
+
+  describe("explain_score", () => {
+    before(async () => {
+      await program.methods
+        .updateWeights(4000, 2500, 2000, 1500)
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+      await trackActivity(Array.from(Buffer.alloc(32, 21)), true, true, false);
+      await trackActivity(Array.from(Buffer.alloc(32, 22)), true, false, true);
+    });
+
+    it("Emits weighted components that sum to the current score", async () => {
+      const { events } = await program.methods
+        .explainScore()
+        .accounts({ hai: haiPda })
+        .simulate();
+
+      const explanation = events.find((e) => e.name === "scoreExplanationEvent")!.data as any;
+      const sum =
+        explanation.complianceComponent.toNumber() +
+        explanation.assetBackingComponent.toNumber() +
+        explanation.economicValueComponent.toNumber() +
+        explanation.validatorParticipationComponent.toNumber();
+
+      expect(sum).to.equal(explanation.currentScore);
+      expect(explanation.computedScore).to.equal(explanation.currentScore);
+    });
+  });
+
+  describe("validate_weights", () => {
+    it("Reports a weight sum of exactly 10000", async () => {
+      const { events } = await program.methods
+        .validateWeights()
+        .accounts({ hai: haiPda })
+        .simulate();
+
+      const validated = events.find((e) => e.name === "weightsValidatedEvent")!.data as any;
+      expect(validated.weightSum).to.equal(10000);
+      expect(validated.isValid).to.equal(true);
+    });
+
+    it("Refuses weights that do not sum to 10000", async () => {
+      try {
+        await program.methods
+          .updateWeights(4000, 2500, 2000, 1000)
+          .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+          .rpc();
+        expect.fail("weights summing to 9500 should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidWeights");
+      }
+    });
+  });
+
+  describe("verify_hai_score", () => {
+    const verify = () =>
+      program.methods
+        .verifyHaiScore()
+        .accounts({ hai: haiPda });
+
+    it("Accepts a score produced by tracking", async () => {
+      await trackActivity(Array.from(Buffer.alloc(32, 43)), true, true, true);
+
+      const { events } = await verify().simulate();
+      const verified = events.find((e) => e.name === "haiVerifiedEvent")!.data as any;
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(verified.storedScore).to.equal(hai.currentScore);
+      expect(verified.computedScore).to.equal(hai.currentScore);
+    });
+
+    it("Rejects a score drifted by a realtime update", async () => {
+      await program.methods
+        .updateHaiRealtime(activityId, -500)
+        .accounts({ hai: haiPda, updaterAccount: null, payer: provider.wallet.publicKey })
+        .rpc();
+
+      try {
+        await verify().rpc();
+        expect.fail("a drifted score should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ScoreMismatch");
+      }
+
+      // Tracking recomputes the score from the counters again
+      await trackActivity(Array.from(Buffer.alloc(32, 44)), true, true, true);
+      await verify().rpc();
+    });
+  });
+
+  describe("validator participation", () => {
+    const trackWithVotes = (id: number[], validatorCount: number, positiveVotes: number) =>
+      program.methods
+        .trackActivity(id, true, true, true, validatorCount, positiveVotes, null, new anchor.BN(0))
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          validator: null,
+          activity: null,
+          updaterAccount: null,
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const participationScore = async () => {
+      const { events } = await program.methods
+        .explainScore()
+        .accounts({ hai: haiPda })
+        .simulate();
+      return (events.find((e) => e.name === "scoreExplanationEvent")!.data as any).validatorParticipationScore.toNumber();
+    };
+
+    it("Scores the share of positive votes across tracked activities", async () => {
+      const before = await program.account.hai.fetch(haiPda);
+      await trackWithVotes(Array.from(Buffer.alloc(32, 45)), 10, 1);
+
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.totalValidatorCount.toNumber()).to.equal(before.totalValidatorCount.toNumber() + 10);
+      expect(hai.totalPositiveVotes.toNumber()).to.equal(before.totalPositiveVotes.toNumber() + 1);
+
+      const expected = Math.floor((hai.totalPositiveVotes.toNumber() * 10000) / hai.totalValidatorCount.toNumber());
+      expect(await participationScore()).to.equal(expected);
+      expect(expected).to.be.lessThan(8000);
+    });
+
+    it("Rejects more positive votes than validators", async () => {
+      try {
+        await trackWithVotes(Array.from(Buffer.alloc(32, 46)), 3, 4);
+        expect.fail("votes exceeding the validator count should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidVotes");
+      }
+    });
+  });
+
+  describe("void_activity_metric", () => {
+    const badId = Array.from(Buffer.alloc(32, 23));
+
+    const voidMetric = async (id: number[]) => {
+      await program.methods
+        .voidActivityMetric(id)
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+    };
+
+    it("Restores the prior counters and score", async () => {
+      const before = await program.account.hai.fetch(haiPda);
+
+      await trackActivity(badId, false, false, false);
+      const polluted = await program.account.hai.fetch(haiPda);
+      expect(polluted.totalActivities.toNumber()).to.equal(before.totalActivities.toNumber() + 1);
+
+      await voidMetric(badId);
+
+      const after = await program.account.hai.fetch(haiPda);
+      expect(after.totalActivities.toNumber()).to.equal(before.totalActivities.toNumber());
+      expect(after.compliantActivities.toNumber()).to.equal(before.compliantActivities.toNumber());
+      expect(after.assetBackedActivities.toNumber()).to.equal(before.assetBackedActivities.toNumber());
+      expect(after.economicValueActivities.toNumber()).to.equal(
+        before.economicValueActivities.toNumber()
+      );
+      expect(after.currentScore).to.equal(before.currentScore);
+      expect(await program.account.activityMetrics.fetchNullable(metricsPdaFor(badId))).to.be.null;
+    });
+
+    it("Cannot void the same metric twice", async () => {
+      try {
+        await voidMetric(badId);
+        expect.fail("second void should have been rejected");
+      } catch (err: any) {
+        expect(err).to.exist;
+      }
+    });
+
+    it("Resets the score once the last tracked activity is voided", async () => {
+      // Needs a deployment whose only tracked activity is the voided one
+      const context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      const bankrunProgram = new Program(program.idl, bankrunProvider);
+      const admin = bankrunProvider.wallet.publicKey;
+      const onlyId = Array.from(Buffer.alloc(32, 24));
+
+      await bankrunProgram.methods
+        .initialize(5000)
+        .accounts({ hai: haiPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .trackActivity(onlyId, true, true, true, 5, 4, null, new anchor.BN(0))
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(onlyId),
+          validator: null,
+          activity: null,
+          updaterAccount: null,
+          payer: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect((await bankrunProgram.account.hai.fetch(haiPda)).currentScore).to.be.greaterThan(0);
+
+      await bankrunProgram.methods
+        .voidActivityMetric(onlyId)
+        .accounts({ hai: haiPda, metrics: metricsPdaFor(onlyId), auditLog: auditLogPda, admin })
+        .rpc();
+
+      const hai = await bankrunProgram.account.hai.fetch(haiPda);
+      expect(hai.totalActivities.toNumber()).to.equal(0);
+      expect(hai.currentScore).to.equal(0);
+    });
+  });
+
+  describe("create_snapshot", () => {
+    const updater = Keypair.generate();
+    const outsider = Keypair.generate();
+
+    const updaterPdaFor = (key: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("updater"), key.toBuffer()],
+        program.programId
+      )[0];
+
+    const createSnapshot = async (signer: Keypair, withUpdater: boolean) => {
+      const { snapshotCount } = await program.account.hai.fetch(haiPda);
+      const [snapshotPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("snapshot"), snapshotCount.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .createSnapshot()
+        .accounts({
+          hai: haiPda,
+          snapshot: snapshotPda,
+          updaterAccount: withUpdater ? updaterPdaFor(signer.publicKey) : null,
+          payer: signer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+    };
+
+    before(async () => {
+      for (const key of [updater, outsider]) {
+        const sig = await provider.connection.requestAirdrop(key.publicKey, 1_000_000_000);
+        await provider.connection.confirmTransaction(sig);
+      }
+
+      await program.methods
+        .authorizeUpdater(updater.publicKey)
+        .accounts({
+          hai: haiPda,
+          updaterAccount: updaterPdaFor(updater.publicKey),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Rejects an unauthorized signer", async () => {
+      try {
+        await createSnapshot(outsider, false);
+        expect.fail("snapshot should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+
+    it("Lets an authorized updater checkpoint", async () => {
+      const before = await program.account.hai.fetch(haiPda);
+      await createSnapshot(updater, true);
+
+      const after = await program.account.hai.fetch(haiPda);
+      expect(after.snapshotCount.toNumber()).to.equal(before.snapshotCount.toNumber() + 1);
+    });
+  });
+
+  describe("updater gating", () => {
+    const tracker = Keypair.generate();
+    const [trackerUpdaterPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("updater"), tracker.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const trackAs = async (id: number[]) => {
+      await program.methods
+        .trackActivity(id, true, true, true, 5, 4, null, new anchor.BN(0))
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          validator: null,
+          activity: null,
+          updaterAccount: trackerUpdaterPda,
+          payer: tracker.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([tracker])
+        .rpc();
+    };
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(tracker.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await program.methods
+        .authorizeUpdater(tracker.publicKey)
+        .accounts({
+          hai: haiPda,
+          updaterAccount: trackerUpdaterPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Lets an authorized updater track activities", async () => {
+      const id = Array.from(Buffer.alloc(32, 41));
+      await trackAs(id);
+      const metrics = await program.account.activityMetrics.fetch(metricsPdaFor(id));
+      expect(metrics.isCompliant).to.equal(true);
+    });
+
+    it("Rejects a revoked updater", async () => {
+      await program.methods
+        .revokeUpdater()
+        .accounts({
+          updaterAccount: trackerUpdaterPda,
+          hai: haiPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      try {
+        await trackAs(Array.from(Buffer.alloc(32, 42)));
+        expect.fail("revoked updater should not be able to track");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+
+    it("Rejects realtime updates from anyone but the admin or an authorized updater", async () => {
+      try {
+        await program.methods
+          .updateHaiRealtime(activityId, 100)
+          .accounts({ hai: haiPda, updaterAccount: trackerUpdaterPda, payer: tracker.publicKey })
+          .signers([tracker])
+          .rpc();
+        expect.fail("revoked updater should not be able to adjust the score");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+
+      try {
+        await program.methods
+          .updateHaiRealtime(activityId, 100)
+          .accounts({ hai: haiPda, updaterAccount: null, payer: tracker.publicKey })
+          .signers([tracker])
+          .rpc();
+        expect.fail("an outsider should not be able to adjust the score");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+  });
+
+  describe("track_activities_batch", () => {
+    const flags: [boolean, boolean, boolean][] = [
+      [true, true, false],
+      [true, false, true],
+      [false, false, false],
+    ];
+    const idsFrom = (fill: number) =>
+      flags.map((_, i) => Array.from(Buffer.alloc(32, fill + i)));
+
+    const trackBatch = async (ids: number[][], entryFlags: [boolean, boolean, boolean][]) => {
+      await program.methods
+        .trackActivitiesBatch(
+          ids.map((id, i) => ({
+            activityId: id,
+            isCompliant: entryFlags[i][0],
+            isAssetBacked: entryFlags[i][1],
+            hasRealEconomicValue: entryFlags[i][2],
+            validatorCount: 5,
+            positiveVotes: 4,
+            category: null,
+            capital: new anchor.BN(0),
+          }))
+        )
+        .accounts({
+          hai: haiPda,
+          validator: null,
+          updaterAccount: null,
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          ids.map((id) => ({ pubkey: metricsPdaFor(id), isSigner: false, isWritable: true }))
+        )
+        .rpc();
+    };
+
+    const counters = (hai: any) => ({
+      total: hai.totalActivities.toNumber(),
+      compliant: hai.compliantActivities.toNumber(),
+      assetBacked: hai.assetBackedActivities.toNumber(),
+      economicValue: hai.economicValueActivities.toNumber(),
+      score: hai.currentScore,
+    });
+
+    it("Matches the counters and score of individual tracking", async () => {
+      const individualIds = idsFrom(24);
+      for (const [i, id] of individualIds.entries()) {
+        await trackActivity(id, ...flags[i]);
+      }
+      const individual = counters(await program.account.hai.fetch(haiPda));
+
+      for (const id of individualIds) {
+        await program.methods
+          .voidActivityMetric(id)
+          .accounts({
+            hai: haiPda,
+            metrics: metricsPdaFor(id),
+            auditLog: auditLogPda,
+            admin: provider.wallet.publicKey,
+          })
+          .rpc();
+      }
+
+      await trackBatch(idsFrom(27), flags);
+      expect(counters(await program.account.hai.fetch(haiPda))).to.deep.equal(individual);
+    });
+
+    it("Rejects duplicate activity ids within a batch", async () => {
+      const id = Array.from(Buffer.alloc(32, 30));
+      try {
+        await trackBatch([id, id], flags.slice(0, 2));
+        expect.fail("batch should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("DuplicateActivity");
+      }
+    });
+  });
+
+  describe("freeze_hai", () => {
+    const id = Array.from(Buffer.alloc(32, 31));
+
+    const setFrozen = async (frozen: boolean) => {
+      const method = frozen ? program.methods.freezeHai() : program.methods.unfreezeHai();
+      await method
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => setFrozen(true));
+
+    after(async () => {
+      if ((await program.account.hai.fetch(haiPda)).isFrozen) {
+        await setFrozen(false);
+      }
+    });
+
+    it("Blocks score updates while frozen", async () => {
+      const attempts = [
+        () => trackActivity(id, true, true, true),
+        () =>
+          program.methods
+            .updateHaiRealtime(id, 100)
+            .accounts({ hai: haiPda, updaterAccount: null, payer: provider.wallet.publicKey })
+            .rpc(),
+        () =>
+          program.methods
+            .updateWeights(4000, 2500, 2000, 1500)
+            .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+            .rpc(),
+      ];
+
+      for (const attempt of attempts) {
+        try {
+          await attempt();
+          expect.fail("update should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("Frozen");
+        }
+      }
+    });
+
+    it("Still allows snapshots while frozen", async () => {
+      const { snapshotCount } = await program.account.hai.fetch(haiPda);
+      const [snapshotPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("snapshot"), snapshotCount.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .createSnapshot()
+        .accounts({
+          hai: haiPda,
+          snapshot: snapshotPda,
+          updaterAccount: null,
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Resumes updates after unfreeze", async () => {
+      await setFrozen(false);
+      await trackActivity(id, true, true, true);
+
+      const metrics = await program.account.activityMetrics.fetch(metricsPdaFor(id));
+      expect(metrics.isCompliant).to.be.true;
+    });
+  });
+
+  describe("creation nonce", () => {
+    const id = Array.from(Buffer.alloc(32, 32));
+
+    it("Stamps a higher nonce on a recreated metrics account", async () => {
+      await trackActivity(id, true, true, true);
+      const first = await program.account.activityMetrics.fetch(metricsPdaFor(id));
+
+      await program.methods
+        .voidActivityMetric(id)
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+      await trackActivity(id, true, true, true);
+
+      const recreated = await program.account.activityMetrics.fetch(metricsPdaFor(id));
+      expect(recreated.creationNonce.toNumber()).to.be.greaterThan(first.creationNonce.toNumber());
+
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.creationNonce.toNumber()).to.equal(recreated.creationNonce.toNumber());
+    });
+  });
+
+  describe("precision mode", () => {
+    const setPrecisionMode = async (mode: object) => {
+      await program.methods
+        .setPrecisionMode(mode)
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const explain = async () => {
+      const { events } = await program.methods
+        .explainScore()
+        .accounts({ hai: haiPda })
+        .simulate();
+      return events.find((e) => e.name === "scoreExplanationEvent")!.data as any;
+    };
+
+    after(async () => setPrecisionMode({ bps: {} }));
+
+    it("Scales the same inputs by the mode's denominator", async () => {
+      await trackActivity(Array.from(Buffer.alloc(32, 33)), true, false, true);
+      const bps = await explain();
+
+      await setPrecisionMode({ ppm: {} });
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.currentScore).to.equal(bps.currentScore * 100);
+
+      const ppm = await explain();
+      for (const key of ["complianceScore", "assetBackingScore", "economicValueScore", "validatorParticipationScore"]) {
+        expect(Math.floor(ppm[key].toNumber() / 100), key).to.equal(bps[key].toNumber());
+      }
+      // Each of the four weighted components rounds down by less than one bps unit
+      expect(ppm.computedScore).to.be.within(bps.computedScore * 100, bps.computedScore * 100 + 400);
+    });
+
+    it("Scales realtime deltas into the mode and clamps at its maximum", async () => {
+      const updateRealtime = (delta: number) =>
+        program.methods
+          .updateHaiRealtime(Array.from(Buffer.alloc(32, 33)), delta)
+          .accounts({ hai: haiPda, updaterAccount: null, payer: provider.wallet.publicKey })
+          .rpc();
+
+      const { currentScore } = await program.account.hai.fetch(haiPda);
+      await updateRealtime(-5);
+      // 5 bps is 500 ppm
+      expect((await program.account.hai.fetch(haiPda)).currentScore).to.equal(Math.max(currentScore - 500, 0));
+
+      await updateRealtime(32767);
+      expect((await program.account.hai.fetch(haiPda)).currentScore).to.equal(1_000_000);
+    });
+
+    it("Rescales the stored score back to basis points", async () => {
+      const { currentScore } = await program.account.hai.fetch(haiPda);
+      await setPrecisionMode({ bps: {} });
+
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.currentScore).to.equal(Math.floor(currentScore / 100));
+    });
+  });
+
+  describe("category sub-scores", () => {
+    const explainCategories = async () => {
+      const { events } = await program.methods
+        .explainCategoryScores()
+        .accounts({ hai: haiPda })
+        .simulate();
+      return events.find((e) => e.name === "categoryScoresEvent")!.data as any;
+    };
+
+    const scoreOf = (event: any, category: string) =>
+      event.scores.find((s: any) => category in s.category);
+
+    it("Reports distinct sub-scores for categories with different compliance", async () => {
+      const before = await explainCategories();
+
+      await trackActivity(Array.from(Buffer.alloc(32, 34)), true, true, true, { realEstate: {} });
+      await trackActivity(Array.from(Buffer.alloc(32, 35)), true, true, true, { realEstate: {} });
+      await trackActivity(Array.from(Buffer.alloc(32, 36)), true, false, true, { trade: {} });
+      await trackActivity(Array.from(Buffer.alloc(32, 37)), false, false, false, { trade: {} });
+
+      const after = await explainCategories();
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(after.currentScore).to.equal(hai.currentScore);
+
+      const realEstate = scoreOf(after, "realEstate");
+      const trade = scoreOf(after, "trade");
+      expect(realEstate.totalActivities.toNumber())
+        .to.equal(scoreOf(before, "realEstate").totalActivities.toNumber() + 2);
+      expect(trade.totalActivities.toNumber())
+        .to.equal(scoreOf(before, "trade").totalActivities.toNumber() + 2);
+
+      for (const entry of [realEstate, trade]) {
+        expect(entry.score).to.equal(
+          Math.floor((entry.compliantActivities.toNumber() * 10_000) / entry.totalActivities.toNumber())
+        );
+      }
+      expect(realEstate.score).to.not.equal(trade.score);
+    });
+
+    it("Reverses category counters when a metric is voided", async () => {
+      const id = Array.from(Buffer.alloc(32, 37));
+      const before = scoreOf(await explainCategories(), "trade");
+
+      await program.methods
+        .voidActivityMetric(id)
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const after = scoreOf(await explainCategories(), "trade");
+      expect(after.totalActivities.toNumber()).to.equal(before.totalActivities.toNumber() - 1);
+      expect(after.compliantActivities.toNumber()).to.equal(before.compliantActivities.toNumber());
+    });
+  });
+
+  describe("weight update interval", () => {
+    const interval = 3;
+
+    const chainTime = async () =>
+      (await provider.connection.getBlockTime(await provider.connection.getSlot()))!;
+
+    const setCooldown = async (seconds: number) => {
+      await program.methods
+        .setWeightUpdateInterval(new anchor.BN(seconds))
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const updateWeights = async () => {
+      await program.methods
+        .updateWeights(4000, 2500, 2000, 1500)
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const waitOutCooldown = async () => {
+      const { lastWeightUpdateAt } = await program.account.hai.fetch(haiPda);
+      while ((await chainTime()) < lastWeightUpdateAt.toNumber() + interval) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+    };
+
+    before(async () => setCooldown(interval));
+
+    after(async () => {
+      await waitOutCooldown();
+      await setCooldown(0);
+    });
+
+    it("Rejects a second update within the interval", async () => {
+      await updateWeights();
+      try {
+        await updateWeights();
+        expect.fail("update should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("WeightUpdateTooSoon");
+      }
+    });
+
+    it("Accepts an update once the interval has passed", async () => {
+      await waitOutCooldown();
+      const before = await program.account.hai.fetch(haiPda);
+      await updateWeights();
+
+      const hai = await program.account.hai.fetch(haiPda);
+      expect(hai.lastWeightUpdateAt.toNumber()).to.be.at.least(before.lastWeightUpdateAt.toNumber() + interval);
+    });
+  });
+
+  describe("capital-weighted score", () => {
+    // Capital is checked against reserve activities, which are injected into a bankrun ledger
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+    const reserveProgram = anchor.workspace.AmanaReserve as Program;
+    const baselineId = Array.from(Buffer.alloc(32, 38));
+    const smallId = Array.from(Buffer.alloc(32, 39));
+    const largeId = Array.from(Buffer.alloc(32, 40));
+
+    const activityPdaFor = (id: number[]) =>
+      PublicKey.findProgramAddressSync([Buffer.from("activity"), Buffer.from(id)], reserveProgram.programId);
+
+    const injectActivity = async (id: number[], capitalRequired: number) => {
+      const [address, bump] = activityPdaFor(id);
+      const data = await reserveProgram.coder.accounts.encode("activity", {
+        activityId: id,
+        initiator: admin,
+        capitalRequired: new anchor.BN(capitalRequired),
+        capitalDeployed: new anchor.BN(0),
+        riskRating: 1,
+        expectedDuration: new anchor.BN(0),
+        maturityAt: new anchor.BN(0),
+        status: { approved: {} },
+        createdAt: new anchor.BN(0),
+        completedAt: new anchor.BN(0),
+        outcome: new anchor.BN(0),
+        isValidated: false,
+        isSettled: false,
+        settledOutcome: new anchor.BN(0),
+        distributionBase: new anchor.BN(0),
+        parentId: Array(32).fill(0),
+        settlementMint: PublicKey.default,
+        approvedAt: new anchor.BN(0),
+        settlementSeq: new anchor.BN(0),
+        unappliedCount: new anchor.BN(0),
+        allocated: new anchor.BN(0),
+        unallocated: new anchor.BN(0),
+        bump,
+      });
+      context.setAccount(address, {
+        lamports: 1_000_000_000,
+        data,
+        owner: reserveProgram.programId,
+        executable: false,
+      });
+    };
+
+    const trackWithCapital = (id: number[], isCompliant: boolean, capital: number, activity: PublicKey | null) =>
+      bankrunProgram.methods
+        .trackActivity(id, isCompliant, isCompliant, isCompliant, 5, 4, null, new anchor.BN(capital))
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          validator: null,
+          activity,
+          updaterAccount: null,
+          payer: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const track = (id: number[], isCompliant: boolean, capital: number) =>
+      trackWithCapital(id, isCompliant, capital, activityPdaFor(id)[0]);
+
+    const currentScore = async () => (await bankrunProgram.account.hai.fetch(haiPda)).currentScore;
+
+    const voidMetric = async (id: number[]) => {
+      await bankrunProgram.methods
+        .voidActivityMetric(id)
+        .accounts({
+          hai: haiPda,
+          metrics: metricsPdaFor(id),
+          auditLog: auditLogPda,
+          admin,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(5000)
+        .accounts({ hai: haiPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+
+      await injectActivity(baselineId, 1_000_000);
+      await injectActivity(smallId, 10_000);
+      await injectActivity(largeId, 1_000_000);
+    });
+
+    it("Records capital on the metrics and the weighted sums", async () => {
+      const before = await bankrunProgram.account.hai.fetch(haiPda);
+      await track(baselineId, true, 1_000_000);
+
+      const metrics = await bankrunProgram.account.activityMetrics.fetch(metricsPdaFor(baselineId));
+      expect(metrics.capital.toNumber()).to.equal(1_000_000);
+
+      const hai = await bankrunProgram.account.hai.fetch(haiPda);
+      expect(hai.totalCapital.toNumber()).to.equal(before.totalCapital.toNumber() + 1_000_000);
+      expect(hai.compliantCapital.toNumber()).to.equal(before.compliantCapital.toNumber() + 1_000_000);
+    });
+
+    it("Depresses the score more for a large non-compliant activity than a small one", async () => {
+      const baseline = await currentScore();
+
+      await track(smallId, false, 10_000);
+      const smallDrop = baseline - (await currentScore());
+      await voidMetric(smallId);
+      expect(await currentScore()).to.equal(baseline);
+
+      await track(largeId, false, 1_000_000);
+      const largeDrop = baseline - (await currentScore());
+
+      expect(smallDrop).to.be.greaterThan(0);
+      expect(largeDrop).to.be.greaterThan(smallDrop);
+    });
+
+    it("Reverses the weighted sums when a metric is voided", async () => {
+      const before = await bankrunProgram.account.hai.fetch(haiPda);
+      await voidMetric(largeId);
+
+      const hai = await bankrunProgram.account.hai.fetch(haiPda);
+      expect(hai.totalCapital.toNumber()).to.equal(before.totalCapital.toNumber() - 1_000_000);
+      expect(hai.compliantCapital.toNumber()).to.equal(before.compliantCapital.toNumber());
+    });
+
+    it("Rejects capital that differs from the reserve's books", async () => {
+      try {
+        await track(largeId, true, 5_000_000);
+        expect.fail("the capital should have been checked against capital_required");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("CapitalNotBooked");
+      }
+    });
+
+    it("Rejects capital without the reserve activity", async () => {
+      try {
+        await trackWithCapital(largeId, true, 1_000_000, null);
+        expect.fail("non-zero capital needs the reserve activity");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("CapitalNotBooked");
+      }
+    });
+  });
+
+  describe("record_final_snapshot", () => {
+    // finalize_hai_session only runs on the ER, so its pending flag is written into a bankrun ledger
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+
+    const snapshotPdaFor = (count: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("snapshot"), count.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const recordFinalSnapshot = async () => {
+      const { snapshotCount } = await bankrunProgram.account.hai.fetch(haiPda);
+      await bankrunProgram.methods
+        .recordFinalSnapshot()
+        .accounts({
+          hai: haiPda,
+          snapshot: snapshotPdaFor(snapshotCount),
+          updaterAccount: null,
+          payer: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      return snapshotPdaFor(snapshotCount);
+    };
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(6000)
+        .accounts({ hai: haiPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+
+      const info = await context.banksClient.getAccount(haiPda);
+      const hai = bankrunProgram.coder.accounts.decode("hai", Buffer.from(info!.data));
+      hai.finalSnapshotPending = true;
+      const data = await bankrunProgram.coder.accounts.encode("hai", hai);
+      context.setAccount(haiPda, { ...info!, data: Buffer.concat([data, Buffer.alloc(info!.data.length - data.length)]) });
+    });
+
+    it("Holds score updates until the final snapshot is recorded", async () => {
+      try {
+        await bankrunProgram.methods
+          .updateHaiRealtime(activityId, 100)
+          .accounts({ hai: haiPda, updaterAccount: null, payer: admin })
+          .rpc();
+        expect.fail("the update should have waited for the snapshot");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("FinalSnapshotPending");
+      }
+
+      const snapshot = await bankrunProgram.account.haiSnapshot.fetch(await recordFinalSnapshot());
+      expect(snapshot.score).to.equal(6000);
+
+      const hai = await bankrunProgram.account.hai.fetch(haiPda);
+      expect(hai.finalSnapshotPending).to.be.false;
+      await bankrunProgram.methods
+        .updateHaiRealtime(activityId, 100)
+        .accounts({ hai: haiPda, updaterAccount: null, payer: admin })
+        .rpc();
+    });
+
+    it("Refuses when no session is waiting for its snapshot", async () => {
+      try {
+        await recordFinalSnapshot();
+        expect.fail("there was no pending snapshot");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NoFinalSnapshotPending");
+      }
+    });
+  });
+
+  describe("aggregate_hai", () => {
+    // Each reserve runs its own HAI deployment, so the other deployments' Hai PDAs are injected into a bankrun ledger
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+    const listed = Array.from({ length: 4 }, () => Keypair.generate().publicKey);
+
+    const haiPdaOf = (programId: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("hai")], programId)[0];
+
+    const injectHai = async (
+      owner: PublicKey,
+      currentScore: number,
+      precisionMode: object,
+      totalCapital: number,
+      address: PublicKey = haiPdaOf(owner)
+    ) => {
+      const hai = await program.account.hai.fetch(haiPda);
+      const data = await program.coder.accounts.encode("hai", {
+        ...hai,
+        currentScore,
+        precisionMode,
+        totalCapital: new anchor.BN(totalCapital),
+      });
+      context.setAccount(address, {
+        lamports: 1_000_000_000,
+        data,
+        owner,
+        executable: false,
+      });
+      return address;
+    };
+
+    const aggregate = async (accounts: PublicKey[]) => {
+      const { events } = await bankrunProgram.methods
+        .aggregateHai()
+        .accounts({ hai: haiPda })
+        .remainingAccounts(accounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+        .simulate();
+      return events.find((e) => e.name === "aggregateHaiEvent")!.data as any;
+    };
+
+    const setAggregatePrograms = (programs: PublicKey[]) =>
+      bankrunProgram.methods
+        .setAggregatePrograms(programs)
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin })
+        .rpc();
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(5000)
+        .accounts({ hai: haiPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ hai: haiPda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await setAggregatePrograms(listed);
+    });
+
+    it("Weights each score by its tracked capital", async () => {
+      // 8000 bps over 3000 lamports and 600000 ppm (6000 bps) over 1000 lamports
+      const first = await injectHai(listed[0], 8000, { bps: {} }, 3000);
+      const second = await injectHai(listed[1], 600_000, { ppm: {} }, 1000);
+
+      const event = await aggregate([first, second]);
+      expect(event.aggregateScore).to.equal(7500);
+      expect(event.reserveCount).to.equal(2);
+    });
+
+    it("Averages the scores when no capital has been tracked", async () => {
+      const first = await injectHai(listed[2], 8000, { bps: {} }, 0);
+      const second = await injectHai(listed[3], 6000, { bps: {} }, 0);
+
+      expect((await aggregate([first, second])).aggregateScore).to.equal(7000);
+    });
+
+    it("Rejects duplicate, unlisted and misplaced accounts", async () => {
+      const hai = haiPdaOf(listed[0]);
+      await expectRejected(aggregate([hai, hai]), "InvalidRemainingAccount");
+
+      const unlisted = await injectHai(Keypair.generate().publicKey, 8000, { bps: {} }, 0);
+      await expectRejected(aggregate([hai, unlisted]), "InvalidRemainingAccount");
+
+      // A listed owner does not vouch for accounts other than its Hai PDA
+      const misplaced = await injectHai(listed[1], 8000, { bps: {} }, 0, Keypair.generate().publicKey);
+      await expectRejected(aggregate([hai, misplaced]), "InvalidRemainingAccount");
+    });
+
+    it("Caps the number of accounts and listed programs", async () => {
+      await expectRejected(aggregate([]), "InvalidAggregateCount");
+      const many = Array.from({ length: 9 }, () => Keypair.generate().publicKey);
+      await expectRejected(aggregate(many), "InvalidAggregateCount");
+
+      await expectRejected(setAggregatePrograms(many), "InvalidAggregateCount");
+      await expectRejected(setAggregatePrograms([listed[0], listed[0]]), "DuplicateAggregateProgram");
+    });
+  });
+});
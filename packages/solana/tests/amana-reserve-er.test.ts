@@ -28,6 +28,7 @@ describe("amana-reserve with MagicBlock ER", () => {
   let reservePda: PublicKey;
   let participantPda: PublicKey;
   let activityPda: PublicKey;
+  let initiatorIndexPda: PublicKey;
   
   const activityId = Array.from(Buffer.alloc(32, 1));
 
@@ -46,6 +47,11 @@ describe("amana-reserve with MagicBlock ER", () => {
       [Buffer.from("activity"), Buffer.from(activityId)],
       program.programId
     );
+
+    [initiatorIndexPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("initiator_index"), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
   });
 
   it("Initializes the reserve", async () => {
@@ -68,10 +74,12 @@ describe("amana-reserve with MagicBlock ER", () => {
 
   it("Joins the reserve", async () => {
     await program.methods
-      .joinReserve(new anchor.BN(2_000_000_000)) // 2 SOL
+      .joinReserve(new anchor.BN(2_000_000_000), { retail: {} }) // 2 SOL
       .accounts({
         reserve: reservePda,
         participant: participantPda,
+        charity: null,
+        tierApprover: null,
         user: provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -97,11 +105,13 @@ describe("amana-reserve with MagicBlock ER", () => {
 
   it("Proposes activity", async () => {
     await program.methods
-      .proposeActivity(activityId, new anchor.BN(1_000_000_000))
+      .proposeActivity(activityId, new anchor.BN(1_000_000_000), 1, new anchor.BN(0))
       .accounts({
         reserve: reservePda,
         participant: participantPda,
         activity: activityPda,
+        initiatorIndex: initiatorIndexPda,
+        complianceFund: null,
         user: provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -128,6 +138,18 @@ describe("amana-reserve with MagicBlock ER", () => {
     const magicContext = Keypair.generate().publicKey; // Mock
     const magicProgram = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
 
+    // Only approved activities can be deployed
+    await erProgram.methods
+      .approveActivity(null)
+      .accounts({
+        reserve: reservePda,
+        activity: activityPda,
+        validator: null,
+        validatorAuthority: null,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
     const tx = await erProgram.methods
       .deployCapitalRealtime(activityId, new anchor.BN(500_000_000))
       .accounts({
@@ -142,6 +164,138 @@ describe("amana-reserve with MagicBlock ER", () => {
     console.log("✅ Capital deployed on ER:", tx);
   });
 
+  it("Refuses to deploy more than the activity requested", async () => {
+    const erProvider = new anchor.AnchorProvider(
+      new anchor.web3.Connection("https://devnet-as.magicblock.app/"),
+      provider.wallet,
+      { commitment: "confirmed" }
+    );
+    const erProgram = new Program(program.idl, erProvider);
+    const id = Array.from(Buffer.alloc(32, 4));
+    const [pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("activity"), Buffer.from(id)],
+      program.programId
+    );
+
+    await erProgram.methods
+      .proposeActivity(id, new anchor.BN(100_000_000), 1, new anchor.BN(0))
+      .accounts({
+        reserve: reservePda,
+        participant: participantPda,
+        activity: pda,
+        initiatorIndex: initiatorIndexPda,
+        complianceFund: null,
+        user: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await erProgram.methods
+      .approveActivity(null)
+      .accounts({
+        reserve: reservePda,
+        activity: pda,
+        validator: null,
+        validatorAuthority: null,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    try {
+      await erProgram.methods
+        .deployCapitalRealtime(id, new anchor.BN(100_000_001))
+        .accounts({
+          reserve: reservePda,
+          activity: pda,
+          payer: provider.wallet.publicKey,
+          magicContext: Keypair.generate().publicKey, // Mock
+          magicProgram: new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh"),
+        })
+        .rpc();
+      expect.fail("the deployment should have been rejected");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("DeploymentExceedsRequest");
+    }
+  });
+
+  it("Approves and deploys atomically, matching the two-step flow", async () => {
+    const erProvider = new anchor.AnchorProvider(
+      new anchor.web3.Connection("https://devnet-as.magicblock.app/"),
+      provider.wallet,
+      { commitment: "confirmed" }
+    );
+    const erProgram = new Program(program.idl, erProvider);
+
+    const magicContext = Keypair.generate().publicKey; // Mock
+    const magicProgram = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+    const [twoStepId, combinedId] = [2, 3].map((b) => Array.from(Buffer.alloc(32, b)));
+    const pdaFor = (id: number[]) =>
+      PublicKey.findProgramAddressSync([Buffer.from("activity"), Buffer.from(id)], program.programId)[0];
+
+    for (const id of [twoStepId, combinedId]) {
+      await erProgram.methods
+        .proposeActivity(id, new anchor.BN(400_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          activity: pdaFor(id),
+          initiatorIndex: initiatorIndexPda,
+          complianceFund: null,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    const freeCapital = async () =>
+      (await erProgram.account.reserve.fetch(reservePda)).totalCapital.toNumber();
+
+    let before = await freeCapital();
+    await erProgram.methods
+      .approveActivity(null)
+      .accounts({
+        reserve: reservePda,
+        activity: pdaFor(twoStepId),
+        validator: null,
+        validatorAuthority: null,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+    await erProgram.methods
+      .deployCapitalRealtime(twoStepId, new anchor.BN(300_000_000))
+      .accounts({
+        reserve: reservePda,
+        activity: pdaFor(twoStepId),
+        payer: provider.wallet.publicKey,
+        magicContext,
+        magicProgram,
+      })
+      .rpc();
+    const twoStepSpent = before - (await freeCapital());
+
+    before = await freeCapital();
+    await erProgram.methods
+      .approveAndDeploy(new anchor.BN(300_000_000))
+      .accounts({
+        reserve: reservePda,
+        activity: pdaFor(combinedId),
+        validator: null,
+        validatorAuthority: null,
+        authority: provider.wallet.publicKey,
+        payer: provider.wallet.publicKey,
+        magicContext,
+        magicProgram,
+      })
+      .rpc();
+    const combinedSpent = before - (await freeCapital());
+
+    expect(combinedSpent).to.equal(twoStepSpent);
+    const twoStep = await erProgram.account.activity.fetch(pdaFor(twoStepId));
+    const combined = await erProgram.account.activity.fetch(pdaFor(combinedId));
+    expect(combined.status).to.deep.equal(twoStep.status);
+    expect(combined.capitalDeployed.toNumber()).to.equal(twoStep.capitalDeployed.toNumber());
+    expect(combined.capitalRequired.toNumber()).to.equal(twoStep.capitalRequired.toNumber());
+  });
+
   it("Commits and undelegates reserve", async () => {
     const magicContext = Keypair.generate().publicKey; // Mock
     const magicProgram = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
@@ -151,6 +305,7 @@ describe("amana-reserve with MagicBlock ER", () => {
       .accounts({
         reserve: reservePda,
         payer: provider.wallet.publicKey,
+        operator: null, // the admin needs no operator record
         magicContext,
         magicProgram,
       })
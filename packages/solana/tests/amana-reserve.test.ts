@@ -0,0 +1,7027 @@
+// Copyright 2026 TAWF Labs
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  getAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { BankrunProvider } from "anchor-bankrun";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import { expectRejected } from "./helpers";
+import { Clock, startAnchor } from "solana-bankrun";
+
+describe("amana-reserve", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.AmanaReserve as Program;
+
+  let reservePda: PublicKey;
+  let participantPda: PublicKey;
+  let auditLogPda: PublicKey;
+
+  const activityPdaFor = (id: number[]) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("activity"), Buffer.from(id)],
+      program.programId
+    )[0];
+
+  const initiatorIndexPda = () =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("initiator_index"), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+  const childActivityId = (parentId: number[], index: number) =>
+    Array.from(
+      createHash("sha256")
+        .update(Buffer.from(parentId))
+        .update(Buffer.from("split"))
+        .update(Buffer.from([index]))
+        .digest()
+    );
+
+  const proposeActivity = async (
+    id: number[],
+    capital: number,
+    riskRating = 1,
+    expectedDuration = 0
+  ) => {
+    await program.methods
+      .proposeActivity(id, new anchor.BN(capital), riskRating, new anchor.BN(expectedDuration))
+      .accounts({
+        reserve: reservePda,
+        participant: participantPda,
+        activity: activityPdaFor(id),
+        initiatorIndex: initiatorIndexPda(),
+        complianceFund: null,
+        user: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  };
+
+  const approveActivity = async (id: number[]) => {
+    await program.methods
+      .approveActivity(null)
+      .accounts({
+        reserve: reservePda,
+        activity: activityPdaFor(id),
+        validator: null,
+        validatorAuthority: null,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+  };
+
+  const denylistPda = (page: number) => {
+    const seed = Buffer.alloc(4);
+    seed.writeUInt32LE(page);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("denylist"), seed],
+      program.programId
+    )[0];
+  };
+
+  const denylistPages = async () => {
+    const { denylistPageCount } = await program.account.reserve.fetch(reservePda);
+    return Array.from({ length: denylistPageCount }, (_, i) => ({
+      pubkey: denylistPda(i),
+      isSigner: false,
+      isWritable: false,
+    }));
+  };
+
+  const chainTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot()))!;
+
+  const completeActivity = async (
+    id: number[],
+    outcome: number,
+    outcomeTimestamp?: number,
+    guaranteeFund: PublicKey | null = null,
+    // Drawing on the guarantee fund needs the admin or an authorized reporter
+    reporter: PublicKey | null = guaranteeFund === null ? null : provider.wallet.publicKey
+  ) => {
+    await program.methods
+      .completeActivity(
+        id,
+        new anchor.BN(outcome),
+        new anchor.BN(outcomeTimestamp ?? (await chainTime())),
+        PublicKey.default
+      )
+      .accounts({
+        reserve: reservePda,
+        activity: activityPdaFor(id),
+        mintVault: null,
+        vault: null,
+        validator: null,
+        validatorAuthority: null,
+        completionBond: null,
+        reporter,
+        reporterRecord: null,
+        guaranteeFund,
+      })
+      .rpc();
+  };
+
+  // Capital is fixed until every settled outcome is applied, so allocate or
+  // realize each one the agent has outstanding, in sequence order
+  const applySettlements = async (agent: PublicKey = provider.wallet.publicKey) => {
+    const [participant] = PublicKey.findProgramAddressSync(
+      [Buffer.from("participant"), agent.toBuffer()],
+      program.programId
+    );
+    const { settledThrough } = await program.account.participant.fetch(participant);
+    const outstanding = (await program.account.activity.all())
+      .filter(({ publicKey, account }) =>
+        publicKey.equals(activityPdaFor(account.activityId as number[])) &&
+        account.settlementSeq.gt(settledThrough)
+      )
+      .map(({ account }) => account)
+      .sort((a, b) => a.settlementSeq.cmp(b.settlementSeq));
+
+    for (const activity of outstanding) {
+      const id = activity.activityId as number[];
+      const accounts = {
+        reserve: reservePda,
+        activity: activityPdaFor(id),
+        participant,
+        payer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      };
+      const recordPda = (prefix: string) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from(prefix), Buffer.from(id), agent.toBuffer()],
+          program.programId
+        )[0];
+
+      if (activity.settledOutcome.isNeg()) {
+        await program.methods
+          .realizeLoss(id)
+          .accounts({
+            ...accounts,
+            lossRealization: recordPda("loss"),
+            guaranteeFund: null,
+            mintVault: activity.settlementMint.equals(PublicKey.default)
+              ? null
+              : PublicKey.findProgramAddressSync(
+                  [Buffer.from("mint_vault"), activity.settlementMint.toBuffer()],
+                  program.programId
+                )[0],
+          })
+          .rpc();
+      } else {
+        await program.methods
+          .allocateProfitShare(id)
+          .accounts({ ...accounts, share: recordPda("share") })
+          .rpc();
+      }
+    }
+  };
+
+  before(async () => {
+    [reservePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reserve")],
+      program.programId
+    );
+    [participantPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("participant"), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+
+    if ((await program.account.reserve.fetchNullable(reservePda)) === null) {
+      await program.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(100))
+        .accounts({
+          reserve: reservePda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    [auditLogPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("audit_log")],
+      program.programId
+    );
+
+    if ((await program.account.auditLog.fetchNullable(auditLogPda)) === null) {
+      await program.methods
+        .initializeAuditLog()
+        .accounts({
+          reserve: reservePda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    if ((await program.account.participant.fetchNullable(participantPda)) === null) {
+      await program.methods
+        .joinReserve(new anchor.BN(5_000_000_000), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          charity: null,
+          tierApprover: null,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  describe("split_activity", () => {
+    const parentId = Array.from(Buffer.alloc(32, 10));
+    const childAccounts = (count: number) =>
+      Array.from({ length: count }, (_, i) => ({
+        pubkey: activityPdaFor(childActivityId(parentId, i)),
+        isSigner: false,
+        isWritable: true,
+      }));
+
+    before(async () => {
+      await proposeActivity(parentId, 900_000_000);
+      await approveActivity(parentId);
+    });
+
+    it("Rejects splits that do not sum to the parent's requirement", async () => {
+      try {
+        await program.methods
+          .splitActivity(parentId, [new anchor.BN(400_000_000), new anchor.BN(400_000_000)])
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(parentId),
+            user: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(childAccounts(2))
+          .rpc();
+        expect.fail("split should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SplitAmountMismatch");
+      }
+    });
+
+    it("Splits an approved activity into children", async () => {
+      const splits = [300_000_000, 600_000_000];
+      await program.methods
+        .splitActivity(parentId, splits.map((s) => new anchor.BN(s)))
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(parentId),
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(childAccounts(2))
+        .rpc();
+
+      const parent = await program.account.activity.fetch(activityPdaFor(parentId));
+      expect(parent.status).to.deep.equal({ split: {} });
+      expect(parent.capitalDeployed.toNumber()).to.equal(0);
+
+      for (let i = 0; i < splits.length; i++) {
+        const child = await program.account.activity.fetch(
+          activityPdaFor(childActivityId(parentId, i))
+        );
+        expect(child.capitalRequired.toNumber()).to.equal(splits[i]);
+        expect(child.status).to.deep.equal({ approved: {} });
+        expect(child.parentId).to.deep.equal(parentId);
+      }
+    });
+  });
+
+  describe("merge_activity_reports", () => {
+    const ids = [11, 12, 13].map((b) => Array.from(Buffer.alloc(32, b)));
+    const outcomes = [50_000_000, -20_000_000, 30_000_000];
+
+    before(async () => {
+      for (let i = 0; i < ids.length; i++) {
+        await proposeActivity(ids[i], 100_000_000);
+        await approveActivity(ids[i]);
+        await completeActivity(ids[i], outcomes[i]);
+      }
+    });
+
+    it("Aggregates three completed activities into one report", async () => {
+      const { events } = await program.methods
+        .mergeActivityReports(ids)
+        .accounts({ reserve: reservePda })
+        .remainingAccounts(
+          ids.map((id) => ({ pubkey: activityPdaFor(id), isSigner: false, isWritable: false }))
+        )
+        .simulate();
+
+      const report = events.find((e) => e.name === "mergedReportEvent")!.data as any;
+      expect(report.totalCapitalDeployed.toNumber()).to.equal(300_000_000);
+      expect(report.totalProfit.toNumber()).to.equal(80_000_000);
+      expect(report.totalLoss.toNumber()).to.equal(20_000_000);
+      expect(report.netOutcome.toNumber()).to.equal(60_000_000);
+    });
+  });
+
+  describe("initiator activity index", () => {
+    const id = Array.from(Buffer.alloc(32, 14));
+
+    it("Indexes a proposed activity under its initiator", async () => {
+      await proposeActivity(id, 100_000_000);
+
+      const index = await program.account.initiatorActivityIndex.fetch(initiatorIndexPda());
+      expect(index.initiator.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      expect(index.activityIds).to.deep.include(id);
+    });
+
+    it("Removes a closed activity from the index", async () => {
+      await approveActivity(id);
+      await completeActivity(id, 0);
+
+      await program.methods
+        .closeActivity(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          initiatorIndex: initiatorIndexPda(),
+          user: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const index = await program.account.initiatorActivityIndex.fetch(initiatorIndexPda());
+      expect(index.activityIds).to.not.deep.include(id);
+      expect(await program.account.activity.fetchNullable(activityPdaFor(id))).to.be.null;
+    });
+  });
+
+  describe("unique activity ids", () => {
+    const id = Array.from(Buffer.alloc(32, 76));
+
+    const setUniqueActivityIds = (unique: boolean) =>
+      program.methods
+        .setUniqueActivityIds(unique)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+    const runAndClose = async (capital: number) => {
+      await proposeActivity(id, capital);
+      await approveActivity(id);
+      await completeActivity(id, 0);
+      await program.methods
+        .closeActivity(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          initiatorIndex: initiatorIndexPda(),
+          user: provider.wallet.publicKey,
+        })
+        .rpc();
+    };
+
+    after(async () => setUniqueActivityIds(false));
+
+    it("Records closed ids while enforcement is off", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      await runAndClose(100_000_000);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.closedIdCount.toNumber()).to.equal(before.closedIdCount.toNumber() + 1);
+
+      // Without enforcement the id can still be proposed again
+      await runAndClose(200_000_000);
+    });
+
+    it("Rejects reusing a closed activity's id once enabled", async () => {
+      await setUniqueActivityIds(true);
+
+      try {
+        await proposeActivity(id, 100_000_000);
+        expect.fail("a closed id should not be proposable again");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ActivityIdReused");
+      }
+    });
+
+    it("Still accepts fresh ids", async () => {
+      const fresh = Array.from(Buffer.alloc(32, 77));
+      const { events } = await program.methods
+        .proposeActivity(fresh, new anchor.BN(100_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          activity: activityPdaFor(fresh),
+          initiatorIndex: initiatorIndexPda(),
+          complianceFund: null,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .simulate();
+      expect(events.find((e) => e.name === "activityProposedEvent")).to.not.be.undefined;
+    });
+  });
+
+  describe("closed-id filter rotation", () => {
+    // Filling the filter takes 200 closures, so this seeds a full one in a bankrun ledger
+    const id = Array.from(Buffer.alloc(32, 91));
+
+    it("Starts a fresh filter once the current one is full", async () => {
+      const context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      const bankrunProgram = new Program(program.idl, bankrunProvider);
+      const admin = bankrunProvider.wallet.publicKey;
+      const participant = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), admin.toBuffer()],
+        program.programId
+      )[0];
+      const initiatorIndex = PublicKey.findProgramAddressSync(
+        [Buffer.from("initiator_index"), admin.toBuffer()],
+        program.programId
+      )[0];
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .joinReserve(new anchor.BN(1_000_000_000), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant,
+          charity: null,
+          tierApprover: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const info = await context.banksClient.getAccount(reservePda);
+      const seeded = bankrunProgram.coder.accounts.decode("reserve", Buffer.from(info!.data));
+      seeded.closedIdFilter = Array(256).fill(0xff);
+      seeded.closedIdCount = new anchor.BN(200);
+      const data = await bankrunProgram.coder.accounts.encode("reserve", seeded);
+      context.setAccount(reservePda, { ...info!, data: Buffer.concat([data, Buffer.alloc(info!.data.length - data.length)]) });
+
+      await bankrunProgram.methods
+        .proposeActivity(id, new anchor.BN(100_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant,
+          activity: activityPdaFor(id),
+          initiatorIndex,
+          complianceFund: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          validator: null,
+          validatorAuthority: null,
+          authority: admin,
+        })
+        .rpc();
+      const clock = await context.banksClient.getClock();
+      await bankrunProgram.methods
+        .completeActivity(id, new anchor.BN(0), new anchor.BN(Number(clock.unixTimestamp)), PublicKey.default)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: null,
+          vault: null,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .closeActivity(id)
+        .accounts({ reserve: reservePda, activity: activityPdaFor(id), initiatorIndex, user: admin })
+        .rpc();
+
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.closedIdCount.toNumber()).to.equal(1);
+      expect(reserve.previousClosedIdFilter.every((byte: number) => byte === 0xff)).to.be.true;
+      const bitsSet = reserve.closedIdFilter.reduce(
+        (total: number, byte: number) => total + byte.toString(2).split("1").length - 1,
+        0
+      );
+      expect(bitsSet).to.be.within(1, 3);
+    });
+  });
+
+  describe("net_settle_initiator", () => {
+    const profitId = Array.from(Buffer.alloc(32, 15));
+    const lossId = Array.from(Buffer.alloc(32, 16));
+    const singleId = Array.from(Buffer.alloc(32, 87));
+
+    const netSettle = async (ids: number[][]) => {
+      await program.methods
+        .netSettleInitiator(ids)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .remainingAccounts(
+          ids.map((id) => ({
+            pubkey: activityPdaFor(id),
+            isSigner: false,
+            isWritable: true,
+          }))
+        )
+        .rpc();
+    };
+
+    const setNetting = async (allow: boolean) => {
+      await program.methods
+        .setAllowNetting(allow)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      await setNetting(true);
+      for (const [id, outcome] of [
+        [profitId, 100_000_000],
+        [lossId, -40_000_000],
+        [singleId, 10_000_000],
+      ] as [number[], number][]) {
+        await proposeActivity(id, 200_000_000);
+        await approveActivity(id);
+        await completeActivity(id, outcome);
+      }
+    });
+
+    after(async () => {
+      await setNetting(false);
+    });
+
+    it("Distributes a net +60 for a +100/-40 pair", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+
+      await netSettle([profitId, lossId]);
+
+      const after = await program.account.reserve.fetch(reservePda);
+      expect(after.totalCapital.sub(before.totalCapital).toNumber()).to.equal(60_000_000);
+
+      const profit = await program.account.activity.fetch(activityPdaFor(profitId));
+      const loss = await program.account.activity.fetch(activityPdaFor(lossId));
+      expect(profit.settledOutcome.toNumber()).to.equal(60_000_000);
+      expect(loss.settledOutcome.toNumber()).to.equal(0);
+      expect(profit.isSettled && loss.isSettled).to.be.true;
+    });
+
+    it("Refuses to close an activity still awaiting netting", async () => {
+      try {
+        await program.methods
+          .closeActivity(singleId)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(singleId),
+            initiatorIndex: initiatorIndexPda(),
+            user: provider.wallet.publicKey,
+          })
+          .rpc();
+        expect.fail("the close should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("OutcomeNotSettled");
+      }
+    });
+
+    it("Settles a set of one activity on its own outcome", async () => {
+      await netSettle([singleId]);
+
+      const single = await program.account.activity.fetch(activityPdaFor(singleId));
+      expect(single.isSettled).to.be.true;
+      expect(single.settledOutcome.toNumber()).to.equal(10_000_000);
+      await applySettlements();
+    });
+  });
+
+  describe("min_activity_capital", () => {
+    const floor = 50_000_000;
+
+    const setFloor = async (value: number) => {
+      await program.methods
+        .setMinActivityCapital(new anchor.BN(value))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => setFloor(floor));
+    after(async () => setFloor(0));
+
+    it("Rejects a proposal one lamport below the floor", async () => {
+      try {
+        await proposeActivity(Array.from(Buffer.alloc(32, 17)), floor - 1);
+        expect.fail("proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("BelowMinimumActivityCapital");
+      }
+    });
+
+    it("Accepts a proposal exactly at the floor", async () => {
+      const id = Array.from(Buffer.alloc(32, 18));
+      await proposeActivity(id, floor);
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.capitalRequired.toNumber()).to.equal(floor);
+    });
+
+    it("Holds a partial approval to the same floor", async () => {
+      const id = Array.from(Buffer.alloc(32, 88));
+      await proposeActivity(id, 2 * floor);
+
+      const approve = (amount: number) =>
+        program.methods
+          .approveActivity(new anchor.BN(amount))
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            validator: null,
+            validatorAuthority: null,
+            authority: provider.wallet.publicKey,
+          })
+          .rpc();
+
+      try {
+        await approve(floor - 1);
+        expect.fail("approval should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("BelowMinimumActivityCapital");
+      }
+
+      await approve(floor);
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.capitalDeployed.toNumber()).to.equal(floor);
+      await completeActivity(id, 0);
+    });
+  });
+
+  describe("participant delegate", () => {
+    const id = Array.from(Buffer.alloc(32, 19));
+    const delegate = Keypair.generate();
+    const DELEGATE_SCOPE_CLAIM = 1;
+
+    const delegatePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("delegate"), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const sharePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    before(async () => {
+      await applySettlements();
+      await proposeActivity(id, 100_000_000);
+      await approveActivity(id);
+      await completeActivity(id, 10_000_000);
+
+      await program.methods
+        .allocateProfitShare(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantPda,
+          share: sharePda(),
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .authorizeDelegate(delegate.publicKey, DELEGATE_SCOPE_CLAIM, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          delegate: delegatePda(),
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Lets the delegate claim profit to the participant", async () => {
+      const balanceBefore = await provider.connection.getBalance(provider.wallet.publicKey);
+
+      await program.methods
+        .claimProfit(id)
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          share: sharePda(),
+          delegate: delegatePda(),
+          agent: provider.wallet.publicKey,
+          authority: delegate.publicKey,
+        })
+        .signers([delegate])
+        .rpc();
+
+      const share = await program.account.participantActivityShare.fetch(sharePda());
+      expect(share.claimed.toNumber()).to.equal(share.amount.toNumber());
+      expect(share.amount.toNumber()).to.be.greaterThan(0);
+      // The payout lands on the participant's key, not the delegate's
+      expect(await provider.connection.getBalance(provider.wallet.publicKey)).to.be.greaterThan(
+        balanceBefore
+      );
+    });
+
+    it("Does not let the delegate withdraw", async () => {
+      try {
+        await program.methods
+          .withdrawCapital(new anchor.BN(1_000_000))
+          .accounts({
+            reserve: reservePda,
+            participant: participantPda,
+            user: delegate.publicKey,
+          })
+          .signers([delegate])
+          .rpc();
+        expect.fail("withdrawal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+  });
+
+  describe("settlement sequence", () => {
+    const id = Array.from(Buffer.alloc(32, 84));
+    const profit = 8_000_000;
+
+    const deposit = () =>
+      program.methods
+        .depositCapital(new anchor.BN(1_000_000))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          delegate: null,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      await applySettlements();
+      await proposeActivity(id, 50_000_000);
+      await approveActivity(id);
+      await completeActivity(id, profit);
+    });
+
+    it("Holds capital fixed until the outcome is allocated", async () => {
+      try {
+        await deposit();
+        expect.fail("the deposit should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SettlementsPending");
+      }
+    });
+
+    it("Keeps the activity open until every participant has allocated", async () => {
+      try {
+        await program.methods
+          .closeActivity(id)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            initiatorIndex: initiatorIndexPda(),
+            user: provider.wallet.publicKey,
+          })
+          .rpc();
+        expect.fail("the close should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("OutcomeNotFullyApplied");
+      }
+    });
+
+    it("Sizes the share on the capital held at settlement", async () => {
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      const { capitalContributed } = await program.account.participant.fetch(participantPda);
+
+      await applySettlements();
+
+      const share = await program.account.participantActivityShare.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("share"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+          program.programId
+        )[0]
+      );
+      expect(share.amount.toString()).to.equal(
+        activity.settledOutcome.mul(capitalContributed).div(activity.distributionBase).toString()
+      );
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.settledThrough.toString()).to.equal(activity.settlementSeq.toString());
+
+      // Caught up, the participant may add capital again
+      await deposit();
+    });
+  });
+
+  describe("list_pending_claims", () => {
+    const ids = [79, 80].map((b) => Array.from(Buffer.alloc(32, b)));
+    const profits = [6_000_000, 4_000_000];
+
+    const sharePdaFor = (id: number[]) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    const listPendingClaims = (shares: PublicKey[]) =>
+      program.methods
+        .listPendingClaims()
+        .accounts({ reserve: reservePda, participant: participantPda })
+        .remainingAccounts(
+          shares.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+        );
+
+    before(async () => {
+      await applySettlements();
+      for (const [i, id] of ids.entries()) {
+        await proposeActivity(id, 50_000_000);
+        await approveActivity(id);
+        await completeActivity(id, profits[i]);
+        await program.methods
+          .allocateProfitShare(id)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            participant: participantPda,
+            share: sharePdaFor(id),
+            payer: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+    });
+
+    it("Totals the unclaimed shares with a per-activity breakdown", async () => {
+      const shares = await Promise.all(
+        ids.map((id) => program.account.participantActivityShare.fetch(sharePdaFor(id)))
+      );
+      const unclaimed = shares.map((share) => share.amount.toNumber() - share.claimed.toNumber());
+
+      const { events } = await listPendingClaims(ids.map(sharePdaFor)).simulate();
+      const event = events.find((e) => e.name === "pendingClaimsEvent")!.data as any;
+
+      expect(event.totalClaimable.toNumber()).to.equal(unclaimed[0] + unclaimed[1]);
+      expect(event.claimCount).to.equal(2);
+      expect(event.amounts.map((amount: anchor.BN) => amount.toNumber())).to.deep.equal(unclaimed);
+      expect(event.activityIds).to.deep.equal(ids);
+    });
+
+    it("Rejects a share passed twice", async () => {
+      try {
+        await listPendingClaims([sharePdaFor(ids[0]), sharePdaFor(ids[0])]).rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidRemainingAccount");
+      }
+    });
+  });
+
+  describe("reconcile_capital", () => {
+    const openActivityAccounts = async () =>
+      (await program.account.activity.all())
+        .filter((a) => "approved" in a.account.status || "active" in a.account.status)
+        .map((a) => ({ pubkey: a.publicKey, isSigner: false, isWritable: false }));
+
+    const reconcile = async () =>
+      program.methods
+        .reconcileCapital()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .remainingAccounts(await openActivityAccounts())
+        .rpc();
+
+    it("Corrects a total desynced by a direct lamport transfer", async () => {
+      // Lamports sent straight to the PDA bypass the reserve's books
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: reservePda,
+            lamports: 250_000_000,
+          })
+        )
+      );
+
+      await reconcile();
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      const info = await provider.connection.getAccountInfo(reservePda);
+      const rent = await provider.connection.getMinimumBalanceForRentExemption(info!.data.length);
+      expect(reserve.totalCapital.toNumber()).to.equal(
+        info!.lamports - rent - reserve.deployedCapital.toNumber()
+      );
+    });
+
+    it("Rejects reconciliation with missing escrow evidence", async () => {
+      try {
+        await program.methods
+          .reconcileCapital()
+          .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+          .remainingAccounts((await openActivityAccounts()).slice(1))
+          .rpc();
+        expect.fail("reconciliation should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReconciliationMismatch");
+      }
+    });
+  });
+
+  describe("max_outcome_age", () => {
+    const id = Array.from(Buffer.alloc(32, 23));
+
+    const setMaxAge = async (seconds: number) => {
+      await program.methods
+        .setMaxOutcomeAge(new anchor.BN(seconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      await setMaxAge(60);
+      await proposeActivity(id, 100_000_000);
+      await approveActivity(id);
+    });
+
+    after(async () => setMaxAge(0));
+
+    it("Rejects a stale outcome", async () => {
+      try {
+        await completeActivity(id, 1_000_000, (await chainTime()) - 3600);
+        expect.fail("stale outcome should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("StaleOutcome");
+      }
+    });
+
+    it("Rejects a future-dated outcome", async () => {
+      try {
+        await completeActivity(id, 1_000_000, (await chainTime()) + 3600);
+        expect.fail("future-dated outcome should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("FutureDatedOutcome");
+      }
+    });
+  });
+
+  describe("risk budget", () => {
+    const capital = 100_000_000;
+    const lowRiskIds = [24, 25].map((b) => Array.from(Buffer.alloc(32, b)));
+    const highRiskIds = [26, 27].map((b) => Array.from(Buffer.alloc(32, b)));
+
+    const setMaxRiskExposure = async (value: number) => {
+      await program.methods
+        .setMaxRiskExposure(new anchor.BN(value))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      // Leave room for 2 * capital * 5 on top of the current exposure
+      const { riskExposure } = await program.account.reserve.fetch(reservePda);
+      await setMaxRiskExposure(riskExposure.toNumber() + 10 * capital);
+
+      for (const id of lowRiskIds) await proposeActivity(id, capital, 1);
+      for (const id of highRiskIds) await proposeActivity(id, capital, 5);
+    });
+
+    after(async () => setMaxRiskExposure(0));
+
+    it("Weights exposure by risk rating", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      await approveActivity(lowRiskIds[0]);
+      const afterLow = await program.account.reserve.fetch(reservePda);
+      await approveActivity(highRiskIds[0]);
+      const afterHigh = await program.account.reserve.fetch(reservePda);
+
+      expect(afterLow.riskExposure.sub(before.riskExposure).toNumber()).to.equal(capital);
+      expect(afterHigh.riskExposure.sub(afterLow.riskExposure).toNumber()).to.equal(5 * capital);
+    });
+
+    it("Trips the limit on a high-risk approval that a low-risk one fits under", async () => {
+      try {
+        await approveActivity(highRiskIds[1]);
+        expect.fail("approval should have exceeded the risk budget");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("RiskBudgetExceeded");
+      }
+
+      await approveActivity(lowRiskIds[1]);
+      const activity = await program.account.activity.fetch(activityPdaFor(lowRiskIds[1]));
+      expect(activity.status).to.deep.equal({ approved: {} });
+    });
+
+    it("Rejects a risk rating outside 1-5", async () => {
+      try {
+        await proposeActivity(Array.from(Buffer.alloc(32, 28)), capital, 6);
+        expect.fail("proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidRiskRating");
+      }
+    });
+  });
+
+  describe("audit log", () => {
+    it("Appends ordered entries for successive admin actions", async () => {
+      const before = await program.account.auditLog.fetch(auditLogPda);
+      const start = before.nextSequence.toNumber();
+
+      for (const allow of [true, false]) {
+        await program.methods
+          .setAllowNetting(allow)
+          .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+          .rpc();
+      }
+      await program.methods
+        .setMinActivityCapital(new anchor.BN(0))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+      const log = await program.account.auditLog.fetch(auditLogPda);
+      expect(log.nextSequence.toNumber()).to.equal(start + 3);
+
+      const capacity = 32;
+      const entries = [start, start + 1, start + 2].map(
+        (seq) => log.entries.find((e: any) => e.sequence.toNumber() === seq)
+      );
+      expect(entries.map((e: any) => e.action)).to.deep.equal([1, 1, 2]);
+      for (const entry of entries) {
+        expect(entry.actor.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      }
+      expect(entries[1].timestamp.toNumber()).to.be.at.least(entries[0].timestamp.toNumber());
+      expect(log.entries.length).to.be.at.most(capacity);
+    });
+  });
+
+  describe("event cursor", () => {
+    const indexer = Keypair.generate();
+    const [cursorPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("index_cursor"), indexer.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const reserveCursor = async () => {
+      const { events } = await program.methods
+        .getCursor()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda })
+        .simulate();
+      return (events.find((e) => e.name === "reserveCursorEvent")!.data as any).nextSequence.toNumber();
+    };
+
+    const advance = (nextSequence: number) =>
+      program.methods
+        .advanceCursor(new anchor.BN(nextSequence))
+        .accounts({ reserve: reservePda, cursor: cursorPda, auditLog: auditLogPda, indexer: indexer.publicKey })
+        .signers([indexer])
+        .rpc();
+
+    const expectInvalidCursor = async (nextSequence: number) => {
+      try {
+        await advance(nextSequence);
+        expect.fail("cursor move should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidCursor");
+      }
+    };
+
+    before(async () => {
+      await program.methods
+        .authorizeIndexer(indexer.publicKey)
+        .accounts({
+          reserve: reservePda,
+          cursor: cursorPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Reflects the latest emitted sequence", async () => {
+      const action = program.methods
+        .setMinActivityCapital(new anchor.BN(0))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey });
+      const { events } = await action.simulate();
+      const entry = events.find((e) => e.name === "auditEntryEvent")!.data as any;
+      await action.rpc();
+
+      expect(await reserveCursor()).to.equal(entry.sequence.toNumber() + 1);
+    });
+
+    it("Lets the indexer advance its cursor up to the reserve's", async () => {
+      const next = await reserveCursor();
+      await advance(next);
+
+      const cursor = await program.account.indexCursor.fetch(cursorPda);
+      expect(cursor.indexer.toBase58()).to.equal(indexer.publicKey.toBase58());
+      expect(cursor.nextSequence.toNumber()).to.equal(next);
+
+      await expectInvalidCursor(next + 1);
+      await expectInvalidCursor(next - 1);
+    });
+  });
+
+  describe("entry donation", () => {
+    const charity = Keypair.generate();
+    const joiner = Keypair.generate();
+    const amount = 2_000_000_000;
+    const entryFeeBps = 250;
+
+    const setEntryDonation = async (bps: number, recipient: PublicKey) => {
+      await program.methods
+        .setEntryDonation(bps, recipient)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      await setEntryDonation(entryFeeBps, charity.publicKey);
+      const sig = await provider.connection.requestAirdrop(joiner.publicKey, 3_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+    });
+
+    after(async () => setEntryDonation(0, PublicKey.default));
+
+    it("Sends the donation to the charity and excludes it from capital", async () => {
+      const [joinerParticipant] = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), joiner.publicKey.toBuffer()],
+        program.programId
+      );
+      const before = await program.account.reserve.fetch(reservePda);
+
+      await program.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: joinerParticipant,
+          charity: charity.publicKey,
+          tierApprover: null,
+          user: joiner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([joiner])
+        .rpc();
+
+      const donation = (amount * entryFeeBps) / 10000;
+      expect(await provider.connection.getBalance(charity.publicKey)).to.equal(donation);
+
+      const participant = await program.account.participant.fetch(joinerParticipant);
+      expect(participant.capitalContributed.toNumber()).to.equal(amount - donation);
+
+      const after = await program.account.reserve.fetch(reservePda);
+      expect(after.totalCapital.sub(before.totalCapital).toNumber()).to.equal(amount - donation);
+    });
+  });
+
+  describe("denylist", () => {
+    const denied = Keypair.generate();
+    let page: number;
+
+    const editAccounts = (editedPage: number) => ({
+      reserve: reservePda,
+      denylistPage: denylistPda(editedPage),
+      participant: null,
+      auditLog: auditLogPda,
+      admin: provider.wallet.publicKey,
+    });
+
+    const denyAddress = async (editedPage: number, agent: PublicKey) =>
+      program.methods
+        .denyAddress(editedPage, agent)
+        .accounts({ ...editAccounts(editedPage), systemProgram: SystemProgram.programId })
+        .remainingAccounts(await denylistPages())
+        .rpc();
+
+    const allowAddress = async (editedPage: number, agent: PublicKey) =>
+      program.methods
+        .allowAddress(editedPage, agent)
+        .accounts(editAccounts(editedPage))
+        .remainingAccounts(await denylistPages())
+        .rpc();
+
+    before(async () => {
+      const { denylistPageCount } = await program.account.reserve.fetch(reservePda);
+      page = Math.max(denylistPageCount - 1, 0);
+      await denyAddress(page, denied.publicKey);
+
+      const sig = await provider.connection.requestAirdrop(denied.publicKey, 3_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+    });
+
+    it("Rejects a denied address at join", async () => {
+      const [deniedParticipant] = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), denied.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .joinReserve(new anchor.BN(2_000_000_000), { retail: {} })
+          .accounts({
+            reserve: reservePda,
+            participant: deniedParticipant,
+            charity: null,
+            tierApprover: null,
+            user: denied.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(await denylistPages())
+          .signers([denied])
+          .rpc();
+        expect.fail("join should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Denied");
+      }
+    });
+
+    it("Rejects a join that omits the denylist pages", async () => {
+      const [deniedParticipant] = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), denied.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .joinReserve(new anchor.BN(2_000_000_000), { retail: {} })
+          .accounts({
+            reserve: reservePda,
+            participant: deniedParticipant,
+            charity: null,
+            tierApprover: null,
+            user: denied.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([denied])
+          .rpc();
+        expect.fail("join should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidDenylistPage");
+      }
+    });
+
+    it("Lists an address on one page only", async () => {
+      const { denylistPageCount } = await program.account.reserve.fetch(reservePda);
+      try {
+        await denyAddress(denylistPageCount, denied.publicKey);
+        expect.fail("a second listing should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Denied");
+      }
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.denylistPageCount).to.equal(denylistPageCount);
+    });
+
+    it("Rejects an edit that omits a denylist page", async () => {
+      try {
+        await program.methods
+          .allowAddress(page, denied.publicKey)
+          .accounts(editAccounts(page))
+          .rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidDenylistPage");
+      }
+    });
+
+    it("Lets the address join once allowed again", async () => {
+      await allowAddress(page, denied.publicKey);
+
+      const denylist = await program.account.denylistPage.fetch(denylistPda(page));
+      expect(denylist.entries.map((e) => e.toString())).not.to.include(denied.publicKey.toString());
+      await denyAddress(page, denied.publicKey);
+    });
+  });
+
+  describe("complete_activity id check", () => {
+    const id = Array.from(Buffer.alloc(32, 29));
+    const otherId = Array.from(Buffer.alloc(32, 30));
+
+    before(async () => {
+      await proposeActivity(id, 100_000_000);
+      await approveActivity(id);
+    });
+
+    it("Rejects an id that does not match the activity account", async () => {
+      try {
+        await program.methods
+          .completeActivity(
+            otherId,
+            new anchor.BN(0),
+            new anchor.BN(await chainTime()),
+            PublicKey.default
+          )
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            mintVault: null,
+            vault: null,
+            validator: null,
+            validatorAuthority: null,
+            completionBond: null,
+            reporter: null,
+            reporterRecord: null,
+            guaranteeFund: null,
+          })
+          .rpc();
+        expect.fail("completion should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ActivityIdMismatch");
+      }
+    });
+  });
+
+  describe("compute_distribution_metrics", () => {
+    const members = [1_000_000_000, 3_000_000_000].map((capital) => ({
+      keypair: Keypair.generate(),
+      capital,
+    }));
+    const participantPdaFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    before(async () => {
+      for (const { keypair, capital } of members) {
+        const sig = await provider.connection.requestAirdrop(keypair.publicKey, capital + 1_000_000_000);
+        await provider.connection.confirmTransaction(sig);
+
+        await program.methods
+          .joinReserve(new anchor.BN(capital), { retail: {} })
+          .accounts({
+            reserve: reservePda,
+            participant: participantPdaFor(keypair.publicKey),
+            charity: null,
+            tierApprover: null,
+            user: keypair.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(await denylistPages())
+          .signers([keypair])
+          .rpc();
+      }
+    });
+
+    it("Emits the expected Gini coefficient for a 1:3 split", async () => {
+      const { events } = await program.methods
+        .computeDistributionMetrics()
+        .accounts({ reserve: reservePda })
+        .remainingAccounts(
+          members.map(({ keypair }) => ({
+            pubkey: participantPdaFor(keypair.publicKey),
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .simulate();
+
+      const metrics = events.find((e) => e.name === "distributionMetricsEvent")!.data as any;
+      // G = |1 - 3| / (2 * 2 * 2) = 0.25
+      expect(metrics.giniBps).to.be.closeTo(2500, 1);
+      expect(metrics.sampleSize).to.equal(2);
+      expect(metrics.totalSampledCapital.toNumber()).to.equal(4_000_000_000);
+    });
+  });
+
+  describe("auto_complete_overdue", () => {
+    const id = Array.from(Buffer.alloc(32, 31));
+    const expectedDuration = 1;
+    const grace = 3;
+
+    const setMaturityGrace = async (seconds: number) => {
+      await program.methods
+        .setMaturityGrace(new anchor.BN(seconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const autoComplete = async () => {
+      await program.methods
+        .autoCompleteOverdue(id)
+        .accounts({ reserve: reservePda, activity: activityPdaFor(id) })
+        .rpc();
+    };
+
+    before(async () => {
+      await setMaturityGrace(grace);
+      await proposeActivity(id, 100_000_000, 1, expectedDuration);
+      await approveActivity(id);
+    });
+
+    after(async () => setMaturityGrace(0));
+
+    it("Refuses to fire within the grace period", async () => {
+      const { maturityAt } = await program.account.activity.fetch(activityPdaFor(id));
+      expect(await chainTime()).to.be.at.most(maturityAt.toNumber() + grace);
+
+      try {
+        await autoComplete();
+        expect.fail("auto-completion should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ActivityNotOverdue");
+      }
+    });
+
+    it("Completes at break-even once the grace period has passed", async () => {
+      const { maturityAt } = await program.account.activity.fetch(activityPdaFor(id));
+      while ((await chainTime()) <= maturityAt.toNumber() + grace) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const before = await program.account.reserve.fetch(reservePda);
+      await autoComplete();
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.status).to.deep.equal({ completed: {} });
+      expect(activity.outcome.toNumber()).to.equal(0);
+
+      const after = await program.account.reserve.fetch(reservePda);
+      expect(after.totalCapital.sub(before.totalCapital).toNumber()).to.equal(100_000_000);
+    });
+  });
+
+  describe("amana-private", () => {
+    const privateProgram = anchor.workspace.AmanaPrivate as Program;
+    const committedAmount = 150_000_000;
+
+    const [privateStatePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("private_state")],
+      privateProgram.programId
+    );
+    const [bridgePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reserve_bridge")],
+      privateProgram.programId
+    );
+
+    const privateActivityPdaFor = (activityHash: number[], deployer = provider.wallet.publicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("private_activity"), deployer.toBuffer(), Buffer.from(activityHash)],
+        privateProgram.programId
+      )[0];
+
+    const privateDeployerPdaFor = (deployer: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("private_deployer"), deployer.toBuffer()],
+        program.programId
+      )[0];
+
+    const setPrivateBridge = async (bridge: PublicKey) => {
+      await program.methods
+        .setPrivateBridge(bridge)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    // The reserve admin deploys without a PrivateDeployer record
+    const deployPrivate = async (
+      activityHash: number[],
+      amount: number | anchor.BN = committedAmount,
+      deployer: Keypair | null = null
+    ) => {
+      const deployerKey = deployer?.publicKey ?? provider.wallet.publicKey;
+      await privateProgram.methods
+        .deployCapitalPrivate(
+          Array.from(Buffer.alloc(32, 1)),
+          activityHash,
+          Array.from(Buffer.alloc(64, 1)),
+          new anchor.BN(amount)
+        )
+        .accounts({
+          privateState: privateStatePda,
+          privateActivity: privateActivityPdaFor(activityHash, deployerKey),
+          reserve: reservePda,
+          bridgeAuthority: bridgePda,
+          deployer: deployerKey,
+          privateDeployer: deployer ? privateDeployerPdaFor(deployerKey) : null,
+          amanaReserveProgram: program.programId,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(deployer ? [deployer] : [])
+        .rpc();
+    };
+
+    const settlePrivate = async (activityHash: number[], deployer = provider.wallet.publicKey) => {
+      await privateProgram.methods
+        .settleCapitalPrivate()
+        .accounts({
+          privateActivity: privateActivityPdaFor(activityHash, deployer),
+          reserve: reservePda,
+          bridgeAuthority: bridgePda,
+          authority: provider.wallet.publicKey,
+          privateDeployer: null,
+          amanaReserveProgram: program.programId,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      if ((await privateProgram.account.privateState.fetchNullable(privateStatePda)) === null) {
+        await privateProgram.methods
+          .initializePrivate()
+          .accounts({
+            privateState: privateStatePda,
+            admin: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+      await setPrivateBridge(bridgePda);
+    });
+
+    after(async () => setPrivateBridge(PublicKey.default));
+
+    describe("reserve bridge", () => {
+      it("Rejects debits not signed by the bridge", async () => {
+        try {
+          await program.methods
+            .debitPrivateDeployment(new anchor.BN(committedAmount))
+            .accounts({
+              reserve: reservePda,
+              bridgeAuthority: provider.wallet.publicKey,
+              deployer: provider.wallet.publicKey,
+              privateDeployer: null,
+            })
+            .rpc();
+          expect.fail("debit should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("Unauthorized");
+        }
+      });
+
+      it("Reduces public free capital by the committed amount", async () => {
+        const before = await program.account.reserve.fetch(reservePda);
+        await deployPrivate(Array.from(Buffer.alloc(32, 32)));
+
+        const after = await program.account.reserve.fetch(reservePda);
+        expect(before.totalCapital.sub(after.totalCapital).toNumber()).to.equal(committedAmount);
+        expect(
+          after.privateDeployedCapital.sub(before.privateDeployedCapital).toNumber()
+        ).to.equal(committedAmount);
+      });
+
+      it("Returns the committed amount on settlement, once", async () => {
+        const activityHash = Array.from(Buffer.alloc(32, 32));
+        const before = await program.account.reserve.fetch(reservePda);
+        await settlePrivate(activityHash);
+
+        const after = await program.account.reserve.fetch(reservePda);
+        expect(after.totalCapital.sub(before.totalCapital).toNumber()).to.equal(committedAmount);
+        expect(
+          before.privateDeployedCapital.sub(after.privateDeployedCapital).toNumber()
+        ).to.equal(committedAmount);
+
+        try {
+          await settlePrivate(activityHash);
+          expect.fail("second settlement should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("ActivityNotActive");
+        }
+      });
+
+      it("Caps a deployment at the free capital left after pending proposals", async () => {
+        const reserve = await program.account.reserve.fetch(reservePda);
+        const freeCapital = reserve.totalCapital.sub(reserve.pendingProposedCapital);
+
+        try {
+          await deployPrivate(Array.from(Buffer.alloc(32, 34)), freeCapital.addn(1));
+          expect.fail("deployment should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("InsufficientCapital");
+        }
+      });
+
+      describe("deployers", () => {
+        const deployer = Keypair.generate();
+        const activityHash = Array.from(Buffer.alloc(32, 35));
+
+        const setDeployerAuthorized = async (authorized: boolean) => {
+          const accounts = {
+            reserve: reservePda,
+            privateDeployer: privateDeployerPdaFor(deployer.publicKey),
+            auditLog: auditLogPda,
+            admin: provider.wallet.publicKey,
+          };
+          if (authorized) {
+            await program.methods
+              .authorizePrivateDeployer(deployer.publicKey)
+              .accounts({ ...accounts, systemProgram: SystemProgram.programId })
+              .rpc();
+          } else {
+            await program.methods.revokePrivateDeployer().accounts(accounts).rpc();
+          }
+        };
+
+        before(async () => {
+          const sig = await provider.connection.requestAirdrop(deployer.publicKey, 1_000_000_000);
+          await provider.connection.confirmTransaction(sig);
+        });
+
+        it("Rejects a deployer the reserve has not authorized", async () => {
+          try {
+            await privateProgram.methods
+              .deployCapitalPrivate(
+                Array.from(Buffer.alloc(32, 1)),
+                activityHash,
+                Array.from(Buffer.alloc(64, 1)),
+                new anchor.BN(committedAmount)
+              )
+              .accounts({
+                privateState: privateStatePda,
+                privateActivity: privateActivityPdaFor(activityHash, deployer.publicKey),
+                reserve: reservePda,
+                bridgeAuthority: bridgePda,
+                deployer: deployer.publicKey,
+                privateDeployer: null,
+                amanaReserveProgram: program.programId,
+                systemProgram: SystemProgram.programId,
+              })
+              .signers([deployer])
+              .rpc();
+            expect.fail("deployment should have been rejected");
+          } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("Unauthorized");
+          }
+        });
+
+        it("Accepts an authorized deployer until revoked", async () => {
+          await setDeployerAuthorized(true);
+          const before = await program.account.reserve.fetch(reservePda);
+          await deployPrivate(activityHash, committedAmount, deployer);
+
+          const after = await program.account.reserve.fetch(reservePda);
+          expect(before.totalCapital.sub(after.totalCapital).toNumber()).to.equal(committedAmount);
+
+          await setDeployerAuthorized(false);
+          try {
+            await deployPrivate(Array.from(Buffer.alloc(32, 36)), committedAmount, deployer);
+            expect.fail("deployment should have been rejected");
+          } catch (err: any) {
+            expect(err.error.errorCode.code).to.equal("Unauthorized");
+          }
+
+          // The reserve admin can still settle the revoked deployer's activity
+          await settlePrivate(activityHash, deployer.publicKey);
+        });
+      });
+    });
+
+    describe("reveal window", () => {
+      const activityHash = Array.from(Buffer.alloc(32, 33));
+
+      const setMaxRevealDelay = async (seconds: number) => {
+        await privateProgram.methods
+          .setMaxRevealDelay(new anchor.BN(seconds))
+          .accounts({ privateState: privateStatePda, admin: provider.wallet.publicKey })
+          .rpc();
+      };
+
+      const requestReveal = async () => {
+        await privateProgram.methods
+          .requestReveal()
+          .accounts({
+            privateState: privateStatePda,
+            privateActivity: privateActivityPdaFor(activityHash),
+            admin: provider.wallet.publicKey,
+          })
+          .rpc();
+      };
+
+      const reveal = async () => {
+        await privateProgram.methods
+          .revealPrivateData(Array.from(Buffer.alloc(32, 1)), Array.from(Buffer.alloc(64, 1)))
+          .accounts({
+            privateState: privateStatePda,
+            privateActivity: privateActivityPdaFor(activityHash),
+            authority: provider.wallet.publicKey,
+          })
+          .rpc();
+      };
+
+      before(async () => deployPrivate(activityHash));
+
+      after(async () => setMaxRevealDelay(0));
+
+      it("Reveals within the requested window", async () => {
+        await setMaxRevealDelay(60);
+        await requestReveal();
+        await reveal();
+
+        const activity = await privateProgram.account.privateActivity.fetch(
+          privateActivityPdaFor(activityHash)
+        );
+        expect(activity.revealDeadline.toNumber()).to.equal(0);
+      });
+
+      it("Rejects a reveal past the deadline until re-requested", async () => {
+        await setMaxRevealDelay(1);
+        await requestReveal();
+
+        const { revealDeadline } = await privateProgram.account.privateActivity.fetch(
+          privateActivityPdaFor(activityHash)
+        );
+        while ((await chainTime()) <= revealDeadline.toNumber()) {
+          await new Promise((resolve) => setTimeout(resolve, 1000));
+        }
+
+        try {
+          await reveal();
+          expect.fail("reveal should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("RevealWindowExpired");
+        }
+
+        await setMaxRevealDelay(60);
+        await requestReveal();
+        await reveal();
+      });
+    });
+  });
+
+  describe("recurring contributions", () => {
+    const amount = 50_000_000;
+    const interval = 2;
+
+    const recurringPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("recurring"), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const executeRecurring = async () => {
+      await program.methods
+        .executeRecurring()
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          recurring: recurringPda(),
+          keeper: provider.wallet.publicKey,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      await applySettlements();
+      await program.methods
+        .scheduleRecurring(new anchor.BN(amount), new anchor.BN(interval), new anchor.BN(amount * 3))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          recurring: recurringPda(),
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .cancelRecurring()
+        .accounts({ reserve: reservePda, recurring: recurringPda(), user: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    it("Refuses to execute before the installment is due", async () => {
+      try {
+        await executeRecurring();
+        expect.fail("execution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("RecurringNotDue");
+      }
+    });
+
+    it("Executes once due and advances the schedule", async () => {
+      const { nextDueAt } = await program.account.recurringContribution.fetch(recurringPda());
+      while ((await chainTime()) < nextDueAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const beforeParticipant = await program.account.participant.fetch(participantPda);
+      const beforeReserve = await program.account.reserve.fetch(reservePda);
+      await executeRecurring();
+
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(
+        participant.capitalContributed.sub(beforeParticipant.capitalContributed).toNumber()
+      ).to.equal(amount);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.sub(beforeReserve.totalCapital).toNumber()).to.equal(amount);
+
+      const recurring = await program.account.recurringContribution.fetch(recurringPda());
+      expect(recurring.nextDueAt.toNumber()).to.equal(nextDueAt.toNumber() + interval);
+    });
+  });
+
+  describe("token settlement", () => {
+    const id = Array.from(Buffer.alloc(32, 34));
+    const profit = 25_000_000; // 25 USDC
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    let usdc: PublicKey;
+    let mintVaultPda: PublicKey;
+    let vaultPda: PublicKey;
+
+    const sharePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const completeInMint = async (mint: PublicKey, activityId = id, outcome = profit) => {
+      await program.methods
+        .completeActivity(activityId, new anchor.BN(outcome), new anchor.BN(await chainTime()), mint)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(activityId),
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      usdc = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 6);
+      [mintVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_vault"), usdc.toBuffer()],
+        program.programId
+      );
+      [vaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), usdc.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeMintVault()
+        .accounts({
+          reserve: reservePda,
+          mint: usdc,
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await proposeActivity(id, 100_000_000);
+      await program.methods
+        .setSettlementMint(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: mintVaultPda,
+          initiator: provider.wallet.publicKey,
+        })
+        .rpc();
+      await approveActivity(id);
+
+      // The activity's USDC proceeds arrive in the vault before completion
+      await mintTo(provider.connection, payer, usdc, vaultPda, payer, profit);
+    });
+
+    it("Rejects an outcome in a different mint", async () => {
+      try {
+        await completeInMint(PublicKey.default);
+        expect.fail("completion should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidSettlementMint");
+      }
+    });
+
+    it("Distributes a USDC-settled profit in USDC", async () => {
+      await applySettlements();
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+      await completeInMint(usdc);
+
+      // SOL capital only regains the principal
+      const reserveAfter = await program.account.reserve.fetch(reservePda);
+      expect(reserveAfter.totalCapital.sub(reserveBefore.totalCapital).toNumber()).to.equal(
+        100_000_000
+      );
+      expect((await program.account.mintVault.fetch(mintVaultPda)).bookedBalance.toNumber()).to.equal(
+        profit
+      );
+
+      await program.methods
+        .allocateProfitShare(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantPda,
+          share: sharePda(),
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const agentAta = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        usdc,
+        provider.wallet.publicKey
+      );
+      await program.methods
+        .claimProfitToken(id)
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          share: sharePda(),
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          agentTokenAccount: agentAta.address,
+          delegate: null,
+          authority: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const share = await program.account.participantActivityShare.fetch(sharePda());
+      expect(share.mint.toBase58()).to.equal(usdc.toBase58());
+      expect(share.amount.toNumber()).to.be.greaterThan(0);
+
+      const received = await getAccount(provider.connection, agentAta.address);
+      expect(Number(received.amount)).to.equal(share.amount.toNumber());
+
+      const mintVault = await program.account.mintVault.fetch(mintVaultPda);
+      expect(mintVault.bookedBalance.toNumber()).to.equal(profit - share.amount.toNumber());
+    });
+
+    it("Realizes a USDC-settled loss in USDC, in sequence", async () => {
+      const lossId = Array.from(Buffer.alloc(32, 86));
+      const loss = 10_000_000; // 10 USDC
+      await proposeActivity(lossId, 100_000_000);
+      await program.methods
+        .setSettlementMint(lossId)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(lossId),
+          mintVault: mintVaultPda,
+          initiator: provider.wallet.publicKey,
+        })
+        .rpc();
+      await approveActivity(lossId);
+      await completeInMint(usdc, lossId, -loss);
+
+      const activity = await program.account.activity.fetch(activityPdaFor(lossId));
+      expect(activity.settlementSeq.toNumber()).to.be.greaterThan(0);
+      const vaultBefore = await program.account.mintVault.fetch(mintVaultPda);
+      const participantBefore = await program.account.participant.fetch(participantPda);
+
+      await applySettlements();
+
+      const expected = Math.floor(
+        (loss * participantBefore.capitalContributed.toNumber()) / activity.distributionBase.toNumber()
+      );
+      const vaultAfter = await program.account.mintVault.fetch(mintVaultPda);
+      expect(vaultAfter.realizedLoss.sub(vaultBefore.realizedLoss).toNumber()).to.equal(expected);
+
+      const record = await program.account.lossRealization.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("loss"), Buffer.from(lossId), provider.wallet.publicKey.toBuffer()],
+          program.programId
+        )[0]
+      );
+      expect(record.mint.toBase58()).to.equal(usdc.toBase58());
+      expect(record.amount.toNumber()).to.equal(expected);
+
+      // The principal came back in SOL, so SOL capital is untouched
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.capitalContributed.toString()).to.equal(
+        participantBefore.capitalContributed.toString()
+      );
+      expect(participant.settledThrough.toString()).to.equal(activity.settlementSeq.toString());
+    });
+  });
+
+  describe("in-kind distribution", () => {
+    const distributionId = Array.from(Buffer.alloc(32, 75));
+    const amount = 1_000_000_000; // 1,000 units of a tokenized commodity
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    let gold: PublicKey;
+    let mintVaultPda: PublicKey;
+    let vaultPda: PublicKey;
+    let adminAta: PublicKey;
+
+    const [distributionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("in_kind"), Buffer.from(distributionId)],
+      program.programId
+    );
+    const [entitlementPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("in_kind_claim"), Buffer.from(distributionId), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const setAllowInKind = (allowInKind: boolean) =>
+      program.methods
+        .setAllowInKind(allowInKind)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+    const distributeInKind = () =>
+      program.methods
+        .distributeInKind(distributionId, new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          distribution: distributionPda,
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          source: adminAta,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      gold = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 6);
+      [mintVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_vault"), gold.toBuffer()],
+        program.programId
+      );
+      [vaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), gold.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeMintVault()
+        .accounts({
+          reserve: reservePda,
+          mint: gold,
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // The activity's return arrives with the admin as the asset itself
+      adminAta = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, payer, gold, provider.wallet.publicKey)
+      ).address;
+      await mintTo(provider.connection, payer, gold, adminAta, payer, amount);
+    });
+
+    after(async () => setAllowInKind(false));
+
+    it("Rejects an in-kind distribution while disabled", async () => {
+      try {
+        await distributeInKind();
+        expect.fail("in-kind distribution should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InKindDisabled");
+      }
+    });
+
+    it("Transfers the participant's pro-rata share of the asset", async () => {
+      await setAllowInKind(true);
+      await applySettlements();
+      await distributeInKind();
+
+      // Capital is held where it was at distribution until the claim
+      try {
+        await program.methods
+          .withdrawCapital(new anchor.BN(1_000_000))
+          .accounts({ reserve: reservePda, participant: participantPda, user: provider.wallet.publicKey })
+          .rpc();
+        expect.fail("the withdrawal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SettlementsPending");
+      }
+
+      const distribution = await program.account.inKindDistribution.fetch(distributionPda);
+      expect(distribution.amount.toNumber()).to.equal(amount);
+      expect((await getAccount(provider.connection, vaultPda)).amount.toString()).to.equal(
+        amount.toString()
+      );
+
+      const participant = await program.account.participant.fetch(participantPda);
+      const expected = participant.capitalContributed
+        .mul(new anchor.BN(amount))
+        .div(distribution.distributionBase)
+        .toNumber();
+      const before = Number((await getAccount(provider.connection, adminAta)).amount);
+
+      await program.methods
+        .claimInKind(distributionId)
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          distribution: distributionPda,
+          entitlement: entitlementPda,
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          agentTokenAccount: adminAta,
+          delegate: null,
+          authority: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const received = Number((await getAccount(provider.connection, adminAta)).amount) - before;
+      expect(received).to.equal(expected);
+
+      const entitlement = await program.account.inKindEntitlement.fetch(entitlementPda);
+      expect(entitlement.amount.toNumber()).to.equal(expected);
+      expect(entitlement.mint.toBase58()).to.equal(gold.toBase58());
+
+      const mintVault = await program.account.mintVault.fetch(mintVaultPda);
+      expect(mintVault.bookedBalance.toNumber()).to.equal(amount - expected);
+
+      const after = await program.account.participant.fetch(participantPda);
+      expect(after.settledThrough.toNumber()).to.equal(distribution.settlementSeq.toNumber());
+    });
+  });
+
+  describe("snapshot_participant", () => {
+    const snapshot = async (participant: PublicKey) => {
+      const { events } = await program.methods
+        .snapshotParticipant()
+        .accounts({ reserve: reservePda, participant })
+        .simulate();
+      return events.find((e) => e.name === "participantSnapshotEvent")!.data as any;
+    };
+
+    it("Emits the participant's position and reserve share", async () => {
+      const event = await snapshot(participantPda);
+      const participant = await program.account.participant.fetch(participantPda);
+      const reserve = await program.account.reserve.fetch(reservePda);
+
+      expect(event.agent.toBase58()).to.equal(participant.agent.toBase58());
+      expect(event.capitalContributed.toNumber()).to.equal(participant.capitalContributed.toNumber());
+      expect(event.profitShare.toNumber()).to.equal(participant.profitShare.toNumber());
+      expect(event.lossShare.toNumber()).to.equal(participant.lossShare.toNumber());
+      expect(event.pendingProfit.toNumber()).to.equal(participant.pendingProfit.toNumber());
+      expect(event.joinedAt.toNumber()).to.equal(participant.joinedAt.toNumber());
+      expect(event.tenureSeconds.toNumber()).to.equal(
+        event.timestamp.toNumber() - participant.joinedAt.toNumber()
+      );
+      expect(event.isActive).to.be.true;
+      expect(event.reserveShareBps).to.equal(
+        participant.capitalContributed.muln(10000).div(reserve.totalContributed).toNumber()
+      );
+    });
+
+    it("Reports a zero share for a participant who has withdrawn everything", async () => {
+      const leaver = Keypair.generate();
+      const amount = 1_000_000_000;
+      const [leaverParticipant] = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), leaver.publicKey.toBuffer()],
+        program.programId
+      );
+      const sig = await provider.connection.requestAirdrop(leaver.publicKey, 2_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await program.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: leaverParticipant,
+          charity: null,
+          tierApprover: null,
+          user: leaver.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([leaver])
+        .rpc();
+      await program.methods
+        .withdrawCapital(new anchor.BN(amount))
+        .accounts({ reserve: reservePda, participant: leaverParticipant, user: leaver.publicKey })
+        .signers([leaver])
+        .rpc();
+
+      const event = await snapshot(leaverParticipant);
+      expect(event.capitalContributed.toNumber()).to.equal(0);
+      expect(event.reserveShareBps).to.equal(0);
+    });
+  });
+
+  describe("leave_reserve", () => {
+    const charity = Keypair.generate();
+    const amount = 1_000_000_000;
+    const residual = 500;
+
+    const setCharity = async (recipient: PublicKey) => {
+      await program.methods
+        .setEntryDonation(0, recipient)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const joinFresh = async () => {
+      const leaver = Keypair.generate();
+      const [leaverParticipant] = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), leaver.publicKey.toBuffer()],
+        program.programId
+      );
+      const sig = await provider.connection.requestAirdrop(leaver.publicKey, 2_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await program.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: leaverParticipant,
+          charity: null,
+          tierApprover: null,
+          user: leaver.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([leaver])
+        .rpc();
+      await program.methods
+        .withdrawCapital(new anchor.BN(amount - residual))
+        .accounts({ reserve: reservePda, participant: leaverParticipant, user: leaver.publicKey })
+        .signers([leaver])
+        .rpc();
+
+      return { leaver, leaverParticipant };
+    };
+
+    const leave = async (
+      leaver: Keypair,
+      leaverParticipant: PublicKey,
+      dustThreshold: number,
+      charityAccount: PublicKey | null
+    ) =>
+      program.methods
+        .leaveReserve(new anchor.BN(dustThreshold))
+        .accounts({
+          reserve: reservePda,
+          participant: leaverParticipant,
+          charity: charityAccount,
+          user: leaver.publicKey,
+        })
+        .signers([leaver])
+        .rpc();
+
+    before(async () => {
+      // The charity must already be rent-exempt to receive a dust-sized credit
+      const sig = await provider.connection.requestAirdrop(charity.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await setCharity(charity.publicKey);
+    });
+
+    after(async () => setCharity(PublicKey.default));
+
+    it("Donates a sub-threshold residual to the charity and closes the participant", async () => {
+      const { leaver, leaverParticipant } = await joinFresh();
+      const charityBefore = await provider.connection.getBalance(charity.publicKey);
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+
+      await leave(leaver, leaverParticipant, residual + 1, charity.publicKey);
+
+      expect(await provider.connection.getBalance(charity.publicKey)).to.equal(
+        charityBefore + residual
+      );
+      expect(await program.account.participant.fetchNullable(leaverParticipant)).to.be.null;
+
+      const reserveAfter = await program.account.reserve.fetch(reservePda);
+      expect(reserveBefore.totalCapital.sub(reserveAfter.totalCapital).toNumber()).to.equal(residual);
+      expect(reserveBefore.participantCount - reserveAfter.participantCount).to.equal(1);
+    });
+
+    it("Returns the residual when the participant does not opt in", async () => {
+      const { leaver, leaverParticipant } = await joinFresh();
+      const charityBefore = await provider.connection.getBalance(charity.publicKey);
+
+      await leave(leaver, leaverParticipant, 0, null);
+
+      expect(await provider.connection.getBalance(charity.publicKey)).to.equal(charityBefore);
+      expect(await program.account.participant.fetchNullable(leaverParticipant)).to.be.null;
+    });
+  });
+
+  describe("validators", () => {
+    const id = Array.from(Buffer.alloc(32, 35));
+    const minStake = 1_000_000_000;
+    const unbondingSeconds = 2;
+    const validator = Keypair.generate();
+
+    const validatorPdaFor = (authority: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("validator"), authority.toBuffer()],
+        program.programId
+      )[0];
+
+    const setValidatorPolicy = async (stake: number, unbonding: number) => {
+      await program.methods
+        .setValidatorPolicy(new anchor.BN(stake), new anchor.BN(unbonding))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const registerValidator = async (authority: Keypair, stake: number) => {
+      await program.methods
+        .registerValidator(new anchor.BN(stake))
+        .accounts({
+          reserve: reservePda,
+          validator: validatorPdaFor(authority.publicKey),
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const withdrawStake = async () => {
+      await program.methods
+        .withdrawValidatorStake()
+        .accounts({
+          reserve: reservePda,
+          validator: validatorPdaFor(validator.publicKey),
+          authority: validator.publicKey,
+        })
+        .signers([validator])
+        .rpc();
+    };
+
+    before(async () => {
+      await setValidatorPolicy(minStake, unbondingSeconds);
+      const sig = await provider.connection.requestAirdrop(validator.publicKey, 3_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+    });
+
+    after(async () => setValidatorPolicy(0, 0));
+
+    it("Rejects an under-staked validator", async () => {
+      try {
+        await registerValidator(validator, minStake - 1);
+        expect.fail("registration should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InsufficientStake");
+      }
+      const account = await program.account.validator.fetchNullable(
+        validatorPdaFor(validator.publicKey)
+      );
+      expect(account).to.be.null;
+    });
+
+    it("Requires a bonded validator to approve", async () => {
+      await proposeActivity(id, 1_000_000);
+
+      try {
+        await approveActivity(id);
+        expect.fail("approval should have required a validator");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ValidatorRequired");
+      }
+
+      await registerValidator(validator, minStake);
+      const registered = await program.account.validator.fetch(validatorPdaFor(validator.publicKey));
+      expect(registered.stake.toNumber()).to.equal(minStake);
+
+      await program.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          validator: validatorPdaFor(validator.publicKey),
+          validatorAuthority: validator.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .signers([validator])
+        .rpc();
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.status).to.deep.equal({ approved: {} });
+    });
+
+    it("Holds the stake until the unbonding delay has passed", async () => {
+      await program.methods
+        .unregisterValidator()
+        .accounts({
+          reserve: reservePda,
+          validator: validatorPdaFor(validator.publicKey),
+          authority: validator.publicKey,
+        })
+        .signers([validator])
+        .rpc();
+
+      try {
+        await withdrawStake();
+        expect.fail("withdrawal should wait for unbonding");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("UnbondingNotElapsed");
+      }
+
+      const { unbondingEndsAt } = await program.account.validator.fetch(
+        validatorPdaFor(validator.publicKey)
+      );
+      while ((await chainTime()) < unbondingEndsAt.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const balanceBefore = await provider.connection.getBalance(validator.publicKey);
+      await withdrawStake();
+      expect(await provider.connection.getBalance(validator.publicKey)).to.be.greaterThan(
+        balanceBefore + minStake - 10_000
+      );
+      const account = await program.account.validator.fetchNullable(
+        validatorPdaFor(validator.publicKey)
+      );
+      expect(account).to.be.null;
+    });
+  });
+
+  describe("compute_portfolio_risk", () => {
+    // Activities only become Active through a realtime deployment on the ER,
+    // so this marks approved ones active in a bankrun ledger
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    const activities = [
+      { id: Array.from(Buffer.alloc(32, 36)), capital: 1_000_000, riskRating: 1 },
+      { id: Array.from(Buffer.alloc(32, 37)), capital: 3_000_000, riskRating: 4 },
+    ];
+    const approvedId = Array.from(Buffer.alloc(32, 38));
+
+    const activityAccounts = (ids: number[][]) =>
+      ids.map((id) => ({ pubkey: activityPdaFor(id), isSigner: false, isWritable: false }));
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      const admin = bankrunProvider.wallet.publicKey;
+      const participant = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), admin.toBuffer()],
+        program.programId
+      )[0];
+      const initiatorIndex = PublicKey.findProgramAddressSync(
+        [Buffer.from("initiator_index"), admin.toBuffer()],
+        program.programId
+      )[0];
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .joinReserve(new anchor.BN(1_000_000_000), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant,
+          charity: null,
+          tierApprover: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      for (const { id, capital, riskRating } of [...activities, { id: approvedId, capital: 1_000_000, riskRating: 1 }]) {
+        await bankrunProgram.methods
+          .proposeActivity(id, new anchor.BN(capital), riskRating, new anchor.BN(0))
+          .accounts({
+            reserve: reservePda,
+            participant,
+            activity: activityPdaFor(id),
+            initiatorIndex,
+            complianceFund: null,
+            user: admin,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        await bankrunProgram.methods
+          .approveActivity(null)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            validator: null,
+            validatorAuthority: null,
+            authority: admin,
+          })
+          .rpc();
+      }
+
+      for (const { id } of activities) {
+        const pda = activityPdaFor(id);
+        const info = await context.banksClient.getAccount(pda);
+        const activity = bankrunProgram.coder.accounts.decode("activity", Buffer.from(info!.data));
+        activity.status = { active: {} };
+        const data = await bankrunProgram.coder.accounts.encode("activity", activity);
+        context.setAccount(pda, { ...info!, data: Buffer.concat([data, Buffer.alloc(info!.data.length - data.length)]) });
+      }
+    });
+
+    it("Emits the capital-weighted average risk rating", async () => {
+      const { events } = await bankrunProgram.methods
+        .computePortfolioRisk()
+        .accounts({ reserve: reservePda })
+        .remainingAccounts(activityAccounts(activities.map(({ id }) => id)))
+        .simulate();
+
+      const risk = events.find((e) => e.name === "portfolioRiskEvent")!.data as any;
+      // (1 * 1M + 4 * 3M) / 4M = 3.25
+      expect(risk.averageRiskRatingBps).to.equal(32500);
+      expect(risk.activityCount).to.equal(2);
+      expect(risk.totalCapitalDeployed.toNumber()).to.equal(4_000_000);
+    });
+
+    it("Rejects an activity that is only approved", async () => {
+      try {
+        await bankrunProgram.methods
+          .computePortfolioRisk()
+          .accounts({ reserve: reservePda })
+          .remainingAccounts(activityAccounts([activities[0].id, approvedId]))
+          .rpc();
+        expect.fail("an approved activity should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidActivityStatus");
+      }
+    });
+  });
+
+  describe("profit vesting", () => {
+    const id = Array.from(Buffer.alloc(32, 39));
+    const cliff = 5;
+    const duration = 1000;
+
+    const sharePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const setVestingSchedule = async (cliffSeconds: number, durationSeconds: number) => {
+      await program.methods
+        .setVestingSchedule(new anchor.BN(cliffSeconds), new anchor.BN(durationSeconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const claimProfit = () =>
+      program.methods
+        .claimProfit(id)
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          share: sharePda(),
+          delegate: null,
+          agent: provider.wallet.publicKey,
+          authority: provider.wallet.publicKey,
+        });
+
+    before(async () => {
+      await setVestingSchedule(cliff, duration);
+      await applySettlements();
+      await proposeActivity(id, 100_000_000);
+      await approveActivity(id);
+      await completeActivity(id, 10_000_000);
+
+      await program.methods
+        .allocateProfitShare(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantPda,
+          share: sharePda(),
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    after(async () => setVestingSchedule(0, 0));
+
+    it("Releases nothing before the cliff", async () => {
+      try {
+        await claimProfit().rpc();
+        expect.fail("claim before the cliff should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NothingToClaim");
+      }
+
+      const share = await program.account.participantActivityShare.fetch(sharePda());
+      expect(share.claimed.toNumber()).to.equal(0);
+      expect(share.vesting.cliffSeconds.toNumber()).to.equal(cliff);
+    });
+
+    it("Releases a partial amount mid-vesting", async () => {
+      const { allocatedAt } = await program.account.participantActivityShare.fetch(sharePda());
+      while ((await chainTime()) < allocatedAt.toNumber() + cliff) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const { events } = await claimProfit().simulate();
+      const claimed = events.find((e) => e.name === "profitClaimedEvent")!.data as any;
+      expect(claimed.vested.toNumber()).to.equal(claimed.amount.toNumber());
+
+      await claimProfit().rpc();
+
+      const share = await program.account.participantActivityShare.fetch(sharePda());
+      expect(share.claimed.toNumber()).to.be.greaterThan(0);
+      expect(share.claimed.toNumber()).to.be.lessThan(share.amount.toNumber());
+    });
+  });
+
+  describe("verify_activity_integrity", () => {
+    const id = Array.from(Buffer.alloc(32, 40));
+    const corruptedId = Array.from(Buffer.alloc(32, 41));
+    const INTEGRITY_OUTCOME_WITHOUT_COMPLETION = 5;
+
+    const verify = async (target: Program, activityId: number[]) => {
+      const { events } = await target.methods
+        .verifyActivityIntegrity(activityId)
+        .accounts({ reserve: reservePda, activity: activityPdaFor(activityId) })
+        .simulate();
+      return events.find((e) => e.name === "integrityCheckEvent")!.data as any;
+    };
+
+    it("Passes an activity that went through the normal lifecycle", async () => {
+      await proposeActivity(id, 1_000_000);
+      await approveActivity(id);
+      await completeActivity(id, 0);
+
+      const result = await verify(program, id);
+      expect(result.passed).to.be.true;
+      expect(result.violationCode).to.equal(0);
+    });
+
+    it("Flags an outcome recorded on an activity that never completed", async () => {
+      // Real instructions cannot produce this state, so write it into a bankrun ledger
+      const now = Math.floor(Date.now() / 1000);
+      const data = await program.coder.accounts.encode(program.account.activity.idlAccount.name, {
+        activityId: corruptedId,
+        initiator: provider.wallet.publicKey,
+        capitalRequired: new anchor.BN(1_000_000),
+        capitalDeployed: new anchor.BN(1_000_000),
+        riskRating: 1,
+        expectedDuration: new anchor.BN(0),
+        maturityAt: new anchor.BN(0),
+        status: { approved: {} },
+        createdAt: new anchor.BN(now - 10),
+        completedAt: new anchor.BN(now),
+        outcome: new anchor.BN(500_000),
+        isValidated: false,
+        isSettled: false,
+        settledOutcome: new anchor.BN(0),
+        distributionBase: new anchor.BN(0),
+        parentId: Array(32).fill(0),
+        settlementMint: PublicKey.default,
+        approvedAt: new anchor.BN(now - 5),
+        bump: PublicKey.findProgramAddressSync(
+          [Buffer.from("activity"), Buffer.from(corruptedId)],
+          program.programId
+        )[1],
+      });
+
+      const context = await startAnchor(
+        "",
+        [],
+        [
+          {
+            address: activityPdaFor(corruptedId),
+            info: {
+              lamports: 1_000_000_000,
+              data,
+              owner: program.programId,
+              executable: false,
+            },
+          },
+        ]
+      );
+      const bankrunProgram = new Program(program.idl, new BankrunProvider(context));
+
+      const result = await verify(bankrunProgram, corruptedId);
+      expect(result.passed).to.be.false;
+      expect(result.violationCode).to.equal(INTEGRITY_OUTCOME_WITHOUT_COMPLETION);
+    });
+  });
+
+  describe("dump_config", () => {
+    it("Emits every configurable parameter as stored", async () => {
+      const { events } = await program.methods
+        .dumpConfig()
+        .accounts({ reserve: reservePda })
+        .simulate();
+      const config = events.find((e) => e.name === "reserveConfigEvent")!.data as any;
+      const reserve = await program.account.reserve.fetch(reservePda);
+
+      for (const key of Object.keys(config)) {
+        expect(config[key].toString(), key).to.equal((reserve as any)[key].toString());
+      }
+      expect(Object.keys(config)).to.include.members([
+        "admin",
+        "minCapitalContribution",
+        "maxParticipants",
+        "allowNetting",
+        "minActivityCapital",
+        "maxOutcomeAgeSeconds",
+        "maxRiskExposure",
+        "entryFeeBps",
+        "charity",
+        "maturityGraceSeconds",
+        "privateBridge",
+        "minValidatorStake",
+        "validatorUnbondingSeconds",
+        "vestingCliffSeconds",
+        "vestingDurationSeconds",
+      ]);
+    });
+  });
+
+  describe("qard limit", () => {
+    const borrower = Keypair.generate();
+    const limitBps = 1000;
+    let qardLoanPda: PublicKey;
+
+    const setQardLimit = async (bps: number) => {
+      await program.methods
+        .setQardLimit(bps)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const issueQard = async (amount: number) => {
+      await program.methods
+        .issueQard(new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanPda,
+          borrower: borrower.publicKey,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    const repayQard = async (amount: number) => {
+      await program.methods
+        .repayQard(new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanPda,
+          borrower: borrower.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([borrower])
+        .rpc();
+    };
+
+    const expectLimitExceeded = async (amount: number) => {
+      try {
+        await issueQard(amount);
+        expect.fail("issuance should have exceeded the Qard limit");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("QardLimitExceeded");
+      }
+    };
+
+    let limit: number;
+
+    before(async () => {
+      [qardLoanPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("qard"), borrower.publicKey.toBuffer()],
+        program.programId
+      );
+      const sig = await provider.connection.requestAirdrop(borrower.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await setQardLimit(limitBps);
+      const reserve = await program.account.reserve.fetch(reservePda);
+      const capital = reserve.totalCapital.add(reserve.totalQardOutstanding);
+      limit = capital.muln(limitBps).divn(10000).sub(reserve.totalQardOutstanding).toNumber();
+    });
+
+    after(async () => {
+      const loan = await program.account.qardLoan.fetchNullable(qardLoanPda);
+      if (loan !== null && loan.outstanding.toNumber() > 0) {
+        await repayQard(loan.outstanding.toNumber());
+      }
+      await setQardLimit(0);
+    });
+
+    it("Rejects issuing beyond the cap", async () => {
+      await expectLimitExceeded(limit + 1);
+
+      const loan = await program.account.qardLoan.fetchNullable(qardLoanPda);
+      expect(loan).to.be.null;
+    });
+
+    it("Issues up to the cap and then stops", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      await issueQard(limit);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalQardOutstanding.sub(before.totalQardOutstanding).toNumber()).to.equal(limit);
+      expect(before.totalCapital.sub(reserve.totalCapital).toNumber()).to.equal(limit);
+      const loan = await program.account.qardLoan.fetch(qardLoanPda);
+      expect(loan.outstanding.toNumber()).to.equal(limit);
+
+      await expectLimitExceeded(1);
+    });
+
+    it("Frees headroom on repayment", async () => {
+      const repaid = Math.floor(limit / 2);
+      await repayQard(repaid);
+
+      const loan = await program.account.qardLoan.fetch(qardLoanPda);
+      expect(loan.outstanding.toNumber()).to.equal(limit - repaid);
+
+      await issueQard(repaid);
+      await expectLimitExceeded(1);
+    });
+  });
+
+  describe("qard extension", () => {
+    const borrower = Keypair.generate();
+    const amount = 1_000_000;
+    const qardLoanFor = (loanBorrower: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("qard"), loanBorrower.toBuffer()],
+        program.programId
+      )[0];
+    const qardLoanPda = qardLoanFor(borrower.publicKey);
+    let dueAt: number;
+
+    const setQardLimit = async (bps: number) => {
+      await program.methods
+        .setQardLimit(bps)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const requestExtension = (newDueAt: number) =>
+      program.methods
+        .requestQardExtension(new anchor.BN(newDueAt))
+        .accounts({ reserve: reservePda, qardLoan: qardLoanPda, borrower: borrower.publicKey })
+        .signers([borrower])
+        .rpc();
+
+    const approveExtension = () =>
+      program.methods
+        .approveQardExtension()
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanPda,
+          auditLog: auditLogPda,
+          approver: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    const issueQard = (loanBorrower: PublicKey, loanAmount: number) =>
+      program.methods
+        .issueQard(new anchor.BN(loanAmount))
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanFor(loanBorrower),
+          borrower: loanBorrower,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const setQardDueDate = (loanBorrower: PublicKey, due: number) =>
+      program.methods
+        .setQardDueDate(new anchor.BN(due))
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanFor(loanBorrower),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    const setShariaBoard = (board: PublicKey) =>
+      program.methods
+        .setShariaBoard(board)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(borrower.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await setQardLimit(1000);
+
+      dueAt = (await chainTime()) + 3600;
+      await issueQard(borrower.publicKey, amount);
+      await setQardDueDate(borrower.publicKey, dueAt);
+    });
+
+    after(async () => {
+      await program.methods
+        .repayQard(new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanPda,
+          borrower: borrower.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([borrower])
+        .rpc();
+      await setQardLimit(0);
+    });
+
+    it("Only accepts a later due date", async () => {
+      await expectRejected(requestExtension(dueAt), "InvalidQardDueDate");
+      await expectRejected(approveExtension(), "NoQardExtensionRequested");
+    });
+
+    it("Moves the due date without adding anything owed", async () => {
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+
+      await requestExtension(dueAt + 86_400);
+      await approveExtension();
+
+      const loan = await program.account.qardLoan.fetch(qardLoanPda);
+      expect(loan.dueAt.toNumber()).to.equal(dueAt + 86_400);
+      expect(loan.requestedDueAt.toNumber()).to.equal(0);
+      expect(loan.extensionCount).to.equal(1);
+      expect(loan.outstanding.toNumber()).to.equal(amount);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalQardOutstanding.toNumber()).to.equal(reserveBefore.totalQardOutstanding.toNumber());
+
+      // Repaying more than the original amount is still refused
+      await expectRejected(
+        program.methods
+          .repayQard(new anchor.BN(amount + 1))
+          .accounts({
+            reserve: reservePda,
+            qardLoan: qardLoanPda,
+            borrower: borrower.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([borrower])
+          .rpc(),
+        "InvalidAmount"
+      );
+    });
+
+    it("Sets the due date only once", async () => {
+      await expectRejected(setQardDueDate(borrower.publicKey, dueAt + 60), "InvalidQardDueDate");
+
+      const loan = await program.account.qardLoan.fetch(qardLoanPda);
+      expect(loan.dueAt.toNumber()).to.equal(dueAt + 86_400);
+    });
+
+    it("Lets the Sharia board approve an extension", async () => {
+      const board = Keypair.generate();
+      await setShariaBoard(board.publicKey);
+
+      try {
+        await requestExtension(dueAt + 2 * 86_400);
+        await program.methods
+          .approveQardExtension()
+          .accounts({
+            reserve: reservePda,
+            qardLoan: qardLoanPda,
+            auditLog: auditLogPda,
+            approver: board.publicKey,
+          })
+          .signers([board])
+          .rpc();
+      } finally {
+        await setShariaBoard(PublicKey.default);
+      }
+
+      const loan = await program.account.qardLoan.fetch(qardLoanPda);
+      expect(loan.dueAt.toNumber()).to.equal(dueAt + 2 * 86_400);
+      expect(loan.extensionCount).to.equal(2);
+    });
+
+    it("Refuses an extension approved by anyone else", async () => {
+      const outsider = Keypair.generate();
+      await requestExtension(dueAt + 3 * 86_400);
+
+      try {
+        await program.methods
+          .approveQardExtension()
+          .accounts({
+            reserve: reservePda,
+            qardLoan: qardLoanPda,
+            auditLog: auditLogPda,
+            approver: outsider.publicKey,
+          })
+          .signers([outsider])
+          .rpc();
+        expect.fail("an outsider should not approve an extension");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+      await approveExtension();
+    });
+
+    it("Refuses to top up an overdue loan", async () => {
+      const late = Keypair.generate();
+      await issueQard(late.publicKey, amount);
+      await setQardDueDate(late.publicKey, (await chainTime()) + 2);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      await expectRejected(issueQard(late.publicKey, 1), "QardOverdue");
+
+      const sig = await provider.connection.requestAirdrop(late.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await program.methods
+        .repayQard(new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          qardLoan: qardLoanFor(late.publicKey),
+          borrower: late.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([late])
+        .rpc();
+
+      const loan = await program.account.qardLoan.fetch(qardLoanFor(late.publicKey));
+      expect(loan.dueAt.toNumber()).to.equal(0);
+    });
+
+    it("Offers no way to attach a fee to an extension", async () => {
+      const args = (name: string) =>
+        program.idl.instructions.find((ix) => ix.name === name)!.args.map((arg) => arg.name);
+      expect(args("request_qard_extension")).to.deep.equal(["new_due_at"]);
+      expect(args("approve_qard_extension")).to.deep.equal([]);
+    });
+  });
+
+  describe("completion bonds", () => {
+    const cleanId = Array.from(Buffer.alloc(32, 42));
+    const disputedId = Array.from(Buffer.alloc(32, 43));
+    const bond = 100_000_000;
+
+    const bondPdaFor = (id: number[], reporter: PublicKey = provider.wallet.publicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("completion_bond"), Buffer.from(id), reporter.toBuffer()],
+        program.programId
+      )[0];
+
+    const setCompletionBond = async (amount: number, windowSeconds: number) => {
+      await program.methods
+        .setCompletionBond(new anchor.BN(amount), new anchor.BN(windowSeconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const postBond = async (id: number[]) => {
+      await program.methods
+        .postCompletionBond(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          completionBond: bondPdaFor(id),
+          reporter: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    const completeWithBond = async (id: number[]) => {
+      await program.methods
+        .completeActivity(id, new anchor.BN(0), new anchor.BN(await chainTime()), PublicKey.default)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: null,
+          vault: null,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: bondPdaFor(id),
+          reporter: provider.wallet.publicKey,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .rpc();
+    };
+
+    const refundBond = async (id: number[]) => {
+      await program.methods
+        .refundCompletionBond()
+        .accounts({
+          reserve: reservePda,
+          completionBond: bondPdaFor(id),
+          reporter: provider.wallet.publicKey,
+        })
+        .rpc();
+    };
+
+    after(async () => setCompletionBond(0, 0));
+
+    it("Refunds the bond after a clean dispute window", async () => {
+      const windowSeconds = 2;
+      await setCompletionBond(bond, windowSeconds);
+      await proposeActivity(cleanId, 1_000_000);
+      await approveActivity(cleanId);
+
+      try {
+        await completeActivity(cleanId, 0);
+        expect.fail("completion should have required a bond");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("CompletionBondRequired");
+      }
+
+      await postBond(cleanId);
+      await completeWithBond(cleanId);
+
+      try {
+        await refundBond(cleanId);
+        expect.fail("refund should have waited for the dispute window");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("DisputeWindowOpen");
+      }
+
+      const { completedAt } = await program.account.completionBond.fetch(bondPdaFor(cleanId));
+      while ((await chainTime()) < completedAt.toNumber() + windowSeconds) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await refundBond(cleanId);
+
+      const account = await program.account.completionBond.fetchNullable(bondPdaFor(cleanId));
+      expect(account).to.be.null;
+    });
+
+    it("Accepts a bond only from the reporter who posted it", async () => {
+      const id = Array.from(Buffer.alloc(32, 89));
+      const other = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(other.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      await setCompletionBond(bond, 3600);
+      await proposeActivity(id, 1_000_000);
+      await approveActivity(id);
+
+      // Another reporter's bond lives at its own address and cannot block this one
+      await program.methods
+        .postCompletionBond(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          completionBond: bondPdaFor(id, other.publicKey),
+          reporter: other.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([other])
+        .rpc();
+      await postBond(id);
+
+      try {
+        await program.methods
+          .completeActivity(id, new anchor.BN(0), new anchor.BN(await chainTime()), PublicKey.default)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            mintVault: null,
+            vault: null,
+            validator: null,
+            validatorAuthority: null,
+            completionBond: bondPdaFor(id, other.publicKey),
+            reporter: null,
+            reporterRecord: null,
+            guaranteeFund: null,
+          })
+          .rpc();
+        expect.fail("completion should have required the bond's reporter");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+
+      await completeWithBond(id);
+      const { completedAt } = await program.account.completionBond.fetch(bondPdaFor(id));
+      expect(completedAt.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("Slashes the bond to the reserve on a successful dispute", async () => {
+      await setCompletionBond(bond, 3600);
+      await proposeActivity(disputedId, 1_000_000);
+      await approveActivity(disputedId);
+      await postBond(disputedId);
+      await completeWithBond(disputedId);
+
+      const before = await program.account.reserve.fetch(reservePda);
+      await program.methods
+        .slashCompletionBond()
+        .accounts({
+          reserve: reservePda,
+          completionBond: bondPdaFor(disputedId),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.sub(before.totalCapital).toNumber()).to.equal(bond);
+      const account = await program.account.completionBond.fetchNullable(bondPdaFor(disputedId));
+      expect(account).to.be.null;
+    });
+  });
+
+  describe("activity duration bounds", () => {
+    const minDuration = 3600;
+    const maxDuration = 30 * 86400;
+
+    const setDurationBounds = async (min: number, max: number) => {
+      await program.methods
+        .setActivityDurationBounds(new anchor.BN(min), new anchor.BN(max))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const expectOutOfBounds = async (id: number[], duration: number) => {
+      try {
+        await proposeActivity(id, 1_000_000, 1, duration);
+        expect.fail("proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("DurationOutOfBounds");
+      }
+      const account = await program.account.activity.fetchNullable(activityPdaFor(id));
+      expect(account).to.be.null;
+    };
+
+    before(async () => setDurationBounds(minDuration, maxDuration));
+
+    after(async () => setDurationBounds(0, 0));
+
+    it("Rejects a duration shorter than the minimum", async () => {
+      await expectOutOfBounds(Array.from(Buffer.alloc(32, 45)), minDuration - 1);
+    });
+
+    it("Rejects a duration longer than the maximum", async () => {
+      await expectOutOfBounds(Array.from(Buffer.alloc(32, 46)), maxDuration + 1);
+    });
+
+    it("Stores a duration within the bounds", async () => {
+      const id = Array.from(Buffer.alloc(32, 47));
+      await proposeActivity(id, 1_000_000, 1, maxDuration);
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.expectedDuration.toNumber()).to.equal(maxDuration);
+    });
+  });
+
+  describe("compute_utilization", () => {
+    const id = Array.from(Buffer.alloc(32, 48));
+
+    const utilization = async () => {
+      const { events } = await program.methods
+        .computeUtilization()
+        .accounts({ reserve: reservePda })
+        .simulate();
+      return events.find((e) => e.name === "utilizationEvent")!.data as any;
+    };
+
+    // A pending proposal reserves part of free capital as the buffer
+    before(async () => proposeActivity(id, 1_000_000));
+
+    after(async () => {
+      await program.methods
+        .rejectActivity(0)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          auditLog: auditLogPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Splits gross capital into shares that sum to 10000 bps", async () => {
+      const result = await utilization();
+      const reserve = await program.account.reserve.fetch(reservePda);
+
+      const gross = reserve.totalCapital
+        .add(reserve.deployedCapital)
+        .add(reserve.privateDeployedCapital)
+        .add(reserve.totalQardOutstanding);
+      const deployed = gross.sub(reserve.totalCapital);
+      const buffer = anchor.BN.min(reserve.pendingProposedCapital, reserve.totalCapital);
+
+      expect(result.grossCapital.toString()).to.equal(gross.toString());
+      expect(result.deployedCapital.toString()).to.equal(deployed.toString());
+      expect(result.bufferCapital.toString()).to.equal(buffer.toString());
+      expect(buffer.toNumber()).to.be.at.least(1_000_000);
+      expect(result.deployedBps).to.equal(deployed.muln(10000).div(gross).toNumber());
+      expect(result.bufferBps).to.equal(buffer.muln(10000).div(gross).toNumber());
+      expect(result.deployedBps + result.idleBps + result.bufferBps).to.equal(10000);
+    });
+  });
+
+  describe("partial approval", () => {
+    const id = Array.from(Buffer.alloc(32, 49));
+    const requested = 1_000_000;
+    const approved = 600_000;
+
+    const approve = (activityId: number[], amount: number) =>
+      program.methods
+        .approveActivity(new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(activityId),
+          validator: null,
+          validatorAuthority: null,
+          authority: provider.wallet.publicKey,
+        });
+
+    it("Rejects an approved amount above the request", async () => {
+      const overId = Array.from(Buffer.alloc(32, 50));
+      await proposeActivity(overId, requested);
+
+      try {
+        await approve(overId, requested + 1).rpc();
+        expect.fail("approval should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidApprovedAmount");
+      }
+    });
+
+    it("Deploys only the approved amount", async () => {
+      await proposeActivity(id, requested);
+      const before = await program.account.reserve.fetch(reservePda);
+
+      const { events } = await approve(id, approved).simulate();
+      const event = events.find((e) => e.name === "activityApprovedEvent")!.data as any;
+      expect(event.approvedAmount.toNumber()).to.equal(approved);
+      expect(event.capitalRequired.toNumber()).to.equal(requested);
+
+      await approve(id, approved).rpc();
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.capitalRequired.toNumber()).to.equal(requested);
+      expect(activity.capitalDeployed.toNumber()).to.equal(approved);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(before.totalCapital.sub(reserve.totalCapital).toNumber()).to.equal(approved);
+      expect(reserve.deployedCapital.sub(before.deployedCapital).toNumber()).to.equal(approved);
+    });
+
+    it("Returns the approved amount on completion", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      await completeActivity(id, 0);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.sub(before.totalCapital).toNumber()).to.equal(approved);
+      expect(before.deployedCapital.sub(reserve.deployedCapital).toNumber()).to.equal(approved);
+    });
+  });
+
+  describe("initialize_if_needed", () => {
+    const initializeIfNeeded = (
+      target: Program,
+      admin: PublicKey,
+      minCapitalContribution: anchor.BN,
+      maxParticipants: anchor.BN
+    ) =>
+      target.methods
+        .initializeIfNeeded(minCapitalContribution, maxParticipants)
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId });
+
+    it("Is a no-op on an existing reserve with matching parameters", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+
+      await initializeIfNeeded(
+        program,
+        provider.wallet.publicKey,
+        before.minCapitalContribution,
+        before.maxParticipants
+      ).rpc();
+
+      const after = await program.account.reserve.fetch(reservePda);
+      expect(after.admin.toBase58()).to.equal(before.admin.toBase58());
+      expect(after.totalCapital.toString()).to.equal(before.totalCapital.toString());
+      expect(after.participantCount.toString()).to.equal(before.participantCount.toString());
+      expect(after.deployedCapital.toString()).to.equal(before.deployedCapital.toString());
+    });
+
+    it("Rejects mismatched parameters on an existing reserve", async () => {
+      const reserve = await program.account.reserve.fetch(reservePda);
+
+      try {
+        await initializeIfNeeded(
+          program,
+          provider.wallet.publicKey,
+          reserve.minCapitalContribution.addn(1),
+          reserve.maxParticipants
+        ).rpc();
+        expect.fail("re-initialization should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InitializationMismatch");
+      }
+    });
+
+    it("Rejects a re-run signed by anyone but the admin", async () => {
+      const reserve = await program.account.reserve.fetch(reservePda);
+      const intruder = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(intruder.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      try {
+        await initializeIfNeeded(
+          program,
+          intruder.publicKey,
+          reserve.minCapitalContribution,
+          reserve.maxParticipants
+        )
+          .signers([intruder])
+          .rpc();
+        expect.fail("re-initialization should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+
+    it("Creates a fresh reserve and tolerates a second call", async () => {
+      // The reserve PDA is a singleton, so the fresh path runs in a bankrun ledger
+      const context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      const bankrunProgram = new Program(program.idl, bankrunProvider);
+      const admin = bankrunProvider.wallet.publicKey;
+      const min = new anchor.BN(1_000_000);
+      const max = new anchor.BN(10);
+
+      await initializeIfNeeded(bankrunProgram, admin, min, max).rpc();
+      const created = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(created.isInitialized).to.be.true;
+      expect(created.admin.toBase58()).to.equal(admin.toBase58());
+      expect(created.minCapitalContribution.toNumber()).to.equal(1_000_000);
+      expect(created.maxParticipants.toNumber()).to.equal(10);
+
+      // The compute budget instruction keeps the re-run from being rejected as a duplicate transaction
+      await initializeIfNeeded(bankrunProgram, admin, min, max)
+        .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 })])
+        .rpc();
+      const rerun = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(rerun.bump).to.equal(created.bump);
+      expect(rerun.maxParticipants.toNumber()).to.equal(10);
+    });
+  });
+
+  describe("distribution schedule", () => {
+    const id = Array.from(Buffer.alloc(32, 51));
+    const profit = 300_000;
+    const interval = 2;
+
+    const setDistributionInterval = async (seconds: number) => {
+      await program.methods
+        .setDistributionInterval(new anchor.BN(seconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const distributionId = (round: number) => {
+      const seed = Buffer.alloc(8);
+      seed.writeBigUInt64LE(BigInt(round));
+      return Array.from(
+        createHash("sha256").update(Buffer.concat([Buffer.from("distribution"), seed])).digest()
+      );
+    };
+
+    const distributionSharePda = (round: number, agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(distributionId(round)), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    const distributePending = () =>
+      program.methods.distributePending().accounts({ reserve: reservePda });
+
+    const claimDistribution = (round: number) =>
+      program.methods
+        .claimDistribution()
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          share: distributionSharePda(round, provider.wallet.publicKey),
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const profitIndexScale = new anchor.BN("1000000000000");
+    let accrued: number;
+
+    before(async () => {
+      await applySettlements();
+      await setDistributionInterval(interval);
+      await proposeActivity(id, 1_000_000);
+      await approveActivity(id);
+    });
+
+    after(async () => setDistributionInterval(0));
+
+    it("Accrues scheduled profit to the pending pool and the profit index", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      await completeActivity(id, profit);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.pendingDistribution.sub(before.pendingDistribution).toNumber()).to.equal(profit);
+      expect(reserve.profitPerUnit.sub(before.profitPerUnit).toString()).to.equal(
+        new anchor.BN(profit).mul(profitIndexScale).div(reserve.totalContributed).toString()
+      );
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.distributionBase.toNumber()).to.equal(0);
+    });
+
+    it("Gives capital deposited after the accrual none of it", async () => {
+      const participant = await program.account.participant.fetch(participantPda);
+      const reserve = await program.account.reserve.fetch(reservePda);
+
+      await program.methods
+        .depositCapital(new anchor.BN(50_000_000))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          delegate: null,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // The deposit brought the accrual up to date on the capital held before it
+      const after = await program.account.participant.fetch(participantPda);
+      accrued = after.accruedUnreleased.toNumber();
+      expect(accrued).to.equal(
+        reserve.profitPerUnit
+          .sub(participant.profitIndex)
+          .mul(participant.capitalContributed)
+          .div(profitIndexScale)
+          .toNumber()
+      );
+      expect(after.profitIndex.toString()).to.equal(reserve.profitPerUnit.toString());
+    });
+
+    it("Is a no-op before the interval has elapsed", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      await distributePending().rpc();
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.pendingDistribution.toString()).to.equal(before.pendingDistribution.toString());
+      expect(reserve.distributionRound.toString()).to.equal(before.distributionRound.toString());
+      expect(reserve.lastDistributionAt.toString()).to.equal(before.lastDistributionAt.toString());
+
+      // Nothing is released yet, so there is nothing to claim
+      try {
+        await claimDistribution(before.distributionRound.toNumber());
+        expect.fail("the claim should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NothingToClaim");
+      }
+    });
+
+    it("Releases the accrued pool once the interval has elapsed", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      const round = before.distributionRound.toNumber() + 1;
+      while ((await chainTime()) < before.lastDistributionAt.toNumber() + interval) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const { events } = await distributePending().simulate();
+      const released = events.find((e) => e.name === "pendingDistributedEvent")!.data as any;
+      expect(released.round.toNumber()).to.equal(round);
+      expect(released.amount.toString()).to.equal(before.pendingDistribution.toString());
+
+      await distributePending().rpc();
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.distributionRound.toNumber()).to.equal(round);
+      expect(reserve.pendingDistribution.toNumber()).to.equal(0);
+      expect(reserve.releasedPerUnit.toString()).to.equal(reserve.profitPerUnit.toString());
+
+      // Each participant claims lazily, in its own transaction
+      const participant = await program.account.participant.fetch(participantPda);
+      await claimDistribution(round);
+
+      const share = await program.account.participantActivityShare.fetch(
+        distributionSharePda(round, provider.wallet.publicKey)
+      );
+      expect(share.amount.toNumber()).to.equal(accrued);
+
+      const after = await program.account.participant.fetch(participantPda);
+      expect(after.pendingProfit.sub(participant.pendingProfit).toNumber()).to.equal(accrued);
+      expect(after.accruedReleased.toNumber()).to.equal(0);
+      expect(after.accruedUnreleased.toNumber()).to.equal(0);
+
+      await program.methods
+        .claimProfit(distributionId(round))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          share: distributionSharePda(round, provider.wallet.publicKey),
+          delegate: null,
+          agent: provider.wallet.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const claimed = await program.account.participantActivityShare.fetch(
+        distributionSharePda(round, provider.wallet.publicKey)
+      );
+      expect(claimed.claimed.toNumber()).to.equal(accrued);
+    });
+  });
+
+  describe("distribution mode", () => {
+    const setDistributionMode = (distributionMode: any) =>
+      program.methods
+        .setDistributionMode(distributionMode)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+    it("Starts out outcome-contingent", async () => {
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.distributionMode).to.deep.equal({ outcomeContingent: {} });
+    });
+
+    it("Refuses a fixed-return distribution mode", async () => {
+      try {
+        await setDistributionMode({ fixedReturn: {} });
+        expect.fail("fixed-return mode should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("GuaranteedReturnForbidden");
+      }
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.distributionMode).to.deep.equal({ outcomeContingent: {} });
+    });
+
+    it("Accepts the outcome-contingent mode", async () => {
+      await setDistributionMode({ outcomeContingent: {} });
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.distributionMode).to.deep.equal({ outcomeContingent: {} });
+    });
+  });
+
+  describe("participant tiers", () => {
+    const retailMin = 2_000_000;
+    const retailMax = 5_000_000;
+    const institutionalMin = 50_000_000;
+    const lockupSeconds = 3600;
+
+    const setTierConfig = (tier: any, min: number, max: number, lockup: number) =>
+      program.methods
+        .setTierConfig(tier, new anchor.BN(min), new anchor.BN(max), new anchor.BN(lockup))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    // The admin vouches for every tier but retail
+    const join = async (
+      amount: number,
+      tier: any,
+      tierApprover: PublicKey | null = tier.retail ? null : provider.wallet.publicKey
+    ) => {
+      const joiner = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(joiner.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await program.methods
+        .joinReserve(new anchor.BN(amount), tier)
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(joiner.publicKey),
+          charity: null,
+          tierApprover,
+          user: joiner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([joiner])
+        .rpc();
+      return joiner;
+    };
+
+    before(async () => {
+      await setTierConfig({ retail: {} }, retailMin, retailMax, 0);
+      await setTierConfig({ institutional: {} }, institutionalMin, 0, lockupSeconds);
+    });
+
+    after(async () => {
+      await setTierConfig({ retail: {} }, 0, 0, 0);
+      await setTierConfig({ institutional: {} }, 0, 0, 0);
+    });
+
+    it("Rejects a maximum below the minimum", async () => {
+      try {
+        await setTierConfig({ retail: {} }, retailMin, retailMin - 1, 0);
+        expect.fail("inverted tier bounds should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidTierConfig");
+      }
+    });
+
+    it("Enforces the retail minimum", async () => {
+      try {
+        await join(retailMin - 1, { retail: {} });
+        expect.fail("join below the retail minimum should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InsufficientContribution");
+      }
+
+      const joiner = await join(retailMin, { retail: {} });
+      const participant = await program.account.participant.fetch(participantFor(joiner.publicKey));
+      expect(participant.tier).to.deep.equal({ retail: {} });
+      expect(participant.lockedUntil.toNumber()).to.equal(participant.joinedAt.toNumber());
+    });
+
+    it("Enforces the retail maximum", async () => {
+      try {
+        await join(retailMax + 1, { retail: {} });
+        expect.fail("join above the retail maximum should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("TierContributionExceeded");
+      }
+    });
+
+    it("Refuses an institutional join the admin has not vouched for", async () => {
+      try {
+        await join(institutionalMin, { institutional: {} }, null);
+        expect.fail("a self-selected tier should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+
+    it("Enforces the institutional minimum", async () => {
+      // Enough for retail, not for institutional
+      try {
+        await join(retailMax, { institutional: {} });
+        expect.fail("join below the institutional minimum should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InsufficientContribution");
+      }
+
+      const joiner = await join(institutionalMin, { institutional: {} });
+      const participant = await program.account.participant.fetch(participantFor(joiner.publicKey));
+      expect(participant.tier).to.deep.equal({ institutional: {} });
+      expect(participant.capitalContributed.toNumber()).to.equal(institutionalMin);
+    });
+
+    it("Locks institutional capital until the lockup passes", async () => {
+      const joiner = await join(institutionalMin, { institutional: {} });
+      const participantPda = participantFor(joiner.publicKey);
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.lockedUntil.toNumber()).to.equal(
+        participant.joinedAt.toNumber() + lockupSeconds
+      );
+
+      try {
+        await program.methods
+          .withdrawCapital(new anchor.BN(1_000_000))
+          .accounts({ reserve: reservePda, participant: participantPda, user: joiner.publicKey })
+          .signers([joiner])
+          .rpc();
+        expect.fail("withdrawal inside the lockup should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("PositionLocked");
+      }
+    });
+  });
+
+  describe("activity fee", () => {
+    const id = Array.from(Buffer.alloc(32, 73));
+    const fee = 5_000_000;
+    const capital = 1_000_000;
+    const [complianceFundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("compliance_fund")],
+      program.programId
+    );
+
+    const setActivityFee = async (activityFee: number) => {
+      await program.methods
+        .setActivityFee(new anchor.BN(activityFee))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const propose = (activityId: number[], complianceFund: PublicKey | null) =>
+      program.methods
+        .proposeActivity(activityId, new anchor.BN(capital), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          activity: activityPdaFor(activityId),
+          initiatorIndex: initiatorIndexPda(),
+          complianceFund,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        });
+
+    before(async () => {
+      await program.methods
+        .initializeComplianceFund()
+        .accounts({
+          reserve: reservePda,
+          complianceFund: complianceFundPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await setActivityFee(fee);
+    });
+
+    after(async () => {
+      await setActivityFee(0);
+      await program.methods
+        .rejectActivity(0)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          auditLog: auditLogPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Requires the compliance fund while a fee is set", async () => {
+      try {
+        await propose(Array.from(Buffer.alloc(32, 74)), null).rpc();
+        expect.fail("proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ComplianceFundRequired");
+      }
+    });
+
+    it("Pays the fee into the compliance fund, outside the activity's capital", async () => {
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+      const lamportsBefore = await provider.connection.getBalance(complianceFundPda);
+
+      const { events } = await propose(id, complianceFundPda).simulate();
+      const collected = events.find((e) => e.name === "activityFeeCollectedEvent")!.data as any;
+      expect(collected.fee.toNumber()).to.equal(fee);
+
+      await propose(id, complianceFundPda).rpc();
+
+      const fund = await program.account.complianceFund.fetch(complianceFundPda);
+      expect(fund.balance.toNumber()).to.equal(fee);
+      expect(fund.totalCollected.toNumber()).to.equal(fee);
+      expect((await provider.connection.getBalance(complianceFundPda)) - lamportsBefore).to.equal(fee);
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.capitalRequired.toNumber()).to.equal(capital);
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toString()).to.equal(reserveBefore.totalCapital.toString());
+      expect(reserve.pendingProposedCapital.sub(reserveBefore.pendingProposedCapital).toNumber())
+        .to.equal(capital);
+    });
+
+    it("Compensates a keeper out of the collected fees", async () => {
+      const keeper = Keypair.generate().publicKey;
+      await program.methods
+        .payComplianceKeeper(new anchor.BN(fee))
+        .accounts({
+          reserve: reservePda,
+          complianceFund: complianceFundPda,
+          keeper,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      expect(await provider.connection.getBalance(keeper)).to.equal(fee);
+      const fund = await program.account.complianceFund.fetch(complianceFundPda);
+      expect(fund.balance.toNumber()).to.equal(0);
+      expect(fund.totalPaid.toNumber()).to.equal(fee);
+    });
+  });
+
+  describe("pending proposed capital", () => {
+    const firstId = Array.from(Buffer.alloc(32, 52));
+    const secondId = Array.from(Buffer.alloc(32, 53));
+    const approvedId = Array.from(Buffer.alloc(32, 54));
+
+    const rejectActivity = async (id: number[]) => {
+      await program.methods
+        .rejectActivity(0)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          auditLog: auditLogPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    };
+
+    const largeRequest = async () => {
+      const reserve = await program.account.reserve.fetch(reservePda);
+      const free = reserve.totalCapital.sub(reserve.pendingProposedCapital);
+      return free.divn(2).addn(1).toNumber();
+    };
+
+    const setMaxProposalBps = (maxProposalBps: number) =>
+      program.methods
+        .setMaxProposalBps(maxProposalBps)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+
+    it("Caps a single proposal at max_proposal_bps of free capital", async () => {
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.maxProposalBps).to.equal(5000);
+
+      try {
+        await proposeActivity(firstId, await largeRequest());
+        expect.fail("an oversized proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ProposalTooLarge");
+      }
+
+      try {
+        await setMaxProposalBps(10001);
+        expect.fail("cap should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidProposalCap");
+      }
+
+      // Over-committing needs two proposals above half the free capital
+      await setMaxProposalBps(10000);
+    });
+
+    after(async () => setMaxProposalBps(5000));
+
+    it("Rejects a second proposal that would over-commit free capital", async () => {
+      const capital = await largeRequest();
+      const before = await program.account.reserve.fetch(reservePda);
+
+      await proposeActivity(firstId, capital);
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.pendingProposedCapital.sub(before.pendingProposedCapital).toNumber())
+        .to.equal(capital);
+      // Proposing alone commits nothing yet
+      expect(reserve.totalCapital.toString()).to.equal(before.totalCapital.toString());
+
+      try {
+        await proposeActivity(secondId, capital);
+        expect.fail("proposal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InsufficientCapital");
+      }
+    });
+
+    it("Releases pending capital when a proposal is rejected", async () => {
+      const before = await program.account.reserve.fetch(reservePda);
+      const { capitalRequired } = await program.account.activity.fetch(activityPdaFor(firstId));
+
+      await rejectActivity(firstId);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(before.pendingProposedCapital.sub(reserve.pendingProposedCapital).toString())
+        .to.equal(capitalRequired.toString());
+      const activity = await program.account.activity.fetch(activityPdaFor(firstId));
+      expect(activity.status).to.deep.equal({ rejected: {} });
+
+      // The freed capital can now back the second proposal
+      await proposeActivity(secondId, capitalRequired.toNumber());
+      await rejectActivity(secondId);
+    });
+
+    it("Releases the full request on approval, even when partially approved", async () => {
+      await proposeActivity(approvedId, 1_000_000);
+      const before = await program.account.reserve.fetch(reservePda);
+
+      await program.methods
+        .approveActivity(new anchor.BN(400_000))
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(approvedId),
+          validator: null,
+          validatorAuthority: null,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(before.pendingProposedCapital.sub(reserve.pendingProposedCapital).toNumber())
+        .to.equal(1_000_000);
+      await completeActivity(approvedId, 0);
+    });
+
+    it("Only lets the admin or the initiator reject", async () => {
+      const id = Array.from(Buffer.alloc(32, 55));
+      const outsider = Keypair.generate();
+      await proposeActivity(id, 1_000_000);
+
+      try {
+        await program.methods
+          .rejectActivity(0)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            auditLog: auditLogPda,
+            authority: outsider.publicKey,
+          })
+          .signers([outsider])
+          .rpc();
+        expect.fail("rejection should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+      await rejectActivity(id);
+    });
+
+    it("Lets an initiator withdraw their own proposal with a reason code", async () => {
+      const id = Array.from(Buffer.alloc(32, 68));
+      const initiator = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(initiator.publicKey, 2_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+      const [initiatorParticipant] = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), initiator.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .joinReserve(new anchor.BN(1_000_000_000), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: initiatorParticipant,
+          charity: null,
+          tierApprover: null,
+          user: initiator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([initiator])
+        .rpc();
+      await program.methods
+        .proposeActivity(id, new anchor.BN(1_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: initiatorParticipant,
+          activity: activityPdaFor(id),
+          initiatorIndex: PublicKey.findProgramAddressSync(
+            [Buffer.from("initiator_index"), initiator.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          complianceFund: null,
+          user: initiator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([initiator])
+        .rpc();
+
+      const before = await program.account.reserve.fetch(reservePda);
+      const reject = program.methods
+        .rejectActivity(3)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          auditLog: auditLogPda,
+          authority: initiator.publicKey,
+        })
+        .signers([initiator]);
+      const { events } = await reject.simulate();
+      const event = events.find((e) => e.name === "activityRejectedEvent")!.data as any;
+      expect(event.reasonCode).to.equal(3);
+      expect(event.rejectedBy.toString()).to.equal(initiator.publicKey.toString());
+      await reject.rpc();
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.status).to.deep.equal({ rejected: {} });
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toString()).to.equal(before.totalCapital.toString());
+      expect(before.pendingProposedCapital.sub(reserve.pendingProposedCapital).toNumber())
+        .to.equal(1_000_000);
+    });
+
+    it("Refuses to reject an approved activity", async () => {
+      const id = Array.from(Buffer.alloc(32, 69));
+      await proposeActivity(id, 1_000_000);
+      await approveActivity(id);
+
+      try {
+        await rejectActivity(id);
+        expect.fail("rejection should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidActivityStatus");
+      }
+      await completeActivity(id, 0);
+    });
+  });
+
+  describe("proposal expiry", () => {
+    const id = Array.from(Buffer.alloc(32, 71));
+    const expiry = 3;
+
+    const setProposalExpiry = async (seconds: number) => {
+      await program.methods
+        .setProposalExpiry(new anchor.BN(seconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const expireProposal = async (activityId: number[]) => {
+      await program.methods
+        .expireProposedActivity(activityId)
+        .accounts({ reserve: reservePda, activity: activityPdaFor(activityId) })
+        .rpc();
+    };
+
+    before(async () => {
+      await setProposalExpiry(expiry);
+      await proposeActivity(id, 1_000_000);
+    });
+
+    after(async () => setProposalExpiry(0));
+
+    it("Refuses to expire a proposal before its expiry", async () => {
+      const { createdAt } = await program.account.activity.fetch(activityPdaFor(id));
+      expect(await chainTime()).to.be.at.most(createdAt.toNumber() + expiry);
+
+      try {
+        await expireProposal(id);
+        expect.fail("expiry should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ProposalNotExpired");
+      }
+    });
+
+    it("Rejects the proposal and releases its pending capital once expired", async () => {
+      const { createdAt } = await program.account.activity.fetch(activityPdaFor(id));
+      while ((await chainTime()) <= createdAt.toNumber() + expiry) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const before = await program.account.reserve.fetch(reservePda);
+      const { events } = await program.methods
+        .expireProposedActivity(id)
+        .accounts({ reserve: reservePda, activity: activityPdaFor(id) })
+        .simulate();
+      const expired = events.find((e) => e.name === "proposalExpiredEvent")!.data as any;
+      expect(expired.capitalRequired.toNumber()).to.equal(1_000_000);
+      expect(expired.createdAt.toNumber()).to.equal(createdAt.toNumber());
+
+      await expireProposal(id);
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.status).to.deep.equal({ rejected: {} });
+      const after = await program.account.reserve.fetch(reservePda);
+      expect(before.pendingProposedCapital.sub(after.pendingProposedCapital).toNumber())
+        .to.equal(1_000_000);
+    });
+
+    it("Only expires proposals that are still awaiting a decision", async () => {
+      try {
+        await expireProposal(id);
+        expect.fail("expiry should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidActivityStatus");
+      }
+    });
+
+    it("Never expires proposals while the expiry is disabled", async () => {
+      const idleId = Array.from(Buffer.alloc(32, 72));
+      await setProposalExpiry(0);
+      await proposeActivity(idleId, 1_000_000);
+
+      try {
+        await expireProposal(idleId);
+        expect.fail("expiry should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ProposalNotExpired");
+      }
+
+      await program.methods
+        .rejectActivity(0)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(idleId),
+          auditLog: auditLogPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    });
+  });
+
+  describe("profit conversion", () => {
+    const id = Array.from(Buffer.alloc(32, 56));
+    const profit = 10_000_000;
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    let settlementMint: PublicKey;
+    let preferredMint: PublicKey;
+    let mintVaultPda: PublicKey;
+    let vaultPda: PublicKey;
+
+    const sharePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const setConversionPolicy = async (swapProgram: PublicKey, maxSlippageBps: number) => {
+      await program.methods
+        .setConversionPolicy(swapProgram, maxSlippageBps)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const setPreferredMint = async (mint: PublicKey) => {
+      await program.methods
+        .setPreferredMint(mint)
+        .accounts({ reserve: reservePda, participant: participantPda, user: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      settlementMint = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 6);
+      preferredMint = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 6);
+      [mintVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_vault"), settlementMint.toBuffer()],
+        program.programId
+      );
+      [vaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), settlementMint.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeMintVault()
+        .accounts({
+          reserve: reservePda,
+          mint: settlementMint,
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await proposeActivity(id, 100_000_000);
+      await program.methods
+        .setSettlementMint(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: mintVaultPda,
+          initiator: provider.wallet.publicKey,
+        })
+        .rpc();
+      await approveActivity(id);
+      await mintTo(provider.connection, payer, settlementMint, vaultPda, payer, profit);
+
+      await applySettlements();
+      await program.methods
+        .completeActivity(id, new anchor.BN(profit), new anchor.BN(await chainTime()), settlementMint)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .rpc();
+      await program.methods
+        .allocateProfitShare(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantPda,
+          share: sharePda(),
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Any executable can be registered; none of these cases reaches the swap itself
+      await setConversionPolicy(TOKEN_PROGRAM_ID, 100);
+      await setPreferredMint(preferredMint);
+    });
+
+    after(async () => {
+      await setPreferredMint(PublicKey.default);
+      await setConversionPolicy(PublicKey.default, 0);
+    });
+
+    it("Rejects a slippage tolerance above 100%", async () => {
+      try {
+        await setConversionPolicy(TOKEN_PROGRAM_ID, 10_001);
+        expect.fail("policy should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidSlippage");
+      }
+    });
+
+    const conversionRatePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("conversion_rate"), settlementMint.toBuffer(), preferredMint.toBuffer()],
+        program.programId
+      )[0];
+
+    const setConversionRate = (rate: number) =>
+      program.methods
+        .setConversionRate(settlementMint, preferredMint, new anchor.BN(rate))
+        .accounts({
+          reserve: reservePda,
+          conversionRate: conversionRatePda(),
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const convert = async (user: Keypair | null) => {
+      const preferredAta = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        preferredMint,
+        provider.wallet.publicKey
+      );
+      // Pool accounts are only read by the venue, which never runs in these cases
+      const pool = Keypair.generate().publicKey;
+      return program.methods
+        .claimProfitTokenConverted(id)
+        .accounts({
+          reserve: reservePda,
+          participant: participantPda,
+          share: sharePda(),
+          mintVault: mintVaultPda,
+          vault: vaultPda,
+          agentTokenAccount: preferredAta.address,
+          conversionRate: conversionRatePda(),
+          swapProgram: TOKEN_PROGRAM_ID,
+          swapPool: pool,
+          swapAuthority: pool,
+          poolSource: pool,
+          poolDestination: pool,
+          poolMint: pool,
+          poolFee: pool,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          user: user ? user.publicKey : provider.wallet.publicKey,
+        })
+        .signers(user ? [user] : [])
+        .rpc();
+    };
+
+    it("Refuses to convert without a stored rate for the pair", async () => {
+      try {
+        await convert(null);
+        expect.fail("conversion should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("AccountNotInitialized");
+      }
+
+      const share = await program.account.participantActivityShare.fetch(sharePda());
+      expect(share.claimed.toNumber()).to.equal(0);
+    });
+
+    it("Rejects a zero conversion rate", async () => {
+      try {
+        await setConversionRate(0);
+        expect.fail("rate should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidQuote");
+      }
+    });
+
+    it("Stores the admin-set rate conversions are held to", async () => {
+      await setConversionRate(2_000_000_000);
+
+      const rate = await program.account.conversionRate.fetch(conversionRatePda());
+      expect(rate.fromMint.toBase58()).to.equal(settlementMint.toBase58());
+      expect(rate.toMint.toBase58()).to.equal(preferredMint.toBase58());
+      expect(rate.rate.toNumber()).to.equal(2_000_000_000);
+    });
+
+    it("Lets only the participant convert their profit", async () => {
+      const other = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(other.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      try {
+        await convert(other);
+        expect.fail("conversion should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ConstraintSeeds");
+      }
+    });
+  });
+
+  describe("verify_participant_balance", () => {
+    // Desyncing a participant needs direct account writes, so this runs in a bankrun ledger
+    const member = Keypair.generate();
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    const verify = async (agent: PublicKey, others: PublicKey[]) => {
+      const { events } = await bankrunProgram.methods
+        .verifyParticipantBalance()
+        .accounts({ reserve: reservePda, participant: participantFor(agent) })
+        .remainingAccounts(
+          others.map((other) => ({ pubkey: participantFor(other), isSigner: false, isWritable: false }))
+        )
+        .simulate();
+      return events.find((e) => e.name === "balanceVerifiedEvent")!.data as any;
+    };
+
+    before(async () => {
+      context = await startAnchor(
+        "",
+        [],
+        [
+          {
+            address: member.publicKey,
+            info: {
+              lamports: 10_000_000_000,
+              data: Buffer.alloc(0),
+              owner: SystemProgram.programId,
+              executable: false,
+            },
+          },
+        ]
+      );
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      for (const [agent, amount] of [[null, 2_000_000_000], [member, 3_000_000_000]] as const) {
+        const user = agent ? agent.publicKey : admin;
+        await bankrunProgram.methods
+          .joinReserve(new anchor.BN(amount), { retail: {} })
+          .accounts({
+            reserve: reservePda,
+            participant: participantFor(user),
+            charity: null,
+            tierApprover: null,
+            user,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers(agent ? [agent] : [])
+          .rpc();
+      }
+    });
+
+    it("Reports a participant consistent with the reserve's books", async () => {
+      const event = await verify(member.publicKey, [admin]);
+      expect(event.consistent).to.be.true;
+      expect(event.discrepancy.toNumber()).to.equal(0);
+      expect(event.capitalContributed.toNumber()).to.equal(3_000_000_000);
+    });
+
+    it("Reports the discrepancy of a desynced participant", async () => {
+      const pda = participantFor(member.publicKey);
+      const info = await context.banksClient.getAccount(pda);
+      const participant = bankrunProgram.coder.accounts.decode("participant", Buffer.from(info!.data));
+      participant.capitalContributed = participant.capitalContributed.addn(500_000);
+      const data = await bankrunProgram.coder.accounts.encode("participant", participant);
+      context.setAccount(pda, { ...info!, data: Buffer.concat([data, Buffer.alloc(info!.data.length - data.length)]) });
+
+      const event = await verify(member.publicKey, [admin]);
+      expect(event.consistent).to.be.false;
+      expect(event.discrepancy.toNumber()).to.equal(500_000);
+
+      // The other participant's own record is unaffected, but the books no longer cover it
+      const other = await verify(admin, [member.publicKey]);
+      expect(other.discrepancy.toNumber()).to.equal(-500_000);
+    });
+
+    it("Requires every other participant as evidence", async () => {
+      try {
+        await bankrunProgram.methods
+          .verifyParticipantBalance()
+          .accounts({ reserve: reservePda, participant: participantFor(member.publicKey) })
+          .rpc();
+        expect.fail("verification should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidRemainingAccount");
+      }
+    });
+  });
+
+  describe("solvency auto-pause", () => {
+    // Under-collateralizing the reserve needs direct account writes, so this runs in a bankrun ledger
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+    let participant: PublicKey;
+
+    const withdraw = (amount: number) =>
+      bankrunProgram.methods
+        .withdrawCapital(new anchor.BN(amount))
+        .accounts({ reserve: reservePda, participant, user: admin });
+
+    const shiftReserveLamports = async (delta: number) => {
+      const info = await context.banksClient.getAccount(reservePda);
+      context.setAccount(reservePda, { ...info!, lamports: Number(info!.lamports) + delta });
+    };
+
+    const resetAfterFix = () =>
+      bankrunProgram.methods
+        .resetAfterSolvencyFix()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+      participant = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), admin.toBuffer()],
+        program.programId
+      )[0];
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .joinReserve(new anchor.BN(2_000_000_000), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant,
+          charity: null,
+          tierApprover: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .setMinSolvency(9000)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+    });
+
+    it("Rejects a threshold above 10000 basis points", async () => {
+      try {
+        await bankrunProgram.methods
+          .setMinSolvency(10001)
+          .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+          .rpc();
+        expect.fail("threshold should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidSolvencyThreshold");
+      }
+    });
+
+    it("Withdraws normally while solvent", async () => {
+      await withdraw(100_000_000).rpc();
+
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.isPaused).to.be.false;
+      expect(reserve.totalCapital.toNumber()).to.equal(1_900_000_000);
+    });
+
+    it("Pauses instead of withdrawing once under-collateralized", async () => {
+      // Half the accounted capital goes missing from the reserve account
+      await shiftReserveLamports(-950_000_000);
+
+      const { events } = await withdraw(200_000_000).simulate();
+      const breach = events.find((e) => e.name === "solvencyBreachEvent")!.data as any;
+      expect(breach.minSolvencyBps).to.equal(9000);
+      expect(breach.accounted.toNumber()).to.equal(1_900_000_000);
+      expect(breach.solvencyBps.toNumber()).to.equal(5000);
+
+      await withdraw(200_000_000).rpc();
+
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.isPaused).to.be.true;
+      expect(reserve.totalCapital.toNumber()).to.equal(1_900_000_000);
+      const record = await bankrunProgram.account.participant.fetch(participant);
+      expect(record.capitalContributed.toNumber()).to.equal(1_900_000_000);
+    });
+
+    it("Refuses withdrawals while paused", async () => {
+      try {
+        await withdraw(300_000_000).rpc();
+        expect.fail("withdrawal should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReservePaused");
+      }
+    });
+
+    it("Resets only once the shortfall is made good", async () => {
+      try {
+        await resetAfterFix();
+        expect.fail("reset should have required solvency");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SolvencyNotRestored");
+      }
+
+      await shiftReserveLamports(950_000_000);
+      await resetAfterFix();
+
+      expect((await bankrunProgram.account.reserve.fetch(reservePda)).isPaused).to.be.false;
+      await withdraw(400_000_000).rpc();
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toNumber()).to.equal(1_500_000_000);
+    });
+  });
+
+  describe("withdrawal cap", () => {
+    // An isolated ledger keeps free capital and the ownership split exact
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+    const member = Keypair.generate();
+    const id = Array.from(Buffer.alloc(32, 81));
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+    const withdraw = (amount: number) =>
+      bankrunProgram.methods
+        .withdrawCapital(new anchor.BN(amount))
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          user: member.publicKey,
+        })
+        .signers([member]);
+
+    before(async () => {
+      context = await startAnchor(
+        "",
+        [],
+        [
+          {
+            address: member.publicKey,
+            info: {
+              lamports: 10_000_000_000,
+              data: Buffer.alloc(0),
+              owner: SystemProgram.programId,
+              executable: false,
+            },
+          },
+        ]
+      );
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      for (const agent of [null, member]) {
+        const user = agent ? agent.publicKey : admin;
+        await bankrunProgram.methods
+          .joinReserve(new anchor.BN(2_000_000_000), { retail: {} })
+          .accounts({
+            reserve: reservePda,
+            participant: participantFor(user),
+            charity: null,
+            tierApprover: null,
+            user,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers(agent ? [agent] : [])
+          .rpc();
+      }
+
+      // One active activity takes half of the 4 SOL pool
+      const activity = activityPdaFor(id);
+      await bankrunProgram.methods
+        .proposeActivity(id, new anchor.BN(2_000_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(admin),
+          activity,
+          initiatorIndex: PublicKey.findProgramAddressSync(
+            [Buffer.from("initiator_index"), admin.toBuffer()],
+            program.programId
+          )[0],
+          complianceFund: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity,
+          validator: null,
+          validatorAuthority: null,
+          authority: admin,
+        })
+        .rpc();
+    });
+
+    it("Refuses more than the participant's share of free capital", async () => {
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toNumber()).to.equal(2_000_000_000);
+
+      // The member contributed 2 SOL but owns half of the 2 SOL still free
+      try {
+        await withdraw(1_000_000_001).rpc();
+        expect.fail("withdrawal should have been capped");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("CapitalLocked");
+      }
+    });
+
+    it("Applies the same cap when leaving", async () => {
+      try {
+        await bankrunProgram.methods
+          .leaveReserve(new anchor.BN(0))
+          .accounts({
+            reserve: reservePda,
+            participant: participantFor(member.publicKey),
+            charity: null,
+            user: member.publicKey,
+          })
+          .signers([member])
+          .rpc();
+        expect.fail("leaving should have been capped");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("CapitalLocked");
+      }
+
+      const record = await bankrunProgram.account.participant.fetch(participantFor(member.publicKey));
+      expect(record.capitalContributed.toNumber()).to.equal(2_000_000_000);
+    });
+
+    it("Pays out up to that share", async () => {
+      await withdraw(1_000_000_000).rpc();
+
+      const record = await bankrunProgram.account.participant.fetch(participantFor(member.publicKey));
+      expect(record.capitalContributed.toNumber()).to.equal(1_000_000_000);
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toNumber()).to.equal(1_000_000_000);
+      expect(reserve.deployedCapital.toNumber()).to.equal(2_000_000_000);
+    });
+  });
+
+  describe("delegation recommendation", () => {
+    const setDelegationPolicy = async (windowSeconds: number, threshold: number) => {
+      await program.methods
+        .setDelegationPolicy(new anchor.BN(windowSeconds), threshold)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    const recommendation = async () => {
+      const { events } = await program.methods
+        .shouldDelegate()
+        .accounts({ reserve: reservePda })
+        .simulate();
+      return events.find((e) => e.name === "delegationRecommendationEvent")!.data as any;
+    };
+
+    before(async () => setDelegationPolicy(3600, 2));
+
+    after(async () => setDelegationPolicy(0, 0));
+
+    it("Does not recommend delegation below the threshold", async () => {
+      await proposeActivity(Array.from(Buffer.alloc(32, 57)), 1_000_000);
+
+      const event = await recommendation();
+      expect(event.recommended).to.be.false;
+      expect(event.proposalsInWindow).to.equal(1);
+
+      try {
+        await program.methods
+          .delegateIfRecommended()
+          .accounts({ payer: provider.wallet.publicKey, validator: null, reserve: reservePda })
+          .rpc();
+        expect.fail("delegation should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("DelegationNotRecommended");
+      }
+    });
+
+    it("Recommends delegation once the window's proposals reach the threshold", async () => {
+      await proposeActivity(Array.from(Buffer.alloc(32, 58)), 1_000_000);
+
+      const event = await recommendation();
+      expect(event.recommended).to.be.true;
+      expect(event.proposalsInWindow).to.equal(2);
+      expect(event.delegationThreshold).to.equal(2);
+    });
+
+    it("Stops recommending once the policy is disabled", async () => {
+      await setDelegationPolicy(0, 0);
+
+      const event = await recommendation();
+      expect(event.recommended).to.be.false;
+      expect(event.proposalsInWindow).to.equal(0);
+    });
+  });
+
+  describe("undelegation authority", () => {
+    const outsider = Keypair.generate();
+    const [operatorPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("operator"), outsider.publicKey.toBuffer()],
+      program.programId
+    );
+    const magicProgram = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+
+    const undelegateAsOutsider = (operator: PublicKey | null) =>
+      program.methods
+        .commitAndUndelegateReserve()
+        .accounts({
+          reserve: reservePda,
+          payer: outsider.publicKey,
+          operator,
+          magicContext: Keypair.generate().publicKey,
+          magicProgram,
+        })
+        .signers([outsider])
+        .rpc();
+
+    it("Rejects an undelegate from a key that is neither admin nor operator", async () => {
+      try {
+        await undelegateAsOutsider(null);
+        expect.fail("undelegate should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+
+    it("Authorizes and revokes an operator", async () => {
+      await program.methods
+        .authorizeOperator(outsider.publicKey)
+        .accounts({
+          reserve: reservePda,
+          operator: operatorPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      let record = await program.account.operator.fetch(operatorPda);
+      expect(record.operator.toString()).to.equal(outsider.publicKey.toString());
+      expect(record.isAuthorized).to.be.true;
+
+      await program.methods
+        .revokeOperator()
+        .accounts({
+          reserve: reservePda,
+          operator: operatorPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      record = await program.account.operator.fetch(operatorPda);
+      expect(record.isAuthorized).to.be.false;
+    });
+
+    it("Rejects an undelegate from a revoked operator", async () => {
+      try {
+        await undelegateAsOutsider(operatorPda);
+        expect.fail("undelegate should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+    });
+  });
+
+  describe("capacity governance", () => {
+    // DAO proposals are injected as raw accounts, so this runs in a bankrun ledger
+    const daoProgram = anchor.workspace.AmanaDao as Program;
+    const [daoPda] = PublicKey.findProgramAddressSync([Buffer.from("dao")], daoProgram.programId);
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let bankrunDao: Program;
+    let admin: PublicKey;
+
+    const capacityCommitment = (maxParticipants: number, maxTotalCapital: number) =>
+      new PublicKey(
+        createHash("sha256")
+          .update(Buffer.from("capacity"))
+          .update(new anchor.BN(maxParticipants).toArrayLike(Buffer, "le", 8))
+          .update(new anchor.BN(maxTotalCapital).toArrayLike(Buffer, "le", 8))
+          .digest()
+      );
+
+    const proposalPdaFor = (proposalId: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), new anchor.BN(proposalId).toArrayLike(Buffer, "le", 8)],
+        daoProgram.programId
+      );
+
+    // Write an amana-dao Proposal approving the given target at its PDA
+    const injectProposal = async (proposalId: number, target: PublicKey, status: object) => {
+      const [address, bump] = proposalPdaFor(proposalId);
+      const data = await daoProgram.coder.accounts.encode("proposal", {
+        proposalId: new anchor.BN(proposalId),
+        proposer: admin,
+        targetAccount: target,
+        amount: new anchor.BN(0),
+        affectsSharia: false,
+        status,
+        createdAt: new anchor.BN(0),
+        votingStartsAt: new anchor.BN(0),
+        votingEndsAt: new anchor.BN(0),
+        forVotes: new anchor.BN(100),
+        againstVotes: new anchor.BN(0),
+        abstainVotes: new anchor.BN(0),
+        shariaApproved: true,
+        quorumBps: 0,
+        shariaClassified: false,
+        approvalCount: 0,
+        shariaReviewed: false,
+        allowlistEnforced: false,
+        allowedPrograms: [],
+        bump,
+      });
+      context.setAccount(address, {
+        lamports: 1_000_000_000,
+        data,
+        owner: daoProgram.programId,
+        executable: false,
+      });
+    };
+
+    const setCapacity = (maxParticipants: number, maxTotalCapital: number) =>
+      bankrunProgram.methods
+        .setCapacity(new anchor.BN(maxParticipants), new anchor.BN(maxTotalCapital), null)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, dao: null, admin })
+        .rpc();
+
+    const applyProposal = (proposalId: number, maxParticipants: number, maxTotalCapital: number) =>
+      bankrunDao.methods
+        .applyCapacityProposal(
+          new anchor.BN(proposalId),
+          new anchor.BN(maxParticipants),
+          new anchor.BN(maxTotalCapital)
+        )
+        .accounts({
+          dao: daoPda,
+          proposal: proposalPdaFor(proposalId)[0],
+          reserve: reservePda,
+          auditLog: auditLogPda,
+          admin,
+          amanaReserveProgram: program.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      bankrunDao = new Program(daoProgram.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .enableCapacityGovernance()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+      await bankrunDao.methods
+        .initialize(new anchor.BN(1), new anchor.BN(3600), 1000)
+        .accounts({
+          dao: daoPda,
+          tokenMint: Keypair.generate().publicKey,
+          timelock: Keypair.generate().publicKey,
+          admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Rejects a governed capacity increase without DAO approval", async () => {
+      await expectRejected(setCapacity(20, 0), "CapacityProposalRequired");
+    });
+
+    it("Rejects a proposal that has not been executed", async () => {
+      await injectProposal(1, capacityCommitment(20, 0), { active: {} });
+      await expectRejected(applyProposal(1, 20, 0), "InvalidProposalStatus");
+    });
+
+    it("Rejects an executed proposal approving a different capacity", async () => {
+      await injectProposal(1, capacityCommitment(50, 0), { executed: {} });
+      await expectRejected(applyProposal(1, 20, 0), "CapacityNotApproved");
+    });
+
+    it("Applies an increase approved by an executed proposal", async () => {
+      await injectProposal(1, capacityCommitment(20, 0), { executed: {} });
+      await applyProposal(1, 20, 0);
+
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.maxParticipants.toNumber()).to.equal(20);
+      expect(reserve.nextCapacityProposalId.toNumber()).to.equal(2);
+    });
+
+    it("Lets the admin lower capacity without a proposal", async () => {
+      await setCapacity(15, 0);
+
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.maxParticipants.toNumber()).to.equal(15);
+    });
+
+    it("Refuses to replay a consumed proposal", async () => {
+      await expectRejected(applyProposal(1, 20, 0), "InvalidCapacityProposal");
+    });
+  });
+
+  describe("estimate_withdrawal_time", () => {
+    const activities = [
+      { id: Array.from(Buffer.alloc(32, 59)), capital: 2_000_000, duration: 2000 },
+      { id: Array.from(Buffer.alloc(32, 60)), capital: 1_000_000, duration: 1000 },
+    ];
+    let freeCapital: number;
+    let maturities: number[];
+
+    const estimate = async (queuePosition: number) => {
+      const { events } = await program.methods
+        .estimateWithdrawalTime(new anchor.BN(queuePosition))
+        .accounts({ reserve: reservePda })
+        .remainingAccounts(
+          activities.map(({ id }) => ({ pubkey: activityPdaFor(id), isSigner: false, isWritable: false }))
+        )
+        .simulate();
+      return events.find((e) => e.name === "estimatedLiquidityEvent")!.data as any;
+    };
+
+    before(async () => {
+      for (const { id, capital, duration } of activities) {
+        await proposeActivity(id, capital, 1, duration);
+        await approveActivity(id);
+      }
+      maturities = await Promise.all(
+        activities.map(async ({ id }) =>
+          (await program.account.activity.fetch(activityPdaFor(id))).maturityAt.toNumber()
+        )
+      );
+      freeCapital = (await program.account.reserve.fetch(reservePda)).totalCapital.toNumber();
+    });
+
+    it("Reports a position covered by free capital as payable now", async () => {
+      const event = await estimate(freeCapital);
+      expect(event.satisfiable).to.be.true;
+      expect(event.activitiesCounted).to.equal(0);
+      expect(event.estimatedAt.toNumber()).to.be.lessThan(maturities[1]);
+    });
+
+    it("Estimates the maturity that frees enough capital, earliest first", async () => {
+      // The shorter activity matures first and covers up to 1M more
+      const first = await estimate(freeCapital + 1);
+      expect(first.estimatedAt.toNumber()).to.equal(maturities[1]);
+      expect(first.activitiesCounted).to.equal(1);
+
+      const second = await estimate(freeCapital + 1_000_001);
+      expect(second.estimatedAt.toNumber()).to.equal(maturities[0]);
+      expect(second.activitiesCounted).to.equal(2);
+      expect(second.projectedLiquidity.toNumber()).to.equal(freeCapital + 3_000_000);
+    });
+
+    it("Reports a position beyond every passed maturity as unsatisfiable", async () => {
+      const event = await estimate(freeCapital + 3_000_001);
+      expect(event.satisfiable).to.be.false;
+      expect(event.estimatedAt.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("dormant profit sweeps", () => {
+    // Dormancy spans years, so this runs in a bankrun ledger with a warped clock
+    const member = Keypair.generate();
+    const charity = Keypair.generate().publicKey;
+    const id = Array.from(Buffer.alloc(32, 61));
+    const shareAmount = 1_000_000;
+    const dormancy = 2 * 365 * 24 * 60 * 60;
+    const reversalWindow = 30 * 24 * 60 * 60;
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+    const [sharePda, shareBump] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share"), Buffer.from(id), member.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const advanceClock = async (seconds: number) => {
+      const clock = await context.banksClient.getClock();
+      context.setClock(
+        new Clock(
+          clock.slot,
+          clock.epochStartTimestamp,
+          clock.epoch,
+          clock.leaderScheduleEpoch,
+          clock.unixTimestamp + BigInt(seconds)
+        )
+      );
+    };
+
+    // A fresh compute limit keeps repeated calls from being rejected as duplicate transactions
+    const sweep = (units = 200_000) =>
+      bankrunProgram.methods
+        .sweepDormant()
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          share: sharePda,
+          auditLog: auditLogPda,
+          admin,
+        })
+        .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units })])
+        .rpc();
+
+    const release = (units = 200_000) =>
+      bankrunProgram.methods
+        .releaseDormantSweep()
+        .accounts({ reserve: reservePda, share: sharePda, charity, auditLog: auditLogPda, admin })
+        .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units })])
+        .rpc();
+
+    before(async () => {
+      const funded = (address: PublicKey) => ({
+        address,
+        info: {
+          lamports: 10_000_000_000,
+          data: Buffer.alloc(0),
+          owner: SystemProgram.programId,
+          executable: false,
+        },
+      });
+      context = await startAnchor("", [], [funded(member.publicKey), funded(charity)]);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .joinReserve(new anchor.BN(2_000_000_000), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          charity: null,
+          tierApprover: null,
+          user: member.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([member])
+        .rpc();
+      await bankrunProgram.methods
+        .setEntryDonation(0, charity)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+      await bankrunProgram.methods
+        .setDormancyPolicy(new anchor.BN(dormancy), new anchor.BN(reversalWindow))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+
+      // Allocate an unclaimed SOL share directly; its profit already sits in the reserve's capital
+      const now = new anchor.BN(Number((await context.banksClient.getClock()).unixTimestamp));
+      const data = await bankrunProgram.coder.accounts.encode("participantActivityShare", {
+        agent: member.publicKey,
+        activityId: id,
+        amount: new anchor.BN(shareAmount),
+        claimed: new anchor.BN(0),
+        allocatedAt: now,
+        mint: PublicKey.default,
+        vesting: { start: now, cliffSeconds: new anchor.BN(0), durationSeconds: new anchor.BN(0) },
+        lastClaimedAt: now,
+        sweptAmount: new anchor.BN(0),
+        sweepReleasableAt: new anchor.BN(0),
+        bump: shareBump,
+      });
+      context.setAccount(sharePda, {
+        lamports: 10_000_000,
+        data,
+        owner: program.programId,
+        executable: false,
+      });
+
+      const participantPda = participantFor(member.publicKey);
+      const info = await context.banksClient.getAccount(participantPda);
+      const participant = bankrunProgram.coder.accounts.decode("participant", Buffer.from(info!.data));
+      participant.pendingProfit = participant.pendingProfit.addn(shareAmount);
+      const encoded = await bankrunProgram.coder.accounts.encode("participant", participant);
+      context.setAccount(participantPda, {
+        ...info!,
+        data: Buffer.concat([encoded, Buffer.alloc(info!.data.length - encoded.length)]),
+      });
+    });
+
+    it("Rejects a dormancy period shorter than the minimum", async () => {
+      await expectRejected(
+        bankrunProgram.methods
+          .setDormancyPolicy(new anchor.BN(30 * 24 * 60 * 60), new anchor.BN(reversalWindow))
+          .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+          .rpc(),
+        "InvalidDuration"
+      );
+    });
+
+    it("Rejects a reversal window shorter than the minimum", async () => {
+      await expectRejected(
+        bankrunProgram.methods
+          .setDormancyPolicy(new anchor.BN(dormancy), new anchor.BN(0))
+          .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+          .rpc(),
+        "InvalidDuration"
+      );
+    });
+
+    it("Refuses to sweep a share before it is dormant", async () => {
+      await expectRejected(sweep(), "NotDormant");
+    });
+
+    it("Sweeps a dormant share, reversibly", async () => {
+      await advanceClock(dormancy);
+      await sweep(200_001);
+
+      const share = await bankrunProgram.account.participantActivityShare.fetch(sharePda);
+      expect(share.sweptAmount.toNumber()).to.equal(shareAmount);
+      expect(share.claimed.toNumber()).to.equal(shareAmount);
+      const participant = await bankrunProgram.account.participant.fetch(participantFor(member.publicKey));
+      expect(participant.pendingProfit.toNumber()).to.equal(0);
+
+      await bankrunProgram.methods
+        .restoreSweptShare()
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          share: sharePda,
+          agent: member.publicKey,
+        })
+        .signers([member])
+        .rpc();
+
+      const restored = await bankrunProgram.account.participantActivityShare.fetch(sharePda);
+      expect(restored.sweptAmount.toNumber()).to.equal(0);
+      expect(restored.claimed.toNumber()).to.equal(0);
+      const refunded = await bankrunProgram.account.participant.fetch(participantFor(member.publicKey));
+      expect(refunded.pendingProfit.toNumber()).to.equal(shareAmount);
+    });
+
+    it("Donates a sweep to the charity once the reversal window has passed", async () => {
+      // Restoring restarted the dormancy clock
+      await expectRejected(sweep(200_002), "NotDormant");
+      await advanceClock(dormancy);
+      await sweep(200_003);
+
+      await expectRejected(release(), "SweepWindowOpen");
+      await advanceClock(reversalWindow);
+
+      const before = (await context.banksClient.getAccount(charity))!.lamports;
+      await release(200_001);
+      const after = (await context.banksClient.getAccount(charity))!.lamports;
+      expect(Number(after - before)).to.equal(shareAmount);
+
+      const share = await bankrunProgram.account.participantActivityShare.fetch(sharePda);
+      expect(share.sweptAmount.toNumber()).to.equal(0);
+    });
+
+    it("Sweeps only the vested part of a share", async () => {
+      const vestingId = Array.from(Buffer.alloc(32, 90));
+      const [vestingShare, vestingBump] = PublicKey.findProgramAddressSync(
+        [Buffer.from("share"), Buffer.from(vestingId), member.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Vests linearly over twice the dormancy period, so half is vested once dormant
+      const now = new anchor.BN(Number((await context.banksClient.getClock()).unixTimestamp));
+      const data = await bankrunProgram.coder.accounts.encode("participantActivityShare", {
+        agent: member.publicKey,
+        activityId: vestingId,
+        amount: new anchor.BN(shareAmount),
+        claimed: new anchor.BN(0),
+        allocatedAt: now,
+        mint: PublicKey.default,
+        vesting: { start: now, cliffSeconds: new anchor.BN(0), durationSeconds: new anchor.BN(2 * dormancy) },
+        lastClaimedAt: now,
+        sweptAmount: new anchor.BN(0),
+        sweepReleasableAt: new anchor.BN(0),
+        bump: vestingBump,
+      });
+      context.setAccount(vestingShare, {
+        lamports: 10_000_000,
+        data,
+        owner: program.programId,
+        executable: false,
+      });
+      const participantPda = participantFor(member.publicKey);
+      const info = await context.banksClient.getAccount(participantPda);
+      const participant = bankrunProgram.coder.accounts.decode("participant", Buffer.from(info!.data));
+      participant.pendingProfit = participant.pendingProfit.addn(shareAmount);
+      const encoded = await bankrunProgram.coder.accounts.encode("participant", participant);
+      context.setAccount(participantPda, {
+        ...info!,
+        data: Buffer.concat([encoded, Buffer.alloc(info!.data.length - encoded.length)]),
+      });
+
+      await advanceClock(dormancy);
+      const sweepVesting = (units: number) =>
+        bankrunProgram.methods
+          .sweepDormant()
+          .accounts({
+            reserve: reservePda,
+            participant: participantPda,
+            share: vestingShare,
+            auditLog: auditLogPda,
+            admin,
+          })
+          .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units })])
+          .rpc();
+      await sweepVesting(200_004);
+
+      const share = await bankrunProgram.account.participantActivityShare.fetch(vestingShare);
+      expect(share.sweptAmount.toNumber()).to.equal(shareAmount / 2);
+      expect(share.claimed.toNumber()).to.equal(shareAmount / 2);
+      const after = await bankrunProgram.account.participant.fetch(participantPda);
+      expect(after.pendingProfit.toNumber()).to.equal(participant.pendingProfit.toNumber() - shareAmount / 2);
+
+      await expectRejected(sweepVesting(200_005), "AlreadySwept");
+    });
+  });
+
+  describe("transfer_position", () => {
+    const amount = 1_000_000_000;
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    it("Moves a whole position to a new owner, preserving tenure and balances", async () => {
+      const oldOwner = Keypair.generate();
+      const newOwner = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(oldOwner.publicKey, 2_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await program.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(oldOwner.publicKey),
+          charity: null,
+          tierApprover: null,
+          user: oldOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([oldOwner])
+        .rpc();
+
+      const before = await program.account.participant.fetch(participantFor(oldOwner.publicKey));
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+
+      const { events } = await program.methods
+        .transferPosition(newOwner.publicKey)
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(oldOwner.publicKey),
+          newParticipant: participantFor(newOwner.publicKey),
+          user: oldOwner.publicKey,
+          newAgent: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([oldOwner, newOwner])
+        .simulate();
+      const event = events.find((e) => e.name === "positionTransferredEvent")!.data as any;
+      expect(event.from.toString()).to.equal(oldOwner.publicKey.toString());
+      expect(event.to.toString()).to.equal(newOwner.publicKey.toString());
+
+      await program.methods
+        .transferPosition(newOwner.publicKey)
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(oldOwner.publicKey),
+          newParticipant: participantFor(newOwner.publicKey),
+          user: oldOwner.publicKey,
+          newAgent: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([oldOwner, newOwner])
+        .rpc();
+
+      const after = await program.account.participant.fetch(participantFor(newOwner.publicKey));
+      expect(after.agent.toString()).to.equal(newOwner.publicKey.toString());
+      expect(after.capitalContributed.toNumber()).to.equal(before.capitalContributed.toNumber());
+      expect(after.profitShare.toNumber()).to.equal(before.profitShare.toNumber());
+      expect(after.lossShare.toNumber()).to.equal(before.lossShare.toNumber());
+      expect(after.joinedAt.toNumber()).to.equal(before.joinedAt.toNumber());
+      expect(after.isActive).to.be.true;
+
+      expect(await program.account.participant.fetchNullable(participantFor(oldOwner.publicKey))).to.be.null;
+
+      // The reserve's books are untouched: same capital, same head count
+      const reserveAfter = await program.account.reserve.fetch(reservePda);
+      expect(reserveAfter.totalContributed.toNumber()).to.equal(reserveBefore.totalContributed.toNumber());
+      expect(reserveAfter.participantCount.toNumber()).to.equal(reserveBefore.participantCount.toNumber());
+    });
+
+    it("Never lets the new owner apply an outcome the position already applied", async () => {
+      const id = Array.from(Buffer.alloc(32, 85));
+      const oldOwner = Keypair.generate();
+      const newOwner = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(oldOwner.publicKey, 2_000_000_000);
+      await provider.connection.confirmTransaction(sig);
+
+      await program.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(oldOwner.publicKey),
+          charity: null,
+          tierApprover: null,
+          user: oldOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(await denylistPages())
+        .signers([oldOwner])
+        .rpc();
+      await proposeActivity(id, 100_000_000);
+      await approveActivity(id);
+      await completeActivity(id, 10_000_000);
+
+      const transfer = async () =>
+        program.methods
+          .transferPosition(newOwner.publicKey)
+          .accounts({
+            reserve: reservePda,
+            participant: participantFor(oldOwner.publicKey),
+            newParticipant: participantFor(newOwner.publicKey),
+            user: oldOwner.publicKey,
+            newAgent: newOwner.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(await denylistPages())
+          .signers([oldOwner, newOwner])
+          .rpc();
+
+      try {
+        await transfer();
+        expect.fail("the transfer should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SettlementsPending");
+      }
+
+      // The old owner applies the outcome and claims it, then hands the position on
+      await applySettlements(oldOwner.publicKey);
+      await program.methods
+        .claimProfit(id)
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(oldOwner.publicKey),
+          share: PublicKey.findProgramAddressSync(
+            [Buffer.from("share"), Buffer.from(id), oldOwner.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          delegate: null,
+          agent: oldOwner.publicKey,
+          authority: oldOwner.publicKey,
+        })
+        .signers([oldOwner])
+        .rpc();
+      await transfer();
+
+      try {
+        await program.methods
+          .allocateProfitShare(id)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            participant: participantFor(newOwner.publicKey),
+            share: PublicKey.findProgramAddressSync(
+              [Buffer.from("share"), Buffer.from(id), newOwner.publicKey.toBuffer()],
+              program.programId
+            )[0],
+            payer: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("the allocation should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NoProfitToAllocate");
+      }
+    });
+  });
+
+  describe("named reserves", () => {
+    const nameOf = (label: string) => {
+      const name = Buffer.alloc(32);
+      name.write(label);
+      return Array.from(name);
+    };
+    const reservePdaFor = (label: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("reserve"), Buffer.from(label)],
+        program.programId
+      )[0];
+    const participantPdaFor = (label: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), Buffer.from(label), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    const auditLogPdaFor = (label: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("audit_log"), Buffer.from(label)],
+        program.programId
+      )[0];
+
+    const join = async (label: string, amount: number) => {
+      await program.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePdaFor(label),
+          participant: participantPdaFor(label),
+          charity: null,
+          tierApprover: null,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      for (const [label, minContribution] of [["alpha", 1_000_000], ["beta", 2_000_000]] as const) {
+        await program.methods
+          .initializeNamedReserve(
+            nameOf(label),
+            `https://example.org/reserves/${label}.json`,
+            new anchor.BN(minContribution),
+            new anchor.BN(10)
+          )
+          .accounts({
+            reserve: reservePdaFor(label),
+            admin: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+    });
+
+    it("Stores each reserve's name and metadata", async () => {
+      const alpha = await program.account.reserve.fetch(reservePdaFor("alpha"));
+      const beta = await program.account.reserve.fetch(reservePdaFor("beta"));
+
+      expect(alpha.name).to.deep.equal(nameOf("alpha"));
+      expect(alpha.metadataUri).to.equal("https://example.org/reserves/alpha.json");
+      expect(alpha.minCapitalContribution.toNumber()).to.equal(1_000_000);
+      expect(beta.name).to.deep.equal(nameOf("beta"));
+      expect(beta.minCapitalContribution.toNumber()).to.equal(2_000_000);
+    });
+
+    it("Keeps participants and capital of each reserve apart", async () => {
+      const defaultBefore = await program.account.reserve.fetch(reservePda);
+
+      await join("alpha", 3_000_000);
+      await join("beta", 5_000_000);
+
+      const alpha = await program.account.reserve.fetch(reservePdaFor("alpha"));
+      const beta = await program.account.reserve.fetch(reservePdaFor("beta"));
+      expect(alpha.participantCount.toNumber()).to.equal(1);
+      expect(alpha.totalContributed.toNumber()).to.equal(3_000_000);
+      expect(beta.participantCount.toNumber()).to.equal(1);
+      expect(beta.totalContributed.toNumber()).to.equal(5_000_000);
+
+      const alphaParticipant = await program.account.participant.fetch(participantPdaFor("alpha"));
+      const betaParticipant = await program.account.participant.fetch(participantPdaFor("beta"));
+      expect(alphaParticipant.capitalContributed.toNumber()).to.equal(3_000_000);
+      expect(betaParticipant.capitalContributed.toNumber()).to.equal(5_000_000);
+
+      const defaultAfter = await program.account.reserve.fetch(reservePda);
+      expect(defaultAfter.totalContributed.toString()).to.equal(defaultBefore.totalContributed.toString());
+      expect(defaultAfter.participantCount.toString()).to.equal(defaultBefore.participantCount.toString());
+    });
+
+    it("Rejects accounts seeded for another reserve", async () => {
+      try {
+        await program.methods
+          .joinReserve(new anchor.BN(3_000_000), { retail: {} })
+          .accounts({
+            reserve: reservePdaFor("beta"),
+            participant: participantPdaFor("alpha"),
+            charity: null,
+            tierApprover: null,
+            user: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ConstraintSeeds");
+      }
+    });
+
+    it("Lets the admin update a reserve's metadata", async () => {
+      await program.methods
+        .initializeAuditLog()
+        .accounts({
+          reserve: reservePdaFor("alpha"),
+          auditLog: auditLogPdaFor("alpha"),
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .setReserveMetadata("https://example.org/reserves/alpha-v2.json")
+        .accounts({
+          reserve: reservePdaFor("alpha"),
+          auditLog: auditLogPdaFor("alpha"),
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const alpha = await program.account.reserve.fetch(reservePdaFor("alpha"));
+      const beta = await program.account.reserve.fetch(reservePdaFor("beta"));
+      expect(alpha.metadataUri).to.equal("https://example.org/reserves/alpha-v2.json");
+      expect(beta.metadataUri).to.equal("https://example.org/reserves/beta.json");
+    });
+  });
+
+  describe("approval authority", () => {
+    const id = Array.from(Buffer.alloc(32, 70));
+
+    const approveAs = (authority: Keypair | null) => {
+      const builder = program.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          validator: null,
+          validatorAuthority: null,
+          authority: authority ? authority.publicKey : provider.wallet.publicKey,
+        });
+      return authority ? builder.signers([authority]) : builder;
+    };
+
+    before(async () => proposeActivity(id, 1_000_000));
+
+    it("Refuses an approval not signed by the admin", async () => {
+      try {
+        await approveAs(Keypair.generate()).rpc();
+        expect.fail("approval should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+      }
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.status).to.deep.equal({ proposed: {} });
+    });
+
+    it("Names the approving admin in the approval event", async () => {
+      const { events } = await approveAs(null).simulate();
+      const event = events.find((e) => e.name === "activityApprovedEvent")!.data as any;
+      expect(event.approvedBy.toString()).to.equal(provider.wallet.publicKey.toString());
+
+      await approveAs(null).rpc();
+      await completeActivity(id, 0);
+    });
+  });
+
+  describe("loss realization", () => {
+    const id = Array.from(Buffer.alloc(32, 65));
+    const capital = 100_000_000;
+
+    const lossRealizationPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("loss"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const realizeLoss = async () => {
+      await program.methods
+        .realizeLoss(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantPda,
+          lossRealization: lossRealizationPda(),
+          guaranteeFund: null,
+          mintVault: null,
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    it("Caps a loss at the capital deployed instead of dropping it", async () => {
+      await applySettlements();
+      await proposeActivity(id, capital);
+      await approveActivity(id);
+      const before = await program.account.reserve.fetch(reservePda);
+
+      await completeActivity(id, -3 * capital);
+
+      const after = await program.account.reserve.fetch(reservePda);
+      expect(after.totalCapital.toNumber()).to.equal(before.totalCapital.toNumber());
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.settledOutcome.toNumber()).to.equal(-capital);
+    });
+
+    it("Keeps the participant from exiting before the loss is realized", async () => {
+      try {
+        await program.methods
+          .withdrawCapital(new anchor.BN(1_000_000))
+          .accounts({ reserve: reservePda, participant: participantPda, user: provider.wallet.publicKey })
+          .rpc();
+        expect.fail("the withdrawal should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SettlementsPending");
+      }
+    });
+
+    it("Applies the participant's pro-rata loss once", async () => {
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+      const participantBefore = await program.account.participant.fetch(participantPda);
+      const expected = Math.min(
+        Math.floor(
+          (capital * participantBefore.capitalContributed.toNumber()) /
+            activity.distributionBase.toNumber()
+        ),
+        participantBefore.capitalContributed.toNumber()
+      );
+
+      await realizeLoss();
+
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.capitalContributed.toNumber())
+        .to.equal(participantBefore.capitalContributed.toNumber() - expected);
+      expect(participant.lossShare.toNumber())
+        .to.equal(participantBefore.lossShare.toNumber() + expected);
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalContributed.toNumber())
+        .to.equal(reserveBefore.totalContributed.toNumber() - expected);
+
+      let applied = false;
+      try {
+        await realizeLoss();
+        applied = true;
+      } catch (_) {
+        // The LossRealization PDA already exists
+      }
+      expect(applied).to.be.false;
+    });
+
+    it("Refuses to realize a loss on a profitable activity", async () => {
+      const profitId = Array.from(Buffer.alloc(32, 66));
+      await proposeActivity(profitId, capital);
+      await approveActivity(profitId);
+      await completeActivity(profitId, 1_000_000);
+
+      try {
+        await program.methods
+          .realizeLoss(profitId)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(profitId),
+            participant: participantPda,
+            lossRealization: PublicKey.findProgramAddressSync(
+              [Buffer.from("loss"), Buffer.from(profitId), provider.wallet.publicKey.toBuffer()],
+              program.programId
+            )[0],
+            guaranteeFund: null,
+            mintVault: null,
+            payer: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NoLossToRealize");
+      }
+    });
+  });
+
+  describe("mudarabah limited liability", () => {
+    // The loss must exceed total_contributed, which needs exact books, so this runs in a bankrun ledger
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+    let participant: PublicKey;
+    const contribution = 1_000_000_000;
+    const profitId = Array.from(Buffer.alloc(32, 82));
+    const lossId = Array.from(Buffer.alloc(32, 83));
+
+    const settle = async (id: number[], capital: number, outcome: number) => {
+      await bankrunProgram.methods
+        .proposeActivity(id, new anchor.BN(capital), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant,
+          activity: activityPdaFor(id),
+          initiatorIndex: PublicKey.findProgramAddressSync(
+            [Buffer.from("initiator_index"), admin.toBuffer()],
+            program.programId
+          )[0],
+          complianceFund: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          validator: null,
+          validatorAuthority: null,
+          authority: admin,
+        })
+        .rpc();
+      const clock = await context.banksClient.getClock();
+      return bankrunProgram.methods
+        .completeActivity(
+          id,
+          new anchor.BN(outcome),
+          new anchor.BN(Number(clock.unixTimestamp)),
+          PublicKey.default
+        )
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: null,
+          vault: null,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        });
+    };
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+      participant = PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), admin.toBuffer()],
+        program.programId
+      )[0];
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .joinReserve(new anchor.BN(contribution), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant,
+          charity: null,
+          tierApprover: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .setMudarabahLimitedLiability(true)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+      // Each activity takes the whole pool
+      await bankrunProgram.methods
+        .setMaxProposalBps(10000)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+
+      // A profit doubles free capital, backed by the lamports it brought in
+      await (await settle(profitId, contribution, contribution)).rpc();
+      const info = await context.banksClient.getAccount(reservePda);
+      context.setAccount(reservePda, { ...info!, lamports: Number(info!.lamports) + contribution });
+    });
+
+    const realizeLoss = () =>
+      bankrunProgram.methods
+        .realizeLoss(lossId)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(lossId),
+          participant,
+          lossRealization: PublicKey.findProgramAddressSync(
+            [Buffer.from("loss"), Buffer.from(lossId), admin.toBuffer()],
+            program.programId
+          )[0],
+          guaranteeFund: null,
+          mintVault: null,
+          payer: admin,
+          systemProgram: SystemProgram.programId,
+        });
+
+    it("Books the whole loss and leaves the cap to realization", async () => {
+      // Everything free is deployed and lost: twice what was contributed
+      await (await settle(lossId, 2 * contribution, -2 * contribution)).rpc();
+
+      const activity = await bankrunProgram.account.activity.fetch(activityPdaFor(lossId));
+      expect(activity.settledOutcome.toNumber()).to.equal(-2 * contribution);
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toNumber()).to.equal(0);
+
+      // The earlier profit comes first in the settlement sequence
+      await bankrunProgram.methods
+        .allocateProfitShare(profitId)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(profitId),
+          participant,
+          share: PublicKey.findProgramAddressSync(
+            [Buffer.from("share"), Buffer.from(profitId), admin.toBuffer()],
+            program.programId
+          )[0],
+          payer: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Carries the excess on the participant while the flag is off", async () => {
+      await bankrunProgram.methods
+        .setMudarabahLimitedLiability(false)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+
+      const { events } = await realizeLoss().simulate();
+      const realized = events.find((e) => e.name === "lossRealizedEvent")!.data as any;
+      expect(realized.amount.toNumber()).to.equal(contribution);
+      expect(realized.carried.toNumber()).to.equal(contribution);
+      expect(events.find((e) => e.name === "lossWrittenOffEvent")).to.be.undefined;
+
+      await bankrunProgram.methods
+        .setMudarabahLimitedLiability(true)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin })
+        .rpc();
+    });
+
+    it("Zeroes but never negates the participant's capital", async () => {
+      const { events } = await realizeLoss().simulate();
+      const writeOff = events.find((e) => e.name === "lossWrittenOffEvent")!.data as any;
+      expect(writeOff.agent.toBase58()).to.equal(admin.toBase58());
+      expect(writeOff.excess.toNumber()).to.equal(contribution);
+      expect(writeOff.absorbed.toNumber()).to.equal(0);
+      expect(writeOff.writtenOff.toNumber()).to.equal(contribution);
+      await realizeLoss().rpc();
+
+      const record = await bankrunProgram.account.participant.fetch(participant);
+      expect(record.capitalContributed.toNumber()).to.equal(0);
+      expect(record.lossShare.toNumber()).to.equal(contribution);
+      expect(record.lossCarried.toNumber()).to.equal(0);
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.totalContributed.toNumber()).to.equal(0);
+      // The written-off part never comes back into free capital
+      expect(reserve.totalCapital.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("distribution audit", () => {
+    // Two participants with uneven capital need exact books, so this runs in a bankrun ledger
+    const member = Keypair.generate();
+    const id = Array.from(Buffer.alloc(32, 93));
+    const profit = 1_000_001;
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    const join = (user: PublicKey, amount: number) =>
+      bankrunProgram.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(user),
+          charity: null,
+          tierApprover: null,
+          user,
+          systemProgram: SystemProgram.programId,
+        });
+
+    const allocate = (agent: PublicKey) =>
+      bankrunProgram.methods
+        .allocateProfitShare(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantFor(agent),
+          share: PublicKey.findProgramAddressSync(
+            [Buffer.from("share"), Buffer.from(id), agent.toBuffer()],
+            program.programId
+          )[0],
+          payer: admin,
+          systemProgram: SystemProgram.programId,
+        });
+
+    before(async () => {
+      context = await startAnchor("", [], [
+        {
+          address: member.publicKey,
+          info: {
+            lamports: 10_000_000_000,
+            data: Buffer.alloc(0),
+            owner: SystemProgram.programId,
+            executable: false,
+          },
+        },
+      ]);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      // Three quarters of the capital against one quarter
+      await join(admin, 3_000_000_000).rpc();
+      await join(member.publicKey, 1_000_000_000).signers([member]).rpc();
+
+      await bankrunProgram.methods
+        .proposeActivity(id, new anchor.BN(100_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(admin),
+          activity: activityPdaFor(id),
+          initiatorIndex: PublicKey.findProgramAddressSync(
+            [Buffer.from("initiator_index"), admin.toBuffer()],
+            program.programId
+          )[0],
+          complianceFund: null,
+          user: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          validator: null,
+          validatorAuthority: null,
+          authority: admin,
+        })
+        .rpc();
+      const clock = await context.banksClient.getClock();
+      await bankrunProgram.methods
+        .completeActivity(id, new anchor.BN(profit), new anchor.BN(Number(clock.unixTimestamp)), PublicKey.default)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: null,
+          vault: null,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .rpc();
+    });
+
+    it("Audits each participant's share against what is left of the outcome", async () => {
+      const audits = [];
+      for (const agent of [admin, member.publicKey]) {
+        const { events } = await allocate(agent).simulate();
+        audits.push(events.find((e) => e.name === "distributionAuditEvent")!.data as any);
+        await allocate(agent).rpc();
+      }
+
+      expect(audits.map((audit) => audit.total.toNumber())).to.deep.equal([profit, profit]);
+      expect(audits.map((audit) => audit.distributedSum.toNumber())).to.deep.equal([750_000, 1_000_000]);
+      // The rounding dust stays unallocated instead of going to the last participant
+      expect(audits.map((audit) => audit.remainder.toNumber())).to.deep.equal([250_001, 1]);
+
+      const activity = await bankrunProgram.account.activity.fetch(activityPdaFor(id));
+      expect(activity.allocated.toNumber()).to.equal(1_000_000);
+      expect(activity.unallocated.toNumber()).to.equal(1);
+    });
+  });
+
+  describe("two-phase withdrawal", () => {
+    const cooldown = 2;
+    const amount = 3_000_000;
+
+    const setCooldown = async (seconds: number) => {
+      await program.methods
+        .setWithdrawalCooldown(new anchor.BN(seconds))
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+    const withdrawAccounts = () => ({
+      reserve: reservePda,
+      participant: participantPda,
+      user: provider.wallet.publicKey,
+    });
+
+    before(async () => {
+      await applySettlements();
+      await setCooldown(cooldown);
+    });
+    after(async () => setCooldown(0));
+
+    it("Closes one-step withdrawals while a cooldown is set", async () => {
+      try {
+        await program.methods
+          .withdrawCapital(new anchor.BN(1_000_000))
+          .accounts(withdrawAccounts())
+          .rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("TwoPhaseWithdrawalRequired");
+      }
+    });
+
+    it("Records the request without moving lamports", async () => {
+      const reserveBefore = await provider.connection.getBalance(reservePda);
+      const { pendingWithdrawalCount } = await program.account.reserve.fetch(reservePda);
+
+      const builder = program.methods
+        .requestWithdrawal(new anchor.BN(amount))
+        .accounts(withdrawAccounts());
+      const { events } = await builder.simulate();
+      const event = events.find((e) => e.name === "withdrawalRequestedEvent")!.data as any;
+      expect(event.amount.toNumber()).to.equal(amount);
+      await builder.rpc();
+
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.pendingWithdrawal.toNumber()).to.equal(amount);
+      expect(event.settleAfter.toNumber()).to.equal(
+        participant.withdrawalRequestedAt.toNumber() + cooldown
+      );
+      expect(await provider.connection.getBalance(reservePda)).to.equal(reserveBefore);
+
+      // The request is counted as queued work for keepers
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.pendingWithdrawalCount.toNumber()).to.equal(pendingWithdrawalCount.toNumber() + 1);
+      const poll = await program.methods.pollWork().accounts({ reserve: reservePda }).simulate();
+      const work = poll.events.find((e) => e.name === "workAvailableEvent")!.data as any;
+      expect(work.withdrawalsQueued.toNumber()).to.equal(reserve.pendingWithdrawalCount.toNumber());
+    });
+
+    it("Rejects a second request while one is pending", async () => {
+      try {
+        await program.methods
+          .requestWithdrawal(new anchor.BN(amount + 1))
+          .accounts(withdrawAccounts())
+          .rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("WithdrawalPending");
+      }
+    });
+
+    it("Settles only after the cooldown", async () => {
+      const { withdrawalRequestedAt } = await program.account.participant.fetch(participantPda);
+      if ((await chainTime()) < withdrawalRequestedAt.toNumber() + cooldown) {
+        try {
+          await program.methods.settleWithdrawal().accounts(withdrawAccounts()).rpc();
+          expect.fail("the call should have been rejected");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("WithdrawalCooldownActive");
+        }
+      }
+
+      while ((await chainTime()) < withdrawalRequestedAt.toNumber() + cooldown) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+
+      const participantBefore = await program.account.participant.fetch(participantPda);
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+      const builder = program.methods.settleWithdrawal().accounts(withdrawAccounts());
+      const { events } = await builder.simulate();
+      const event = events.find((e) => e.name === "withdrawalSettledEvent")!.data as any;
+      expect(event.amount.toNumber()).to.equal(amount);
+      await builder.rpc();
+
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.pendingWithdrawal.toNumber()).to.equal(0);
+      expect(participant.capitalContributed.toNumber()).to.equal(
+        participantBefore.capitalContributed.toNumber() - amount
+      );
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.pendingWithdrawalCount.toNumber()).to.equal(
+        reserveBefore.pendingWithdrawalCount.toNumber() - 1
+      );
+    });
+
+    it("Cancels a pending request", async () => {
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+      await program.methods
+        .requestWithdrawal(new anchor.BN(amount))
+        .accounts(withdrawAccounts())
+        .rpc();
+      await program.methods.cancelWithdrawal().accounts(withdrawAccounts()).rpc();
+
+      const participant = await program.account.participant.fetch(participantPda);
+      expect(participant.pendingWithdrawal.toNumber()).to.equal(0);
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.pendingWithdrawalCount.toNumber()).to.equal(
+        reserveBefore.pendingWithdrawalCount.toNumber()
+      );
+
+      try {
+        await program.methods.settleWithdrawal().accounts(withdrawAccounts()).rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("NoWithdrawalPending");
+      }
+    });
+  });
+
+  describe("management fee", () => {
+    const id = Array.from(Buffer.alloc(32, 78));
+    const capital = 100_000_000;
+    const profit = 20_000_000;
+    const feeBps = 1000;
+    const fee = (profit * feeBps) / 10_000;
+    const [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+
+    const setFee = async (bps: number) => {
+      await program.methods
+        .setManagementFee(bps)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      await program.methods
+        .initializeTreasury()
+        .accounts({
+          reserve: reservePda,
+          treasury: treasuryPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await setFee(feeBps);
+
+      await proposeActivity(id, capital);
+      await approveActivity(id);
+    });
+
+    after(async () => setFee(0));
+
+    it("Caps the fee at 10% of profit", async () => {
+      try {
+        await setFee(1001);
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidManagementFee");
+      }
+    });
+
+    it("Books the fee as owed and settles the remainder", async () => {
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+      const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+
+      const { events } = await program.methods
+        .completeActivity(id, new anchor.BN(profit), new anchor.BN(await chainTime()), PublicKey.default)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: null,
+          vault: null,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .simulate();
+      const accrued = events.find((e) => e.name === "managementFeeAccruedEvent")!.data as any;
+      expect(accrued.fee.toNumber()).to.equal(fee);
+      expect(accrued.outcome.toNumber()).to.equal(profit);
+
+      await completeActivity(id, profit);
+
+      // Nothing leaves the reserve until the fee is collected
+      expect(await provider.connection.getBalance(treasuryPda)).to.equal(treasuryBefore);
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.settledOutcome.toNumber()).to.equal(profit - fee);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.sub(reserveBefore.totalCapital).toNumber()).to.equal(capital + profit - fee);
+      expect(reserve.feesOwed.sub(reserveBefore.feesOwed).toNumber()).to.equal(fee);
+    });
+
+    it("Collects owed fees only from lamports beyond the books", async () => {
+      const reserve = await program.account.reserve.fetch(reservePda);
+      const info = (await provider.connection.getAccountInfo(reservePda))!;
+      const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      const accounted = reserve.totalCapital
+        .add(reserve.deployedCapital)
+        .add(reserve.privateDeployedCapital)
+        .toNumber();
+      const owed = reserve.feesOwed.toNumber();
+
+      // Deposit whatever the reported profit left missing, so the fee is covered
+      const shortfall = accounted + owed - (info.lamports - rentFloor);
+      if (shortfall > 0) {
+        await provider.sendAndConfirm(
+          new anchor.web3.Transaction().add(
+            SystemProgram.transfer({
+              fromPubkey: provider.wallet.publicKey,
+              toPubkey: reservePda,
+              lamports: shortfall,
+            })
+          )
+        );
+      }
+
+      await program.methods
+        .collectManagementFee()
+        .accounts({
+          reserve: reservePda,
+          treasury: treasuryPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const treasury = await program.account.treasury.fetch(treasuryPda);
+      expect(treasury.balance.toNumber()).to.equal(owed);
+      expect(treasury.totalCollected.toNumber()).to.equal(owed);
+      expect((await program.account.reserve.fetch(reservePda)).feesOwed.toNumber()).to.equal(0);
+    });
+
+    it("Lets the admin withdraw collected fees", async () => {
+      const recipient = Keypair.generate().publicKey;
+      await program.methods
+        .withdrawTreasury(new anchor.BN(1_000_000))
+        .accounts({
+          reserve: reservePda,
+          treasury: treasuryPda,
+          recipient,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const treasury = await program.account.treasury.fetch(treasuryPda);
+      expect(treasury.balance.toNumber()).to.equal(1_000_000);
+      expect(treasury.totalWithdrawn.toNumber()).to.equal(1_000_000);
+      expect(await provider.connection.getBalance(recipient)).to.equal(1_000_000);
+    });
+
+    it("Keeps owed fees out of participant capital across a reconcile", async () => {
+      const secondId = Array.from(Buffer.alloc(32, 92));
+      await proposeActivity(secondId, capital);
+      await approveActivity(secondId);
+      await completeActivity(secondId, profit);
+
+      // Fund the reported profit so the reserve holds both the books and the fee
+      const booked = await program.account.reserve.fetch(reservePda);
+      const info = (await provider.connection.getAccountInfo(reservePda))!;
+      const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      const owed = booked.feesOwed.toNumber();
+      const accounted = booked.totalCapital
+        .add(booked.deployedCapital)
+        .add(booked.privateDeployedCapital)
+        .toNumber();
+      const shortfall = accounted + owed - (info.lamports - rentFloor);
+      if (shortfall > 0) {
+        await provider.sendAndConfirm(
+          new anchor.web3.Transaction().add(
+            SystemProgram.transfer({
+              fromPubkey: provider.wallet.publicKey,
+              toPubkey: reservePda,
+              lamports: shortfall,
+            })
+          )
+        );
+      }
+
+      const openActivities = (await program.account.activity.all())
+        .filter((a) => "approved" in a.account.status || "active" in a.account.status)
+        .map((a) => ({ pubkey: a.publicKey, isSigner: false, isWritable: false }));
+      await program.methods
+        .reconcileCapital()
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .remainingAccounts(openActivities)
+        .rpc();
+
+      const reconciled = await program.account.reserve.fetch(reservePda);
+      const lamports = await provider.connection.getBalance(reservePda);
+      expect(reconciled.feesOwed.toNumber()).to.equal(owed);
+      expect(reconciled.totalCapital.toNumber()).to.equal(
+        lamports - rentFloor - reconciled.deployedCapital.toNumber()
+          - reconciled.privateDeployedCapital.toNumber() - owed
+      );
+
+      const treasuryBefore = await program.account.treasury.fetch(treasuryPda);
+      await program.methods
+        .collectManagementFee()
+        .accounts({
+          reserve: reservePda,
+          treasury: treasuryPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const treasury = await program.account.treasury.fetch(treasuryPda);
+      expect(treasury.balance.sub(treasuryBefore.balance).toNumber()).to.equal(owed);
+      expect((await program.account.reserve.fetch(reservePda)).feesOwed.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("guarantee fund", () => {
+    const id = Array.from(Buffer.alloc(32, 67));
+    const capital = 100_000_000;
+    const loss = 40_000_000;
+    const coverageBps = 5000;
+    const [guaranteeFundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guarantee_fund")],
+      program.programId
+    );
+
+    const setCoverage = async (bps: number) => {
+      await program.methods
+        .setGuaranteeCoverage(bps)
+        .accounts({ reserve: reservePda, auditLog: auditLogPda, admin: provider.wallet.publicKey })
+        .rpc();
+    };
+
+    before(async () => {
+      await program.methods
+        .initializeGuaranteeFund()
+        .accounts({
+          reserve: reservePda,
+          guaranteeFund: guaranteeFundPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .fundGuarantee(new anchor.BN(1_000_000_000))
+        .accounts({
+          reserve: reservePda,
+          guaranteeFund: guaranteeFundPda,
+          donor: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await setCoverage(coverageBps);
+
+      await applySettlements();
+      await proposeActivity(id, capital);
+      await approveActivity(id);
+    });
+
+    after(async () => setCoverage(0));
+
+    it("Rejects coverage above 100%", async () => {
+      try {
+        await setCoverage(10_001);
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidGuaranteeCoverage");
+      }
+    });
+
+    it("Requires the fund to settle a loss while coverage is set", async () => {
+      try {
+        await completeActivity(id, -loss);
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("GuaranteeFundRequired");
+      }
+    });
+
+    it("Refuses a payout unless the admin or an authorized reporter signs", async () => {
+      const stranger = Keypair.generate();
+      const reporterRecordPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("reporter"), stranger.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const complete = async (reporter: Keypair | null, reporterRecord: PublicKey | null) => {
+        const builder = program.methods
+          .completeActivity(id, new anchor.BN(-loss), new anchor.BN(await chainTime()), PublicKey.default)
+          .accounts({
+            reserve: reservePda,
+            activity: activityPdaFor(id),
+            mintVault: null,
+            vault: null,
+            validator: null,
+            validatorAuthority: null,
+            completionBond: null,
+            reporter: reporter?.publicKey ?? null,
+            reporterRecord,
+            guaranteeFund: guaranteeFundPda,
+          });
+        return (reporter ? builder.signers([reporter]) : builder).rpc();
+      };
+
+      await expectRejected(complete(null, null), "Unauthorized");
+      await expectRejected(complete(stranger, null), "Unauthorized");
+
+      // A revoked reporter is no better than one never authorized
+      await program.methods
+        .authorizeReporter(stranger.publicKey)
+        .accounts({
+          reserve: reservePda,
+          reporterRecord: reporterRecordPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .revokeReporter()
+        .accounts({
+          reserve: reservePda,
+          reporterRecord: reporterRecordPda,
+          auditLog: auditLogPda,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+      await expectRejected(complete(stranger, reporterRecordPda), "Unauthorized");
+
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect("approved" in activity.status).to.be.true;
+    });
+
+    it("Covers the configured share of a loss from the fund", async () => {
+      const fundBefore = await program.account.guaranteeFund.fetch(guaranteeFundPda);
+      const reserveBefore = await program.account.reserve.fetch(reservePda);
+
+      await completeActivity(id, -loss, undefined, guaranteeFundPda);
+
+      const covered = (loss * coverageBps) / 10_000;
+      const fund = await program.account.guaranteeFund.fetch(guaranteeFundPda);
+      expect(fundBefore.balance.sub(fund.balance).toNumber()).to.equal(covered);
+      expect(fund.totalCovered.sub(fundBefore.totalCovered).toNumber()).to.equal(covered);
+
+      const reserve = await program.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.sub(reserveBefore.totalCapital).toNumber())
+        .to.equal(capital - (loss - covered));
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      expect(activity.settledOutcome.toNumber()).to.equal(-(loss - covered));
+    });
+
+    it("Reduces the participant's realized loss by the coverage fraction", async () => {
+      const activity = await program.account.activity.fetch(activityPdaFor(id));
+      const participantBefore = await program.account.participant.fetch(participantPda);
+      const uncovered = Math.floor(
+        (loss * participantBefore.capitalContributed.toNumber()) / activity.distributionBase.toNumber()
+      );
+
+      await program.methods
+        .realizeLoss(id)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          participant: participantPda,
+          lossRealization: PublicKey.findProgramAddressSync(
+            [Buffer.from("loss"), Buffer.from(id), provider.wallet.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          guaranteeFund: null,
+          mintVault: null,
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const participant = await program.account.participant.fetch(participantPda);
+      const realized = participant.lossShare.sub(participantBefore.lossShare).toNumber();
+      // Rounding may differ by a lamport between the two divisions
+      expect(realized).to.be.closeTo((uncovered * (10_000 - coverageBps)) / 10_000, 1);
+    });
+  });
+
+  describe("poll_work", () => {
+    // Pending work is seeded straight into a bankrun ledger rather than driven there
+    const overdueId = Array.from(Buffer.alloc(32, 62));
+    const openEndedId = Array.from(Buffer.alloc(32, 63));
+    const completedId = Array.from(Buffer.alloc(32, 64));
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+
+    const pollWork = async (activityIds: number[][] = []) => {
+      const { events } = await bankrunProgram.methods
+        .pollWork()
+        .accounts({ reserve: reservePda })
+        .remainingAccounts(
+          activityIds.map((id) => ({ pubkey: activityPdaFor(id), isSigner: false, isWritable: false }))
+        )
+        .simulate();
+      return events.find((e) => e.name === "workAvailableEvent")!.data as any;
+    };
+
+    const seedActivity = async (id: number[], status: object, maturityAt: number) => {
+      const [pda, bump] = PublicKey.findProgramAddressSync(
+        [Buffer.from("activity"), Buffer.from(id)],
+        program.programId
+      );
+      const data = await bankrunProgram.coder.accounts.encode("activity", {
+        activityId: id,
+        initiator: admin,
+        capitalRequired: new anchor.BN(1_000_000),
+        capitalDeployed: new anchor.BN(1_000_000),
+        riskRating: 1,
+        expectedDuration: new anchor.BN(maturityAt > 0 ? 1 : 0),
+        maturityAt: new anchor.BN(maturityAt),
+        status,
+        createdAt: new anchor.BN(0),
+        completedAt: new anchor.BN(0),
+        outcome: new anchor.BN(0),
+        isValidated: false,
+        isSettled: false,
+        settledOutcome: new anchor.BN(0),
+        distributionBase: new anchor.BN(0),
+        parentId: Array(32).fill(0),
+        settlementMint: PublicKey.default,
+        approvedAt: new anchor.BN(0),
+        bump,
+      });
+      context.setAccount(pda, {
+        lamports: 10_000_000,
+        data,
+        owner: program.programId,
+        executable: false,
+      });
+    };
+
+    before(async () => {
+      context = await startAnchor("", [], []);
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+    });
+
+    it("Reports no work on an idle reserve", async () => {
+      const event = await pollWork();
+      expect(event.overdueCount).to.equal(0);
+      expect(event.distributionsDue).to.be.false;
+      expect(event.withdrawalsQueued.toNumber()).to.equal(0);
+      expect(event.breakerTripped).to.be.false;
+    });
+
+    it("Reflects seeded pending work", async () => {
+      await seedActivity(overdueId, { approved: {} }, 1);
+      await seedActivity(openEndedId, { active: {} }, 0);
+      await seedActivity(completedId, { completed: {} }, 1);
+
+      // Accrued profit past its interval, a wind-down with participants left,
+      // and a tripped solvency breaker
+      const info = await context.banksClient.getAccount(reservePda);
+      const reserve = bankrunProgram.coder.accounts.decode("reserve", Buffer.from(info!.data));
+      reserve.distributionIntervalSeconds = new anchor.BN(60);
+      reserve.lastDistributionAt = new anchor.BN(0);
+      reserve.pendingDistribution = new anchor.BN(5_000);
+      reserve.status = { windingDown: {} };
+      reserve.participantCount = new anchor.BN(2);
+      reserve.totalCapital = new anchor.BN(1_000_000);
+      reserve.deployedCapital = new anchor.BN(9_000_000);
+      reserve.isPaused = true;
+      const encoded = await bankrunProgram.coder.accounts.encode("reserve", reserve);
+      context.setAccount(reservePda, {
+        ...info!,
+        data: Buffer.concat([encoded, Buffer.alloc(info!.data.length - encoded.length)]),
+      });
+
+      const event = await pollWork([overdueId, openEndedId, completedId]);
+      expect(event.overdueCount).to.equal(1);
+      expect(event.distributionsDue).to.be.true;
+      expect(event.withdrawalsQueued.toNumber()).to.equal(2);
+      expect(event.breakerTripped).to.be.true;
+    });
+
+    it("Rejects an activity passed twice", async () => {
+      try {
+        await bankrunProgram.methods
+          .pollWork()
+          .accounts({ reserve: reservePda })
+          .remainingAccounts(
+            [overdueId, overdueId].map((id) => ({
+              pubkey: activityPdaFor(id),
+              isSigner: false,
+              isWritable: false,
+            }))
+          )
+          .rpc();
+        expect.fail("the call should have been rejected");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("DuplicateActivity");
+      }
+    });
+  });
+
+  describe("reserve shutdown", () => {
+    // Shutting down is terminal, so it runs against a fresh reserve in a bankrun ledger
+    const id = Array.from(Buffer.alloc(32, 44));
+    const member = Keypair.generate();
+    const charity = Keypair.generate().publicKey;
+    const donation = 1_000_000;
+    const dust = 7;
+    const [treasuryPda] = PublicKey.findProgramAddressSync([Buffer.from("treasury")], program.programId);
+    const [guaranteeFundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guarantee_fund")],
+      program.programId
+    );
+    let context: Awaited<ReturnType<typeof startAnchor>>;
+    let bankrunProgram: Program;
+    let admin: PublicKey;
+    let clock: () => Promise<number>;
+
+    const participantFor = (agent: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), agent.toBuffer()],
+        program.programId
+      )[0];
+
+    const join = async (agent: Keypair | null, amount: number) => {
+      const user = agent ? agent.publicKey : admin;
+      await bankrunProgram.methods
+        .joinReserve(new anchor.BN(amount), { retail: {} })
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(user),
+          charity: null,
+          tierApprover: null,
+          user,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(agent ? [agent] : [])
+        .rpc();
+    };
+
+    const shutdownAccounts = () => ({ reserve: reservePda, auditLog: auditLogPda, admin });
+
+    const closeReserve = async () => {
+      await bankrunProgram.methods
+        .closeReserve()
+        .accounts({ ...shutdownAccounts(), charity: null })
+        .rpc();
+    };
+
+    const sweepGuaranteeFund = () =>
+      bankrunProgram.methods
+        .sweepGuaranteeFund()
+        .accounts({ ...shutdownAccounts(), guaranteeFund: guaranteeFundPda, charity })
+        .rpc();
+
+    before(async () => {
+      context = await startAnchor(
+        "",
+        [],
+        [
+          {
+            address: member.publicKey,
+            info: {
+              lamports: 10_000_000_000,
+              data: Buffer.alloc(0),
+              owner: SystemProgram.programId,
+              executable: false,
+            },
+          },
+        ]
+      );
+      const bankrunProvider = new BankrunProvider(context);
+      bankrunProgram = new Program(program.idl, bankrunProvider);
+      admin = bankrunProvider.wallet.publicKey;
+      clock = async () => Number((await context.banksClient.getClock()).unixTimestamp);
+
+      await bankrunProgram.methods
+        .initialize(new anchor.BN(1_000_000), new anchor.BN(10))
+        .accounts({ reserve: reservePda, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeAuditLog()
+        .accounts({ ...shutdownAccounts(), systemProgram: SystemProgram.programId })
+        .rpc();
+      await join(member, 2_000_000_000);
+
+      await bankrunProgram.methods
+        .initializeTreasury()
+        .accounts({ ...shutdownAccounts(), treasury: treasuryPda, systemProgram: SystemProgram.programId })
+        .rpc();
+      await bankrunProgram.methods
+        .initializeGuaranteeFund()
+        .accounts({
+          ...shutdownAccounts(),
+          guaranteeFund: guaranteeFundPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .fundGuarantee(new anchor.BN(donation))
+        .accounts({
+          reserve: reservePda,
+          guaranteeFund: guaranteeFundPda,
+          donor: admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bankrunProgram.methods
+        .setEntryDonation(0, charity)
+        .accounts(shutdownAccounts())
+        .rpc();
+    });
+
+    it("Refuses to start while an activity is open", async () => {
+      await bankrunProgram.methods
+        .proposeActivity(id, new anchor.BN(1_000_000), 1, new anchor.BN(0))
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          activity: activityPdaFor(id),
+          initiatorIndex: PublicKey.findProgramAddressSync(
+            [Buffer.from("initiator_index"), member.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          complianceFund: null,
+          user: member.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([member])
+        .rpc();
+      await bankrunProgram.methods
+        .approveActivity(null)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          validator: null,
+          validatorAuthority: null,
+          authority: admin,
+        })
+        .rpc();
+
+      try {
+        await bankrunProgram.methods.initiateShutdown().accounts(shutdownAccounts()).rpc();
+        expect.fail("shutdown should have waited for the open activity");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ActivitiesStillOpen");
+      }
+
+      await bankrunProgram.methods
+        .completeActivity(id, new anchor.BN(0), new anchor.BN(await clock()), PublicKey.default)
+        .accounts({
+          reserve: reservePda,
+          activity: activityPdaFor(id),
+          mintVault: null,
+          vault: null,
+          validator: null,
+          validatorAuthority: null,
+          completionBond: null,
+          reporter: null,
+          reporterRecord: null,
+          guaranteeFund: null,
+        })
+        .rpc();
+    });
+
+    it("Blocks new joins once winding down", async () => {
+      await bankrunProgram.methods.initiateShutdown().accounts(shutdownAccounts()).rpc();
+
+      try {
+        await join(null, 1_000_000_000);
+        expect.fail("join should have been refused");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReserveWindingDown");
+      }
+    });
+
+    it("Refuses to close before every participant has exited", async () => {
+      try {
+        await closeReserve();
+        expect.fail("close should have waited for participants");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReserveNotEmpty");
+      }
+      try {
+        await sweepGuaranteeFund();
+        expect.fail("the sweep should have waited for participants");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReserveNotEmpty");
+      }
+    });
+
+    it("Pays the last participant whatever free capital is left", async () => {
+      // Book a little rounding dust beyond the participant's capital
+      const info = await context.banksClient.getAccount(reservePda);
+      const reserveBefore = bankrunProgram.coder.accounts.decode("reserve", Buffer.from(info!.data));
+      reserveBefore.totalCapital = reserveBefore.totalCapital.addn(dust);
+      const encoded = await bankrunProgram.coder.accounts.encode("reserve", reserveBefore);
+      context.setAccount(reservePda, {
+        ...info!,
+        lamports: Number(info!.lamports) + dust,
+        data: Buffer.concat([encoded, Buffer.alloc(info!.data.length - encoded.length)]),
+      });
+
+      const { events } = await bankrunProgram.methods
+        .settleAndExit()
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          user: member.publicKey,
+        })
+        .signers([member])
+        .simulate();
+      const event = events.find((e) => e.name === "participantSettledEvent")!.data as any;
+      expect(event.capital.add(event.profit).toNumber()).to.equal(reserveBefore.totalCapital.toNumber());
+
+      await bankrunProgram.methods
+        .settleAndExit()
+        .accounts({
+          reserve: reservePda,
+          participant: participantFor(member.publicKey),
+          user: member.publicKey,
+        })
+        .signers([member])
+        .rpc();
+
+      const reserve = await bankrunProgram.account.reserve.fetch(reservePda);
+      expect(reserve.totalCapital.toNumber()).to.equal(0);
+      expect(reserve.totalContributed.toNumber()).to.equal(0);
+      expect(reserve.participantCount.toNumber()).to.equal(0);
+    });
+
+    it("Sweeps the treasury and the guarantee fund, then closes the reserve", async () => {
+      await bankrunProgram.methods
+        .sweepTreasury()
+        .accounts({ ...shutdownAccounts(), treasury: treasuryPda })
+        .rpc();
+      expect(await bankrunProgram.account.treasury.fetchNullable(treasuryPda)).to.be.null;
+
+      await sweepGuaranteeFund();
+      expect(await bankrunProgram.account.guaranteeFund.fetchNullable(guaranteeFundPda)).to.be.null;
+      const charityAccount = await context.banksClient.getAccount(charity);
+      expect(Number(charityAccount!.lamports)).to.equal(donation);
+
+      await closeReserve();
+      expect(await bankrunProgram.account.reserve.fetchNullable(reservePda)).to.be.null;
+    });
+  });
+});
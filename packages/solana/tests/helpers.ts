@@ -0,0 +1,24 @@
+// Copyright 2026 TAWF Labs
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import { expect } from "chai";
+
+// Await a call that must fail with the given program error code
+export const expectRejected = async (call: Promise<unknown>, code: string) => {
+  try {
+    await call;
+    expect.fail("the call should have been rejected");
+  } catch (err: any) {
+    expect(err.error.errorCode.code).to.equal(code);
+  }
+};